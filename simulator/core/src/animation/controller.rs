@@ -2,19 +2,51 @@
 //!
 //! Manages animation state updates for the overlay.
 
-use crate::config::FirmwareConfig;
-use crate::app::state::{AnimationState, EinkState};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{EinkElementConfig, FirmwareConfig};
+use crate::state::{AnimationState, EinkState};
 use crate::render::bezier::ease_in_out;
 
+/// Notable points of the overlay animation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Milestone {
+    /// Entry slide-in finished
+    EntryComplete,
+    /// Barcode EINK effect reached its content
+    BarcodeShown,
+    /// Class icon EINK effect reached its content
+    ClassIconShown,
+    /// Secondary barcode EINK effect reached its content
+    SecondaryBarcodeShown,
+    /// Color fade reached its full radius
+    ColorFadeComplete,
+    /// Logo fully faded in
+    LogoShown,
+    /// AK bar and divider lines fully drawn
+    BarsComplete,
+}
+
 /// Animation controller
 pub struct AnimationController {
     config: FirmwareConfig,
+    /// EINK timing of the optional secondary barcode
+    secondary_barcode: Option<EinkElementConfig>,
 }
 
 impl AnimationController {
     /// Create new animation controller
     pub fn new(config: FirmwareConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            secondary_barcode: None,
+        }
+    }
+
+    /// Set EINK timing of the secondary barcode (None = no secondary barcode)
+    pub fn set_secondary_barcode(&mut self, eink: Option<EinkElementConfig>) {
+        self.secondary_barcode = eink;
     }
 
     /// Reset animation state
@@ -31,8 +63,9 @@ impl AnimationController {
         // Will be handled in update()
     }
 
-    /// Update animation state for one frame
-    pub fn update(&self, state: &mut AnimationState) {
+    /// Update animation state for one frame, returning the milestones reached
+    pub fn update(&self, state: &mut AnimationState) -> Vec<Milestone> {
+        let before = state.clone();
         state.frame_counter += 1;
         let frame = state.frame_counter;
 
@@ -58,6 +91,31 @@ impl AnimationController {
 
         // Update arrow animation
         self.update_arrow(state);
+
+        self.milestones(&before, state)
+    }
+
+    /// Milestones passed between two consecutive animation states
+    fn milestones(&self, before: &AnimationState, after: &AnimationState) -> Vec<Milestone> {
+        let line_width = self.config.animation.bars_lines.line_width;
+        let fade_end = self.config.color_fade_end_value();
+        let bars_complete = |s: &AnimationState| {
+            s.ak_bar_width >= line_width && s.upper_line_width >= line_width && s.lower_line_width >= line_width
+        };
+
+        let reached = [
+            (Milestone::EntryComplete, !before.is_entry_complete() && after.is_entry_complete()),
+            (Milestone::BarcodeShown, !before.barcode_state.is_content() && after.barcode_state.is_content()),
+            (Milestone::ClassIconShown, !before.classicon_state.is_content() && after.classicon_state.is_content()),
+            (
+                Milestone::SecondaryBarcodeShown,
+                !before.secondary_barcode_state.is_content() && after.secondary_barcode_state.is_content(),
+            ),
+            (Milestone::ColorFadeComplete, before.color_fade_radius < fade_end && after.color_fade_radius >= fade_end),
+            (Milestone::LogoShown, before.logo_alpha < 255 && after.logo_alpha == 255),
+            (Milestone::BarsComplete, !bars_complete(before) && bars_complete(after)),
+        ];
+        reached.into_iter().filter(|(_, reached)| *reached).map(|(milestone, _)| milestone).collect()
     }
 
     fn update_entry_animation(&self, state: &mut AnimationState, frame: u32) {
@@ -118,6 +176,12 @@ impl AnimationController {
             self.config.classicon_start_frame(),
             self.config.classicon_frame_per_state(),
         );
+
+        // Secondary barcode: timing comes from the overlay options
+        if let Some(ref eink) = self.secondary_barcode {
+            state.secondary_barcode_state =
+                EinkState::from_frame(frame, eink.start_frame, eink.frame_per_state.max(1));
+        }
     }
 
     fn update_color_fade(&self, state: &mut AnimationState, frame: u32) {
@@ -208,4 +272,44 @@ mod tests {
         // Entry should be complete after 50 frames
         assert!(state.is_entry_complete());
     }
+
+    #[test]
+    fn test_secondary_barcode_eink() {
+        let mut controller = AnimationController::new(FirmwareConfig::get_default());
+        let mut state = controller.reset();
+        controller.update(&mut state);
+        assert_eq!(state.secondary_barcode_state, EinkState::Idle);
+
+        controller.set_secondary_barcode(Some(EinkElementConfig {
+            start_frame: 5,
+            frame_per_state: 2,
+        }));
+        for _ in 0..200 {
+            controller.update(&mut state);
+        }
+        assert_eq!(state.secondary_barcode_state, EinkState::Content);
+    }
+
+    #[test]
+    fn test_milestones() {
+        let controller = AnimationController::new(FirmwareConfig::get_default());
+        let mut state = controller.reset();
+        let mut reached = Vec::new();
+        for _ in 0..1000 {
+            reached.extend(controller.update(&mut state));
+        }
+
+        // Each milestone fires once; the secondary barcode is not configured
+        for milestone in [
+            Milestone::EntryComplete,
+            Milestone::BarcodeShown,
+            Milestone::ClassIconShown,
+            Milestone::ColorFadeComplete,
+            Milestone::LogoShown,
+            Milestone::BarsComplete,
+        ] {
+            assert_eq!(reached.iter().filter(|m| **m == milestone).count(), 1, "{:?}", milestone);
+        }
+        assert!(!reached.contains(&Milestone::SecondaryBarcodeShown));
+    }
 }
@@ -4,4 +4,4 @@
 
 mod controller;
 
-pub use controller::AnimationController;
+pub use controller::{AnimationController, Milestone};
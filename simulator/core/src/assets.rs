@@ -0,0 +1,108 @@
+//! Asset status listing
+//!
+//! Every file the loaded config references, with where it resolves to and
+//! what it contains, so a missing or unexpected asset is easy to spot.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::EPConfig;
+use crate::utils::{file_exists, read_file, resolve_asset_path};
+use crate::video::probe_video;
+
+/// What is known about one asset the config references
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetStatus {
+    /// Config field referencing it (e.g. `overlay.options.logo`)
+    pub field: String,
+    /// Path as written in the config
+    pub path: String,
+    /// Path after resolving against the base directory
+    pub resolved: PathBuf,
+    pub exists: bool,
+    /// Resolution, plus frame rate and length for videos
+    pub details: Option<String>,
+    /// Why the file could not be read, if it exists but could not
+    pub error: Option<String>,
+}
+
+/// Look up every asset `config` references, resolved against `base_dir`
+///
+/// Videos are probed for their stream parameters and images for their
+/// size, which reads file headers but decodes nothing.
+pub fn asset_statuses(config: &EPConfig, base_dir: &Path) -> Vec<AssetStatus> {
+    let mut references = Vec::new();
+    config.clone().for_each_asset_path(|field, path| references.push((field.to_string(), path.clone())));
+
+    references
+        .into_iter()
+        .map(|(field, path)| {
+            let resolved = resolve_asset_path(&path, base_dir);
+            let exists = file_exists(&resolved);
+            let probed = if !exists {
+                None
+            } else if is_video_field(&field) {
+                Some(probe_video(&resolved).map(|info| {
+                    let mut details = format!("{}x{} @ {:.1}fps", info.width, info.height, info.fps);
+                    if let Some(secs) = info.duration_secs {
+                        details.push_str(&format!(", {:.2}s", secs));
+                    }
+                    details
+                }))
+            } else {
+                Some(
+                    read_file(&resolved)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|data| Ok(image_dimensions(&data)?))
+                        .map(|(width, height)| format!("{}x{}", width, height)),
+                )
+            };
+            let (details, error) = match probed {
+                Some(Ok(details)) => (Some(details), None),
+                Some(Err(e)) => (None, Some(format!("{:#}", e))),
+                None => (None, None),
+            };
+            AssetStatus { field, path, resolved, exists, details, error }
+        })
+        .collect()
+}
+
+/// Size of an encoded image, from its header
+fn image_dimensions(data: &[u8]) -> image::ImageResult<(u32, u32)> {
+    image::ImageReader::new(std::io::Cursor::new(data)).with_guessed_format()?.into_dimensions()
+}
+
+/// Whether a field from `EPConfig::for_each_asset_path` names a video
+fn is_video_field(field: &str) -> bool {
+    matches!(field, "loop.file" | "intro.file")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_statuses() {
+        let dir = std::env::temp_dir().join(format!("asset_status_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        image::RgbaImage::new(4, 3).save(dir.join("logo.png")).unwrap();
+
+        let config: EPConfig = serde_json::from_str(
+            r#"{
+                "loop": {"file": "loop.mp4"},
+                "overlay": {"type": "arknights", "options": {"logo": "logo.png"}}
+            }"#,
+        )
+        .unwrap();
+        let statuses = asset_statuses(&config, &dir);
+
+        let video = statuses.iter().find(|s| s.field == "loop.file").unwrap();
+        assert!(!video.exists);
+        assert_eq!((video.details.as_ref(), video.error.as_ref()), (None, None));
+        let logo = statuses.iter().find(|s| s.path == "logo.png").unwrap();
+        assert!(logo.exists);
+        assert_eq!(logo.resolved, dir.join("logo.png"));
+        assert_eq!(logo.details.as_deref(), Some("4x3"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,83 @@
+//! Simulation clock
+//!
+//! Playback normally follows the wall clock. A stepped clock moves by a
+//! fixed amount each frame instead, so tests and headless runs play exactly
+//! the same on every machine however long a frame takes to draw.
+
+use web_time::{Duration, Instant};
+
+/// Source of time for playback
+#[derive(Debug, Clone)]
+pub enum Clock {
+    /// Wall-clock time
+    System { start: Instant },
+    /// Time that only moves when ticked or advanced
+    Stepped {
+        start: Instant,
+        elapsed: Duration,
+        /// Time per tick
+        step: Duration,
+    },
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock::System { start: Instant::now() }
+    }
+}
+
+impl Clock {
+    /// A clock moving `step` per frame
+    pub fn stepped(step: Duration) -> Self {
+        Clock::Stepped { start: Instant::now(), elapsed: Duration::ZERO, step }
+    }
+
+    /// Current time
+    pub fn now(&self) -> Instant {
+        match *self {
+            Clock::System { .. } => Instant::now(),
+            Clock::Stepped { start, elapsed, .. } => start + elapsed,
+        }
+    }
+
+    /// Time since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        match *self {
+            Clock::System { start } => start.elapsed(),
+            Clock::Stepped { elapsed, .. } => elapsed,
+        }
+    }
+
+    /// Start a frame, returning its time
+    pub fn tick(&mut self) -> Instant {
+        if let Clock::Stepped { ref mut elapsed, step, .. } = *self {
+            *elapsed += step;
+        }
+        self.now()
+    }
+
+    /// Move a stepped clock forward by `by`; the wall clock moves on its own
+    pub fn advance(&mut self, by: Duration) {
+        if let Clock::Stepped { ref mut elapsed, .. } = *self {
+            *elapsed += by;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stepped_clock() {
+        let mut clock = Clock::stepped(Duration::from_millis(20));
+        let start = clock.now();
+        assert_eq!(clock.tick() - start, Duration::from_millis(20));
+        assert_eq!(clock.tick() - start, Duration::from_millis(40));
+        clock.advance(Duration::from_millis(5));
+        assert_eq!(clock.now() - start, Duration::from_millis(45));
+        assert_eq!(clock.elapsed(), Duration::from_millis(45));
+        // Does not move on its own
+        assert_eq!(clock.now(), clock.now());
+    }
+}
@@ -0,0 +1,1376 @@
+//! EPConfig data structure
+//!
+//! Corresponds to Python's config/epconfig.py
+
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::utils::{expand_template, merge_json, parse_color, TemplateVars};
+
+use super::firmware_config::EinkElementConfig;
+use super::overlay_template::OverlayTemplateRegistry;
+
+/// Screen resolution type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+pub enum ScreenType {
+    #[default]
+    #[serde(rename = "360x640")]
+    S360x640,
+    #[serde(rename = "480x854")]
+    S480x854,
+    #[serde(rename = "720x1080")]
+    S720x1080,
+}
+
+impl ScreenType {
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            ScreenType::S360x640 => (360, 640),
+            ScreenType::S480x854 => (480, 854),
+            ScreenType::S720x1080 => (720, 1080),
+        }
+    }
+}
+
+/// Transition effect type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TransitionType {
+    #[default]
+    None,
+    Fade,
+    Move,
+    Swipe,
+}
+
+/// Overlay UI type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlayType {
+    #[default]
+    None,
+    Arknights,
+    Image,
+    /// Generic layered overlay (see [`CustomOverlayOptions`])
+    Custom,
+}
+
+/// Transition options
+///
+/// During the hold phase the screen shows `image` if one is set, scaled to
+/// fit; `background_color` then only fills the margins around it and shows
+/// through transparent pixels. Without an image the background color fills
+/// the whole screen.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TransitionOptions {
+    /// Duration in microseconds (default: 500000 = 0.5s)
+    #[serde(default = "default_transition_duration")]
+    pub duration: i64,
+
+    /// Optional transition image path (takes precedence over `background_color`)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub image: String,
+
+    /// Background color (e.g., "#000000"); with an image, only the margins around it
+    #[serde(default = "default_background_color")]
+    pub background_color: String,
+}
+
+fn default_transition_duration() -> i64 {
+    500000
+}
+
+fn default_background_color() -> String {
+    "#000000".to_string()
+}
+
+impl TransitionOptions {
+    /// Whether the hold phase shows an image rather than a plain color
+    pub fn has_image(&self) -> bool {
+        !self.image.trim().is_empty()
+    }
+
+    /// Whether `background_color` differs from the default black
+    pub fn has_background_color(&self) -> bool {
+        parse_color(&self.background_color) != parse_color(&default_background_color())
+    }
+}
+
+impl Default for TransitionOptions {
+    fn default() -> Self {
+        Self {
+            duration: default_transition_duration(),
+            image: String::new(),
+            background_color: default_background_color(),
+        }
+    }
+}
+
+/// Transition configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct Transition {
+    #[serde(rename = "type", default)]
+    pub transition_type: TransitionType,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<TransitionOptions>,
+}
+
+/// Loop video configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct LoopConfig {
+    /// Video file path
+    #[serde(default)]
+    pub file: String,
+
+    /// True if using image mode instead of video
+    #[serde(default)]
+    pub is_image: bool,
+}
+
+/// Intro video configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct IntroConfig {
+    /// Whether intro is enabled
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Intro video file path
+    #[serde(default)]
+    pub file: String,
+
+    /// Duration in microseconds (default: 5000000 = 5s)
+    #[serde(default = "default_intro_duration")]
+    pub duration: i64,
+}
+
+fn default_intro_duration() -> i64 {
+    5000000
+}
+
+/// Simulator playback defaults for this material (ignored by the firmware)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PreviewConfig {
+    /// Playback speed multiplier (1.0 = real time)
+    #[serde(default = "default_preview_speed")]
+    pub speed: f32,
+
+    /// How many times the sequence restarts automatically after reaching the loop
+    #[serde(default)]
+    pub replay_count: u32,
+
+    /// Time spent in the loop state before a replay, in microseconds
+    #[serde(default = "default_replay_after")]
+    pub replay_after: i64,
+
+    /// Go straight to the loop video even if an intro is configured
+    #[serde(default)]
+    pub skip_intro: bool,
+}
+
+fn default_preview_speed() -> f32 {
+    1.0
+}
+
+fn default_replay_after() -> i64 {
+    5000000
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        Self {
+            speed: default_preview_speed(),
+            replay_count: 0,
+            replay_after: default_replay_after(),
+            skip_intro: false,
+        }
+    }
+}
+
+/// Text orientation for overlay text areas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextOrientation {
+    /// Rotated 90° clockwise (current firmware behavior)
+    #[default]
+    Rot90,
+    /// Upright characters stacked top to bottom
+    VerticalStacked,
+    /// Unrotated single line
+    Horizontal,
+}
+
+impl TextOrientation {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Arknights overlay UI options
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ArknightsOverlayOptions {
+    /// Time to appear in microseconds
+    #[serde(default = "default_appear_time")]
+    pub appear_time: i64,
+
+    /// Operator name (uppercase)
+    #[serde(default = "default_operator_name")]
+    pub operator_name: String,
+
+    /// Custom text for top-left area (replaces Rhodes logo when non-empty)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub top_left_rhodes: String,
+
+    /// Orientation of the top-left custom text
+    #[serde(default, skip_serializing_if = "TextOrientation::is_default")]
+    pub top_left_rhodes_orientation: TextOrientation,
+
+    /// Custom text for top-right bar (replaces embedded text when non-empty)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub top_right_bar_text: String,
+
+    /// Operator code
+    #[serde(default = "default_operator_code")]
+    pub operator_code: String,
+
+    /// Barcode text
+    #[serde(default = "default_barcode_text")]
+    pub barcode_text: String,
+
+    /// Optional second, smaller barcode drawn by some firmware variants
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secondary_barcode: Option<SecondaryBarcodeOptions>,
+
+    /// Auxiliary text (multiline)
+    #[serde(default = "default_aux_text")]
+    pub aux_text: String,
+
+    /// Staff text
+    #[serde(default = "default_staff_text")]
+    pub staff_text: String,
+
+    /// Theme color in hex format
+    #[serde(default = "default_color")]
+    pub color: String,
+
+    /// Second theme color for dual-tone gradient themes (empty = single color)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub color2: String,
+
+    /// Operator name color in hex format (empty = white)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub operator_name_color: String,
+
+    /// Operator code color in hex format (empty = theme color)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub operator_code_color: String,
+
+    /// Staff text color in hex format (empty = white)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub staff_text_color: String,
+
+    /// Auxiliary text color in hex format (empty = gray)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub aux_text_color: String,
+
+    /// Optional logo image path
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub logo: String,
+
+    /// Optional progress bar image path (replaces the bundled ak_bar.png)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub ak_bar_image: String,
+
+    /// Optional operator class icon path
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub operator_class_icon: String,
+
+    /// Operator class name ("sniper", "狙击"...), resolved to the bundled
+    /// class icon set when `operator_class_icon` is empty
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub operator_class: String,
+
+    /// Element visibility (all shown by default)
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub show_operator_name: bool,
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub show_operator_code: bool,
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub show_staff_text: bool,
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub show_aux_text: bool,
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub show_barcode: bool,
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub show_class_icon: bool,
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub show_logo: bool,
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub show_arrow: bool,
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub show_ak_bar: bool,
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub show_divider_lines: bool,
+    /// Static decorations (top-left/top-right bars, bottom-left bar)
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub show_decorations: bool,
+    /// Theme color fade in the bottom-right corner
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub show_color_fade: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn default_appear_time() -> i64 {
+    100000
+}
+
+fn default_operator_name() -> String {
+    "OPERATOR".to_string()
+}
+
+fn default_operator_code() -> String {
+    "ARKNIGHTS - UNK0".to_string()
+}
+
+fn default_barcode_text() -> String {
+    "OPERATOR - ARKNIGHTS".to_string()
+}
+
+fn default_aux_text() -> String {
+    "Operator of Rhodes Island\nUndefined/Rhodes Island\n Hypergryph".to_string()
+}
+
+fn default_staff_text() -> String {
+    "STAFF".to_string()
+}
+
+fn default_color() -> String {
+    "#000000".to_string()
+}
+
+impl Default for ArknightsOverlayOptions {
+    fn default() -> Self {
+        Self {
+            appear_time: default_appear_time(),
+            operator_name: default_operator_name(),
+            top_left_rhodes: String::new(),
+            top_left_rhodes_orientation: TextOrientation::default(),
+            top_right_bar_text: String::new(),
+            operator_code: default_operator_code(),
+            barcode_text: default_barcode_text(),
+            secondary_barcode: None,
+            aux_text: default_aux_text(),
+            staff_text: default_staff_text(),
+            color: default_color(),
+            color2: String::new(),
+            operator_name_color: String::new(),
+            operator_code_color: String::new(),
+            staff_text_color: String::new(),
+            aux_text_color: String::new(),
+            logo: String::new(),
+            ak_bar_image: String::new(),
+            operator_class_icon: String::new(),
+            operator_class: String::new(),
+            show_operator_name: true,
+            show_operator_code: true,
+            show_staff_text: true,
+            show_aux_text: true,
+            show_barcode: true,
+            show_class_icon: true,
+            show_logo: true,
+            show_arrow: true,
+            show_ak_bar: true,
+            show_divider_lines: true,
+            show_decorations: true,
+            show_color_fade: true,
+        }
+    }
+}
+
+impl ArknightsOverlayOptions {
+    /// Bundled class icon name for `operator_class` (file stem under class_icons/)
+    pub fn operator_class_key(&self) -> Option<&'static str> {
+        let class = self.operator_class.trim().to_lowercase();
+        let key = match class.as_str() {
+            "vanguard" | "先锋" => "vanguard",
+            "guard" | "近卫" => "guard",
+            "defender" | "重装" => "defender",
+            "sniper" | "狙击" => "sniper",
+            "caster" | "术师" => "caster",
+            "medic" | "医疗" => "medic",
+            "supporter" | "辅助" => "supporter",
+            "specialist" | "特种" => "specialist",
+            _ => return None,
+        };
+        Some(key)
+    }
+
+    /// Expand `{var}` placeholders in all text fields
+    pub fn expand_templates(&mut self, vars: &TemplateVars) {
+        for field in [
+            &mut self.operator_name,
+            &mut self.top_left_rhodes,
+            &mut self.top_right_bar_text,
+            &mut self.operator_code,
+            &mut self.barcode_text,
+            &mut self.aux_text,
+            &mut self.staff_text,
+        ] {
+            *field = expand_template(field, vars);
+        }
+        if let Some(ref mut barcode) = self.secondary_barcode {
+            barcode.text = expand_template(&barcode.text, vars);
+        }
+    }
+}
+
+/// Secondary barcode element (own text, layout rect and EINK timing)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SecondaryBarcodeOptions {
+    /// Barcode text
+    pub text: String,
+
+    /// Layout rect in firmware pixels
+    #[serde(default = "default_secondary_barcode_x")]
+    pub x: u32,
+    #[serde(default = "default_secondary_barcode_y")]
+    pub y: u32,
+    #[serde(default = "default_secondary_barcode_width")]
+    pub width: u32,
+    #[serde(default = "default_secondary_barcode_height")]
+    pub height: u32,
+
+    /// Rotate to read top-to-bottom like the main barcode
+    #[serde(default)]
+    pub vertical: bool,
+
+    /// EINK refresh timing
+    #[serde(default = "default_secondary_barcode_eink")]
+    pub eink: EinkElementConfig,
+}
+
+fn default_secondary_barcode_x() -> u32 {
+    70
+}
+
+fn default_secondary_barcode_y() -> u32 {
+    612
+}
+
+fn default_secondary_barcode_width() -> u32 {
+    140
+}
+
+fn default_secondary_barcode_height() -> u32 {
+    20
+}
+
+fn default_secondary_barcode_eink() -> EinkElementConfig {
+    EinkElementConfig {
+        start_frame: 75,
+        frame_per_state: 15,
+    }
+}
+
+/// Image overlay options
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct ImageOverlayOptions {
+    /// Time to appear in microseconds
+    #[serde(default = "default_appear_time")]
+    pub appear_time: i64,
+
+    /// Display duration in microseconds
+    #[serde(default = "default_appear_time")]
+    pub duration: i64,
+
+    /// Image path
+    #[serde(default)]
+    pub image: String,
+
+    /// Offset from the anchor point in firmware pixels
+    #[serde(default)]
+    pub x: i32,
+    #[serde(default)]
+    pub y: i32,
+
+    /// Screen point (and matching image point) the image is pinned to
+    #[serde(default, skip_serializing_if = "Anchor::is_default")]
+    pub anchor: Anchor,
+
+    /// Scale applied to the image's native size
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+
+    /// Fade-in duration in microseconds (0 = appear instantly)
+    #[serde(default)]
+    pub fade_in: i64,
+
+    /// Fade-out duration in microseconds before `duration` ends (0 = disappear instantly)
+    #[serde(default)]
+    pub fade_out: i64,
+
+    /// Slide in from (and out towards) this direction during the fades
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slide: Option<SlideDirection>,
+
+    /// Slide distance in firmware pixels
+    #[serde(default = "default_slide_distance")]
+    pub slide_distance: f32,
+}
+
+/// Anchor point of an image overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Horizontal and vertical position as fractions (0.0 = left/top, 1.0 = right/bottom)
+    pub fn fraction(&self) -> (f32, f32) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+/// Direction an image overlay slides in towards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SlideDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Easing curve for layer animations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
+}
+
+/// Appear/disappear animation of a custom overlay layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LayerAnimation {
+    #[default]
+    None,
+    Fade,
+    SlideUp,
+    SlideDown,
+    SlideLeft,
+    SlideRight,
+}
+
+/// Drawable content of a custom overlay layer
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum LayerContent {
+    /// Image file; width/height of 0 keep the image's own size
+    Image {
+        image: String,
+        #[serde(default)]
+        width: u32,
+        #[serde(default)]
+        height: u32,
+    },
+    /// Single line of text
+    Text {
+        text: String,
+        #[serde(default = "default_layer_font_size")]
+        font_size: f32,
+        #[serde(default = "default_layer_color")]
+        color: String,
+        #[serde(default)]
+        bold: bool,
+        #[serde(default)]
+        orientation: TextOrientation,
+    },
+    /// Code128 barcode stretched to width x height
+    Barcode {
+        text: String,
+        width: u32,
+        height: u32,
+        #[serde(default)]
+        vertical: bool,
+        #[serde(default = "default_layer_color")]
+        color: String,
+    },
+    /// Rectangle; filled when stroke is 0
+    Rect {
+        width: u32,
+        height: u32,
+        #[serde(default = "default_layer_color")]
+        color: String,
+        #[serde(default)]
+        stroke: f32,
+    },
+    /// Line from (x, y) to (x2, y2)
+    Line {
+        x2: i32,
+        y2: i32,
+        #[serde(default = "default_layer_color")]
+        color: String,
+        #[serde(default = "default_line_thickness")]
+        thickness: f32,
+    },
+}
+
+fn default_layer_font_size() -> f32 {
+    16.0
+}
+
+fn default_layer_color() -> String {
+    "#FFFFFF".to_string()
+}
+
+fn default_line_thickness() -> f32 {
+    1.0
+}
+
+/// One layer of a custom overlay
+///
+/// Positions are in firmware pixels (360x640), times in microseconds
+/// relative to the start of the loop state.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct OverlayLayer {
+    #[serde(flatten)]
+    pub content: LayerContent,
+
+    #[serde(default)]
+    pub x: i32,
+
+    #[serde(default)]
+    pub y: i32,
+
+    /// Time to appear in microseconds
+    #[serde(default)]
+    pub appear_time: i64,
+
+    /// Display duration in microseconds (0 = until the loop ends)
+    #[serde(default)]
+    pub duration: i64,
+
+    /// Animation played when the layer appears (and reversed when it disappears)
+    #[serde(default)]
+    pub animation: LayerAnimation,
+
+    /// Animation duration in microseconds
+    #[serde(default = "default_layer_animation_duration")]
+    pub animation_duration: i64,
+
+    #[serde(default)]
+    pub easing: Easing,
+
+    /// Slide distance in pixels
+    #[serde(default = "default_slide_distance")]
+    pub slide_distance: f32,
+
+    /// Layer opacity (0.0 - 1.0)
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+}
+
+fn default_layer_animation_duration() -> i64 {
+    300000
+}
+
+fn default_slide_distance() -> f32 {
+    40.0
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+/// Custom (layered) overlay options
+///
+/// Layers are drawn in order, so later layers appear on top.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct CustomOverlayOptions {
+    /// Name of an overlay template whose layers are drawn below `layers`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub template: String,
+
+    /// Values for template-specific `{var}` placeholders
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub variables: HashMap<String, String>,
+
+    #[serde(default)]
+    pub layers: Vec<OverlayLayer>,
+}
+
+impl CustomOverlayOptions {
+    /// Merge the selected template's layers and variable defaults
+    ///
+    /// Returns false if a template is selected but not found in the registry.
+    pub fn apply_template(&mut self, registry: &OverlayTemplateRegistry) -> bool {
+        if self.template.is_empty() {
+            return true;
+        }
+        let Some(template) = registry.get(&self.template) else {
+            return false;
+        };
+
+        let mut layers = template.layers.clone();
+        layers.append(&mut self.layers);
+        self.layers = layers;
+
+        for (key, value) in &template.variables {
+            self.variables.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        true
+    }
+
+    /// Expand `{var}` placeholders in text and barcode layers
+    ///
+    /// `variables` are added on top of `vars`.
+    pub fn expand_templates(&mut self, vars: &TemplateVars) {
+        let mut vars = vars.clone();
+        for (key, value) in &self.variables {
+            vars.set(key, value);
+        }
+
+        for layer in &mut self.layers {
+            match &mut layer.content {
+                LayerContent::Text { text, .. } | LayerContent::Barcode { text, .. } => {
+                    *text = expand_template(text, &vars);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Overlay configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct Overlay {
+    #[serde(rename = "type", default)]
+    pub overlay_type: OverlayType,
+
+    /// Options - interpreted based on overlay_type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "overlay_options_schema")]
+    pub options: Option<serde_json::Value>,
+
+    /// Stacking order when several overlays are configured (higher = on top)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub z_index: i32,
+}
+
+/// Schema of `Overlay::options`: the options of one of the overlay types
+fn overlay_options_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+    use schemars::schema::{SchemaObject, SubschemaValidation};
+
+    let variants = vec![
+        gen.subschema_for::<ArknightsOverlayOptions>(),
+        gen.subschema_for::<ImageOverlayOptions>(),
+        gen.subschema_for::<Vec<ImageOverlayOptions>>(),
+        gen.subschema_for::<CustomOverlayOptions>(),
+    ];
+    SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            any_of: Some(variants),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+fn is_zero(value: &i32) -> bool {
+    *value == 0
+}
+
+impl Overlay {
+    /// Get Arknights overlay options if type is Arknights
+    pub fn arknights_options(&self) -> Option<ArknightsOverlayOptions> {
+        if self.overlay_type == OverlayType::Arknights {
+            self.options
+                .as_ref()
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+        } else {
+            None
+        }
+    }
+
+    /// Get Image overlay entries if type is Image
+    ///
+    /// Options may be a single entry or a list of timed entries; invalid
+    /// entries are skipped.
+    pub fn image_entries(&self) -> Vec<ImageOverlayOptions> {
+        if self.overlay_type != OverlayType::Image {
+            return Vec::new();
+        }
+        match self.options.as_ref() {
+            Some(serde_json::Value::Array(entries)) => entries
+                .iter()
+                .filter_map(|v| serde_json::from_value(v.clone()).ok())
+                .collect(),
+            Some(v) => serde_json::from_value(v.clone()).into_iter().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Get Custom overlay options if type is Custom
+    pub fn custom_options(&self) -> Option<CustomOverlayOptions> {
+        if self.overlay_type == OverlayType::Custom {
+            self.options
+                .as_ref()
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+        } else {
+            None
+        }
+    }
+}
+
+/// EPConfig - Complete material configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EPConfig {
+    /// Config version
+    #[serde(default = "default_version")]
+    pub version: i32,
+
+    /// Unique identifier
+    #[serde(default = "default_uuid")]
+    pub uuid: String,
+
+    /// Material name
+    #[serde(default)]
+    pub name: String,
+
+    /// Serial number of the unit the pass is made for, shown by `{serial}`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub serial: String,
+
+    /// Description
+    #[serde(default)]
+    pub description: String,
+
+    /// Icon path
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub icon: String,
+
+    /// Screen resolution
+    #[serde(default)]
+    pub screen: ScreenType,
+
+    /// Loop video configuration
+    #[serde(default, rename = "loop")]
+    pub loop_config: LoopConfig,
+
+    /// Intro video configuration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intro: Option<IntroConfig>,
+
+    /// Transition in effect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transition_in: Option<Transition>,
+
+    /// Transition loop effect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transition_loop: Option<Transition>,
+
+    /// Overlay configuration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overlay: Option<Overlay>,
+
+    /// Additional overlays composed with `overlay` (see [`EPConfig::overlay_stack`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overlays: Vec<Overlay>,
+
+    /// Partial FirmwareConfig `layout` merged over the global one for this material
+    /// (e.g. `{"offsets": {"opname_y": 400}, "barcode": {"y": 440}}`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<serde_json::Value>,
+
+    /// Partial FirmwareConfig `animation` merged over the global one for this material
+    /// (e.g. `{"typewriter": {"name": {"start_frame": 10}}, "eink": {"barcode": {"frame_per_state": 10}}}`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub animation: Option<serde_json::Value>,
+
+    /// Simulator playback defaults applied when the config is loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<PreviewConfig>,
+}
+
+/// Pass `value[key]` to `f` if it is a string
+fn visit_string_field(
+    value: &mut serde_json::Value,
+    path: &str,
+    key: &str,
+    f: &mut impl FnMut(&str, &mut String),
+) {
+    if let Some(serde_json::Value::String(s)) = value.get_mut(key) {
+        f(&format!("{}.{}", path, key), s);
+    }
+}
+
+fn default_version() -> i32 {
+    1
+}
+
+fn default_uuid() -> String {
+    Uuid::new_v4().to_string()
+}
+
+impl Default for EPConfig {
+    fn default() -> Self {
+        Self {
+            version: default_version(),
+            uuid: default_uuid(),
+            name: String::new(),
+            serial: String::new(),
+            description: String::new(),
+            icon: String::new(),
+            screen: ScreenType::default(),
+            loop_config: LoopConfig::default(),
+            intro: None,
+            transition_in: None,
+            transition_loop: None,
+            overlay: None,
+            overlays: Vec::new(),
+            layout: None,
+            animation: None,
+            preview: None,
+        }
+    }
+}
+
+impl EPConfig {
+    /// Load configuration from JSON file
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let config: EPConfig = serde_json::from_str(&content)?;
+        Ok(config)
+    }
+
+    /// JSON Schema of the config format this simulator accepts
+    pub fn schema_json() -> serde_json::Value {
+        let mut schema = serde_json::to_value(schemars::schema_for!(EPConfig)).unwrap_or_default();
+        // The default is a fresh random UUID, which would make the schema differ per call
+        if let Some(uuid) = schema.pointer_mut("/properties/uuid").and_then(|v| v.as_object_mut()) {
+            uuid.remove("default");
+        }
+        schema
+    }
+
+    /// Serialize to pretty JSON (4-space indent, like the editor writes)
+    ///
+    /// Fields keep their declaration order and option objects are sorted by
+    /// key, so saving the same config twice gives identical output.
+    pub fn to_json_pretty(&self) -> Result<String> {
+        let mut buf = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+        let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
+        self.serialize(&mut serializer)?;
+        buf.push(b'\n');
+        Ok(String::from_utf8(buf)?)
+    }
+
+    /// Save configuration to a JSON file
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_json_pretty()?)?;
+        Ok(())
+    }
+
+    /// Call `f` with every non-empty asset path in the config
+    ///
+    /// `f` gets the field's location (e.g. `overlay.options.logo`) and the
+    /// path itself, which it may rewrite.
+    pub fn for_each_asset_path(&mut self, mut f: impl FnMut(&str, &mut String)) {
+        let mut visit = |field: &str, path: &mut String| {
+            if !path.is_empty() {
+                f(field, path);
+            }
+        };
+
+        visit("icon", &mut self.icon);
+        visit("loop.file", &mut self.loop_config.file);
+        if let Some(ref mut intro) = self.intro {
+            visit("intro.file", &mut intro.file);
+        }
+        for (name, transition) in [("transition_in", &mut self.transition_in), ("transition_loop", &mut self.transition_loop)] {
+            if let Some(options) = transition.as_mut().and_then(|t| t.options.as_mut()) {
+                visit(&format!("{}.options.image", name), &mut options.image);
+            }
+        }
+
+        let overlays = self
+            .overlay
+            .iter_mut()
+            .map(|o| ("overlay".to_string(), o))
+            .chain(self.overlays.iter_mut().enumerate().map(|(i, o)| (format!("overlays[{}]", i), o)));
+        for (name, overlay) in overlays {
+            let Some(ref mut options) = overlay.options else {
+                continue;
+            };
+            let path = format!("{}.options", name);
+            match overlay.overlay_type {
+                OverlayType::Arknights => {
+                    for key in ["logo", "ak_bar_image", "operator_class_icon"] {
+                        visit_string_field(options, &path, key, &mut visit);
+                    }
+                }
+                OverlayType::Image => match options {
+                    serde_json::Value::Array(entries) => {
+                        for (i, entry) in entries.iter_mut().enumerate() {
+                            visit_string_field(entry, &format!("{}[{}]", path, i), "image", &mut visit);
+                        }
+                    }
+                    entry => visit_string_field(entry, &path, "image", &mut visit),
+                },
+                OverlayType::Custom => {
+                    if let Some(layers) = options.get_mut("layers").and_then(|l| l.as_array_mut()) {
+                        for (i, layer) in layers.iter_mut().enumerate() {
+                            visit_string_field(layer, &format!("{}.layers[{}]", path, i), "image", &mut visit);
+                        }
+                    }
+                }
+                OverlayType::None => {}
+            }
+        }
+    }
+
+    /// Get transition in type
+    pub fn get_transition_in_type(&self) -> TransitionType {
+        self.transition_in
+            .as_ref()
+            .map(|t| t.transition_type)
+            .unwrap_or(TransitionType::None)
+    }
+
+    /// Get transition loop type
+    pub fn get_transition_loop_type(&self) -> TransitionType {
+        self.transition_loop
+            .as_ref()
+            .map(|t| t.transition_type)
+            .unwrap_or(TransitionType::None)
+    }
+
+    /// Get transition in duration in microseconds
+    pub fn get_transition_in_duration(&self) -> i64 {
+        self.transition_in
+            .as_ref()
+            .and_then(|t| t.options.as_ref())
+            .map(|o| o.duration)
+            .unwrap_or(500000)
+    }
+
+    /// Get transition loop duration in microseconds
+    pub fn get_transition_loop_duration(&self) -> i64 {
+        self.transition_loop
+            .as_ref()
+            .and_then(|t| t.options.as_ref())
+            .map(|o| o.duration)
+            .unwrap_or(500000)
+    }
+
+    /// All overlays in drawing order (bottom to top)
+    ///
+    /// `overlay` comes first, then `overlays` in file order; the list is
+    /// stably sorted by `z_index`, so equal values keep that order.
+    pub fn overlay_stack(&self) -> Vec<&Overlay> {
+        let mut stack: Vec<&Overlay> = self.overlay.iter().chain(self.overlays.iter()).collect();
+        stack.sort_by_key(|o| o.z_index);
+        stack
+    }
+
+    /// Options of the first Arknights overlay in the stack
+    pub fn arknights_options(&self) -> Option<ArknightsOverlayOptions> {
+        self.overlay_stack().iter().find_map(|o| o.arknights_options())
+    }
+
+    /// Merge a partial options object into the first Arknights overlay
+    ///
+    /// The config is left untouched if there is no Arknights overlay or the
+    /// patched options are invalid. Returns the new options.
+    pub fn update_arknights_options(&mut self, patch: &serde_json::Value) -> Result<ArknightsOverlayOptions> {
+        let mut stack: Vec<&mut Overlay> = self.overlay.iter_mut().chain(self.overlays.iter_mut()).collect();
+        stack.sort_by_key(|o| o.z_index);
+        let overlay = stack
+            .into_iter()
+            .find(|o| o.arknights_options().is_some())
+            .ok_or_else(|| anyhow::anyhow!("No Arknights overlay to update"))?;
+
+        let mut options = overlay.options.clone().unwrap_or_default();
+        merge_json(&mut options, patch);
+        let parsed: ArknightsOverlayOptions = serde_json::from_value(options.clone())?;
+        overlay.options = Some(options);
+        Ok(parsed)
+    }
+
+    /// Get appear time in microseconds
+    pub fn get_appear_time(&self) -> i64 {
+        self.arknights_options()
+            .map(|a| a.appear_time)
+            .unwrap_or(100000)
+    }
+
+    /// Check if intro is enabled
+    pub fn has_intro(&self) -> bool {
+        self.intro.as_ref().map(|i| i.enabled).unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let config = EPConfig::default();
+        assert_eq!(config.version, 1);
+        assert_eq!(config.screen, ScreenType::S360x640);
+    }
+
+    #[test]
+    fn test_preview_defaults() {
+        let config: EPConfig = serde_json::from_str(r#"{"preview": {"speed": 0.5}}"#).unwrap();
+        let preview = config.preview.unwrap();
+        assert_eq!(preview.speed, 0.5);
+        assert_eq!(preview.replay_count, 0);
+        assert_eq!(preview.replay_after, 5000000);
+        assert!(!preview.skip_intro);
+    }
+
+    #[test]
+    fn test_screen_dimensions() {
+        assert_eq!(ScreenType::S360x640.dimensions(), (360, 640));
+        assert_eq!(ScreenType::S480x854.dimensions(), (480, 854));
+        assert_eq!(ScreenType::S720x1080.dimensions(), (720, 1080));
+    }
+
+    #[test]
+    fn test_text_orientation_parse() {
+        let options: ArknightsOverlayOptions =
+            serde_json::from_str(r#"{"top_left_rhodes_orientation": "vertical-stacked"}"#).unwrap();
+        assert_eq!(options.top_left_rhodes_orientation, TextOrientation::VerticalStacked);
+
+        let options: ArknightsOverlayOptions = serde_json::from_str("{}").unwrap();
+        assert_eq!(options.top_left_rhodes_orientation, TextOrientation::Rot90);
+    }
+
+    #[test]
+    fn test_custom_overlay_layers() {
+        let json = r##"{
+            "type": "custom",
+            "options": {
+                "layers": [
+                    {"type": "image", "image": "bg.png"},
+                    {"type": "text", "text": "{name}", "x": 20, "y": 500, "animation": "fade"},
+                    {"type": "line", "x2": 340, "y2": 600, "color": "#FF0000"}
+                ]
+            }
+        }"##;
+        let overlay: Overlay = serde_json::from_str(json).unwrap();
+        let options = overlay.custom_options().unwrap();
+        assert_eq!(options.layers.len(), 3);
+
+        let text = &options.layers[1];
+        assert_eq!((text.x, text.y), (20, 500));
+        assert_eq!(text.animation, LayerAnimation::Fade);
+        assert_eq!(text.easing, Easing::EaseInOut);
+        assert!(matches!(&text.content, LayerContent::Text { font_size, .. } if *font_size == 16.0));
+        assert!(matches!(&options.layers[2].content, LayerContent::Line { x2: 340, .. }));
+    }
+
+    #[test]
+    fn test_custom_overlay_apply_template() {
+        use super::super::overlay_template::OverlayTemplate;
+
+        let template: OverlayTemplate = serde_json::from_str(r#"{
+            "name": "minimal",
+            "variables": {"title": "DEFAULT", "subtitle": "SUB"},
+            "layers": [{"type": "text", "text": "{title} {subtitle}"}]
+        }"#).unwrap();
+        let registry = OverlayTemplateRegistry::from_templates(vec![template]);
+
+        let mut options: CustomOverlayOptions = serde_json::from_str(r#"{
+            "template": "minimal",
+            "variables": {"title": "AMIYA"},
+            "layers": [{"type": "rect", "width": 10, "height": 10}]
+        }"#).unwrap();
+        assert!(options.apply_template(&registry));
+        options.expand_templates(&TemplateVars::default());
+
+        assert_eq!(options.layers.len(), 2);
+        assert!(matches!(&options.layers[0].content, LayerContent::Text { text, .. } if text == "AMIYA SUB"));
+        assert!(matches!(&options.layers[1].content, LayerContent::Rect { .. }));
+
+        options.template = "missing".to_string();
+        assert!(!options.apply_template(&registry));
+    }
+
+    #[test]
+    fn test_overlay_stack_order() {
+        let json = r#"{
+            "overlay": {"type": "arknights", "options": {"appear_time": 200000}},
+            "overlays": [
+                {"type": "image", "z_index": -1, "options": {"image": "under.png"}},
+                {"type": "image", "options": {"image": "over.png"}}
+            ]
+        }"#;
+        let config: EPConfig = serde_json::from_str(json).unwrap();
+        let images: Vec<String> = config
+            .overlay_stack()
+            .iter()
+            .map(|o| o.image_entries().first().map(|i| i.image.clone()).unwrap_or_default())
+            .collect();
+        assert_eq!(images, vec!["under.png", "", "over.png"]);
+        assert_eq!(config.get_appear_time(), 200000);
+    }
+
+    #[test]
+    fn test_image_overlay_entries() {
+        let overlay: Overlay = serde_json::from_str(
+            r#"{"type": "image", "options": [
+                {"image": "stamp.png", "appear_time": 0, "duration": 1000000, "x": 20, "y": 300},
+                {"image": "card.png", "appear_time": 1000000, "duration": 2000000}
+            ]}"#,
+        )
+        .unwrap();
+        let entries = overlay.image_entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].x, entries[0].y), (20, 300));
+        assert_eq!(entries[1].image, "card.png");
+
+        let single: Overlay = serde_json::from_str(r#"{"type": "image", "options": {"image": "a.png"}}"#).unwrap();
+        assert_eq!(single.image_entries().len(), 1);
+    }
+
+    #[test]
+    fn test_visibility_defaults() {
+        let options: ArknightsOverlayOptions =
+            serde_json::from_str(r#"{"show_barcode": false}"#).unwrap();
+        assert!(!options.show_barcode);
+        assert!(options.show_logo && options.show_arrow && options.show_aux_text);
+
+        // Only hidden elements are written back
+        let json = serde_json::to_string(&options).unwrap();
+        assert!(json.contains("show_barcode"));
+        assert!(!json.contains("show_logo"));
+    }
+
+    #[test]
+    fn test_operator_class_key() {
+        let mut options = ArknightsOverlayOptions::default();
+        assert_eq!(options.operator_class_key(), None);
+
+        options.operator_class = " Sniper ".to_string();
+        assert_eq!(options.operator_class_key(), Some("sniper"));
+
+        options.operator_class = "医疗".to_string();
+        assert_eq!(options.operator_class_key(), Some("medic"));
+
+        options.operator_class = "unknown".to_string();
+        assert_eq!(options.operator_class_key(), None);
+    }
+
+    #[test]
+    fn test_update_arknights_options() {
+        let mut config: EPConfig = serde_json::from_str(
+            r##"{"overlay": {"type": "arknights", "options": {"operator_name": "AMIYA", "color": "#112233"}}}"##,
+        )
+        .unwrap();
+
+        let updated = config
+            .update_arknights_options(&serde_json::json!({"operator_name": "KAL'TSIT"}))
+            .unwrap();
+        assert_eq!(updated.operator_name, "KAL'TSIT");
+        assert_eq!(updated.color, "#112233");
+        assert_eq!(config.arknights_options().unwrap().operator_name, "KAL'TSIT");
+
+        // Invalid patches leave the config unchanged
+        assert!(config.update_arknights_options(&serde_json::json!({"appear_time": "soon"})).is_err());
+        assert_eq!(config.get_appear_time(), 100000);
+    }
+
+    #[test]
+    fn test_schema_json() {
+        let schema = EPConfig::schema_json();
+        assert_eq!(schema, EPConfig::schema_json());
+        assert!(schema.pointer("/properties/loop").is_some());
+        assert!(schema.pointer("/definitions/ArknightsOverlayOptions/properties/operator_name").is_some());
+        assert!(schema.pointer("/definitions/OverlayLayer").is_some());
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let json = r##"{
+            "name": "test",
+            "screen": "480x854",
+            "loop": {"file": "loop.mp4"},
+            "overlay": {"type": "arknights", "options": {"operator_name": "AMIYA", "color": "#112233"}},
+            "layout": {"offsets": {"opname_y": 400}}
+        }"##;
+        let config: EPConfig = serde_json::from_str(json).unwrap();
+
+        let path = std::env::temp_dir().join(format!("epconfig_save_{}.json", std::process::id()));
+        config.save_to_file(&path).unwrap();
+        let reloaded = EPConfig::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(
+            serde_json::to_value(&config).unwrap(),
+            serde_json::to_value(&reloaded).unwrap()
+        );
+        assert_eq!(config.to_json_pretty().unwrap(), reloaded.to_json_pretty().unwrap());
+        assert_eq!(reloaded.screen, ScreenType::S480x854);
+    }
+
+    #[test]
+    fn test_secondary_barcode() {
+        let options: ArknightsOverlayOptions =
+            serde_json::from_str(r#"{"secondary_barcode": {"text": "{name}-2", "y": 600}}"#).unwrap();
+        let barcode = options.secondary_barcode.as_ref().unwrap();
+        assert_eq!(barcode.y, 600);
+        assert_eq!(barcode.height, 20);
+        assert_eq!(barcode.eink.start_frame, 75);
+
+        let json = serde_json::to_string(&ArknightsOverlayOptions::default()).unwrap();
+        assert!(!json.contains("secondary_barcode"));
+    }
+}
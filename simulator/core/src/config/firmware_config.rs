@@ -3,7 +3,14 @@
 //! Contains animation timing constants extracted from the firmware.
 //! Corresponds to Python's config/firmware_config.py
 
+use anyhow::Result;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::utils::merge_json;
 
 /// Typewriter element configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,7 +42,7 @@ pub struct TypewriterConfig {
 }
 
 /// EINK element configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EinkElementConfig {
     pub start_frame: u32,
     pub frame_per_state: u32,
@@ -387,6 +394,10 @@ pub struct FirmwareConfig {
     pub transition: TransitionAnimConfig,
     #[serde(default)]
     pub bezier_presets: BezierPresets,
+    /// Layout overrides per screen resolution, keyed by "WIDTHxHEIGHT"
+    /// (resolutions without a profile are scaled proportionally)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub layout_profiles: HashMap<String, Value>,
 }
 
 fn default_config_version() -> i32 {
@@ -415,7 +426,96 @@ impl FirmwareConfig {
             },
             transition: TransitionAnimConfig::default(),
             bezier_presets: BezierPresets::default(),
+            layout_profiles: HashMap::new(),
+        }
+    }
+
+    /// Copy of this config laid out for a `width` x `height` screen
+    ///
+    /// Uses the matching entry of `layout_profiles` when there is one, merged
+    /// over the base layout; otherwise scales the layout proportionally.
+    pub fn for_resolution(&self, width: u32, height: u32) -> Self {
+        if (width, height) == (self.overlay_width(), self.overlay_height()) {
+            return self.clone();
+        }
+
+        let mut config = match self.layout_profiles.get(&format!("{}x{}", width, height)) {
+            Some(profile) => match self.with_layout_override(profile) {
+                Ok(config) => config,
+                Err(e) => {
+                    warn!("Ignoring invalid layout profile for {}x{}: {}", width, height, e);
+                    self.scaled_to(width, height)
+                }
+            },
+            None => self.scaled_to(width, height),
+        };
+        config.layout.overlay = SizeConfig { width, height };
+        config
+    }
+
+    /// Copy of this config with all layout positions and sizes scaled to a new screen size
+    fn scaled_to(&self, width: u32, height: u32) -> Self {
+        let sx = width as f32 / self.overlay_width() as f32;
+        let sy = height as f32 / self.overlay_height() as f32;
+        let x = |v: u32| (v as f32 * sx).round() as u32;
+        let y = |v: u32| (v as f32 * sy).round() as u32;
+
+        let mut config = self.clone();
+        let layout = &mut config.layout;
+        let offsets = &mut layout.offsets;
+        offsets.btm_info_x = x(offsets.btm_info_x);
+        for v in [
+            &mut offsets.opname_y,
+            &mut offsets.upperline_y,
+            &mut offsets.lowerline_y,
+            &mut offsets.opcode_y,
+            &mut offsets.staff_text_y,
+            &mut offsets.class_icon_y,
+            &mut offsets.ak_bar_y,
+            &mut offsets.aux_text_y,
+            &mut offsets.aux_text_line_height,
+            &mut offsets.arrow_y,
+        ] {
+            *v = y(*v);
         }
+
+        let barcode = &mut layout.barcode;
+        barcode.x = x(barcode.x);
+        barcode.y = y(barcode.y);
+        barcode.width = x(barcode.width);
+        barcode.height = y(barcode.height);
+
+        // Keep the class icon square
+        let icon_scale = sx.min(sy);
+        layout.class_icon.width = (layout.class_icon.width as f32 * icon_scale).round() as u32;
+        layout.class_icon.height = (layout.class_icon.height as f32 * icon_scale).round() as u32;
+
+        config.animation.bars_lines.line_width = x(config.animation.bars_lines.line_width);
+        layout.overlay = SizeConfig { width, height };
+        config
+    }
+
+    /// Copy of this config with a partial `layout` section merged over it
+    ///
+    /// Only the given fields change, e.g. `{"offsets": {"opname_y": 400}}`.
+    pub fn with_layout_override(&self, layout: &Value) -> Result<Self> {
+        self.with_section_override("layout", layout)
+    }
+
+    /// Copy of this config with a partial `animation` section merged over it
+    ///
+    /// e.g. `{"typewriter": {"name": {"start_frame": 10}}, "eink": {...}}`
+    pub fn with_animation_override(&self, animation: &Value) -> Result<Self> {
+        self.with_section_override("animation", animation)
+    }
+
+    /// Merge a partial JSON value over one top-level section
+    fn with_section_override(&self, section: &str, patch: &Value) -> Result<Self> {
+        let mut value = serde_json::to_value(self)?;
+        if let Some(target) = value.get_mut(section) {
+            merge_json(target, patch);
+        }
+        Ok(serde_json::from_value(value)?)
     }
 
     // Convenience accessors
@@ -528,4 +628,57 @@ mod tests {
         assert_eq!(config.overlay_width(), 360);
         assert_eq!(config.overlay_height(), 640);
     }
+
+    #[test]
+    fn test_layout_override() {
+        let base = FirmwareConfig::get_default();
+        let patched = base
+            .with_layout_override(&serde_json::json!({
+                "offsets": {"opname_y": 400},
+                "barcode": {"height": 150}
+            }))
+            .unwrap();
+        assert_eq!(patched.layout.offsets.opname_y, 400);
+        assert_eq!(patched.layout.offsets.opcode_y, base.layout.offsets.opcode_y);
+        assert_eq!(patched.layout.barcode.height, 150);
+        assert_eq!(patched.layout.barcode.y, base.layout.barcode.y);
+
+        // Type mismatches are rejected
+        assert!(base.with_layout_override(&serde_json::json!({"offsets": {"opname_y": "x"}})).is_err());
+    }
+
+    #[test]
+    fn test_animation_override() {
+        let base = FirmwareConfig::get_default();
+        let patched = base
+            .with_animation_override(&serde_json::json!({
+                "typewriter": {"name": {"start_frame": 10}},
+                "bars_lines": {"ak_bar": {"frame_count": 20}}
+            }))
+            .unwrap();
+        assert_eq!(patched.name_start_frame(), 10);
+        assert_eq!(patched.name_frame_per_char(), base.name_frame_per_char());
+        assert_eq!(patched.animation.bars_lines.ak_bar.frame_count, 20);
+        assert_eq!(patched.animation.bars_lines.ak_bar.start_frame, 100);
+    }
+
+    #[test]
+    fn test_for_resolution() {
+        let base = FirmwareConfig::get_default();
+        let scaled = base.for_resolution(720, 1280);
+        assert_eq!((scaled.overlay_width(), scaled.overlay_height()), (720, 1280));
+        assert_eq!(scaled.layout.offsets.opname_y, base.layout.offsets.opname_y * 2);
+        assert_eq!(scaled.layout.barcode.width, base.layout.barcode.width * 2);
+        assert_eq!(scaled.animation.bars_lines.line_width, 560);
+
+        // An explicit profile wins over proportional scaling
+        let mut with_profile = base.clone();
+        with_profile
+            .layout_profiles
+            .insert("480x854".to_string(), serde_json::json!({"offsets": {"opname_y": 560}}));
+        let profiled = with_profile.for_resolution(480, 854);
+        assert_eq!(profiled.layout.offsets.opname_y, 560);
+        assert_eq!(profiled.layout.offsets.opcode_y, base.layout.offsets.opcode_y);
+        assert_eq!(profiled.overlay_height(), 854);
+    }
 }
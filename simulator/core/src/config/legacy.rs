@@ -0,0 +1,329 @@
+//! Legacy material conversion
+//!
+//! Old materials used a flat config: videos given as plain strings and the
+//! overlay texts at the top level instead of in `overlay.options`. These are
+//! converted to the current EPConfig layout with the defaults the firmware
+//! applied to them (fade transitions, Arknights overlay when texts are set).
+//! Whole material folders are converted with `convert_legacy_material`,
+//! which also looks at the assets.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Map, Value};
+use tracing::{info, warn};
+
+use crate::utils::resolve_asset_path;
+use crate::video::probe_video;
+
+use super::epconfig::{ArknightsOverlayOptions, EPConfig, Overlay, OverlayType};
+use super::package::CONFIG_FILE_NAME;
+
+/// Names old materials gave their config, most common first
+const LEGACY_CONFIG_FILES: &[&str] = &["config.json", CONFIG_FILE_NAME];
+
+/// Legacy top-level key(s) -> Arknights overlay option
+const ARKNIGHTS_KEYS: &[(&[&str], &str)] = &[
+    (&["operator_name", "opname"], "operator_name"),
+    (&["operator_code", "opcode"], "operator_code"),
+    (&["barcode_text", "barcode"], "barcode_text"),
+    (&["staff_text", "staff"], "staff_text"),
+    (&["aux_text", "aux"], "aux_text"),
+    (&["color", "theme_color"], "color"),
+    (&["logo"], "logo"),
+    (&["operator_class", "class"], "operator_class"),
+    (&["operator_class_icon", "class_icon"], "operator_class_icon"),
+    (&["top_left_rhodes"], "top_left_rhodes"),
+    (&["top_right_bar_text"], "top_right_bar_text"),
+];
+
+/// Keys copied over unchanged
+const PASSTHROUGH_KEYS: &[&str] = &["uuid", "name", "description", "icon", "screen"];
+
+/// Other keys the converter understands
+const LEGACY_KEYS: &[&str] = &[
+    "version",
+    "video",
+    "loop_video",
+    "loop",
+    "is_image",
+    "intro",
+    "intro_video",
+    "intro_duration",
+    "transition",
+    "transition_in",
+    "transition_loop",
+    "transition_duration",
+    "overlay",
+    "overlay_image",
+    "appear_time",
+    "overlay_duration",
+];
+
+/// Check whether a config uses the legacy flat layout
+fn is_legacy_config(value: &Value) -> bool {
+    let Some(object) = value.as_object() else {
+        return false;
+    };
+    let has_loop_object = object.get("loop").is_some_and(Value::is_object);
+    let has_legacy_video = ["video", "loop_video", "loop"]
+        .iter()
+        .any(|key| object.get(*key).is_some_and(Value::is_string));
+    !has_loop_object && has_legacy_video
+}
+
+/// First string value among `keys`
+fn string_field<'a>(object: &'a Map<String, Value>, keys: &[&str]) -> Option<&'a str> {
+    keys.iter().find_map(|key| object.get(*key).and_then(Value::as_str))
+}
+
+fn transition(kind: Option<&str>, duration: Option<&Value>) -> Value {
+    let mut options = json!({});
+    if let Some(duration) = duration {
+        options["duration"] = duration.clone();
+    }
+    json!({"type": kind.unwrap_or("fade"), "options": options})
+}
+
+impl EPConfig {
+    /// Convert a legacy flat config to the current layout
+    ///
+    /// Keys the converter does not know are dropped with a warning.
+    pub fn from_legacy(value: &Value) -> Result<Self> {
+        let Some(object) = value.as_object() else {
+            bail!("Legacy config must be a JSON object");
+        };
+        if !is_legacy_config(value) {
+            bail!("Not a legacy config (no plain video/loop_video entry)");
+        }
+
+        let mut config = Map::new();
+        for key in PASSTHROUGH_KEYS {
+            if let Some(v) = object.get(*key) {
+                config.insert(key.to_string(), v.clone());
+            }
+        }
+        config.insert("version".to_string(), json!(1));
+
+        let loop_file = string_field(object, &["video", "loop_video", "loop"]).unwrap_or_default();
+        let mut loop_config = json!({"file": loop_file});
+        if let Some(is_image) = object.get("is_image") {
+            loop_config["is_image"] = is_image.clone();
+        }
+        config.insert("loop".to_string(), loop_config);
+
+        if let Some(intro) = string_field(object, &["intro", "intro_video"]).filter(|f| !f.is_empty()) {
+            let mut intro = json!({"enabled": true, "file": intro});
+            if let Some(duration) = object.get("intro_duration") {
+                intro["duration"] = duration.clone();
+            }
+            config.insert("intro".to_string(), intro);
+        }
+
+        // The firmware faded both transitions unless told otherwise
+        let duration = object.get("transition_duration");
+        config.insert(
+            "transition_in".to_string(),
+            transition(string_field(object, &["transition_in", "transition"]), duration),
+        );
+        config.insert(
+            "transition_loop".to_string(),
+            transition(string_field(object, &["transition_loop"]), duration),
+        );
+
+        let mut arknights = Map::new();
+        for (legacy_keys, key) in ARKNIGHTS_KEYS {
+            if let Some(v) = legacy_keys.iter().find_map(|k| object.get(*k)) {
+                arknights.insert(key.to_string(), v.clone());
+            }
+        }
+        let overlay_image = string_field(object, &["overlay_image", "overlay"]).filter(|f| !f.is_empty());
+        let appear_time = object.get("appear_time");
+        let overlay = if !arknights.is_empty() {
+            if let Some(t) = appear_time {
+                arknights.insert("appear_time".to_string(), t.clone());
+            }
+            Some(json!({"type": "arknights", "options": arknights}))
+        } else if let Some(image) = overlay_image {
+            let mut options = json!({"image": image});
+            if let Some(t) = appear_time {
+                options["appear_time"] = t.clone();
+            }
+            if let Some(d) = object.get("overlay_duration") {
+                options["duration"] = d.clone();
+            }
+            Some(json!({"type": "image", "options": options}))
+        } else {
+            None
+        };
+        if let Some(overlay) = overlay {
+            config.insert("overlay".to_string(), overlay);
+        }
+
+        for key in object.keys() {
+            let known = PASSTHROUGH_KEYS.contains(&key.as_str())
+                || LEGACY_KEYS.contains(&key.as_str())
+                || ARKNIGHTS_KEYS.iter().any(|(keys, _)| keys.contains(&key.as_str()));
+            if !known {
+                warn!("Legacy config key '{}' has no equivalent and was dropped", key);
+            }
+        }
+
+        Ok(serde_json::from_value(Value::Object(config))?)
+    }
+}
+
+/// Legacy config at `old` (the file, or the first legacy config in the folder)
+/// and the folder its assets resolve against
+fn find_legacy_config(old: &Path) -> Result<(Value, PathBuf)> {
+    let candidates: Vec<PathBuf> = if old.is_dir() {
+        LEGACY_CONFIG_FILES.iter().map(|name| old.join(name)).collect()
+    } else {
+        vec![old.to_path_buf()]
+    };
+    for path in candidates.iter().filter(|p| p.is_file()) {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let value: Value = serde_json::from_str(&text).with_context(|| format!("Invalid JSON in {}", path.display()))?;
+        if is_legacy_config(&value) {
+            let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."));
+            return Ok((value, base_dir));
+        }
+    }
+    bail!("No legacy config found in {}", old.display())
+}
+
+/// Replace an overlay image covering the whole screen with the Arknights overlay
+///
+/// Old materials often drew the pass UI into a full-screen image; the
+/// Arknights overlay draws the same UI and animates it like the device.
+fn pass_image_to_arknights(config: &mut EPConfig, base_dir: &Path) {
+    let Some(overlay) = config.overlay.as_mut() else {
+        return;
+    };
+    let entries = overlay.image_entries();
+    let [entry] = entries.as_slice() else {
+        return;
+    };
+    let path = resolve_asset_path(&entry.image, base_dir);
+    let Ok(size) = image::image_dimensions(&path) else {
+        return;
+    };
+    if size != config.screen.dimensions() {
+        return;
+    }
+    let options = ArknightsOverlayOptions { appear_time: entry.appear_time, ..Default::default() };
+    info!("{} covers the screen, using the Arknights overlay instead", entry.image);
+    *overlay = Overlay {
+        overlay_type: OverlayType::Arknights,
+        options: serde_json::to_value(options).ok(),
+        z_index: overlay.z_index,
+    };
+}
+
+/// Let an intro without a legacy duration play to its end, as the firmware did
+fn intro_duration_from_video(config: &mut EPConfig, base_dir: &Path) {
+    let Some(intro) = config.intro.as_mut() else {
+        return;
+    };
+    let path = resolve_asset_path(&intro.file, base_dir);
+    match probe_video(&path) {
+        Ok(info) => match info.duration_secs {
+            Some(secs) => intro.duration = (secs * 1_000_000.0).round() as i64,
+            None => warn!("{} has no recorded length, keeping the default intro duration", intro.file),
+        },
+        Err(e) => warn!("Could not probe intro {}: {:#}", intro.file, e),
+    }
+}
+
+/// Convert a legacy material (folder or config file) into a current one in `out_dir`
+///
+/// Applies the rules of `EPConfig::from_legacy` plus those that need the
+/// assets: a full-screen overlay image becomes the Arknights overlay and an
+/// intro without a duration gets its video's length. Assets are copied with
+/// the same layout as in a package. Returns the written `epconfig.json`.
+pub fn convert_legacy_material(old: &Path, out_dir: &Path) -> Result<PathBuf> {
+    let (legacy, base_dir) = find_legacy_config(old)?;
+    let mut config = EPConfig::from_legacy(&legacy)?;
+    pass_image_to_arknights(&mut config, &base_dir);
+    if legacy.get("intro_duration").is_none() {
+        intro_duration_from_video(&mut config, &base_dir);
+    }
+    config.export_dir(&base_dir, out_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{OverlayType, TransitionType};
+
+    #[test]
+    fn test_from_legacy_arknights() {
+        let legacy = json!({
+            "name": "old",
+            "video": "loop.mp4",
+            "intro": "intro.mp4",
+            "opname": "AMIYA",
+            "opcode": "R001",
+            "class": "caster",
+            "color": "#112233"
+        });
+        assert!(is_legacy_config(&legacy));
+
+        let config = EPConfig::from_legacy(&legacy).unwrap();
+        assert_eq!(config.name, "old");
+        assert_eq!(config.loop_config.file, "loop.mp4");
+        assert!(config.intro.as_ref().unwrap().enabled);
+        assert_eq!(config.get_transition_in_type(), TransitionType::Fade);
+        assert_eq!(config.get_transition_loop_type(), TransitionType::Fade);
+
+        let options = config.arknights_options().unwrap();
+        assert_eq!(options.operator_name, "AMIYA");
+        assert_eq!(options.operator_code, "R001");
+        assert_eq!(options.operator_class, "caster");
+    }
+
+    #[test]
+    fn test_from_legacy_image_overlay() {
+        let legacy = json!({"loop_video": "loop.mp4", "overlay": "pass.png", "transition": "swipe"});
+        let config = EPConfig::from_legacy(&legacy).unwrap();
+        let overlay = config.overlay.as_ref().unwrap();
+        assert_eq!(overlay.overlay_type, OverlayType::Image);
+        assert_eq!(overlay.image_entries()[0].image, "pass.png");
+        assert_eq!(config.get_transition_in_type(), TransitionType::Swipe);
+    }
+
+    #[test]
+    fn test_convert_legacy_material() {
+        let dir = std::env::temp_dir().join(format!("legacy_material_{}", std::process::id()));
+        let old = dir.join("old");
+        std::fs::create_dir_all(&old).unwrap();
+        std::fs::write(old.join("loop.mp4"), b"video").unwrap();
+        image::RgbaImage::new(360, 640).save(old.join("pass.png")).unwrap();
+        image::RgbaImage::new(100, 50).save(old.join("stamp.png")).unwrap();
+
+        let legacy = json!({"video": "loop.mp4", "overlay": "pass.png", "appear_time": 500000});
+        std::fs::write(old.join("config.json"), legacy.to_string()).unwrap();
+        let path = convert_legacy_material(&old, &dir.join("pass")).unwrap();
+        let config = EPConfig::load_from_file(&path).unwrap();
+        assert_eq!(config.arknights_options().unwrap().appear_time, 500000);
+        assert!(dir.join("pass/loop.mp4").is_file());
+        assert!(!dir.join("pass/pass.png").exists());
+
+        let legacy = json!({"video": "loop.mp4", "overlay": "stamp.png"});
+        std::fs::write(old.join("config.json"), legacy.to_string()).unwrap();
+        let path = convert_legacy_material(&old, &dir.join("stamp")).unwrap();
+        let config = EPConfig::load_from_file(&path).unwrap();
+        assert_eq!(config.overlay.unwrap().image_entries()[0].image, "stamp.png");
+        assert!(dir.join("stamp/stamp.png").is_file());
+
+        std::fs::remove_dir_all(&dir).ok();
+        assert!(convert_legacy_material(&old, &dir).is_err());
+    }
+
+    #[test]
+    fn test_current_config_is_not_legacy() {
+        let current = json!({"loop": {"file": "loop.mp4"}});
+        assert!(!is_legacy_config(&current));
+        assert!(EPConfig::from_legacy(&current).is_err());
+    }
+}
@@ -0,0 +1,19 @@
+//! Configuration module
+//!
+//! Contains data structures for EPConfig, FirmwareConfig and overlay templates.
+
+mod epconfig;
+mod firmware_config;
+mod legacy;
+mod overlay_template;
+mod package;
+mod template;
+mod validation;
+
+pub use epconfig::*;
+pub use firmware_config::*;
+pub use legacy::convert_legacy_material;
+pub use overlay_template::*;
+pub use package::*;
+pub use template::*;
+pub use validation::*;
@@ -0,0 +1,138 @@
+//! Overlay templates
+//!
+//! Reusable custom overlay definitions loaded from JSON files in
+//! `app_dir/resources/templates`, so new pass styles can ship as data.
+//! A config selects one by name via `CustomOverlayOptions::template`.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+use super::epconfig::OverlayLayer;
+
+/// A named overlay template (element list + animation timings)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct OverlayTemplate {
+    /// Template name used for selection (defaults to the file name)
+    #[serde(default)]
+    pub name: String,
+
+    #[serde(default)]
+    pub description: String,
+
+    /// Default values for template-specific `{var}` placeholders
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+
+    #[serde(default)]
+    pub layers: Vec<OverlayLayer>,
+}
+
+impl OverlayTemplate {
+    /// Load a template from a JSON file
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let mut template: OverlayTemplate = serde_json::from_str(&content)?;
+        if template.name.is_empty() {
+            template.name = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+        Ok(template)
+    }
+}
+
+/// Templates discovered in a directory
+#[derive(Debug, Clone, Default)]
+pub struct OverlayTemplateRegistry {
+    templates: Vec<OverlayTemplate>,
+}
+
+impl OverlayTemplateRegistry {
+    /// Load all `*.json` templates in `dir` (missing dir = empty registry)
+    ///
+    /// Invalid files are skipped with a warning; on duplicate names the
+    /// first file in name order wins.
+    pub fn load_from_dir(dir: &Path) -> Self {
+        let mut registry = Self::default();
+
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return registry;
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            match OverlayTemplate::load_from_file(&path) {
+                Ok(template) if registry.get(&template.name).is_some() => {
+                    warn!("Duplicate overlay template '{}' in {}", template.name, path.display());
+                }
+                Ok(template) => registry.templates.push(template),
+                Err(e) => warn!("Failed to load overlay template {}: {}", path.display(), e),
+            }
+        }
+
+        info!("Loaded {} overlay template(s) from {}", registry.templates.len(), dir.display());
+        registry
+    }
+
+    /// Build a registry from already loaded templates
+    #[cfg(test)]
+    pub fn from_templates(templates: Vec<OverlayTemplate>) -> Self {
+        Self { templates }
+    }
+
+    /// Find a template by name
+    pub fn get(&self, name: &str) -> Option<&OverlayTemplate> {
+        self.templates.iter().find(|t| t.name == name)
+    }
+
+    /// Names of all available templates
+    pub fn names(&self) -> Vec<&str> {
+        self.templates.iter().map(|t| t.name.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_templates_from_dir() {
+        let dir = std::env::temp_dir().join(format!("ep_templates_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("minimal.json"),
+            r#"{"layers": [{"type": "text", "text": "{name}"}]}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let registry = OverlayTemplateRegistry::load_from_dir(&dir);
+        assert_eq!(registry.names(), vec!["minimal"]);
+        assert_eq!(registry.get("minimal").unwrap().layers.len(), 1);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_missing_dir_is_empty() {
+        let registry = OverlayTemplateRegistry::load_from_dir(Path::new("/nonexistent/templates"));
+        assert!(registry.names().is_empty());
+    }
+
+    #[test]
+    fn test_bundled_template_parses() {
+        let template: OverlayTemplate =
+            serde_json::from_str(include_str!("../../../../resources/templates/minimal.json")).unwrap();
+        assert_eq!(template.name, "minimal");
+        assert!(!template.layers.is_empty());
+    }
+}
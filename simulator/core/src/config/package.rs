@@ -0,0 +1,376 @@
+//! Material packages (.eppkg)
+//!
+//! A package is a zip archive with `epconfig.json` and the assets it
+//! references, either at the root or inside a single top-level folder.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{Cursor, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tracing::info;
+
+use crate::utils::{mount_files, resolve_asset_path};
+
+use super::epconfig::EPConfig;
+
+/// File extension of material packages
+pub const PACKAGE_EXTENSION: &str = "eppkg";
+
+/// Config file name inside a package
+pub const CONFIG_FILE_NAME: &str = "epconfig.json";
+
+/// Where packages loaded from memory are mounted
+const MOUNT_ROOT: &str = "/eppkg";
+
+/// Assets a config references that do not exist, as `field (path)` entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingAssets(pub Vec<String>);
+
+impl fmt::Display for MissingAssets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Missing assets: {}", self.0.join(", "))
+    }
+}
+
+impl std::error::Error for MissingAssets {}
+
+/// Check whether a path looks like a material package
+pub fn is_package(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(PACKAGE_EXTENSION))
+}
+
+/// Extract a package into `dest`, returning the directory holding `epconfig.json`
+///
+/// Entries with unsafe paths (absolute or escaping `dest`) are rejected.
+pub fn extract_package(path: &Path, dest: &Path) -> Result<PathBuf> {
+    let file = File::open(path).with_context(|| format!("Failed to open package {}", path.display()))?;
+    let mut archive = zip::ZipArchive::new(file).with_context(|| format!("Not a valid package: {}", path.display()))?;
+    archive.extract(dest).context("Failed to extract package")?;
+
+    if dest.join(CONFIG_FILE_NAME).is_file() {
+        return Ok(dest.to_path_buf());
+    }
+    // Archives made by zipping a folder put everything below it
+    let mut dirs = std::fs::read_dir(dest)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|p| p.is_dir());
+    match (dirs.next(), dirs.next()) {
+        (Some(dir), None) if dir.join(CONFIG_FILE_NAME).is_file() => Ok(dir),
+        _ => Err(anyhow!("Package has no {}", CONFIG_FILE_NAME)),
+    }
+}
+
+/// Temp directory a package is extracted to
+///
+/// Stable per package path, so reopening a package replaces the previous
+/// extraction instead of piling up copies.
+fn extract_dir_for(path: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf()).hash(&mut hasher);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("package");
+    std::env::temp_dir()
+        .join("arknights_pass_simulator")
+        .join(format!("{}-{:016x}", stem, hasher.finish()))
+}
+
+/// Name of an asset inside a package
+///
+/// Assets inside the material folder keep their layout, wherever a `${VAR}`
+/// in their path pointed; anything else goes to `assets/`, numbered if the
+/// name is already taken.
+fn archive_name(resolved: &Path, base_dir: &Path, taken: &HashMap<String, PathBuf>) -> String {
+    let relative = resolved.strip_prefix(base_dir).ok().filter(|relative| {
+        !relative.as_os_str().is_empty() && relative.components().all(|c| matches!(c, Component::Normal(_)))
+    });
+    if let Some(relative) = relative {
+        let name = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if taken.get(&name).is_none_or(|source| source == resolved) {
+            return name;
+        }
+    }
+
+    let stem = resolved.file_stem().and_then(|s| s.to_str()).unwrap_or("asset");
+    let ext = resolved
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|e| format!(".{}", e))
+        .unwrap_or_default();
+    let mut name = format!("assets/{}{}", stem, ext);
+    let mut n = 1;
+    while taken.get(&name).is_some_and(|source| source != resolved) {
+        name = format!("assets/{}_{}{}", stem, n, ext);
+        n += 1;
+    }
+    name
+}
+
+impl EPConfig {
+    /// This config with asset paths rewritten relative to a package root,
+    /// and the files to put under each new path
+    ///
+    /// Fails if an asset is missing.
+    fn bundle_assets(&self, base_dir: &Path) -> Result<(Self, HashMap<String, PathBuf>)> {
+        let mut config = self.clone();
+        let mut assets: HashMap<String, PathBuf> = HashMap::new();
+        let mut missing = Vec::new();
+        config.for_each_asset_path(|field, asset| {
+            let resolved = resolve_asset_path(asset, base_dir);
+            if !resolved.is_file() {
+                missing.push(format!("{} ({})", field, resolved.display()));
+                return;
+            }
+            let name = archive_name(&resolved, base_dir, &assets);
+            assets.insert(name.clone(), resolved);
+            *asset = name;
+        });
+        if !missing.is_empty() {
+            return Err(MissingAssets(missing).into());
+        }
+        Ok((config, assets))
+    }
+
+    /// Write the config and every asset it references into a package
+    ///
+    /// Asset paths are resolved against `base_dir` and rewritten relative to
+    /// the package root. Fails without writing anything if an asset is missing.
+    pub fn export_package<P: AsRef<Path>>(&self, base_dir: &Path, path: P) -> Result<()> {
+        let (config, assets) = self.bundle_assets(base_dir)?;
+        let path = path.as_ref();
+        let file = File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut writer = zip::ZipWriter::new(file);
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file(CONFIG_FILE_NAME, options)?;
+        writer.write_all(config.to_json_pretty()?.as_bytes())?;
+
+        let mut names: Vec<_> = assets.keys().cloned().collect();
+        names.sort();
+        for name in names {
+            let source = &assets[&name];
+            writer.start_file(name.as_str(), options)?;
+            let mut input = File::open(source).with_context(|| format!("Failed to read {}", source.display()))?;
+            std::io::copy(&mut input, &mut writer)?;
+        }
+        writer.finish()?;
+
+        info!("Exported package {} ({} assets)", path.display(), assets.len());
+        Ok(())
+    }
+
+    /// Write the config and copies of its assets into `dir`, laid out like a package
+    ///
+    /// Returns the path of the written `epconfig.json`.
+    pub fn export_dir(&self, base_dir: &Path, dir: &Path) -> Result<PathBuf> {
+        let (config, assets) = self.bundle_assets(base_dir)?;
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        for (name, source) in &assets {
+            let dest = dir.join(name);
+            // Exporting over the source folder leaves its assets in place
+            if dest.is_file() && dest.canonicalize()? == source.canonicalize()? {
+                continue;
+            }
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(source, &dest).with_context(|| format!("Failed to copy {}", source.display()))?;
+        }
+        let path = dir.join(CONFIG_FILE_NAME);
+        config.save_to_file(&path)?;
+        info!("Exported {} ({} assets)", path.display(), assets.len());
+        Ok(path)
+    }
+
+    /// Load an epconfig.json or a package
+    ///
+    /// Returns the config and the base directory its asset paths resolve
+    /// against: the config's directory, or where the package was extracted.
+    pub fn load_with_base_dir(path: &Path) -> Result<(Self, PathBuf)> {
+        if is_package(path) {
+            return Self::load_package(path);
+        }
+        let config = Self::load_from_file(path).with_context(|| format!("Failed to load {}", path.display()))?;
+        let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."));
+        Ok((config, base_dir))
+    }
+
+    /// Extract a package to a temp dir and load its config
+    ///
+    /// Returns the config and the base directory its asset paths resolve against.
+    pub fn load_package<P: AsRef<Path>>(path: P) -> Result<(Self, PathBuf)> {
+        let path = path.as_ref();
+        let dest = extract_dir_for(path);
+        if dest.exists() {
+            std::fs::remove_dir_all(&dest)
+                .with_context(|| format!("Failed to clear {}", dest.display()))?;
+        }
+        std::fs::create_dir_all(&dest)?;
+
+        let base_dir = extract_package(path, &dest)?;
+        info!("Extracted package {} to {}", path.display(), base_dir.display());
+        let config = Self::load_from_file(base_dir.join(CONFIG_FILE_NAME))?;
+        Ok((config, base_dir))
+    }
+
+    /// Load a package from memory, serving its files with `mount_files`
+    ///
+    /// For builds without a filesystem (the web preview). Returns the config
+    /// and the base directory its asset paths resolve against.
+    pub fn load_package_bytes(data: &[u8]) -> Result<(Self, PathBuf)> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(data)).context("Not a valid package")?;
+        let root = Path::new(MOUNT_ROOT);
+        let mut files = HashMap::new();
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index)?;
+            if entry.is_dir() {
+                continue;
+            }
+            let name = entry
+                .enclosed_name()
+                .ok_or_else(|| anyhow!("Unsafe path in package: {}", entry.name()))?;
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            files.insert(root.join(name), content);
+        }
+
+        // Archives made by zipping a folder put everything below it
+        let mut dirs: Vec<PathBuf> = files
+            .keys()
+            .filter_map(|path| {
+                let mut components = path.strip_prefix(root).ok()?.components();
+                let first = components.next()?;
+                components.next().map(|_| root.join(first))
+            })
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+        let base_dir = match dirs.as_slice() {
+            _ if files.contains_key(&root.join(CONFIG_FILE_NAME)) => root.to_path_buf(),
+            [dir] if files.contains_key(&dir.join(CONFIG_FILE_NAME)) => dir.clone(),
+            _ => bail!("Package has no {}", CONFIG_FILE_NAME),
+        };
+        let config: EPConfig = serde_json::from_slice(&files[&base_dir.join(CONFIG_FILE_NAME)])?;
+        info!("Loaded package from memory ({} files)", files.len());
+        mount_files(files);
+        Ok((config, base_dir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_package(path: &Path, prefix: &str) {
+        let mut writer = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file(format!("{}{}", prefix, CONFIG_FILE_NAME), options).unwrap();
+        writer.write_all(br#"{"name": "packaged", "loop": {"file": "loop.mp4"}}"#).unwrap();
+        writer.start_file(format!("{}loop.mp4", prefix), options).unwrap();
+        writer.write_all(b"video").unwrap();
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_load_package() {
+        for prefix in ["", "material/"] {
+            let path = std::env::temp_dir().join(format!("epconfig_pkg_{}.eppkg", std::process::id()));
+            write_package(&path, prefix);
+
+            let (config, base_dir) = EPConfig::load_package(&path).unwrap();
+            std::fs::remove_file(&path).ok();
+
+            assert_eq!(config.name, "packaged");
+            assert!(base_dir.join(&config.loop_config.file).is_file());
+        }
+    }
+
+    #[test]
+    fn test_load_package_bytes() {
+        let path = std::env::temp_dir().join(format!("epconfig_pkg_bytes_{}.eppkg", std::process::id()));
+        write_package(&path, "material/");
+        let data = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let (config, base_dir) = EPConfig::load_package_bytes(&data).unwrap();
+        assert_eq!(config.name, "packaged");
+        let video = resolve_asset_path(&config.loop_config.file, &base_dir);
+        assert_eq!(crate::utils::read_file(&video).unwrap(), b"video");
+        assert!(crate::utils::file_exists(&video));
+        assert!(EPConfig::load_package_bytes(b"not a zip").is_err());
+    }
+
+    #[test]
+    fn test_export_package() {
+        let dir = std::env::temp_dir().join(format!("epconfig_export_{}", std::process::id()));
+        let external = dir.join("external");
+        std::fs::create_dir_all(dir.join("material/videos")).unwrap();
+        std::fs::create_dir_all(&external).unwrap();
+        std::fs::write(dir.join("material/videos/loop.mp4"), b"video").unwrap();
+        std::fs::write(external.join("logo.png"), b"png").unwrap();
+
+        let json = format!(
+            r#"{{
+                "loop": {{"file": "videos/loop.mp4"}},
+                "overlay": {{"type": "arknights", "options": {{"logo": "{}"}}}}
+            }}"#,
+            external.join("logo.png").display().to_string().replace('\\', "/")
+        );
+        let config: EPConfig = serde_json::from_str(&json).unwrap();
+        let package = dir.join("material.eppkg");
+        config.export_package(&dir.join("material"), &package).unwrap();
+
+        let (exported, base_dir) = EPConfig::load_package(&package).unwrap();
+        assert_eq!(exported.loop_config.file, "videos/loop.mp4");
+        let logo = exported.arknights_options().unwrap().logo;
+        assert_eq!(logo, "assets/logo.png");
+        assert!(base_dir.join(logo).is_file());
+
+        let out = dir.join("exported");
+        let path = config.export_dir(&dir.join("material"), &out).unwrap();
+        let exported = EPConfig::load_from_file(&path).unwrap();
+        assert_eq!(exported.arknights_options().unwrap().logo, "assets/logo.png");
+        assert_eq!(std::fs::read(out.join("videos/loop.mp4")).unwrap(), b"video");
+
+        std::fs::remove_file(dir.join("material/videos/loop.mp4")).unwrap();
+        assert!(config.export_package(&dir.join("material"), &package).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_bundle_assets_expands_variables() {
+        let dir = std::env::temp_dir().join(format!("epconfig_bundle_vars_{}", std::process::id()));
+        let shared = dir.join("shared");
+        std::fs::create_dir_all(dir.join("material")).unwrap();
+        std::fs::create_dir_all(&shared).unwrap();
+        std::fs::write(dir.join("material/loop.mp4"), b"video").unwrap();
+        std::fs::write(shared.join("logo.png"), b"png").unwrap();
+        std::env::set_var("EPASS_TEST_BUNDLE_SHARED", &shared);
+
+        let json = r#"{
+            "loop": {"file": "${BASE_DIR}/loop.mp4"},
+            "overlay": {"type": "arknights", "options": {"logo": "${EPASS_TEST_BUNDLE_SHARED}/logo.png"}}
+        }"#;
+        let config: EPConfig = serde_json::from_str(json).unwrap();
+        let (bundled, assets) = config.bundle_assets(&dir.join("material")).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        // No path in the package points back at the author's machine
+        assert_eq!(bundled.loop_config.file, "loop.mp4");
+        assert_eq!(bundled.arknights_options().unwrap().logo, "assets/logo.png");
+        assert_eq!(assets["assets/logo.png"], shared.join("logo.png"));
+    }
+
+    #[test]
+    fn test_is_package() {
+        assert!(is_package(Path::new("material.EPPKG")));
+        assert!(!is_package(Path::new("epconfig.json")));
+    }
+}
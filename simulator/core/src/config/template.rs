@@ -0,0 +1,126 @@
+//! Starter material template
+//!
+//! Writes a default `epconfig.json` with placeholder assets so new creators
+//! have a working material to edit. JSON has no comments, so the config
+//! points at `epconfig.schema.json` through `$schema`; editors show the
+//! schema's field descriptions on hover.
+
+use anyhow::{bail, Context, Result};
+use image::{Rgb, RgbImage};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use super::epconfig::{ArknightsOverlayOptions, EPConfig};
+use super::package::CONFIG_FILE_NAME;
+
+/// Schema file written next to the template config
+pub const SCHEMA_FILE_NAME: &str = "epconfig.schema.json";
+
+/// Placeholder loop image
+const LOOP_FILE_NAME: &str = "loop.png";
+
+/// Placeholder material icon
+const ICON_FILE_NAME: &str = "icon.png";
+
+/// Build the template config
+fn template_config() -> Result<EPConfig> {
+    let transition = json!({"type": "fade", "options": {"duration": 500000}});
+    let config = json!({
+        "name": "New Material",
+        "description": "Replace loop.png with your loop video and edit the overlay texts",
+        "icon": ICON_FILE_NAME,
+        "screen": "360x640",
+        "loop": {"file": LOOP_FILE_NAME, "is_image": true},
+        "intro": {"enabled": false, "file": "", "duration": 5000000},
+        "transition_in": transition,
+        "transition_loop": transition,
+        "overlay": {
+            "type": "arknights",
+            "options": serde_json::to_value(ArknightsOverlayOptions::default())?
+        }
+    });
+    Ok(serde_json::from_value(config)?)
+}
+
+/// A minimal config playing `video` (relative to the base directory) as
+/// its loop, with the template's overlay
+pub fn config_for_video(video: &str) -> Result<EPConfig> {
+    let mut config = template_config()?;
+    config.name = Path::new(video)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    config.description = String::new();
+    config.icon = String::new();
+    config.loop_config.file = video.to_string();
+    config.loop_config.is_image = false;
+    Ok(config)
+}
+
+/// Dark placeholder with a lighter frame, so its bounds show in the preview
+fn placeholder_image(width: u32, height: u32) -> RgbImage {
+    let border = (width.min(height) / 32).max(1);
+    RgbImage::from_fn(width, height, |x, y| {
+        let edge = x < border || y < border || x >= width - border || y >= height - border;
+        if edge { Rgb([96, 96, 96]) } else { Rgb([32, 32, 32]) }
+    })
+}
+
+/// Write the template material into `dir`, returning the config path
+///
+/// Refuses to overwrite an existing `epconfig.json`.
+pub fn write_template(dir: &Path) -> Result<PathBuf> {
+    let config_path = dir.join(CONFIG_FILE_NAME);
+    if config_path.exists() {
+        bail!("{} already exists", config_path.display());
+    }
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let config = template_config()?;
+    let (width, height) = config.screen.dimensions();
+    placeholder_image(width, height).save(dir.join(LOOP_FILE_NAME))?;
+    placeholder_image(128, 128).save(dir.join(ICON_FILE_NAME))?;
+
+    let schema = serde_json::to_string_pretty(&EPConfig::schema_json())?;
+    std::fs::write(dir.join(SCHEMA_FILE_NAME), schema)?;
+
+    // `$schema` goes first so editors pick it up; EPConfig ignores it when loading
+    let json = config.to_json_pretty()?;
+    let json = format!("{{\n    \"$schema\": \"{}\",{}", SCHEMA_FILE_NAME, &json[1..]);
+    std::fs::write(&config_path, json)?;
+
+    info!("Wrote template material to {}", dir.display());
+    Ok(config_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OverlayType;
+
+    #[test]
+    fn test_write_template() {
+        let dir = std::env::temp_dir().join(format!("epconfig_template_{}", std::process::id()));
+        let config_path = write_template(&dir).unwrap();
+
+        let config = EPConfig::load_from_file(&config_path).unwrap();
+        assert!(config.loop_config.is_image);
+        assert!(dir.join(&config.loop_config.file).is_file());
+        assert!(dir.join(&config.icon).is_file());
+        assert_eq!(config.overlay.as_ref().unwrap().overlay_type, OverlayType::Arknights);
+        assert!(config.validate(&dir).is_empty());
+
+        assert!(write_template(&dir).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_config_for_video() {
+        let config = config_for_video("clip.mp4").unwrap();
+        assert_eq!(config.name, "clip");
+        assert_eq!(config.loop_config.file, "clip.mp4");
+        assert!(!config.loop_config.is_image);
+        assert!(config.overlay.is_some());
+    }
+}
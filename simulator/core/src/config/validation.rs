@@ -0,0 +1,491 @@
+//! Config validation
+//!
+//! Checks an EPConfig for problems that would otherwise make it silently
+//! half-work (missing files, bad colors, negative durations, unknown option
+//! keys...) and reports them as a list of diagnostics.
+//!
+//! Unknown option keys are dropped by serde, so a typo like `operater_name`
+//! otherwise just loses the value. They are warnings by default; strict
+//! validation makes them errors. Either way the closest known key is suggested.
+
+use serde::de::{self, DeserializeOwned, Deserializer, Visitor};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+use crate::utils::{file_exists, parse_color, resolve_asset_path};
+
+use super::epconfig::{
+    ArknightsOverlayOptions, CustomOverlayOptions, EPConfig, ImageOverlayOptions, LayerContent, Overlay,
+    OverlayType, Transition,
+};
+
+/// Diagnostic severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Works, but probably not as intended
+    Warning,
+    /// Part of the config cannot work
+    Error,
+}
+
+/// A single validation finding
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Location in the config, e.g. `overlay.options.color`
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "[{}] {}: {}", severity, self.path, self.message)
+    }
+}
+
+/// Check a cropbox (x, y, w, h) against the rotated source video size
+pub fn validate_cropbox(cropbox: (u32, u32, u32, u32), source: (u32, u32)) -> Option<Diagnostic> {
+    let (x, y, w, h) = cropbox;
+    let message = if w == 0 || h == 0 {
+        format!("cropbox {}x{} is empty", w, h)
+    } else if x.saturating_add(w) > source.0 || y.saturating_add(h) > source.1 {
+        format!(
+            "cropbox ({}, {}, {}x{}) exceeds the {}x{} video",
+            x, y, w, h, source.0, source.1
+        )
+    } else {
+        return None;
+    };
+    Some(Diagnostic { severity: Severity::Error, path: "cropbox".to_string(), message })
+}
+
+/// Edit distance between two keys
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Known key closest to `key`, if it is close enough to be a typo
+fn suggest_key<'a>(key: &str, known: &[&'a str]) -> Option<&'a str> {
+    let max_distance = (key.chars().count() / 3).max(1);
+    known
+        .iter()
+        .map(|candidate| (edit_distance(key, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+impl EPConfig {
+    /// Check the config, resolving relative asset paths against `base_dir`
+    pub fn validate(&self, base_dir: &Path) -> Vec<Diagnostic> {
+        self.run_validation(base_dir, false)
+    }
+
+    /// Like `validate`, but unknown option keys are errors
+    pub fn validate_strict(&self, base_dir: &Path) -> Vec<Diagnostic> {
+        self.run_validation(base_dir, true)
+    }
+
+    fn run_validation(&self, base_dir: &Path, strict: bool) -> Vec<Diagnostic> {
+        let mut v = Validator { base_dir, strict, diagnostics: Vec::new() };
+
+        if !self.icon.is_empty() {
+            v.check_file("icon", &self.icon, Severity::Warning);
+        }
+
+        if self.loop_config.file.is_empty() {
+            v.push(Severity::Error, "loop.file", "no loop video configured");
+        } else {
+            v.check_file("loop.file", &self.loop_config.file, Severity::Error);
+        }
+
+        if let Some(ref intro) = self.intro {
+            v.check_non_negative("intro.duration", intro.duration);
+            if intro.enabled {
+                if intro.file.is_empty() {
+                    v.push(Severity::Error, "intro.file", "intro is enabled but has no file");
+                } else {
+                    v.check_file("intro.file", &intro.file, Severity::Error);
+                }
+            }
+        }
+
+        if let Some(ref preview) = self.preview {
+            if preview.speed <= 0.0 {
+                v.push(Severity::Error, "preview.speed", "must be positive");
+            }
+            v.check_non_negative("preview.replay_after", preview.replay_after);
+        }
+
+        v.check_transition("transition_in", self.transition_in.as_ref());
+        v.check_transition("transition_loop", self.transition_loop.as_ref());
+
+        let overlays = self
+            .overlay
+            .iter()
+            .map(|o| ("overlay".to_string(), o))
+            .chain(self.overlays.iter().enumerate().map(|(i, o)| (format!("overlays[{}]", i), o)));
+        for (path, overlay) in overlays {
+            v.check_overlay(&path, overlay);
+        }
+
+        v.diagnostics
+    }
+}
+
+struct Validator<'a> {
+    base_dir: &'a Path,
+    /// Report unknown option keys as errors
+    strict: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator<'_> {
+    fn push(&mut self, severity: Severity, path: &str, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic { severity, path: path.to_string(), message: message.into() });
+    }
+
+    fn check_file(&mut self, path: &str, file: &str, severity: Severity) {
+        let resolved = resolve_asset_path(file, self.base_dir);
+        if !file_exists(&resolved) {
+            self.push(severity, path, format!("file not found: {}", resolved.display()));
+        }
+    }
+
+    fn check_color(&mut self, path: &str, color: &str) {
+        if parse_color(color).is_none() {
+            self.push(
+                Severity::Error,
+                path,
+                format!("unsupported color '{}' (expected #RRGGBB, #RGB, #RRGGBBAA, rgb(...) or a color name)", color),
+            );
+        }
+    }
+
+    /// Check an optional color (empty = use the default)
+    fn check_optional_color(&mut self, path: &str, color: &str) {
+        if !color.trim().is_empty() {
+            self.check_color(path, color);
+        }
+    }
+
+    fn check_non_negative(&mut self, path: &str, value: i64) {
+        if value < 0 {
+            self.push(Severity::Error, path, format!("must not be negative (got {})", value));
+        }
+    }
+
+    /// Report keys of an options object that `T` does not know
+    fn check_unknown_keys<T: DeserializeOwned>(&mut self, path: &str, options: &serde_json::Value) {
+        let Some(object) = options.as_object() else {
+            return;
+        };
+        let known = struct_fields::<T>();
+        let severity = if self.strict { Severity::Error } else { Severity::Warning };
+        for key in object.keys().filter(|k| !known.contains(&k.as_str())) {
+            let message = match suggest_key(key, known) {
+                Some(suggestion) => format!("unknown option (ignored), did you mean '{}'?", suggestion),
+                None => "unknown option (ignored)".to_string(),
+            };
+            self.push(severity, &format!("{}.{}", path, key), message);
+        }
+    }
+
+    /// Parse overlay options, reporting failures as errors
+    fn parse_options<T: DeserializeOwned>(&mut self, path: &str, options: &serde_json::Value) -> Option<T> {
+        match serde_json::from_value(options.clone()) {
+            Ok(parsed) => {
+                self.check_unknown_keys::<T>(path, options);
+                Some(parsed)
+            }
+            Err(e) => {
+                self.push(Severity::Error, path, format!("invalid options: {}", e));
+                None
+            }
+        }
+    }
+
+    fn check_transition(&mut self, path: &str, transition: Option<&Transition>) {
+        let Some(options) = transition.and_then(|t| t.options.as_ref()) else {
+            return;
+        };
+        self.check_non_negative(&format!("{}.options.duration", path), options.duration);
+        if options.has_image() {
+            self.check_file(&format!("{}.options.image", path), &options.image, Severity::Error);
+        }
+        self.check_color(&format!("{}.options.background_color", path), &options.background_color);
+        if options.has_image() && options.has_background_color() {
+            self.push(
+                Severity::Warning,
+                &format!("{}.options.background_color", path),
+                "the transition image takes precedence; this color only fills the margins around it",
+            );
+        }
+    }
+
+    fn check_overlay(&mut self, path: &str, overlay: &Overlay) {
+        let options_path = format!("{}.options", path);
+        let Some(ref options) = overlay.options else {
+            if overlay.overlay_type != OverlayType::None {
+                self.push(Severity::Warning, &options_path, "overlay has no options");
+            }
+            return;
+        };
+
+        match overlay.overlay_type {
+            OverlayType::Arknights => {
+                if let Some(parsed) = self.parse_options::<ArknightsOverlayOptions>(&options_path, options) {
+                    self.check_arknights(&options_path, &parsed);
+                }
+            }
+            OverlayType::Image => {
+                let entries: Vec<(String, &serde_json::Value)> = match options.as_array() {
+                    Some(list) => list
+                        .iter()
+                        .enumerate()
+                        .map(|(i, entry)| (format!("{}[{}]", options_path, i), entry))
+                        .collect(),
+                    None => vec![(options_path.clone(), options)],
+                };
+                for (entry_path, entry) in entries {
+                    if let Some(parsed) = self.parse_options::<ImageOverlayOptions>(&entry_path, entry) {
+                        self.check_image(&entry_path, &parsed);
+                    }
+                }
+            }
+            OverlayType::Custom => {
+                if let Some(parsed) = self.parse_options::<CustomOverlayOptions>(&options_path, options) {
+                    self.check_custom(&options_path, &parsed);
+                }
+            }
+            OverlayType::None => {}
+        }
+    }
+
+    fn check_arknights(&mut self, path: &str, options: &ArknightsOverlayOptions) {
+        self.check_non_negative(&format!("{}.appear_time", path), options.appear_time);
+        self.check_color(&format!("{}.color", path), &options.color);
+        for (field, color) in [
+            ("color2", &options.color2),
+            ("operator_name_color", &options.operator_name_color),
+            ("operator_code_color", &options.operator_code_color),
+            ("staff_text_color", &options.staff_text_color),
+            ("aux_text_color", &options.aux_text_color),
+        ] {
+            self.check_optional_color(&format!("{}.{}", path, field), color);
+        }
+
+        // These fall back to bundled images, so a missing file is only a warning
+        for (field, file) in [
+            ("logo", &options.logo),
+            ("ak_bar_image", &options.ak_bar_image),
+            ("operator_class_icon", &options.operator_class_icon),
+        ] {
+            if !file.is_empty() {
+                self.check_file(&format!("{}.{}", path, field), file, Severity::Warning);
+            }
+        }
+        if !options.operator_class.is_empty() && options.operator_class_key().is_none() {
+            self.push(
+                Severity::Warning,
+                &format!("{}.operator_class", path),
+                format!("unknown operator class '{}'", options.operator_class),
+            );
+        }
+        if let Some(ref barcode) = options.secondary_barcode {
+            if barcode.eink.frame_per_state == 0 {
+                self.push(
+                    Severity::Warning,
+                    &format!("{}.secondary_barcode.eink.frame_per_state", path),
+                    "must be at least 1",
+                );
+            }
+        }
+    }
+
+    fn check_image(&mut self, path: &str, options: &ImageOverlayOptions) {
+        if options.image.is_empty() {
+            self.push(Severity::Error, &format!("{}.image", path), "no image configured");
+        } else {
+            self.check_file(&format!("{}.image", path), &options.image, Severity::Error);
+        }
+        for (field, value) in [
+            ("appear_time", options.appear_time),
+            ("fade_in", options.fade_in),
+            ("fade_out", options.fade_out),
+        ] {
+            self.check_non_negative(&format!("{}.{}", path, field), value);
+        }
+        if options.scale <= 0.0 {
+            self.push(Severity::Error, &format!("{}.scale", path), "must be positive");
+        }
+    }
+
+    fn check_custom(&mut self, path: &str, options: &CustomOverlayOptions) {
+        for (i, layer) in options.layers.iter().enumerate() {
+            let layer_path = format!("{}.layers[{}]", path, i);
+            self.check_non_negative(&format!("{}.appear_time", layer_path), layer.appear_time);
+            self.check_non_negative(&format!("{}.duration", layer_path), layer.duration);
+            match &layer.content {
+                LayerContent::Image { image, .. } => {
+                    self.check_file(&format!("{}.image", layer_path), image, Severity::Error);
+                }
+                LayerContent::Text { color, .. }
+                | LayerContent::Barcode { color, .. }
+                | LayerContent::Rect { color, .. }
+                | LayerContent::Line { color, .. } => {
+                    self.check_color(&format!("{}.color", layer_path), color);
+                }
+            }
+        }
+    }
+}
+
+/// Field names a struct accepts when deserialized (serde renames applied)
+pub(crate) fn struct_fields<T: DeserializeOwned>() -> &'static [&'static str] {
+    struct FieldsDeserializer<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> Deserializer<'de> for FieldsDeserializer<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("fields captured"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldsDeserializer(&mut fields));
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_validate_reports_problems() {
+        let config: EPConfig = serde_json::from_str(
+            r##"{
+                "loop": {"file": "missing.mp4"},
+                "intro": {"enabled": true, "file": "", "duration": -1},
+                "transition_in": {"type": "fade", "options": {"background_color": "blackish"}},
+                "overlay": {"type": "arknights", "options": {"operater_name": "AMIYA", "color": "#12345"}},
+                "overlays": [{"type": "image", "options": [{"image": ""}]}]
+            }"##,
+        )
+        .unwrap();
+
+        let diagnostics = config.validate(Path::new("/nonexistent"));
+        let paths = paths(&diagnostics);
+        assert!(paths.contains(&"loop.file"));
+        assert!(paths.contains(&"intro.duration"));
+        assert!(paths.contains(&"intro.file"));
+        assert!(paths.contains(&"transition_in.options.background_color"));
+        assert!(paths.contains(&"overlay.options.operater_name"));
+        assert!(paths.contains(&"overlay.options.color"));
+        assert!(paths.contains(&"overlays[0].options[0].image"));
+    }
+
+    #[test]
+    fn test_unknown_keys_suggest_and_strict() {
+        let config: EPConfig = serde_json::from_str(
+            r#"{"overlay": {"type": "arknights", "options": {"operater_name": "AMIYA", "zzz": 1}}}"#,
+        )
+        .unwrap();
+        let find = |diagnostics: &[Diagnostic], key: &str| {
+            diagnostics
+                .iter()
+                .find(|d| d.path == format!("overlay.options.{}", key))
+                .cloned()
+                .unwrap()
+        };
+
+        let lenient = config.validate(Path::new("."));
+        let typo = find(&lenient, "operater_name");
+        assert_eq!(typo.severity, Severity::Warning);
+        assert!(typo.message.contains("did you mean 'operator_name'"));
+        assert!(!find(&lenient, "zzz").message.contains("did you mean"));
+
+        let strict = config.validate_strict(Path::new("."));
+        assert_eq!(find(&strict, "operater_name").severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_transition_image_and_color() {
+        let config: EPConfig = serde_json::from_str(
+            r##"{"transition_loop": {"type": "fade", "options": {"image": "t.png", "background_color": "#FF0000"}}}"##,
+        )
+        .unwrap();
+        let diagnostics = config.validate(Path::new("/nonexistent"));
+        let color = diagnostics
+            .iter()
+            .find(|d| d.path == "transition_loop.options.background_color")
+            .unwrap();
+        assert_eq!(color.severity, Severity::Warning);
+
+        let config: EPConfig = serde_json::from_str(
+            r##"{"transition_loop": {"type": "fade", "options": {"image": "t.png", "background_color": "black"}}}"##,
+        )
+        .unwrap();
+        let diagnostics = config.validate(Path::new("/nonexistent"));
+        assert!(!paths(&diagnostics).contains(&"transition_loop.options.background_color"));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("operater_name", "operator_name"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_struct_fields() {
+        let fields = struct_fields::<ImageOverlayOptions>();
+        assert!(fields.contains(&"appear_time"));
+        assert!(fields.contains(&"anchor"));
+    }
+
+    #[test]
+    fn test_validate_cropbox() {
+        assert!(validate_cropbox((0, 0, 360, 640), (360, 640)).is_none());
+        assert!(validate_cropbox((10, 0, 360, 640), (360, 640)).is_some());
+        assert!(validate_cropbox((0, 0, 0, 640), (360, 640)).is_some());
+    }
+}
@@ -0,0 +1,22 @@
+//! Arknights Electronic Pass Simulator core
+//!
+//! Everything the simulator does that needs no window: configs, the
+//! playback state machine, animations, compositing the preview with egui,
+//! rendering it in software and video decoding. The egui app is a frontend
+//! over this, and other tools (exporters, tests, editor bindings) can use
+//! it directly.
+
+pub mod animation;
+pub mod assets;
+pub mod clock;
+pub mod config;
+pub mod render;
+pub mod simulator;
+pub mod state;
+pub mod utils;
+pub mod validate;
+pub mod video;
+
+pub use render::HeadlessRenderer;
+pub use simulator::Simulator;
+pub use validate::ValidationReport;
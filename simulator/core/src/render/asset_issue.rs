@@ -0,0 +1,33 @@
+//! Asset load failures
+
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// An asset that failed to load
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetIssue {
+    /// What the asset is used for (e.g. `logo`, `loop video`)
+    pub asset: String,
+    /// Resolved file path
+    pub path: String,
+    /// Why loading failed
+    pub reason: String,
+}
+
+impl AssetIssue {
+    pub fn new(asset: &str, path: &Path, reason: impl fmt::Display) -> Self {
+        Self {
+            asset: asset.to_string(),
+            path: path.display().to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for AssetIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.asset, self.path, self.reason)
+    }
+}
@@ -0,0 +1,168 @@
+//! Rendering without a window
+//!
+//! Paints a simulator through an egui context that is never shown and
+//! rasterizes the output in software, so previews can be made from the
+//! command line, CI or the editor bindings.
+
+use std::path::PathBuf;
+
+use web_time::{Duration, Instant};
+
+use anyhow::Result;
+use egui::{Color32, Pos2, RawInput, Rect, Vec2};
+use image::RgbaImage;
+
+use crate::clock::Clock;
+use crate::config::EPConfig;
+use crate::simulator::Simulator;
+
+use super::SoftwareRenderer;
+
+/// Time spent rendering one frame, by stage
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderTimes {
+    /// Drawing the simulator: pixel effects and uploading the frame texture
+    pub texture: Duration,
+    /// Tessellating and rasterizing the result
+    pub composite: Duration,
+}
+
+/// A simulator rendering frames into images
+pub struct HeadlessRenderer {
+    ctx: egui::Context,
+    simulator: Simulator,
+    renderer: SoftwareRenderer,
+    /// Playback time, which also drives egui's animations
+    clock: Clock,
+}
+
+impl HeadlessRenderer {
+    /// Open `config` with its videos, at the start of playback
+    pub fn new(
+        config: EPConfig,
+        base_dir: PathBuf,
+        app_dir: PathBuf,
+        cropbox: Option<(u32, u32, u32, u32)>,
+        rotation: i32,
+    ) -> Result<Self> {
+        let mut simulator = Simulator::new(Some(config), base_dir, app_dir, cropbox, rotation);
+        if let Some(message) = simulator.load_videos() {
+            anyhow::bail!("{}", message);
+        }
+        simulator.start_playback();
+        Ok(Self {
+            ctx: egui::Context::default(),
+            simulator,
+            renderer: SoftwareRenderer::new(),
+            clock: Clock::stepped(Duration::ZERO),
+        })
+    }
+
+    /// Size of the rendered frames
+    pub fn size(&self) -> [u32; 2] {
+        self.simulator.frame_size()
+    }
+
+    /// Logic frame rate of the firmware
+    pub fn fps(&self) -> u32 {
+        self.simulator.fps()
+    }
+
+    /// Move to `time_us` of playback from the start
+    pub fn seek(&mut self, time_us: i64) {
+        self.simulator.seek_to_time(time_us);
+        self.simulator.state.pause();
+        self.simulator.frame_dirty = true;
+    }
+
+    /// Play on for `elapsed_us`
+    pub fn advance(&mut self, elapsed_us: i64) {
+        self.simulator.advance_detached(elapsed_us);
+        self.simulator.state.pause();
+        self.simulator.frame_dirty = true;
+        self.clock.advance(Duration::from_micros(elapsed_us.max(0) as u64));
+    }
+
+    /// Play on for `frames` logic frames
+    pub fn step(&mut self, frames: u32) {
+        self.advance(self.simulator.step_time_us() * frames as i64);
+    }
+
+    /// Show `serial` as `{serial}` instead of the config's, or the config's own with None
+    pub fn set_serial(&mut self, serial: Option<String>) {
+        self.simulator.set_serial(serial);
+    }
+
+    /// Render the current frame, with overlays, at the device resolution
+    pub fn render(&mut self) -> RgbaImage {
+        self.render_timed().0
+    }
+
+    /// Render like `render`, also timing its stages
+    pub fn render_timed(&mut self) -> (RgbaImage, RenderTimes) {
+        let started = Instant::now();
+        let size = self.size();
+        let screen = Rect::from_min_size(Pos2::ZERO, Vec2::new(size[0] as f32, size[1] as f32));
+        let input = RawInput {
+            screen_rect: Some(screen),
+            time: Some(self.clock.elapsed().as_secs_f64()),
+            ..Default::default()
+        };
+        let simulator = &mut self.simulator;
+        let output = self.ctx.run(input, |ctx| {
+            simulator.load_textures(ctx);
+            if simulator.frame_dirty {
+                simulator.render_frame(ctx);
+                simulator.frame_dirty = false;
+            }
+            simulator.paint(&ctx.layer_painter(egui::LayerId::background()), screen);
+        });
+        self.renderer.update_textures(&output.textures_delta);
+        let drawn = Instant::now();
+        let primitives = self.ctx.tessellate(output.shapes, output.pixels_per_point);
+        let image = self.renderer.render(&primitives, size, output.pixels_per_point, Color32::BLACK);
+        let times = RenderTimes { texture: drawn - started, composite: drawn.elapsed() };
+        (image, times)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headless_needs_loop_video() {
+        let config: EPConfig = serde_json::from_str(r#"{"loop": {"file": "missing.mp4"}}"#).unwrap();
+        let dir = std::env::temp_dir();
+        assert!(HeadlessRenderer::new(config, dir.clone(), dir, None, 0).is_err());
+    }
+
+    #[test]
+    fn test_render_with_serial() {
+        let dir = std::env::temp_dir().join(format!("headless_serial_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // A still image plays as a one-frame loop
+        RgbaImage::from_pixel(360, 640, image::Rgba([40, 40, 40, 255])).save(dir.join("loop.png")).unwrap();
+        let config: EPConfig = serde_json::from_str(
+            r#"{
+                "serial": "AAAA",
+                "loop": {"file": "loop.png"},
+                "overlay": {"type": "arknights", "options": {"operator_name": "{serial}", "appear_time": 0}}
+            }"#,
+        )
+        .unwrap();
+        let render = |serial: Option<&str>| {
+            let mut renderer = HeadlessRenderer::new(config.clone(), dir.clone(), dir.clone(), None, 0).unwrap();
+            renderer.set_serial(serial.map(str::to_string));
+            renderer.seek(5_000_000);
+            renderer.render()
+        };
+        let own = render(None);
+        let same = render(Some("AAAA"));
+        let other = render(Some("ZZZZ"));
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(own, same);
+        assert_ne!(own, other);
+    }
+}
@@ -2,19 +2,27 @@
 //!
 //! Provides utilities for loading images from disk and converting them to egui textures.
 
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
 use std::path::{Path, PathBuf};
 
 use egui::{Color32, ColorImage, Context, TextureHandle, TextureId, TextureOptions};
-use image::GenericImageView;
+use image::{imageops, DynamicImage, RgbaImage};
 use tracing::{info, warn};
 
+use crate::utils::{read_file, resolve_asset_path};
+
+use super::AssetIssue;
+
 /// Image loader for managing textures
 pub struct ImageLoader {
     /// Cached textures by path
     textures: HashMap<String, TextureHandle>,
     /// Base directory for resolving relative paths
     base_dir: PathBuf,
+    /// Load failures not yet collected by `take_failures`
+    failures: RefCell<Vec<AssetIssue>>,
 }
 
 impl ImageLoader {
@@ -23,21 +31,47 @@ impl ImageLoader {
         Self {
             textures: HashMap::new(),
             base_dir,
+            failures: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Open an image (relative paths resolve against the base directory)
+    ///
+    /// Read through `read_file`, so mounted packages load too. Failures are
+    /// logged and recorded for the asset report.
+    pub fn open_image(&self, asset: &str, path: &Path) -> Option<DynamicImage> {
+        let full_path = resolve_asset_path(&path.to_string_lossy(), &self.base_dir);
+        let decoded = read_file(&full_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Ok(image::load_from_memory(&data)?));
+        match decoded {
+            Ok(img) => Some(img),
+            Err(e) => {
+                self.report_failure(asset, &full_path, e);
+                None
+            }
         }
     }
 
+    /// Log a failed asset and record it for the asset report
+    pub fn report_failure(&self, asset: &str, path: &Path, reason: impl fmt::Display) {
+        warn!("Failed to load {} '{}': {}", asset, path.display(), reason);
+        self.failures.borrow_mut().push(AssetIssue::new(asset, path, reason));
+    }
+
+    /// Take the failures recorded since the last call
+    pub fn take_failures(&self) -> Vec<AssetIssue> {
+        std::mem::take(&mut *self.failures.borrow_mut())
+    }
+
     /// Set the base directory for resolving relative paths
     pub fn set_base_dir(&mut self, base_dir: PathBuf) {
         self.base_dir = base_dir;
     }
 
-    /// Resolve a path relative to the base directory
+    /// Resolve a path relative to the base directory (`${VAR}` references expanded)
     pub fn resolve_path(&self, relative_path: &str) -> PathBuf {
-        if Path::new(relative_path).is_absolute() {
-            PathBuf::from(relative_path)
-        } else {
-            self.base_dir.join(relative_path)
-        }
+        resolve_asset_path(relative_path, &self.base_dir)
     }
 
     /// Load an image from disk and create a texture
@@ -47,17 +81,8 @@ impl ImageLoader {
             return Some(handle.id());
         }
 
-        // Resolve the path
-        let full_path = self.resolve_path(path);
-
         // Load the image
-        let img = match image::open(&full_path) {
-            Ok(img) => img,
-            Err(e) => {
-                warn!("Failed to load image '{}': {}", full_path.display(), e);
-                return None;
-            }
-        };
+        let img = self.open_image("image", Path::new(path))?;
 
         // Convert to ColorImage
         let size = [img.width() as usize, img.height() as usize];
@@ -101,17 +126,8 @@ impl ImageLoader {
             return Some((handle.id(), size));
         }
 
-        // Resolve the path
-        let full_path = self.resolve_path(path);
-
         // Load the image
-        let img = match image::open(&full_path) {
-            Ok(img) => img,
-            Err(e) => {
-                warn!("Failed to load image '{}': {}", full_path.display(), e);
-                return None;
-            }
-        };
+        let img = self.open_image("image", Path::new(path))?;
 
         // Convert to ColorImage
         let size = [img.width() as usize, img.height() as usize];
@@ -322,6 +338,32 @@ pub fn generate_vertical_barcode_gradient(text: &str, width: u32, use_gradient:
     })
 }
 
+/// A barcode as an RGBA image, for artwork made outside the simulator
+///
+/// `bar_length` is the width of a vertical barcode or the height of a
+/// horizontal one; every module is `scale` pixels thick. The gradient only
+/// applies to vertical barcodes, as on the pass.
+pub fn render_barcode(text: &str, vertical: bool, use_gradient: bool, bar_length: u32, scale: u32) -> Option<RgbaImage> {
+    let image = if vertical {
+        generate_vertical_barcode_gradient(text, bar_length, use_gradient)?
+    } else {
+        generate_barcode(text, bar_length)?
+    };
+    let raw = image.pixels.iter().flat_map(|pixel| pixel.to_srgba_unmultiplied()).collect();
+    let [width, height] = image.size;
+    let barcode = RgbaImage::from_raw(width as u32, height as u32, raw)?;
+    let scale = scale.max(1);
+    if scale == 1 {
+        return Some(barcode);
+    }
+    let (width, height) = if vertical {
+        (barcode.width(), barcode.height() * scale)
+    } else {
+        (barcode.width() * scale, barcode.height())
+    };
+    Some(imageops::resize(&barcode, width, height, imageops::FilterType::Nearest))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -343,4 +385,29 @@ mod tests {
         assert_eq!(img.size[0], 30);
         assert!(img.size[1] > 0);
     }
+
+    #[test]
+    fn test_render_barcode() {
+        let plain = render_barcode("TEST", false, false, 40, 1).unwrap();
+        let scaled = render_barcode("TEST", false, false, 40, 3).unwrap();
+        assert_eq!(scaled.dimensions(), (plain.width() * 3, 40));
+
+        let vertical = render_barcode("TEST", true, true, 20, 2).unwrap();
+        assert_eq!(vertical.dimensions(), (20, plain.width() * 2));
+        assert!(vertical.pixels().any(|p| p.0[3] == 255 && p.0 != [255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_open_mounted_image() {
+        let mut png = std::io::Cursor::new(Vec::new());
+        RgbaImage::new(3, 2).write_to(&mut png, image::ImageFormat::Png).unwrap();
+        let base_dir = PathBuf::from("mounted_package");
+        crate::utils::mount_files(HashMap::from([(base_dir.join("overlay.png"), png.into_inner())]));
+
+        let loader = ImageLoader::new(base_dir);
+        let image = loader.open_image("overlay", Path::new("overlay.png")).unwrap();
+        assert_eq!((image.width(), image.height()), (3, 2));
+        assert!(loader.open_image("logo", Path::new("logo.png")).is_none());
+        assert_eq!(loader.take_failures().len(), 1);
+    }
 }
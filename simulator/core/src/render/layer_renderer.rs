@@ -0,0 +1,311 @@
+//! Layered overlay renderer
+//!
+//! Draws `OverlayType::Custom` overlays: an ordered list of image, text,
+//! barcode, rect and line layers, each with its own timing and easing.
+//! Also computes the fade/slide state of timed image overlays.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use egui::{Color32, ColorImage, Pos2, Rect, Stroke, TextureHandle, Vec2};
+use tracing::info;
+
+use crate::config::{
+    CustomOverlayOptions, Easing, ImageOverlayOptions, LayerAnimation, LayerContent, OverlayLayer, SlideDirection,
+};
+use crate::utils::parse_color;
+
+use super::bezier::{ease_in, ease_in_out, ease_out};
+use super::image_loader::{generate_barcode, generate_vertical_barcode, ImageLoader};
+use super::text_renderer::render_text_oriented;
+
+/// Animated state of a visible layer at a point in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerVisual {
+    /// Opacity (0.0 - 1.0)
+    pub alpha: f32,
+    /// Position offset in firmware pixels
+    pub offset: Vec2,
+}
+
+/// Apply an easing curve
+fn ease(easing: Easing, t: f32) -> f32 {
+    match easing {
+        Easing::Linear => t.clamp(0.0, 1.0),
+        Easing::EaseIn => ease_in(t),
+        Easing::EaseOut => ease_out(t),
+        Easing::EaseInOut => ease_in_out(t),
+    }
+}
+
+/// Compute a layer's visual state, or None if it is hidden at `time_us`
+pub fn layer_visual(layer: &OverlayLayer, time_us: i64) -> Option<LayerVisual> {
+    let local = time_us - layer.appear_time;
+    if local < 0 || (layer.duration > 0 && local >= layer.duration) {
+        return None;
+    }
+
+    let opacity = layer.opacity.clamp(0.0, 1.0);
+    if layer.animation == LayerAnimation::None || layer.animation_duration <= 0 {
+        return Some(LayerVisual { alpha: opacity, offset: Vec2::ZERO });
+    }
+
+    // Progress of the appear animation, mirrored before the layer disappears
+    let anim = layer.animation_duration as f32;
+    let mut progress = (local as f32 / anim).min(1.0);
+    if layer.duration > 0 {
+        progress = progress.min((layer.duration - local) as f32 / anim);
+    }
+    let eased = ease(layer.easing, progress);
+
+    let distance = (1.0 - eased) * layer.slide_distance;
+    let visual = match layer.animation {
+        LayerAnimation::None => LayerVisual { alpha: opacity, offset: Vec2::ZERO },
+        LayerAnimation::Fade => LayerVisual { alpha: opacity * eased, offset: Vec2::ZERO },
+        LayerAnimation::SlideUp => LayerVisual { alpha: opacity, offset: Vec2::new(0.0, distance) },
+        LayerAnimation::SlideDown => LayerVisual { alpha: opacity, offset: Vec2::new(0.0, -distance) },
+        LayerAnimation::SlideLeft => LayerVisual { alpha: opacity, offset: Vec2::new(distance, 0.0) },
+        LayerAnimation::SlideRight => LayerVisual { alpha: opacity, offset: Vec2::new(-distance, 0.0) },
+    };
+    Some(visual)
+}
+
+/// Compute an image overlay's visual state, or None if it is hidden at `time_us`
+///
+/// `duration <= 0` shows the image indefinitely (and never fades out).
+pub fn image_overlay_visual(options: &ImageOverlayOptions, time_us: i64) -> Option<LayerVisual> {
+    let local = time_us - options.appear_time;
+    if local < 0 || (options.duration > 0 && local >= options.duration) {
+        return None;
+    }
+
+    let mut progress: f32 = 1.0;
+    if options.fade_in > 0 {
+        progress = progress.min(local as f32 / options.fade_in as f32);
+    }
+    if options.duration > 0 && options.fade_out > 0 {
+        progress = progress.min((options.duration - local) as f32 / options.fade_out as f32);
+    }
+    let eased = ease(Easing::EaseInOut, progress);
+
+    let distance = (1.0 - eased) * options.slide_distance;
+    let offset = match options.slide {
+        None => Vec2::ZERO,
+        Some(SlideDirection::Up) => Vec2::new(0.0, distance),
+        Some(SlideDirection::Down) => Vec2::new(0.0, -distance),
+        Some(SlideDirection::Left) => Vec2::new(distance, 0.0),
+        Some(SlideDirection::Right) => Vec2::new(-distance, 0.0),
+    };
+    Some(LayerVisual { alpha: eased, offset })
+}
+
+/// Rect of an image overlay in firmware pixels (before any slide offset)
+///
+/// The anchor point of the image is placed on the same anchor point of the
+/// screen, then moved by (x, y).
+pub fn image_overlay_rect(options: &ImageOverlayOptions, image_size: Vec2, screen_size: Vec2) -> Rect {
+    let size = image_size * options.scale.max(0.0);
+    let (fx, fy) = options.anchor.fraction();
+    let anchor = Vec2::new(fx, fy);
+    let min = anchor * screen_size - anchor * size + Vec2::new(options.x as f32, options.y as f32);
+    Rect::from_min_size(Pos2::ZERO + min, size)
+}
+
+/// Parse a layer color, applying the layer alpha on top of the color's own
+fn layer_color(color: &str, alpha: f32) -> Color32 {
+    let (r, g, b, a) = parse_color(color).unwrap_or((255, 255, 255, 255));
+    Color32::from_rgba_unmultiplied(r, g, b, (a as f32 * alpha) as u8)
+}
+
+/// Renderer for custom layered overlays
+///
+/// Image, text and barcode layers are rasterized once and cached by layer
+/// index, so use a fresh renderer when the configuration changes.
+#[derive(Default)]
+pub struct LayerRenderer {
+    textures: HashMap<usize, Option<TextureHandle>>,
+}
+
+impl LayerRenderer {
+    /// Rasterize a layer's content (None for vector layers or on failure)
+    fn rasterize(content: &LayerContent, image_loader: &ImageLoader) -> Option<ColorImage> {
+        match content {
+            LayerContent::Image { image, .. } => {
+                let img = image_loader.open_image("layer image", Path::new(image))?;
+                let rgba = img.to_rgba8();
+                let size = [rgba.width() as usize, rgba.height() as usize];
+                let pixels = rgba
+                    .pixels()
+                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+                    .collect();
+                info!("Loaded layer image: {}", image);
+                Some(ColorImage { size, pixels })
+            }
+            LayerContent::Text { text, font_size, color, bold, orientation } => {
+                let color = layer_color(color, 1.0);
+                Some(render_text_oriented(text, *font_size, color, *bold, *orientation))
+            }
+            LayerContent::Barcode { text, width, height, vertical, .. } => {
+                if *vertical {
+                    generate_vertical_barcode(text, *width)
+                } else {
+                    generate_barcode(text, *height)
+                }
+            }
+            LayerContent::Rect { .. } | LayerContent::Line { .. } => None,
+        }
+    }
+
+    /// Paint all visible layers into `image_rect`
+    ///
+    /// `fw_size` is the firmware screen size the layer coordinates refer to.
+    pub fn paint(
+        &mut self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        fw_size: Vec2,
+        time_us: i64,
+        options: &CustomOverlayOptions,
+        image_loader: &ImageLoader,
+    ) {
+        let scale = image_rect.size() / fw_size;
+        let uv_full = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+
+        for (index, layer) in options.layers.iter().enumerate() {
+            let Some(visual) = layer_visual(layer, time_us) else {
+                continue;
+            };
+            let to_screen = |x: f32, y: f32| {
+                image_rect.min + Vec2::new(x + visual.offset.x, y + visual.offset.y) * scale
+            };
+            let origin = to_screen(layer.x as f32, layer.y as f32);
+
+            match &layer.content {
+                LayerContent::Rect { width, height, color, stroke } => {
+                    let rect = Rect::from_min_size(origin, Vec2::new(*width as f32, *height as f32) * scale);
+                    let color = layer_color(color, visual.alpha);
+                    if *stroke > 0.0 {
+                        painter.rect_stroke(rect, 0.0, Stroke::new(stroke * scale.x, color));
+                    } else {
+                        painter.rect_filled(rect, 0.0, color);
+                    }
+                }
+                LayerContent::Line { x2, y2, color, thickness } => {
+                    let end = to_screen(*x2 as f32, *y2 as f32);
+                    let color = layer_color(color, visual.alpha);
+                    painter.line_segment([origin, end], Stroke::new(thickness * scale.x, color));
+                }
+                content => {
+                    let texture = self.textures.entry(index).or_insert_with(|| {
+                        Self::rasterize(content, image_loader).map(|img| {
+                            painter.ctx().load_texture(
+                                format!("overlay_layer_{}", index),
+                                img,
+                                egui::TextureOptions::LINEAR,
+                            )
+                        })
+                    });
+                    let Some(texture) = texture else {
+                        continue;
+                    };
+
+                    let [tex_w, tex_h] = texture.size().map(|v| v as f32);
+                    let (size, tint) = match content {
+                        LayerContent::Image { width, height, .. } => {
+                            let w = if *width > 0 { *width as f32 } else { tex_w };
+                            let h = if *height > 0 { *height as f32 } else { tex_h };
+                            (Vec2::new(w, h), layer_color("#FFFFFF", visual.alpha))
+                        }
+                        LayerContent::Barcode { width, height, color, .. } => {
+                            (Vec2::new(*width as f32, *height as f32), layer_color(color, visual.alpha))
+                        }
+                        _ => (Vec2::new(tex_w, tex_h), layer_color("#FFFFFF", visual.alpha)),
+                    };
+
+                    let rect = Rect::from_min_size(origin, size * scale);
+                    painter.image(texture.id(), rect, uv_full, tint);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_layer(animation: LayerAnimation) -> OverlayLayer {
+        OverlayLayer {
+            content: LayerContent::Rect {
+                width: 10,
+                height: 10,
+                color: "#FFFFFF".to_string(),
+                stroke: 0.0,
+            },
+            x: 0,
+            y: 0,
+            appear_time: 1_000_000,
+            duration: 2_000_000,
+            animation,
+            animation_duration: 500_000,
+            easing: Easing::Linear,
+            slide_distance: 40.0,
+            opacity: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_layer_timing() {
+        let layer = test_layer(LayerAnimation::None);
+        assert!(layer_visual(&layer, 999_999).is_none());
+        assert!(layer_visual(&layer, 1_000_000).is_some());
+        assert!(layer_visual(&layer, 3_000_000).is_none());
+    }
+
+    #[test]
+    fn test_layer_fade_in_and_out() {
+        let layer = test_layer(LayerAnimation::Fade);
+        let half_in = layer_visual(&layer, 1_250_000).unwrap();
+        assert!((half_in.alpha - 0.5).abs() < 0.01);
+        let full = layer_visual(&layer, 2_000_000).unwrap();
+        assert_eq!(full.alpha, 1.0);
+        let half_out = layer_visual(&layer, 2_750_000).unwrap();
+        assert!((half_out.alpha - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_layer_slide_up() {
+        let layer = test_layer(LayerAnimation::SlideUp);
+        let start = layer_visual(&layer, 1_000_000).unwrap();
+        assert_eq!(start.offset, Vec2::new(0.0, 40.0));
+        let settled = layer_visual(&layer, 2_000_000).unwrap();
+        assert_eq!(settled.offset, Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_image_overlay_fade_and_slide() {
+        let options: ImageOverlayOptions = serde_json::from_str(
+            r#"{"appear_time": 0, "duration": 1000000, "fade_in": 200000, "fade_out": 200000, "slide": "left"}"#,
+        )
+        .unwrap();
+
+        let start = image_overlay_visual(&options, 0).unwrap();
+        assert_eq!(start.alpha, 0.0);
+        assert_eq!(start.offset, Vec2::new(40.0, 0.0));
+        assert_eq!(image_overlay_visual(&options, 500_000).unwrap().alpha, 1.0);
+        assert!(image_overlay_visual(&options, 900_000).unwrap().alpha < 1.0);
+        assert!(image_overlay_visual(&options, 1_000_000).is_none());
+    }
+
+    #[test]
+    fn test_image_overlay_anchor() {
+        let options: ImageOverlayOptions =
+            serde_json::from_str(r#"{"anchor": "bottom", "y": -10, "scale": 0.5}"#).unwrap();
+        let rect = image_overlay_rect(&options, Vec2::new(200.0, 100.0), Vec2::new(360.0, 640.0));
+        assert_eq!(rect, Rect::from_min_size(Pos2::new(130.0, 580.0), Vec2::new(100.0, 50.0)));
+
+        let default: ImageOverlayOptions = serde_json::from_str("{}").unwrap();
+        let rect = image_overlay_rect(&default, Vec2::new(200.0, 100.0), Vec2::new(360.0, 640.0));
+        assert_eq!(rect.min, Pos2::ZERO);
+    }
+}
@@ -0,0 +1,26 @@
+//! Render module
+//!
+//! Transition and overlay math, the egui renderers for images, text and
+//! layers, and a software rasterizer for egui output.
+
+mod asset_issue;
+pub mod bezier;
+mod headless;
+pub mod image_loader;
+pub mod layer_renderer;
+mod overlay;
+mod software;
+mod status_bar;
+pub mod text_renderer;
+mod transition;
+
+pub use asset_issue::AssetIssue;
+pub use bezier::*;
+pub use headless::{HeadlessRenderer, RenderTimes};
+pub use image_loader::{ImageLoader, generate_barcode, generate_vertical_barcode, generate_vertical_barcode_gradient, render_barcode};
+pub use layer_renderer::{image_overlay_rect, image_overlay_visual, LayerRenderer};
+pub use overlay::OverlayRenderer;
+pub use software::SoftwareRenderer;
+pub use status_bar::StatusBar;
+pub use text_renderer::{render_text_oriented, render_top_right_bar_text_rotated, set_text_quality, TextRenderQuality};
+pub use transition::TransitionRenderer;
@@ -4,7 +4,7 @@
 //! Corresponds to Python's core/overlay_animator.py
 
 use crate::config::FirmwareConfig;
-use crate::app::state::AnimationState;
+use crate::state::AnimationState;
 
 /// Overlay renderer
 pub struct OverlayRenderer {
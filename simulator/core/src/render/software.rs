@@ -0,0 +1,273 @@
+//! Software rasterizer for egui output
+//!
+//! Draws the meshes egui tessellates into an image on the CPU, so the
+//! preview can be rendered without a window or GPU (CLI renders, CI).
+//! Blending follows egui's own painters: premultiplied colors, mixed in
+//! gamma space.
+
+use std::collections::HashMap;
+
+use epaint::textures::{TextureFilter, TexturesDelta};
+use epaint::{ClippedPrimitive, Color32, ImageData, Pos2, Primitive, Rect, TextureId, Vertex};
+use image::RgbaImage;
+
+struct Texture {
+    size: [usize; 2],
+    /// Premultiplied sRGBA, row by row
+    pixels: Vec<Color32>,
+    magnification: TextureFilter,
+    minification: TextureFilter,
+}
+
+impl Texture {
+    /// Color at `uv` (0..1), filtered with `filter`
+    fn sample(&self, uv: Pos2, filter: TextureFilter) -> [f32; 4] {
+        let [width, height] = self.size;
+        if width == 0 || height == 0 {
+            return [0.0; 4];
+        }
+        let texel = |x: isize, y: isize| {
+            let x = x.clamp(0, width as isize - 1) as usize;
+            let y = y.clamp(0, height as isize - 1) as usize;
+            let c = self.pixels[y * width + x];
+            [c.r() as f32, c.g() as f32, c.b() as f32, c.a() as f32]
+        };
+        let x = uv.x * width as f32 - 0.5;
+        let y = uv.y * height as f32 - 0.5;
+        match filter {
+            TextureFilter::Nearest => texel(x.round() as isize, y.round() as isize),
+            TextureFilter::Linear => {
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as isize, y0 as isize);
+                let (a, b) = (texel(x0, y0), texel(x0 + 1, y0));
+                let (c, d) = (texel(x0, y0 + 1), texel(x0 + 1, y0 + 1));
+                std::array::from_fn(|i| {
+                    let top = a[i] + (b[i] - a[i]) * fx;
+                    let bottom = c[i] + (d[i] - c[i]) * fx;
+                    top + (bottom - top) * fy
+                })
+            }
+        }
+    }
+}
+
+/// CPU renderer keeping its own copy of egui's textures
+#[derive(Default)]
+pub struct SoftwareRenderer {
+    textures: HashMap<TextureId, Texture>,
+}
+
+impl SoftwareRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply the texture changes of an egui frame
+    pub fn update_textures(&mut self, delta: &TexturesDelta) {
+        for (id, image_delta) in &delta.set {
+            let (size, pixels): ([usize; 2], Vec<Color32>) = match &image_delta.image {
+                ImageData::Color(image) => (image.size, image.pixels.clone()),
+                ImageData::Font(image) => (image.size, image.srgba_pixels(None).collect()),
+            };
+            let options = image_delta.options;
+            match image_delta.pos {
+                None => {
+                    self.textures.insert(
+                        *id,
+                        Texture {
+                            size,
+                            pixels,
+                            magnification: options.magnification,
+                            minification: options.minification,
+                        },
+                    );
+                }
+                Some([x, y]) => {
+                    let Some(texture) = self.textures.get_mut(id) else {
+                        continue;
+                    };
+                    let width = texture.size[0];
+                    for (row, chunk) in pixels.chunks_exact(size[0].max(1)).enumerate() {
+                        let start = (y + row) * width + x;
+                        if let Some(target) = texture.pixels.get_mut(start..start + chunk.len()) {
+                            target.copy_from_slice(chunk);
+                        }
+                    }
+                }
+            }
+        }
+        for id in &delta.free {
+            self.textures.remove(id);
+        }
+    }
+
+    /// Draw tessellated primitives over an opaque `background`
+    ///
+    /// `size` is in pixels; primitives are in points at `pixels_per_point`.
+    pub fn render(
+        &self,
+        primitives: &[ClippedPrimitive],
+        size: [u32; 2],
+        pixels_per_point: f32,
+        background: Color32,
+    ) -> RgbaImage {
+        let [width, height] = size;
+        let mut target = vec![
+            [background.r() as f32, background.g() as f32, background.b() as f32, 255.0];
+            width as usize * height as usize
+        ];
+        let bounds = Rect::from_min_size(Pos2::ZERO, epaint::vec2(width as f32, height as f32));
+
+        for primitive in primitives {
+            let Primitive::Mesh(ref mesh) = primitive.primitive else {
+                continue;
+            };
+            let clip = Rect::from_min_max(
+                (primitive.clip_rect.min.to_vec2() * pixels_per_point).round().to_pos2(),
+                (primitive.clip_rect.max.to_vec2() * pixels_per_point).round().to_pos2(),
+            )
+            .intersect(bounds);
+            let texture = self.textures.get(&mesh.texture_id);
+            for triangle in mesh.indices.chunks_exact(3) {
+                let vertex = |i: u32| {
+                    let v = mesh.vertices[i as usize];
+                    Vertex { pos: (v.pos.to_vec2() * pixels_per_point).to_pos2(), ..v }
+                };
+                let vertices = [vertex(triangle[0]), vertex(triangle[1]), vertex(triangle[2])];
+                fill_triangle(&mut target, width as usize, clip, vertices, texture);
+            }
+        }
+
+        let raw = target
+            .iter()
+            .flat_map(|[r, g, b, _]| [*r, *g, *b].map(|c| c.round().clamp(0.0, 255.0) as u8).into_iter().chain([255]))
+            .collect();
+        RgbaImage::from_raw(width, height, raw).unwrap_or_else(|| RgbaImage::new(width, height))
+    }
+}
+
+/// Twice the signed area of `a`, `b`, `p`; positive when `p` is on the inner side of `a`→`b`
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Whether pixels exactly on `a`→`b` belong to the triangle, so pixels on an
+/// edge shared by two triangles are drawn once
+fn is_top_left(a: Pos2, b: Pos2) -> bool {
+    (a.y == b.y && b.x > a.x) || b.y < a.y
+}
+
+fn fill_triangle(
+    target: &mut [[f32; 4]],
+    width: usize,
+    clip: Rect,
+    [v0, mut v1, mut v2]: [Vertex; 3],
+    texture: Option<&Texture>,
+) {
+    let mut area = edge(v0.pos, v1.pos, v2.pos);
+    if area == 0.0 {
+        return;
+    }
+    if area < 0.0 {
+        std::mem::swap(&mut v1, &mut v2);
+        area = -area;
+    }
+
+    // Texels per pixel decide between the texture's filters, like a GPU does
+    let filter = texture.map(|texture| {
+        let gradient = |f: fn(&Vertex) -> f32| {
+            let dx = f(&v0) * (v1.pos.y - v2.pos.y) + f(&v1) * (v2.pos.y - v0.pos.y) + f(&v2) * (v0.pos.y - v1.pos.y);
+            let dy = f(&v0) * (v2.pos.x - v1.pos.x) + f(&v1) * (v0.pos.x - v2.pos.x) + f(&v2) * (v1.pos.x - v0.pos.x);
+            dx.abs().max(dy.abs()) / area
+        };
+        let texels_per_pixel = (gradient(|v| v.uv.x) * texture.size[0] as f32).max(gradient(|v| v.uv.y) * texture.size[1] as f32);
+        if texels_per_pixel > 1.0 {
+            texture.minification
+        } else {
+            texture.magnification
+        }
+    });
+
+    let min = v0.pos.min(v1.pos).min(v2.pos).max(clip.min);
+    let max = v0.pos.max(v1.pos).max(v2.pos).min(clip.max);
+    if min.x >= max.x || min.y >= max.y {
+        return;
+    }
+    let edges = [(v1.pos, v2.pos), (v2.pos, v0.pos), (v0.pos, v1.pos)];
+    let top_left = edges.map(|(a, b)| is_top_left(a, b));
+
+    for y in min.y.floor() as usize..max.y.ceil() as usize {
+        for x in min.x.floor() as usize..max.x.ceil() as usize {
+            let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+            if !clip.contains(p) {
+                continue;
+            }
+            let w = [0, 1, 2].map(|i| edge(edges[i].0, edges[i].1, p));
+            if (0..3).any(|i| w[i] < 0.0 || (w[i] == 0.0 && !top_left[i])) {
+                continue;
+            }
+            let [b0, b1, b2] = w.map(|w| w / area);
+            let lerp = |f: fn(&Vertex) -> f32| b0 * f(&v0) + b1 * f(&v1) + b2 * f(&v2);
+
+            let color = [
+                lerp(|v| v.color.r() as f32),
+                lerp(|v| v.color.g() as f32),
+                lerp(|v| v.color.b() as f32),
+                lerp(|v| v.color.a() as f32),
+            ];
+            let src = match (texture, filter) {
+                (Some(texture), Some(filter)) => {
+                    let texel = texture.sample(Pos2::new(lerp(|v| v.uv.x), lerp(|v| v.uv.y)), filter);
+                    std::array::from_fn(|i| color[i] * texel[i] / 255.0)
+                }
+                _ => color,
+            };
+
+            // Premultiplied "over"
+            let dst = &mut target[y * width + x];
+            let keep = 1.0 - src[3] / 255.0;
+            for i in 0..4 {
+                dst[i] = src[i] + dst[i] * keep;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use epaint::textures::TextureOptions;
+    use epaint::{ClippedShape, ColorImage, ImageDelta, Shape, TessellationOptions, Tessellator};
+
+    #[test]
+    fn test_software_render_rects() {
+        let clip_rect = Rect::from_min_size(Pos2::ZERO, epaint::vec2(8.0, 8.0));
+        let shapes = [
+            Shape::rect_filled(Rect::from_min_max(Pos2::new(2.0, 2.0), Pos2::new(6.0, 6.0)), 0.0, Color32::RED),
+            // Half transparent white over everything
+            Shape::rect_filled(clip_rect, 0.0, Color32::from_rgba_premultiplied(128, 128, 128, 128)),
+        ];
+        let options = TessellationOptions { feathering: false, ..Default::default() };
+        let mut tessellator = Tessellator::new(1.0, options, [1, 1], Vec::new());
+        let mut primitives = Vec::new();
+        for shape in shapes {
+            tessellator.tessellate_clipped_shape(ClippedShape { clip_rect, shape }, &mut primitives);
+        }
+
+        // Untextured meshes sample the white texel of the font texture
+        let mut renderer = SoftwareRenderer::new();
+        let white = ColorImage::new([1, 1], Color32::WHITE);
+        renderer.update_textures(&TexturesDelta {
+            set: vec![(TextureId::default(), ImageDelta::full(white, TextureOptions::LINEAR))],
+            free: Vec::new(),
+        });
+
+        let image = renderer.render(&primitives, [8, 8], 1.0, Color32::BLACK);
+        assert_eq!(image.get_pixel(3, 4).0, [255, 128, 128, 255]);
+        assert_eq!(image.get_pixel(7, 0).0, [128, 128, 128, 255]);
+        // Pixels on the diagonal shared by both triangles of a rect are blended once
+        assert_eq!(image.get_pixel(3, 3).0, [255, 128, 128, 255]);
+        assert_eq!(image.get_pixel(7, 7).0, [128, 128, 128, 255]);
+    }
+}
@@ -0,0 +1,122 @@
+//! Device status bar
+//!
+//! The firmware draws a strip with the clock and battery level over the top
+//! of the material. Painting it over the preview shows whether anything
+//! important ends up underneath.
+
+use egui::{Align2, Color32, FontId, Painter, Pos2, Rect, Stroke, Vec2};
+use serde::{Deserialize, Serialize};
+
+/// Height of the strip on a 360 pixel wide screen
+const STATUS_BAR_HEIGHT: f32 = 18.0;
+
+/// Screen width the status bar metrics are given for
+const DESIGN_WIDTH: f32 = 360.0;
+
+/// Battery level at and below which the firmware shows it in red
+const LOW_BATTERY: u8 = 20;
+
+/// Simulated status bar contents
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusBar {
+    /// Paint the status bar at all
+    #[serde(default)]
+    pub enabled: bool,
+    /// Battery charge in percent (0-100)
+    #[serde(default = "default_battery")]
+    pub battery: u8,
+    /// Clock text, e.g. "12:00"
+    #[serde(default = "default_time")]
+    pub time: String,
+}
+
+fn default_battery() -> u8 {
+    80
+}
+
+fn default_time() -> String {
+    "12:00".to_string()
+}
+
+impl Default for StatusBar {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            battery: default_battery(),
+            time: default_time(),
+        }
+    }
+}
+
+impl StatusBar {
+    /// Area covered by the strip on a preview shown in `image_rect`
+    pub fn rect(image_rect: Rect) -> Rect {
+        let height = STATUS_BAR_HEIGHT * image_rect.width() / DESIGN_WIDTH;
+        Rect::from_min_size(image_rect.min, Vec2::new(image_rect.width(), height))
+    }
+
+    /// Paint the strip over the top of the preview shown in `image_rect`
+    pub fn paint(&self, painter: &Painter, image_rect: Rect) {
+        if !self.enabled {
+            return;
+        }
+        let scale = image_rect.width() / DESIGN_WIDTH;
+        let rect = Self::rect(image_rect);
+        let margin = 6.0 * scale;
+        let font = FontId::proportional(11.0 * scale);
+        painter.rect_filled(rect, 0.0, Color32::from_black_alpha(140));
+
+        painter.text(
+            Pos2::new(rect.left() + margin, rect.center().y),
+            Align2::LEFT_CENTER,
+            &self.time,
+            font.clone(),
+            Color32::WHITE,
+        );
+
+        // Battery outline with its terminal nub, filled to the charge level
+        let level = self.battery.min(100);
+        let color = if level <= LOW_BATTERY {
+            Color32::from_rgb(230, 60, 60)
+        } else {
+            Color32::WHITE
+        };
+        let body = Rect::from_min_size(
+            Pos2::new(rect.right() - margin - 24.0 * scale, rect.center().y - 5.0 * scale),
+            Vec2::new(22.0 * scale, 10.0 * scale),
+        );
+        let nub = Rect::from_min_size(
+            Pos2::new(body.right(), body.center().y - 2.0 * scale),
+            Vec2::new(2.0 * scale, 4.0 * scale),
+        );
+        painter.rect_stroke(body, 1.5 * scale, Stroke::new(scale, color));
+        painter.rect_filled(nub, 0.0, color);
+        let inner = body.shrink(2.0 * scale);
+        let fill = Rect::from_min_size(inner.min, Vec2::new(inner.width() * level as f32 / 100.0, inner.height()));
+        painter.rect_filled(fill, 0.0, color);
+
+        painter.text(
+            Pos2::new(body.left() - 4.0 * scale, rect.center().y),
+            Align2::RIGHT_CENTER,
+            format!("{}%", level),
+            font,
+            color,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_bar_rect_scales() {
+        let image_rect = Rect::from_min_size(Pos2::new(10.0, 20.0), Vec2::new(720.0, 1280.0));
+        let rect = StatusBar::rect(image_rect);
+        assert_eq!(rect.min, image_rect.min);
+        assert_eq!(rect.height(), STATUS_BAR_HEIGHT * 2.0);
+
+        let parsed: StatusBar = serde_json::from_str(r#"{"enabled": true}"#).unwrap();
+        assert_eq!(parsed, StatusBar { enabled: true, ..Default::default() });
+    }
+}
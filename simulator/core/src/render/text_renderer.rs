@@ -0,0 +1,548 @@
+//! Text renderer for rotated text
+//!
+//! Uses fontdue to rasterize text, then rotates 90° clockwise.
+//! Emulates the firmware's fbdraw_text_rot90() behavior. Horizontal and
+//! vertically stacked layouts are available for newer firmware.
+//!
+//! Emoji, which the embedded font cannot draw, are drawn in the text color
+//! with the Noto Emoji font egui ships; there are no color emoji.
+
+use std::sync::RwLock;
+
+use egui::{Color32, ColorImage};
+use fontdue::{Font, FontSettings};
+
+use crate::config::TextOrientation;
+
+/// Embedded font for text rendering (DejaVuSans-Bold as Bebas substitute)
+static FONT_DATA: &[u8] = include_bytes!("../../../resources/fonts/DejaVuSans-Bold.ttf");
+
+/// Lazy-initialized font instance
+fn get_font() -> &'static Font {
+    use std::sync::OnceLock;
+    static FONT: OnceLock<Font> = OnceLock::new();
+    FONT.get_or_init(|| {
+        Font::from_bytes(FONT_DATA, FontSettings::default())
+            .expect("Failed to load embedded font")
+    })
+}
+
+/// Monochrome emoji font from egui's default fonts, if present
+fn get_emoji_font() -> Option<&'static Font> {
+    use std::sync::OnceLock;
+    static FONT: OnceLock<Option<Font>> = OnceLock::new();
+    FONT.get_or_init(|| {
+        let data = egui::FontDefinitions::default().font_data.remove("NotoEmoji-Regular")?;
+        Font::from_bytes(data.font, FontSettings::default()).ok()
+    })
+    .as_ref()
+}
+
+/// Font to draw `ch` with: the text font if it has the glyph, else the emoji font
+fn font_for(ch: char) -> &'static Font {
+    let font = get_font();
+    if font.lookup_glyph_index(ch) != 0 {
+        return font;
+    }
+    get_emoji_font()
+        .filter(|emoji| emoji.lookup_glyph_index(ch) != 0)
+        .unwrap_or(font)
+}
+
+/// Rasterization quality for pre-rendered overlay text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextRenderQuality {
+    /// Grayscale anti-aliasing; when off, coverage is thresholded to on/off pixels
+    pub antialias: bool,
+    /// Grid fitting: snap the font size to whole pixels and sharpen stem edges
+    ///
+    /// fontdue has no TrueType hinter, so this approximates the crisp look of
+    /// the firmware's small labels rather than running font instructions.
+    pub hinting: bool,
+}
+
+impl TextRenderQuality {
+    pub const DEFAULT: Self = Self {
+        antialias: true,
+        hinting: false,
+    };
+
+    /// Map raw glyph coverage to output alpha
+    fn coverage(&self, alpha: u8) -> u8 {
+        if !self.antialias {
+            return if alpha >= 128 { 255 } else { 0 };
+        }
+        if self.hinting {
+            // Contrast curve: faint fringes drop out, near-full pixels become full
+            return ((alpha as i32 - 64) * 255 / 127).clamp(0, 255) as u8;
+        }
+        alpha
+    }
+
+    /// Font size actually used for rasterization
+    fn font_size(&self, font_size: f32) -> f32 {
+        if self.hinting {
+            font_size.round().max(1.0)
+        } else {
+            font_size
+        }
+    }
+}
+
+impl Default for TextRenderQuality {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Current text rasterization quality
+static TEXT_QUALITY: RwLock<TextRenderQuality> = RwLock::new(TextRenderQuality::DEFAULT);
+
+/// Set the quality used by subsequent text renders
+pub fn set_text_quality(quality: TextRenderQuality) {
+    if let Ok(mut guard) = TEXT_QUALITY.write() {
+        *guard = quality;
+    }
+}
+
+/// Get the current text rasterization quality
+pub fn text_quality() -> TextRenderQuality {
+    TEXT_QUALITY.read().map(|q| *q).unwrap_or_default()
+}
+
+/// A run of text split by [`split_emoji_segments`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TextSegment {
+    /// Plain text drawable with the regular font
+    Text(String),
+    /// One emoji cluster, kept together when stacking characters
+    Emoji(String),
+}
+
+/// Check if a character starts a color emoji
+///
+/// Only characters shown as emoji by default (Unicode `Emoji_Presentation`)
+/// count, or any character followed by the emoji variation selector, so
+/// symbols like © or ↔ stay text.
+fn is_emoji_start(c: char, next: Option<char>) -> bool {
+    next == Some('\u{FE0F}')
+        || matches!(c as u32,
+            0x1F1E6..=0x1F1FF   // Regional indicators (flags)
+            | 0x1F300..=0x1F64F // Pictographs, emoticons
+            | 0x1F680..=0x1F6FF // Transport and map symbols
+            | 0x1F900..=0x1FAFF // Supplemental pictographs
+            | 0x1F004 | 0x1F0CF | 0x1F18E | 0x1F191..=0x1F19A
+            | 0x231A..=0x231B | 0x23E9..=0x23EC | 0x23F0 | 0x23F3
+            | 0x25FD..=0x25FE | 0x2614..=0x2615 | 0x2648..=0x2653 | 0x267F
+            | 0x2693 | 0x26A1 | 0x26AA..=0x26AB | 0x26BD..=0x26BE | 0x26C4..=0x26C5
+            | 0x26CE | 0x26D4 | 0x26EA | 0x26F2..=0x26F3 | 0x26F5 | 0x26FA | 0x26FD
+            | 0x2705 | 0x270A..=0x270B | 0x2728 | 0x274C | 0x274E | 0x2753..=0x2755
+            | 0x2757 | 0x2795..=0x2797 | 0x27B0 | 0x27BF | 0x2B1B..=0x2B1C | 0x2B50 | 0x2B55
+        )
+}
+
+/// Check if a character only modifies the preceding emoji
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(c as u32,
+        0xFE0F              // Variation selector-16
+        | 0x1F3FB..=0x1F3FF // Skin tone modifiers
+        | 0x20E3            // Combining enclosing keycap
+        | 0xE0020..=0xE007F // Tag sequences (subdivision flags)
+    )
+}
+
+/// Split text into plain runs and emoji clusters.
+///
+/// Clusters follow ZWJ sequences, variation selectors, skin tones and
+/// regional-indicator pairs.
+fn split_emoji_segments(text: &str) -> Vec<TextSegment> {
+    let mut segments = Vec::new();
+    let mut plain = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if !is_emoji_start(c, chars.peek().copied()) {
+            plain.push(c);
+            continue;
+        }
+
+        if !plain.is_empty() {
+            segments.push(TextSegment::Text(std::mem::take(&mut plain)));
+        }
+
+        let mut cluster = vec![c];
+        let is_flag = (0x1F1E6..=0x1F1FF).contains(&(c as u32));
+        if is_flag {
+            if let Some(&next) = chars.peek() {
+                if (0x1F1E6..=0x1F1FF).contains(&(next as u32)) {
+                    cluster.push(next);
+                    chars.next();
+                }
+            }
+        }
+
+        while let Some(&next) = chars.peek() {
+            if is_emoji_modifier(next) {
+                cluster.push(next);
+                chars.next();
+            } else if next == '\u{200D}' {
+                // Zero-width joiner: glue the following emoji into this cluster
+                cluster.push(next);
+                chars.next();
+                if let Some(joined) = chars.next() {
+                    cluster.push(joined);
+                }
+            } else {
+                break;
+            }
+        }
+
+        segments.push(TextSegment::Emoji(cluster.into_iter().collect()));
+    }
+
+    if !plain.is_empty() {
+        segments.push(TextSegment::Text(plain));
+    }
+
+    segments
+}
+
+/// Render text as an unrotated horizontal line.
+///
+/// 1. Rasterize each character using fontdue
+/// 2. Compose into a horizontal bitmap (line height = 1.2 × `font_size`)
+///
+/// If `bold` is true, applies faux bold by rendering twice with 1px x-offset
+/// (matching firmware's double-render technique).
+pub fn render_text_horizontal(
+    text: &str,
+    font_size: f32,
+    color: Color32,
+    bold: bool,
+) -> ColorImage {
+    let quality = text_quality();
+    let font_size = quality.font_size(font_size);
+
+    // Step 1: Rasterize each character and calculate total dimensions
+    let mut glyphs: Vec<(fontdue::Metrics, Vec<u8>)> = Vec::new();
+    let mut total_width: usize = 0;
+    let mut max_height: usize = 0;
+
+    // Joiners and selectors have no glyph of their own
+    for ch in text.chars().filter(|&c| c != '\u{200D}' && c != '\u{FE0F}') {
+        let (metrics, bitmap) = font_for(ch).rasterize(ch, font_size);
+        total_width += metrics.advance_width.ceil() as usize;
+        let glyph_height = (font_size.ceil() as usize).max(metrics.height + metrics.ymin.unsigned_abs() as usize);
+        max_height = max_height.max(glyph_height);
+        glyphs.push((metrics, bitmap));
+    }
+
+    if total_width == 0 || max_height == 0 {
+        return ColorImage::new([1, 1], Color32::TRANSPARENT);
+    }
+
+    // Add padding
+    let img_height = (font_size * 1.2).ceil() as usize;
+    let img_height = img_height.max(max_height);
+
+    // Step 2: Compose glyphs into horizontal bitmap
+    let mut horizontal = vec![Color32::TRANSPARENT; total_width * img_height];
+    let baseline = (font_size * 0.85).ceil() as i32;
+    let mut cursor_x: i32 = 0;
+
+    let [r, g, b, _] = color.to_array();
+
+    for (metrics, bitmap) in &glyphs {
+        let glyph_x = cursor_x + metrics.xmin;
+        let glyph_y = baseline - metrics.height as i32 - metrics.ymin;
+
+        for gy in 0..metrics.height {
+            for gx in 0..metrics.width {
+                let px = glyph_x + gx as i32;
+                let py = glyph_y + gy as i32;
+
+                if px >= 0 && (px as usize) < total_width && py >= 0 && (py as usize) < img_height {
+                    let src_alpha = quality.coverage(bitmap[gy * metrics.width + gx]);
+                    let src = Color32::from_rgba_unmultiplied(r, g, b, src_alpha);
+                    let dst_idx = py as usize * total_width + px as usize;
+                    // Max blend for overlapping glyphs
+                    if src_alpha > horizontal[dst_idx].a() {
+                        horizontal[dst_idx] = src;
+                    }
+
+                    // Faux bold: render again at x+1
+                    if bold && (px + 1) < total_width as i32 {
+                        let bold_idx = py as usize * total_width + (px + 1) as usize;
+                        if src_alpha > horizontal[bold_idx].a() {
+                            horizontal[bold_idx] = src;
+                        }
+                    }
+                }
+            }
+        }
+        cursor_x += metrics.advance_width.ceil() as i32;
+    }
+
+    ColorImage {
+        size: [total_width, img_height],
+        pixels: horizontal,
+    }
+}
+
+/// Render text rotated 90° clockwise as a ColorImage.
+///
+/// Emulates the firmware's `fbdraw_text_rot90()`:
+/// 1. Compose the line horizontally ([`render_text_horizontal`])
+/// 2. Rotate 90° clockwise
+pub fn render_text_rotated_90(
+    text: &str,
+    font_size: f32,
+    color: Color32,
+    bold: bool,
+) -> ColorImage {
+    let horizontal = render_text_horizontal(text, font_size, color, bold);
+    let [width, height] = horizontal.size;
+
+    // Original: width=width, height=height
+    // Rotated:  width=height, height=width
+    let rot_width = height;
+    let rot_height = width;
+
+    let mut pixels = vec![Color32::TRANSPARENT; rot_width * rot_height];
+
+    for oy in 0..height {
+        for ox in 0..width {
+            let pixel = horizontal.pixels[oy * width + ox];
+            if pixel.a() > 0 {
+                // Clockwise 90°: (x, y) -> (height-1-y, x)
+                let rx = height - 1 - oy;
+                let ry = ox;
+                pixels[ry * rot_width + rx] = pixel;
+            }
+        }
+    }
+
+    ColorImage {
+        size: [rot_width, rot_height],
+        pixels,
+    }
+}
+
+/// Render text as upright characters stacked top to bottom.
+///
+/// Each character (or emoji cluster) keeps its normal orientation and is
+/// centered horizontally in the column, one line height per character.
+pub fn render_text_vertical_stacked(
+    text: &str,
+    font_size: f32,
+    color: Color32,
+    bold: bool,
+) -> ColorImage {
+    let mut cells: Vec<ColorImage> = Vec::new();
+    for segment in split_emoji_segments(text) {
+        match segment {
+            TextSegment::Text(run) => {
+                for ch in run.chars() {
+                    cells.push(render_text_horizontal(&ch.to_string(), font_size, color, bold));
+                }
+            }
+            TextSegment::Emoji(text) => {
+                cells.push(render_text_horizontal(&text, font_size, color, bold));
+            }
+        }
+    }
+
+    let width = cells.iter().map(|c| c.size[0]).max().unwrap_or(0);
+    let height: usize = cells.iter().map(|c| c.size[1]).sum();
+    if width == 0 || height == 0 {
+        return ColorImage::new([1, 1], Color32::TRANSPARENT);
+    }
+
+    let mut pixels = vec![Color32::TRANSPARENT; width * height];
+    let mut offset_y = 0;
+    for cell in &cells {
+        let [cw, ch] = cell.size;
+        let offset_x = (width - cw) / 2;
+        for y in 0..ch {
+            for x in 0..cw {
+                pixels[(offset_y + y) * width + offset_x + x] = cell.pixels[y * cw + x];
+            }
+        }
+        offset_y += ch;
+    }
+
+    ColorImage {
+        size: [width, height],
+        pixels,
+    }
+}
+
+/// Render text using the given orientation
+pub fn render_text_oriented(
+    text: &str,
+    font_size: f32,
+    color: Color32,
+    bold: bool,
+    orientation: TextOrientation,
+) -> ColorImage {
+    match orientation {
+        TextOrientation::Rot90 => render_text_rotated_90(text, font_size, color, bold),
+        TextOrientation::VerticalStacked => render_text_vertical_stacked(text, font_size, color, bold),
+        TextOrientation::Horizontal => render_text_horizontal(text, font_size, color, bold),
+    }
+}
+
+/// Render text for the top_right_bar area with split bold/regular rendering.
+///
+/// The firmware splits text at the first space:
+/// - Part before space: faux bold (double-rendered)
+/// - Part after space: regular
+/// Both parts are rendered rotated 90° and composed vertically.
+pub fn render_top_right_bar_text_rotated(
+    text: &str,
+    font_size: f32,
+    color: Color32,
+) -> ColorImage {
+    if let Some(space_idx) = text.find(' ') {
+        let bold_part = &text[..space_idx];
+        let regular_part = &text[space_idx + 1..];
+
+        let bold_img = render_text_rotated_90(bold_part, font_size, color, true);
+        let regular_img = render_text_rotated_90(regular_part, font_size, color, false);
+
+        // Combine vertically: bold on top, gap, then regular
+        let gap = 6; // pixels, matching firmware's space_gap
+        let combined_width = bold_img.size[0].max(regular_img.size[0]);
+        let combined_height = bold_img.size[1] + gap + regular_img.size[1];
+
+        let mut pixels = vec![Color32::TRANSPARENT; combined_width * combined_height];
+
+        // Copy bold part
+        for y in 0..bold_img.size[1] {
+            for x in 0..bold_img.size[0] {
+                if x < combined_width {
+                    pixels[y * combined_width + x] = bold_img.pixels[y * bold_img.size[0] + x];
+                }
+            }
+        }
+
+        // Copy regular part
+        let reg_offset_y = bold_img.size[1] + gap;
+        for y in 0..regular_img.size[1] {
+            for x in 0..regular_img.size[0] {
+                if x < combined_width && (reg_offset_y + y) < combined_height {
+                    pixels[(reg_offset_y + y) * combined_width + x] =
+                        regular_img.pixels[y * regular_img.size[0] + x];
+                }
+            }
+        }
+
+        ColorImage {
+            size: [combined_width, combined_height],
+            pixels,
+        }
+    } else {
+        // No space: render all as faux bold
+        render_text_rotated_90(text, font_size, color, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_basic_text() {
+        let img = render_text_rotated_90("TEST", 20.0, Color32::WHITE, false);
+        assert!(img.size[0] > 0);
+        assert!(img.size[1] > 0);
+    }
+
+    #[test]
+    fn test_render_bold_text() {
+        let img = render_text_rotated_90("BOLD", 20.0, Color32::WHITE, true);
+        assert!(img.size[0] > 0);
+        assert!(img.size[1] > 0);
+    }
+
+    #[test]
+    fn test_render_top_right_bar_with_space() {
+        let img = render_top_right_bar_text_rotated("RHODES ISLAND", 10.0, Color32::WHITE);
+        assert!(img.size[0] > 0);
+        assert!(img.size[1] > 0);
+    }
+
+    #[test]
+    fn test_render_top_right_bar_without_space() {
+        let img = render_top_right_bar_text_rotated("NOBREAK", 10.0, Color32::WHITE);
+        assert!(img.size[0] > 0);
+        assert!(img.size[1] > 0);
+    }
+
+    #[test]
+    fn test_split_emoji_segments() {
+        let segments = split_emoji_segments("Hi 😀!");
+        assert_eq!(segments, vec![
+            TextSegment::Text("Hi ".to_string()),
+            TextSegment::Emoji("😀".to_string()),
+            TextSegment::Text("!".to_string()),
+        ]);
+
+        // ZWJ sequence and skin tone stay in one cluster
+        assert_eq!(split_emoji_segments("👩🏽\u{200D}💻"), vec![TextSegment::Emoji("👩🏽\u{200D}💻".to_string())]);
+
+        // Regional indicator pair forms a flag
+        assert_eq!(split_emoji_segments("🇨🇳"), vec![TextSegment::Emoji("🇨🇳".to_string())]);
+
+        // Symbols are text unless asked to be emoji
+        assert_eq!(split_emoji_segments("© ® ↔ ★"), vec![TextSegment::Text("© ® ↔ ★".to_string())]);
+        assert_eq!(
+            split_emoji_segments("⭐☀\u{FE0F}"),
+            vec![TextSegment::Emoji("⭐".to_string()), TextSegment::Emoji("☀\u{FE0F}".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_render_emoji() {
+        // Emoji are drawn with the emoji font instead of disappearing
+        let img = render_text_horizontal("🚀", 20.0, Color32::WHITE, false);
+        assert!(img.pixels.iter().filter(|p| p.a() > 0).count() > 20);
+        assert!(std::ptr::eq(font_for('A'), get_font()));
+        assert!(!std::ptr::eq(font_for('🚀'), get_font()));
+    }
+
+    #[test]
+    fn test_render_orientations() {
+        let rot = render_text_oriented("AB", 20.0, Color32::WHITE, false, TextOrientation::Rot90);
+        let horizontal = render_text_oriented("AB", 20.0, Color32::WHITE, false, TextOrientation::Horizontal);
+        let stacked = render_text_oriented("AB", 20.0, Color32::WHITE, false, TextOrientation::VerticalStacked);
+
+        // Rotation swaps the axes of the horizontal line
+        assert_eq!(rot.size, [horizontal.size[1], horizontal.size[0]]);
+        // Stacked: one line height per character, upright
+        assert_eq!(stacked.size[1], horizontal.size[1] * 2);
+        assert!(stacked.size[0] < horizontal.size[0]);
+    }
+
+    #[test]
+    fn test_text_quality_coverage() {
+        let aliased = TextRenderQuality { antialias: false, hinting: false };
+        assert_eq!(aliased.coverage(127), 0);
+        assert_eq!(aliased.coverage(128), 255);
+
+        let hinted = TextRenderQuality { antialias: true, hinting: true };
+        assert_eq!(hinted.coverage(40), 0);
+        assert_eq!(hinted.coverage(200), 255);
+        assert_eq!(hinted.font_size(10.4), 10.0);
+
+        assert_eq!(TextRenderQuality::default().coverage(100), 100);
+    }
+
+    #[test]
+    fn test_render_empty_text() {
+        let img = render_text_rotated_90("", 20.0, Color32::WHITE, false);
+        // Should return a minimal image
+        assert!(img.size[0] >= 1);
+        assert!(img.size[1] >= 1);
+    }
+}
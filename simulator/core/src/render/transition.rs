@@ -4,7 +4,7 @@
 //! Corresponds to Python's core/transition_renderer.py
 
 use crate::config::{FirmwareConfig, TransitionType};
-use crate::app::state::TransitionPhase;
+use crate::state::TransitionPhase;
 use super::bezier::{ease_in, ease_out, ease_in_out, precompute_swipe_bezier};
 
 /// Transition renderer
@@ -0,0 +1,2986 @@
+//! Simulator
+//!
+//! A config played back and composited with egui: the playback state
+//! machine driving the videos and animations, the video frame with its
+//! transition effects, and the overlays painted over it. Nothing here needs
+//! a window; the egui app shows a simulator in its preview and
+//! `HeadlessRenderer` rasterizes one in software.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use web_time::{Duration, Instant};
+
+use egui::{Color32, Rect, Pos2, Vec2, Stroke, FontId, Align2};
+use image::RgbImage;
+use tracing::{info, warn};
+
+use crate::animation::{AnimationController, Milestone};
+use crate::config::{EPConfig, FirmwareConfig, EinkElementConfig, TransitionType, TransitionOptions, OverlayType, ArknightsOverlayOptions, ImageOverlayOptions, CustomOverlayOptions, Overlay, OverlayTemplateRegistry, PreviewConfig, TextOrientation};
+use crate::render::{AssetIssue, TransitionRenderer, OverlayRenderer, LayerRenderer, image_overlay_rect, image_overlay_visual, ImageLoader, generate_barcode, generate_vertical_barcode, generate_vertical_barcode_gradient, render_text_oriented, render_top_right_bar_text_rotated, set_text_quality, TextRenderQuality};
+use crate::state::{AutoReplay, EinkState, PlayState, SimulatorState, TransitionPhase};
+use crate::utils::{file_exists, parse_color, TemplateVars};
+use crate::video::VideoPlayer;
+
+/// Playback speed range accepted from configs
+pub const MIN_PLAYBACK_SPEED: f32 = 0.1;
+pub const MAX_PLAYBACK_SPEED: f32 = 4.0;
+
+/// Longest replay a seek may trigger (logic frames)
+pub const MAX_SEEK_FRAMES: u32 = 100_000;
+
+/// Frame pixels stay sharp when scaled up and are averaged when scaled down
+const FRAME_TEXTURE_OPTIONS: egui::TextureOptions = egui::TextureOptions {
+    magnification: egui::TextureFilter::Nearest,
+    ..egui::TextureOptions::LINEAR
+};
+
+/// Something that happened during playback, for a frontend to report
+///
+/// Recorded only while `set_record_events` is on, and never while seeking.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaybackEvent {
+    /// A logic frame passed
+    Frame { frame: u64 },
+    /// Playback moved from one state to another
+    StateChanged { from: PlayState, to: PlayState, frame: u64 },
+    /// A transition began (`state` is transition in or transition loop)
+    TransitionStarted { state: PlayState, transition: TransitionType, frames: u32, frame: u64 },
+    /// The intro finished and the loop transition begins
+    IntroEnded { frame: u64 },
+    /// The overlay animation reached a milestone
+    AnimationMilestone { milestone: Milestone, frame: u64 },
+    /// A video frame was decoded in `time`
+    FrameDecoded { time: Duration },
+    /// Video frames decoded in one update but never shown
+    FramesDropped { frames: u32 },
+}
+
+/// A config played back and composited into egui textures and shapes
+pub struct Simulator {
+    /// Firmware configuration (with per-material overrides applied)
+    firmware_config: FirmwareConfig,
+    /// Global firmware configuration, before per-material overrides
+    base_firmware_config: FirmwareConfig,
+    /// Current EP configuration
+    pub epconfig: Option<EPConfig>,
+    /// Base directory for assets
+    pub base_dir: PathBuf,
+    /// Application directory for program resources (modular assets, etc.)
+    app_dir: PathBuf,
+
+    /// Simulator state
+    pub state: SimulatorState,
+
+    /// Video player
+    pub video_player: VideoPlayer,
+
+    /// Transition renderer
+    transition_renderer: TransitionRenderer,
+    /// Overlay renderer
+    overlay_renderer: OverlayRenderer,
+    /// Custom overlay renderers, by position in the overlay stack
+    layer_renderers: HashMap<usize, LayerRenderer>,
+    /// Overlay templates from app_dir/resources/templates
+    overlay_templates: OverlayTemplateRegistry,
+    /// Animation controller
+    animation_controller: AnimationController,
+
+    /// Current frame texture
+    frame_texture: Option<egui::TextureHandle>,
+
+    /// Playback speed multiplier
+    pub playback_speed: f32,
+    /// Start straight with the loop video, ignoring the intro
+    pub skip_intro: bool,
+    /// Start in the pre-opinfo wait, skipping the intro and entry transitions
+    pub loop_only: bool,
+    /// Automatic restarts left before the loop plays on indefinitely
+    pub replays_remaining: u32,
+    /// Time in the loop state before an automatic restart (microseconds)
+    pub replay_after_us: i64,
+    /// Unlimited automatic restarts, independent of the config's replay count
+    pub auto_replay: AutoReplay,
+
+    /// Reusable color buffer to avoid allocations every frame
+    color_image_buffer: Vec<Color32>,
+
+    /// Whether the frame content has changed and needs re-rendering
+    pub frame_dirty: bool,
+
+    /// Transitions picked for the intro and the loop, by `transition_type_to_index`
+    pub selected_transition_in: usize,
+    pub selected_transition_loop: usize,
+
+    /// Is first transition (forces SWIPE)
+    is_first_transition: bool,
+
+    /// Events are recorded for `take_events`
+    record_events: bool,
+    /// Events since the last `take_events`
+    events: Vec<PlaybackEvent>,
+    /// Playback state last reported in a `StateChanged` event
+    reported_state: PlayState,
+
+    /// Image loader for textures
+    image_loader: ImageLoader,
+
+    /// Barcode texture (dynamically generated)
+    barcode_texture: Option<egui::TextureHandle>,
+
+    /// Secondary barcode texture (dynamically generated)
+    secondary_barcode_texture: Option<egui::TextureHandle>,
+
+    /// Class icon texture
+    class_icon_texture: Option<egui::TextureHandle>,
+
+    /// Logo texture
+    logo_texture: Option<egui::TextureHandle>,
+
+    /// Image overlay textures (for OverlayType::Image), keyed by image path
+    image_overlay_textures: HashMap<String, egui::TextureHandle>,
+
+    /// Transition image texture (for transition effect)
+    transition_image_texture: Option<egui::TextureHandle>,
+
+    /// Transition image raw pixel data (for direct pixel access during transition)
+    transition_image_data: Option<(Vec<Color32>, usize, usize)>, // (pixels, width, height)
+
+    /// AK progress bar image texture (ak_bar_image or res/ak_bar.png)
+    ak_bar_texture: Option<egui::TextureHandle>,
+
+    /// Top-right arrow image texture (from res/top_right_arrow.png)
+    top_right_arrow_texture: Option<egui::TextureHandle>,
+
+    /// Left upper L-shape black decoration (modular asset)
+    top_left_rect_texture: Option<egui::TextureHandle>,
+
+    /// Left upper Rhodes decoration below L-shape (modular asset)
+    top_left_rhodes_texture: Option<egui::TextureHandle>,
+
+    /// Right upper yellow bar + full vertical bar (modular asset)
+    top_right_bar_texture: Option<egui::TextureHandle>,
+
+    /// Left side colorful gradient bar (modular asset)
+    btm_left_bar_texture: Option<egui::TextureHandle>,
+
+    /// Pre-rendered rotated text texture for top_left_rhodes custom text
+    top_left_rhodes_text_texture: Option<egui::TextureHandle>,
+    /// Pre-rendered rotated text texture for top_right_bar custom text
+    top_right_bar_text_texture: Option<egui::TextureHandle>,
+    /// Cached text value to detect changes
+    cached_rhodes_text: String,
+    /// Cached orientation of top_left_rhodes text
+    cached_rhodes_orientation: TextOrientation,
+    /// Cached text value to detect changes
+    cached_top_right_bar_text: String,
+
+    /// Rasterization quality for pre-rendered rotated texts
+    text_quality: TextRenderQuality,
+
+    /// Whether textures have been loaded for current config
+    textures_loaded: bool,
+
+    /// `{serial}` shown instead of the config's serial
+    serial_override: Option<String>,
+}
+
+impl Simulator {
+    /// Set up a simulator for `config`, or for none yet
+    ///
+    /// Its videos are not opened; see `load_videos`.
+    pub fn new(
+        config: Option<EPConfig>,
+        base_dir: PathBuf,
+        app_dir: PathBuf,
+        cropbox: Option<(u32, u32, u32, u32)>,
+        rotation: i32,
+    ) -> Self {
+        let base_firmware_config = FirmwareConfig::get_default();
+        let firmware_config = match config {
+            Some(ref config) => Self::firmware_config_for(&base_firmware_config, config),
+            None => base_firmware_config.clone(),
+        };
+        let width = firmware_config.overlay_width();
+        let height = firmware_config.overlay_height();
+
+        // Overlay templates shipped as data
+        let overlay_templates = OverlayTemplateRegistry::load_from_dir(&app_dir.join("resources/templates"));
+
+        let mut state = SimulatorState::new();
+
+        // Set appear time from config if available
+        if let Some(ref config) = config {
+            let appear_us = config.get_appear_time();
+            state.appear_time_frames = microseconds_to_frames(appear_us, firmware_config.fps());
+        }
+
+        info!(
+            "Simulator initialized: {}x{} @ {}fps",
+            width, height,
+            firmware_config.fps()
+        );
+
+        // Read transition settings from config
+        let (selected_transition_in, selected_transition_loop) = if let Some(ref config) = config {
+            let trans_in = config.get_transition_in_type();
+            let trans_loop = config.get_transition_loop_type();
+            info!("Transition settings from config: in={:?}, loop={:?}", trans_in, trans_loop);
+            (
+                Self::transition_type_to_index(trans_in),
+                Self::transition_type_to_index(trans_loop),
+            )
+        } else {
+            (0, 0) // Default to Fade
+        };
+
+        // Pre-allocate color buffer for frame rendering
+        let buffer_size = (width * height) as usize;
+
+        let mut animation_controller = AnimationController::new(firmware_config.clone());
+        animation_controller.set_secondary_barcode(config.as_ref().and_then(Self::secondary_barcode_eink));
+
+        let mut simulator = Self {
+            firmware_config: firmware_config.clone(),
+            base_firmware_config,
+            epconfig: config,
+            base_dir: base_dir.clone(),
+            app_dir,
+            state,
+            video_player: VideoPlayer::new(width, height, cropbox, rotation),
+            transition_renderer: TransitionRenderer::new(firmware_config.clone()),
+            overlay_renderer: OverlayRenderer::new(firmware_config),
+            layer_renderers: HashMap::new(),
+            overlay_templates,
+            animation_controller,
+            frame_texture: None,
+            playback_speed: 1.0,
+            skip_intro: false,
+            loop_only: false,
+            replays_remaining: 0,
+            replay_after_us: 0,
+            auto_replay: AutoReplay::Off,
+            color_image_buffer: Vec::with_capacity(buffer_size),
+            frame_dirty: true,
+            selected_transition_in,
+            selected_transition_loop,
+            is_first_transition: true,
+            record_events: false,
+            events: Vec::new(),
+            reported_state: PlayState::Idle,
+            image_loader: ImageLoader::new(base_dir),
+            barcode_texture: None,
+            secondary_barcode_texture: None,
+            class_icon_texture: None,
+            logo_texture: None,
+            image_overlay_textures: HashMap::new(),
+            transition_image_texture: None,
+            transition_image_data: None,
+            ak_bar_texture: None,
+            top_right_arrow_texture: None,
+            top_left_rect_texture: None,
+            top_left_rhodes_texture: None,
+            top_right_bar_texture: None,
+            btm_left_bar_texture: None,
+            top_left_rhodes_text_texture: None,
+            top_right_bar_text_texture: None,
+            cached_rhodes_text: String::new(),
+            cached_rhodes_orientation: TextOrientation::default(),
+            cached_top_right_bar_text: String::new(),
+            text_quality: TextRenderQuality::default(),
+            textures_loaded: false,
+            serial_override: None,
+        };
+        simulator.apply_preview_defaults();
+        simulator
+    }
+
+    /// Open the videos of the current config, returning the loop video error if any
+    ///
+    /// Blocks until they are open, which can take a while for large files.
+    pub fn load_videos(&mut self) -> Option<String> {
+        let config = self.epconfig.as_ref()?;
+        let error = self.video_player.load_from_config(config, &self.base_dir);
+        self.frame_dirty = true;
+        error
+    }
+
+    /// Show `config`, returning true if the screen size changed
+    ///
+    /// Playback is reset and textures are reloaded. With `videos_loaded`,
+    /// the video player already holds the config's videos; otherwise it is
+    /// left for the caller to open them (see `load_videos`).
+    pub fn set_config(&mut self, config: EPConfig, base_dir: PathBuf, videos_loaded: bool) -> bool {
+        // Apply per-material firmware overrides
+        let firmware_config = Self::firmware_config_for(&self.base_firmware_config, &config);
+        let resized = (firmware_config.overlay_width(), firmware_config.overlay_height())
+            != (self.firmware_config.overlay_width(), self.firmware_config.overlay_height());
+        if resized {
+            let (width, height) = (firmware_config.overlay_width(), firmware_config.overlay_height());
+            info!("Screen size changed to {}x{}", width, height);
+            if !videos_loaded {
+                self.video_player.set_target_size(width, height);
+            }
+            self.frame_texture = None;
+            self.color_image_buffer = Vec::new();
+        }
+        self.apply_firmware_config(firmware_config);
+        self.animation_controller.set_secondary_barcode(Self::secondary_barcode_eink(&config));
+
+        // Update appear time
+        let appear_us = config.get_appear_time();
+        self.state.appear_time_frames = microseconds_to_frames(appear_us, self.firmware_config.fps());
+        self.image_loader.take_failures();
+
+        // Apply transition settings from config
+        let trans_in = config.get_transition_in_type();
+        if trans_in != TransitionType::None {
+            self.selected_transition_in = Self::transition_type_to_index(trans_in);
+        }
+        let trans_loop = config.get_transition_loop_type();
+        if trans_loop != TransitionType::None {
+            self.selected_transition_loop = Self::transition_type_to_index(trans_loop);
+        }
+
+        self.epconfig = Some(config);
+        self.base_dir = base_dir.clone();
+        self.apply_preview_defaults();
+        self.reset_playback();
+
+        // Reset textures for new config
+        self.image_loader.set_base_dir(base_dir);
+        self.layer_renderers.clear();
+        self.reload_overlay_templates();
+        self.barcode_texture = None;
+        self.secondary_barcode_texture = None;
+        self.class_icon_texture = None;
+        self.logo_texture = None;
+        self.image_overlay_textures.clear();
+        self.transition_image_texture = None;
+        self.transition_image_data = None;
+        self.ak_bar_texture = None;
+        self.top_right_arrow_texture = None;
+        self.top_left_rect_texture = None;
+        self.top_left_rhodes_texture = None;
+        self.top_right_bar_texture = None;
+        self.btm_left_bar_texture = None;
+        self.top_left_rhodes_text_texture = None;
+        self.top_right_bar_text_texture = None;
+        self.cached_rhodes_text.clear();
+        self.cached_rhodes_orientation = TextOrientation::default();
+        self.cached_top_right_bar_text.clear();
+        self.textures_loaded = false;
+        self.frame_dirty = true;
+        resized
+    }
+
+    /// Global firmware configuration, before per-material overrides
+    pub fn base_firmware_config(&self) -> &FirmwareConfig {
+        &self.base_firmware_config
+    }
+
+    /// Firmware configuration, with the current config's overrides applied
+    pub fn firmware_config(&self) -> &FirmwareConfig {
+        &self.firmware_config
+    }
+
+    /// Application directory the bundled resources are read from
+    pub fn app_dir(&self) -> &Path {
+        &self.app_dir
+    }
+
+    /// Size of the device screen, which frames are rendered at
+    pub fn frame_size(&self) -> [u32; 2] {
+        [self.firmware_config.overlay_width(), self.firmware_config.overlay_height()]
+    }
+
+    /// Logic frame rate of the firmware
+    pub fn fps(&self) -> u32 {
+        self.firmware_config.fps()
+    }
+
+    /// Length of one logic frame
+    pub fn step_time_us(&self) -> i64 {
+        self.firmware_config.animation.step_time_us as i64
+    }
+
+    /// Rasterization quality of pre-rendered overlay texts
+    pub fn text_quality(&self) -> TextRenderQuality {
+        self.text_quality
+    }
+
+    /// Record playback events for `take_events`, or stop recording them
+    pub fn set_record_events(&mut self, enabled: bool) {
+        self.record_events = enabled;
+    }
+
+    /// Events recorded since the last call, oldest first
+    pub fn take_events(&mut self) -> Vec<PlaybackEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn record(&mut self, event: PlaybackEvent) {
+        if self.record_events {
+            self.events.push(event);
+        }
+    }
+
+    /// Asset load failures since the last call, from videos and images alike
+    pub fn take_asset_failures(&mut self) -> Vec<AssetIssue> {
+        let mut failures = self.video_player.take_failures();
+        failures.extend(self.image_loader.take_failures());
+        failures
+    }
+
+    /// Lay out the global firmware config for a material's screen and merge
+    /// its firmware overrides over it
+    ///
+    /// Invalid overrides are logged and ignored.
+    pub fn firmware_config_for(base: &FirmwareConfig, config: &EPConfig) -> FirmwareConfig {
+        let (width, height) = config.screen.dimensions();
+        let mut firmware_config = base.for_resolution(width, height);
+        if let Some(ref layout) = config.layout {
+            match firmware_config.with_layout_override(layout) {
+                Ok(merged) => firmware_config = merged,
+                Err(e) => warn!("Ignoring invalid layout override: {}", e),
+            }
+        }
+        if let Some(ref animation) = config.animation {
+            match firmware_config.with_animation_override(animation) {
+                Ok(merged) => firmware_config = merged,
+                Err(e) => warn!("Ignoring invalid animation override: {}", e),
+            }
+        }
+        firmware_config
+    }
+
+    /// Apply a partial Arknights overlay options patch without resetting playback
+    ///
+    /// Only textures generated from changed fields are invalidated; text and
+    /// colors are read every frame and need no invalidation.
+    pub fn update_overlay(&mut self, patch: &serde_json::Value) -> anyhow::Result<()> {
+        let old = self.get_arknights_options().unwrap_or_default();
+        let config = self
+            .epconfig
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("No configuration loaded"))?;
+        config.update_arknights_options(patch)?;
+        let eink = Self::secondary_barcode_eink(config);
+        let new = self.get_arknights_options().unwrap_or_default();
+
+        if old.barcode_text != new.barcode_text || old.color2.is_empty() != new.color2.is_empty() {
+            self.barcode_texture = None;
+        }
+        let secondary_text = |o: &ArknightsOverlayOptions| {
+            o.secondary_barcode.as_ref().map(|b| (b.text.clone(), b.width, b.height, b.vertical))
+        };
+        if secondary_text(&old) != secondary_text(&new) {
+            self.secondary_barcode_texture = None;
+        }
+        self.animation_controller.set_secondary_barcode(eink);
+        if old.operator_class_icon != new.operator_class_icon || old.operator_class != new.operator_class {
+            self.class_icon_texture = None;
+        }
+        if old.logo != new.logo {
+            self.logo_texture = None;
+        }
+        if old.ak_bar_image != new.ak_bar_image {
+            self.ak_bar_texture = None;
+        }
+        if old.appear_time != new.appear_time {
+            self.state.appear_time_frames = microseconds_to_frames(new.appear_time, self.firmware_config.fps());
+        }
+
+        self.textures_loaded = false;
+        self.frame_dirty = true;
+        Ok(())
+    }
+
+    /// EINK timing of the config's secondary barcode, if it has one
+    pub fn secondary_barcode_eink(config: &EPConfig) -> Option<EinkElementConfig> {
+        config.arknights_options()?.secondary_barcode.map(|barcode| barcode.eink)
+    }
+
+    /// Replace the firmware config and rebuild everything derived from it
+    fn apply_firmware_config(&mut self, firmware_config: FirmwareConfig) {
+        self.transition_renderer = TransitionRenderer::new(firmware_config.clone());
+        self.overlay_renderer = OverlayRenderer::new(firmware_config.clone());
+        self.animation_controller = AnimationController::new(firmware_config.clone());
+        self.firmware_config = firmware_config;
+    }
+
+    /// Rescan overlay templates so newly added files are picked up
+    pub fn reload_overlay_templates(&mut self) {
+        self.overlay_templates = OverlayTemplateRegistry::load_from_dir(&self.app_dir.join("resources/templates"));
+
+        let Some(config) = self.epconfig.as_ref() else {
+            return;
+        };
+        for options in config.overlay_stack().iter().filter_map(|o| o.custom_options()) {
+            if !options.template.is_empty() && self.overlay_templates.get(&options.template).is_none() {
+                warn!(
+                    "Overlay template '{}' not found (available: {:?})",
+                    options.template,
+                    self.overlay_templates.names()
+                );
+            }
+        }
+    }
+
+
+    /// Get transition type from index
+    pub fn transition_type_from_index(index: usize) -> TransitionType {
+        match index {
+            0 => TransitionType::Fade,
+            1 => TransitionType::Move,
+            2 => TransitionType::Swipe,
+            _ => TransitionType::None,
+        }
+    }
+
+    /// Get index from transition type
+    pub fn transition_type_to_index(trans_type: TransitionType) -> usize {
+        match trans_type {
+            TransitionType::Fade => 0,
+            TransitionType::Move => 1,
+            TransitionType::Swipe => 2,
+            TransitionType::None => 3,
+        }
+    }
+
+    /// Get transition frames
+    pub fn get_transition_frames(&self, is_intro: bool) -> u32 {
+        let fps = self.firmware_config.fps();
+        let default_frames = self.firmware_config.transition.default_frames;
+
+        if let Some(ref config) = self.epconfig {
+            let duration = if is_intro {
+                config.get_transition_in_duration()
+            } else {
+                config.get_transition_loop_duration()
+            };
+
+            if duration > 0 {
+                // Total duration = 3 × stage duration
+                let stage_frames = microseconds_to_frames(duration, fps);
+                return stage_frames * 3;
+            }
+        }
+
+        default_frames
+    }
+
+    /// Playback defaults of the current config
+    pub fn preview_config(&self) -> PreviewConfig {
+        self.epconfig
+            .as_ref()
+            .and_then(|config| config.preview.clone())
+            .unwrap_or_default()
+    }
+
+    /// Take speed, intro skipping and auto-replay from the current config
+    pub fn apply_preview_defaults(&mut self) {
+        let preview = self.preview_config();
+        self.playback_speed = preview.speed.clamp(MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED);
+        self.skip_intro = preview.skip_intro;
+        self.replays_remaining = preview.replay_count;
+        self.replay_after_us = preview.replay_after;
+    }
+
+    /// Set the playback speed multiplier, clamped to the supported range
+    pub fn set_playback_speed(&mut self, speed: f32) {
+        if !(speed.is_finite() && speed > 0.0) {
+            warn!("Ignoring invalid playback speed {}", speed);
+            return;
+        }
+        self.playback_speed = speed.clamp(MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED);
+    }
+
+    /// Change the loop video cropbox and rotation, reopening its decoder
+    ///
+    /// Playback keeps its state; the loop video restarts from its first frame.
+    /// Returns the loop video error, if it fails to reopen.
+    pub fn set_loop_transform(&mut self, cropbox: Option<(u32, u32, u32, u32)>, rotation: i32) -> Option<String> {
+        info!("Loop video cropbox: {:?}, rotation: {}", cropbox, rotation);
+        self.video_player.set_loop_transform(cropbox, rotation);
+        let config = self.epconfig.as_ref()?;
+        let error = self.video_player.load_loop_video(config, &self.base_dir);
+        self.frame_dirty = true;
+        error
+    }
+
+    /// Start playback
+    pub fn start_playback(&mut self) {
+        if self.loop_only {
+            self.start_playback_at_loop();
+            return;
+        }
+        let has_intro = self.video_player.has_intro() && !self.skip_intro;
+
+        // Firmware behavior: first transition is always SWIPE
+        let transition_type = if self.is_first_transition {
+            self.is_first_transition = false;
+            TransitionType::Swipe
+        } else {
+            Self::transition_type_from_index(
+                if has_intro { self.selected_transition_in } else { self.selected_transition_loop }
+            )
+        };
+
+        let total_frames = self.get_transition_frames(has_intro);
+
+        self.state.start_playback(has_intro, transition_type, total_frames);
+        self.animation_controller.reset();
+
+        // Reset frame accumulators for FPS sync
+        self.state.loop_frame_accumulator = 0;
+        self.state.intro_frame_accumulator = 0;
+        self.state.intro_played_us = 0;
+
+        // Prepare videos
+        if has_intro {
+            self.video_player.seek_intro_to_start();
+        }
+        self.video_player.seek_loop_to_start();
+
+        self.frame_dirty = true;
+        info!("Playback started: has_intro={}, transition={:?}", has_intro, transition_type);
+    }
+
+    /// Start playback as if the entry transition had just finished
+    ///
+    /// Overlay tweaks only show in the loop, so waiting through the intro and
+    /// transitions on every restart slows down iteration.
+    pub fn start_playback_at_loop(&mut self) {
+        let total_frames = self.get_transition_frames(false);
+        self.state.start_playback(false, TransitionType::None, total_frames);
+        self.state.play_state = PlayState::PreOpinfo;
+        self.state.pre_opinfo_counter = 0;
+        self.animation_controller.reset();
+
+        self.state.loop_frame_accumulator = 0;
+        self.state.intro_frame_accumulator = 0;
+        self.state.intro_played_us = 0;
+        self.video_player.seek_loop_to_start();
+
+        self.frame_dirty = true;
+        info!("Playback started in loop-only mode");
+    }
+
+    /// Reset playback
+    pub fn reset_playback(&mut self) {
+        self.state.reset();
+        self.animation_controller.reset();
+        self.video_player.reset();
+        self.is_first_transition = true;
+        self.replays_remaining = self.preview_config().replay_count;
+        self.frame_dirty = true;
+        info!("Playback reset");
+    }
+
+    /// Show `serial` as `{serial}` whatever config is loaded, or the config's own with None
+    pub fn set_serial(&mut self, serial: Option<String>) {
+        self.serial_override = serial;
+        // Barcodes may encode a text showing the serial
+        self.barcode_texture = None;
+        self.secondary_barcode_texture = None;
+        self.textures_loaded = false;
+        self.frame_dirty = true;
+    }
+
+    /// Template variables of `config`, with the serial override applied
+    fn template_vars(&self, config: &EPConfig) -> TemplateVars {
+        let mut vars = TemplateVars::from_config(config);
+        if let Some(ref serial) = self.serial_override {
+            vars.set("serial", serial);
+        }
+        vars
+    }
+
+
+    /// Replay from the start in fixed logic steps until `done` holds
+    ///
+    /// Deterministic regardless of wall-clock timing. Events and
+    /// auto-replay are suppressed meanwhile. Returns false if `done` does not
+    /// hold within `MAX_SEEK_FRAMES`.
+    pub fn fast_forward(&mut self, mut done: impl FnMut(&SimulatorState) -> bool) -> bool {
+        let record_events = std::mem::take(&mut self.record_events);
+        self.reset_playback();
+        self.start_playback();
+        let replays_remaining = std::mem::take(&mut self.replays_remaining);
+        let auto_replay = std::mem::take(&mut self.auto_replay);
+        let step_us = self.firmware_config.animation.step_time_us as i64;
+
+        let mut reached = done(&self.state);
+        for _ in 0..MAX_SEEK_FRAMES {
+            if reached {
+                break;
+            }
+            self.update_simulation(step_us);
+            reached = done(&self.state);
+        }
+
+        self.record_events = record_events;
+        self.replays_remaining = replays_remaining;
+        self.auto_replay = auto_replay;
+        reached
+    }
+
+    /// Simulate `elapsed_us` of playback with events and auto-replay suppressed
+    pub fn advance_detached(&mut self, elapsed_us: i64) {
+        let record_events = std::mem::take(&mut self.record_events);
+        let replays_remaining = std::mem::take(&mut self.replays_remaining);
+        let auto_replay = std::mem::take(&mut self.auto_replay);
+        self.state.resume();
+        self.update_simulation(elapsed_us);
+        self.record_events = record_events;
+        self.replays_remaining = replays_remaining;
+        self.auto_replay = auto_replay;
+    }
+
+    /// Replay from the start to `time_us` of playback
+    pub fn seek_to_time(&mut self, time_us: i64) {
+        let step_us = self.firmware_config.animation.step_time_us as i64;
+        let mut steps = time_us / step_us;
+        self.fast_forward(|_| {
+            steps -= 1;
+            steps < 0
+        });
+        self.advance_detached(time_us % step_us);
+    }
+
+    /// Seek to logic frame `frame` of `play_state`, counted from entering it
+    pub fn seek_to_frame(&mut self, play_state: PlayState, frame: u64) -> anyhow::Result<()> {
+        if play_state == PlayState::Idle {
+            self.reset_playback();
+            return Ok(());
+        }
+        let mut entered_at = None;
+        let reached = self.fast_forward(|state| {
+            if state.play_state != play_state {
+                // Loop never ends, so a state not seen by then never comes
+                return entered_at.is_some() || state.play_state == PlayState::Loop;
+            }
+            let entered = *entered_at.get_or_insert(state.frame_counter);
+            state.frame_counter - entered >= frame
+        });
+        if !reached || self.state.play_state != play_state {
+            match entered_at {
+                Some(entered) => anyhow::bail!(
+                    "{} lasts {} frames, frame {} requested",
+                    play_state.display_name(),
+                    self.state.frame_counter - entered,
+                    frame
+                ),
+                None => anyhow::bail!("{} is not reached with this config", play_state.display_name()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Pause if playing, otherwise start or resume playback
+    pub fn toggle_playback(&mut self) {
+        if self.state.is_playing {
+            self.state.pause();
+            self.frame_dirty = true;
+        } else if self.state.play_state == PlayState::Idle {
+            self.start_playback();
+        } else {
+            self.state.resume();
+        }
+    }
+
+
+
+    /// Pause and move `frames` logic frames forward, or back if negative
+    ///
+    /// Rewinding replays from the start, as the simulation only runs forward.
+    /// At most `MAX_SEEK_FRAMES` are stepped either way.
+    pub fn step_frames(&mut self, frames: i32) {
+        let frames = clamp_step(frames);
+        if self.state.play_state == PlayState::Idle {
+            if frames <= 0 {
+                return;
+            }
+            self.start_playback();
+        }
+        if frames < 0 {
+            let target = self.state.frame_counter.saturating_sub(frames.unsigned_abs() as u64);
+            self.fast_forward(|state| state.frame_counter >= target);
+        } else {
+            let step_us = self.firmware_config.animation.step_time_us as i64;
+            self.state.resume();
+            for _ in 0..frames {
+                self.update_simulation(step_us);
+            }
+        }
+        self.state.pause();
+        self.frame_dirty = true;
+    }
+
+    /// Report a change of playback state since the last report
+    ///
+    /// Called after every logic frame; call it after changing the state
+    /// from outside as well. Nothing is reported while seeking, so a seek
+    /// shows up as one change.
+    pub fn emit_state_events(&mut self) {
+        let (from, to) = (self.reported_state, self.state.play_state);
+        if from == to || !self.record_events {
+            return;
+        }
+        self.reported_state = to;
+
+        let frame = self.state.frame_counter;
+        self.record(PlaybackEvent::StateChanged { from, to, frame });
+        if from == PlayState::Intro && to == PlayState::TransitionLoop {
+            self.record(PlaybackEvent::IntroEnded { frame });
+        }
+        if matches!(to, PlayState::TransitionIn | PlayState::TransitionLoop) {
+            self.record(PlaybackEvent::TransitionStarted {
+                state: to,
+                transition: self.state.transition.transition_type,
+                frames: self.state.transition.total_frames,
+                frame,
+            });
+        }
+    }
+
+    /// Update simulation state
+    pub fn update_simulation(&mut self, elapsed_us: i64) {
+        if !self.state.is_playing {
+            return;
+        }
+
+        let step_us = self.firmware_config.animation.step_time_us as i64;
+
+        // Accumulate wall-clock time, step N logic frames
+        self.state.logic_time_remainder_us += elapsed_us;
+        let logic_ticks = (self.state.logic_time_remainder_us / step_us) as u32;
+        self.state.logic_time_remainder_us %= step_us;
+
+        for _ in 0..logic_ticks {
+            self.state.frame_counter += 1;
+
+            match self.state.play_state {
+                PlayState::TransitionIn => self.process_transition_in(),
+                PlayState::Intro => {} // video advanced below via wall-clock
+                PlayState::TransitionLoop => self.process_transition_loop(),
+                PlayState::PreOpinfo => {
+                    self.state.pre_opinfo_counter += 1;
+                    if self.state.pre_opinfo_counter >= self.state.appear_time_frames {
+                        self.state.play_state = PlayState::Loop;
+                        self.animation_controller.reset();
+                        self.animation_controller.start_entry_animation();
+                    }
+                }
+                PlayState::Loop => {
+                    let frame = self.state.frame_counter;
+                    for milestone in self.animation_controller.update(&mut self.state.animation) {
+                        self.record(PlaybackEvent::AnimationMilestone { milestone, frame });
+                    }
+                }
+                PlayState::Idle => {}
+            }
+            self.emit_state_events();
+            self.record(PlaybackEvent::Frame { frame: self.state.frame_counter });
+        }
+
+        self.state.played_us += elapsed_us;
+        let loop_done = self.state.advance_loop_clock(elapsed_us, self.replay_after_us);
+        if self.replays_remaining > 0 && loop_done {
+            self.replays_remaining -= 1;
+            info!("Auto-replay ({} left)", self.replays_remaining);
+            self.start_playback();
+            return;
+        }
+        if self.auto_replay.is_due(&self.state) {
+            info!("Auto-replay ({:?})", self.auto_replay);
+            self.start_playback();
+            return;
+        }
+
+        // Video frame advancement uses wall-clock elapsed (not logic ticks)
+        match self.state.play_state {
+            PlayState::Intro => self.advance_intro_video(elapsed_us),
+            PlayState::PreOpinfo | PlayState::Loop => self.advance_loop_video(elapsed_us),
+            _ => {}
+        }
+    }
+
+    fn process_transition_in(&mut self) {
+        self.state.transition.frame += 1;
+        let phase = self.state.transition.phase();
+
+        // Switch video during hold phase
+        if phase == TransitionPhase::PhaseHold && !self.state.transition.video_switched {
+            self.state.transition.video_switched = true;
+            self.video_player.seek_intro_to_start();
+        }
+
+        // Transition complete
+        if self.state.transition.is_complete() {
+            self.state.play_state = PlayState::Intro;
+            self.state.intro_frame_accumulator = 0;  // Reset for FPS sync
+            self.state.intro_played_us = 0;
+            self.video_player.seek_intro_to_start();
+        }
+    }
+
+    /// Configured intro length in microseconds (None = until the video ends)
+    fn intro_duration_limit(&self) -> Option<i64> {
+        self.epconfig
+            .as_ref()
+            .and_then(|config| config.intro.as_ref())
+            .map(|intro| intro.duration)
+            .filter(|duration| *duration > 0)
+    }
+
+    /// Advance intro video frames based on wall-clock elapsed time
+    ///
+    /// Like the firmware, the intro ends at the configured duration or the
+    /// end of the video, whichever comes first.
+    fn advance_intro_video(&mut self, elapsed_us: i64) {
+        let video_fps = self.video_player.intro_fps();
+        let frame_duration_us = (1_000_000.0 / video_fps) as i64;
+        let limit_us = self.intro_duration_limit();
+
+        self.state.intro_frame_accumulator += elapsed_us;
+
+        let mut advanced = 0;
+        while self.state.intro_frame_accumulator >= frame_duration_us {
+            self.state.intro_frame_accumulator -= frame_duration_us;
+            if !self.state.advance_intro_clock(frame_duration_us, limit_us) || !self.advance_video_frame(true) {
+                self.start_transition_loop();
+                return;
+            }
+            advanced += 1;
+        }
+        self.record_dropped_frames(advanced);
+    }
+
+    /// Decode the next intro or loop video frame, timing it for the metrics
+    fn advance_video_frame(&mut self, intro: bool) -> bool {
+        let started = Instant::now();
+        let read = if intro {
+            self.video_player.advance_intro_frame()
+        } else {
+            self.video_player.advance_loop_frame()
+        };
+        // Seeks run without recording and would skew live timings
+        self.record(PlaybackEvent::FrameDecoded { time: started.elapsed() });
+        read
+    }
+
+    /// Count all but the last of `advanced` frames decoded in one update as dropped
+    fn record_dropped_frames(&mut self, advanced: u32) {
+        if advanced > 1 {
+            self.record(PlaybackEvent::FramesDropped { frames: advanced - 1 });
+        }
+    }
+
+    fn start_transition_loop(&mut self) {
+        self.state.play_state = PlayState::TransitionLoop;
+        let transition_type = Self::transition_type_from_index(self.selected_transition_loop);
+        let total_frames = self.get_transition_frames(false);
+        self.state.transition.reset(transition_type, total_frames);
+    }
+
+    fn process_transition_loop(&mut self) {
+        self.state.transition.frame += 1;
+        let phase = self.state.transition.phase();
+
+        // Switch video during hold phase
+        if phase == TransitionPhase::PhaseHold && !self.state.transition.video_switched {
+            self.state.transition.video_switched = true;
+            self.video_player.seek_loop_to_start();
+        }
+
+        // Transition complete
+        if self.state.transition.is_complete() {
+            self.state.play_state = PlayState::PreOpinfo;
+            self.state.pre_opinfo_counter = 0;
+            self.state.loop_frame_accumulator = 0;  // Reset for FPS sync
+            self.video_player.seek_loop_to_start();
+        }
+    }
+
+    /// Advance loop video frames based on wall-clock elapsed time
+    fn advance_loop_video(&mut self, elapsed_us: i64) {
+        let video_fps = self.video_player.loop_fps();
+        let frame_duration_us = (1_000_000.0 / video_fps) as i64;
+
+        self.state.loop_frame_accumulator += elapsed_us;
+
+        let mut advanced = 0;
+        while self.state.loop_frame_accumulator >= frame_duration_us {
+            self.state.loop_frame_accumulator -= frame_duration_us;
+            self.advance_video_frame(false);
+            advanced += 1;
+        }
+        self.record_dropped_frames(advanced);
+    }
+
+    /// Update a color buffer from an RgbImage
+    /// Takes the buffer as a separate parameter to avoid borrow checker issues
+    fn update_color_buffer(buffer: &mut Vec<Color32>, img: &RgbImage) {
+        let pixels = img.as_raw();
+        let len = img.width() as usize * img.height() as usize;
+
+        // Clear and reuse the existing buffer
+        buffer.clear();
+
+        // Reserve capacity if needed (only allocates if buffer is too small)
+        if buffer.capacity() < len {
+            buffer.reserve(len - buffer.capacity());
+        }
+
+        // Convert RGB pixels to Color32
+        for i in 0..len {
+            let idx = i * 3;
+            buffer.push(Color32::from_rgb(
+                pixels[idx],
+                pixels[idx + 1],
+                pixels[idx + 2],
+            ));
+        }
+    }
+
+    /// Fill color buffer with black pixels
+    fn fill_color_buffer_black(buffer: &mut Vec<Color32>, width: usize, height: usize) {
+        let len = width * height;
+        buffer.clear();
+        if buffer.capacity() < len {
+            buffer.reserve(len - buffer.capacity());
+        }
+        buffer.resize(len, Color32::BLACK);
+    }
+
+    /// Render the current frame
+    pub fn render_frame(&mut self, ctx: &egui::Context) {
+        let width = self.firmware_config.overlay_width() as usize;
+        let height = self.firmware_config.overlay_height() as usize;
+
+        // Determine frame source based on current state (avoids multiple borrows)
+        enum FrameSource {
+            Loop,
+            Intro,
+            Black,
+        }
+
+        let source = match self.state.play_state {
+            PlayState::Idle => FrameSource::Loop,
+            PlayState::TransitionIn => FrameSource::Loop,
+            PlayState::Intro => FrameSource::Intro,
+            PlayState::TransitionLoop => {
+                if self.state.transition.video_switched {
+                    FrameSource::Loop
+                } else if self.video_player.has_intro() {
+                    FrameSource::Intro
+                } else {
+                    FrameSource::Loop
+                }
+            }
+            PlayState::PreOpinfo | PlayState::Loop => FrameSource::Loop,
+        };
+
+        // Frames decoded before a screen size change are treated as missing
+        let fits = |f: &RgbImage| f.width() as usize == width && f.height() as usize == height;
+
+        // Update color buffer from the appropriate frame source (using references, no clone)
+        let has_frame = match source {
+            FrameSource::Loop => {
+                if let Some(frame) = self.video_player.get_loop_current_frame().filter(|f| fits(f)) {
+                    Self::update_color_buffer(&mut self.color_image_buffer, frame);
+                    true
+                } else {
+                    false
+                }
+            }
+            FrameSource::Intro => {
+                if let Some(frame) = self.video_player.get_intro_last_frame().filter(|f| fits(f)) {
+                    Self::update_color_buffer(&mut self.color_image_buffer, frame);
+                    true
+                } else {
+                    false
+                }
+            }
+            FrameSource::Black => false,
+        };
+
+        // Fill with black if no frame available
+        if !has_frame {
+            Self::fill_color_buffer_black(&mut self.color_image_buffer, width, height);
+        }
+
+        // Create ColorImage from the buffer
+        // We clone here because egui needs ownership, but the buffer retains its capacity for reuse
+        // The main memory savings come from not cloning RgbImage (2.7MB per frame saved)
+        let mut image = egui::ColorImage {
+            size: [width, height],
+            pixels: self.color_image_buffer.clone(),
+        };
+
+        // Apply transition effect if in transition state
+        if matches!(self.state.play_state, PlayState::TransitionIn | PlayState::TransitionLoop) {
+            self.apply_transition_overlay(&mut image);
+        }
+
+        // If in loop state with arknights overlay, render color fade at pixel level
+        if self.state.play_state == PlayState::Loop {
+            if let Some(ref config) = self.epconfig {
+                let show_color_fade = config.arknights_options().is_some_and(|o| o.show_color_fade);
+                if show_color_fade {
+                    self.render_color_fade(&mut image.pixels, width, height);
+                }
+            }
+        }
+
+        // Update texture
+        if let Some(ref mut texture) = self.frame_texture {
+            texture.set(image, FRAME_TEXTURE_OPTIONS);
+        } else {
+            self.frame_texture = Some(ctx.load_texture(
+                "frame",
+                image,
+                FRAME_TEXTURE_OPTIONS,
+            ));
+        }
+    }
+
+    /// Hold-phase fill from the transition image, letterboxed on the background color
+    ///
+    /// None when the transition has no image (or it failed to load), in which
+    /// case the background color alone is used. See `TransitionOptions`.
+    fn transition_image_fill(&self, options: Option<&TransitionOptions>, bg_color: Color32, width: usize, height: usize) -> Option<Vec<Color32>> {
+        if !options.is_some_and(|o| o.has_image()) {
+            return None;
+        }
+        let (ref trans_pixels, trans_width, trans_height) = *self.transition_image_data.as_ref()?;
+
+        // Calculate aspect-ratio-preserving scale (contain mode, centered)
+        let screen_aspect = width as f32 / height as f32;
+        let image_aspect = trans_width as f32 / trans_height as f32;
+
+        let (scaled_w, scaled_h, offset_x, offset_y) = if image_aspect > screen_aspect {
+            // Image is wider - fit to width
+            let scaled_w = width as f32;
+            let scaled_h = width as f32 / image_aspect;
+            let offset_y = ((height as f32 - scaled_h) / 2.0) as i32;
+            (scaled_w, scaled_h, 0i32, offset_y)
+        } else {
+            // Image is taller - fit to height
+            let scaled_h = height as f32;
+            let scaled_w = height as f32 * image_aspect;
+            let offset_x = ((width as f32 - scaled_w) / 2.0) as i32;
+            (scaled_w, scaled_h, offset_x, 0i32)
+        };
+
+        let fill = (0..width * height)
+            .map(|i| {
+                let x = i % width;
+                let y = i / width;
+
+                // Map screen coordinates to source image coordinates
+                let src_x = ((x as i32 - offset_x) as f32 * trans_width as f32 / scaled_w) as i32;
+                let src_y = ((y as i32 - offset_y) as f32 * trans_height as f32 / scaled_h) as i32;
+
+                // Outside the image (or where it is transparent) the background color shows
+                if src_x >= 0 && src_x < trans_width as i32 && src_y >= 0 && src_y < trans_height as i32 {
+                    let tex_idx = src_y as usize * trans_width + src_x as usize;
+                    trans_pixels.get(tex_idx).map_or(bg_color, |&p| Self::over(p, bg_color))
+                } else {
+                    bg_color
+                }
+            })
+            .collect();
+        Some(fill)
+    }
+
+    /// Apply transition overlay effect to the image
+    ///
+    /// During the hold phase a transition image takes precedence over the
+    /// background color, which then only fills the margins around it.
+    fn apply_transition_overlay(&self, image: &mut egui::ColorImage) {
+        let progress = self.state.transition.progress();
+        let trans_type = self.state.transition.transition_type;
+        let phase = self.state.transition.phase();
+        let width = image.size[0];
+        let height = image.size[1];
+
+        // Get transition options based on current state
+        let is_intro = self.state.play_state == PlayState::TransitionIn;
+        let options = self.get_transition_options(is_intro);
+
+        // Get background color from config (default black)
+        let bg_color = options
+            .map(|o| Self::parse_color(&o.background_color))
+            .unwrap_or(Color32::BLACK);
+
+        let hold_fill = if phase == TransitionPhase::PhaseHold {
+            self.transition_image_fill(options, bg_color, width, height)
+        } else {
+            None
+        };
+
+        match trans_type {
+            TransitionType::Fade => {
+                // Calculate fade alpha based on progress
+                let opacity = self.transition_renderer.calculate_fade_alpha(progress) as f32 / 255.0;
+
+                // Fade towards the transition image during Hold, otherwise the background color
+                for (i, pixel) in image.pixels.iter_mut().enumerate() {
+                    let fill = hold_fill.as_ref().map_or(bg_color, |fill| fill[i]);
+                    *pixel = Self::composite(*pixel, fill, opacity);
+                }
+            }
+            TransitionType::Move => {
+                // Calculate move offset
+                let offset = self.transition_renderer.calculate_move_offset(progress);
+
+                // During Hold phase, fill the area above the line
+                if phase == TransitionPhase::PhaseHold {
+                    for idx in 0..(offset as usize).min(height) * width {
+                        let fill = hold_fill.as_ref().map_or(bg_color, |fill| fill[idx]);
+                        image.pixels[idx] = Self::composite(image.pixels[idx], fill, 1.0);
+                    }
+                }
+
+                // Draw line at the offset position
+                if offset > 0 && (offset as usize) < height {
+                    for x in 0..width {
+                        image.pixels[offset as usize * width + x] = Color32::WHITE;
+                    }
+                }
+            }
+            TransitionType::Swipe => {
+                // Calculate swipe progress (0.0 to 1.0)
+                let swipe_progress = self.transition_renderer.calculate_swipe_progress(progress);
+                let swipe_y = (swipe_progress * height as f32) as usize;
+
+                // Draw swipe line
+                if swipe_y > 0 && swipe_y < height {
+                    for x in 0..width {
+                        image.pixels[swipe_y * width + x] = Color32::from_rgb(200, 200, 200);
+                    }
+
+                    // Fill area above swipe line with the image, the background color,
+                    // or darkened video if neither is configured
+                    for idx in 0..swipe_y.min(height) * width {
+                        if let Some(ref fill) = hold_fill {
+                            image.pixels[idx] = Self::composite(image.pixels[idx], fill[idx], 1.0);
+                        } else if bg_color != Color32::BLACK {
+                            image.pixels[idx] = Self::composite(image.pixels[idx], bg_color, 1.0);
+                        } else {
+                            let p = image.pixels[idx];
+                            image.pixels[idx] = Color32::from_rgb(
+                                p.r() / 3,
+                                p.g() / 3,
+                                p.b() / 3,
+                            );
+                        }
+                    }
+                }
+            }
+            TransitionType::None => {}
+        }
+    }
+
+    /// Render color fade effect at pixel level (blends with video)
+    fn render_color_fade(&self, pixels: &mut [Color32], width: usize, height: usize) {
+        let anim = &self.state.animation;
+        let radius = anim.color_fade_radius as usize;
+
+        if radius == 0 {
+            return;
+        }
+
+        // Get theme color (fading to color2 towards the edge for gradient themes)
+        let theme_color = self.get_theme_color();
+        let edge_color = self.get_arknights_options().and_then(|o| Self::theme_gradient(&o)).map(|(_, end)| end);
+
+        // Draw color fade in bottom-right corner (matching C firmware draw_color_fade)
+        for x in 0..radius.min(width) {
+            for y in 0..radius.min(height) {
+                // C firmware: if(x+y > radius - 2) break;
+                if x + y > radius.saturating_sub(2) {
+                    break;
+                }
+
+                // Calculate alpha: 255 - ((x+y)*255 / radius)
+                let alpha = 255.0 - ((x + y) as f32 * 255.0 / radius as f32);
+                let alpha = (alpha * 0.8).clamp(0.0, 255.0) as u8; // Slightly reduce opacity
+
+                // Calculate real coordinates (bottom-right corner)
+                let real_x = width - x - 1;
+                let real_y = height - y - 1;
+
+                if real_y < height && real_x < width {
+                    let idx = real_y * width + real_x;
+                    // Blend with existing pixel
+                    let bg = pixels[idx];
+                    let color = match edge_color {
+                        Some(end) => Self::blend_colors(theme_color, end, ((x + y) * 255 / radius).min(255) as u8),
+                        None => theme_color,
+                    };
+                    pixels[idx] = Self::composite(bg, color, alpha as f32 / 255.0);
+                }
+            }
+        }
+    }
+
+    /// Blend two colors with alpha
+    fn blend_colors(bg: Color32, fg: Color32, alpha: u8) -> Color32 {
+        let a = alpha as f32 / 255.0;
+        let inv_a = 1.0 - a;
+
+        Color32::from_rgb(
+            ((fg.r() as f32 * a) + (bg.r() as f32 * inv_a)) as u8,
+            ((fg.g() as f32 * a) + (bg.g() as f32 * inv_a)) as u8,
+            ((fg.b() as f32 * a) + (bg.b() as f32 * inv_a)) as u8,
+        )
+    }
+
+    /// Draw `color` (premultiplied) over an opaque `pixel` at `opacity`
+    ///
+    /// Same as `blend_colors` for opaque colors; translucent colors let the
+    /// pixel show through.
+    fn composite(pixel: Color32, color: Color32, opacity: f32) -> Color32 {
+        let coverage = color.a() as f32 / 255.0 * opacity;
+        let mix = |c: u8, p: u8| ((c as f32 * opacity) + (p as f32 * (1.0 - coverage))) as u8;
+        Color32::from_rgb(mix(color.r(), pixel.r()), mix(color.g(), pixel.g()), mix(color.b(), pixel.b()))
+    }
+
+    /// Stack premultiplied `top` over premultiplied `bottom`
+    fn over(top: Color32, bottom: Color32) -> Color32 {
+        let inv = 1.0 - top.a() as f32 / 255.0;
+        let mix = |t: u8, b: u8| (t as f32 + b as f32 * inv) as u8;
+        Color32::from_rgba_premultiplied(mix(top.r(), bottom.r()), mix(top.g(), bottom.g()), mix(top.b(), bottom.b()), mix(top.a(), bottom.a()))
+    }
+
+    /// Parse a config color (see `utils::parse_color`), white if invalid
+    pub fn parse_color(color: &str) -> Color32 {
+        match parse_color(color) {
+            Some((r, g, b, a)) => Color32::from_rgba_unmultiplied(r, g, b, a),
+            None => Color32::WHITE,
+        }
+    }
+
+    /// Parse an optional per-element color, falling back to the default when empty
+    fn element_color(hex: &str, default: Color32) -> Color32 {
+        if hex.trim().is_empty() {
+            default
+        } else {
+            Self::parse_color(hex)
+        }
+    }
+
+    /// Get theme color from config
+    fn get_theme_color(&self) -> Color32 {
+        self.get_arknights_options()
+            .map(|opts| Self::parse_color(&opts.color))
+            .unwrap_or(Color32::from_rgb(255, 100, 100))
+    }
+
+    /// Start and end colors of a dual-tone theme (None for single-color themes)
+    fn theme_gradient(options: &ArknightsOverlayOptions) -> Option<(Color32, Color32)> {
+        if options.color2.trim().is_empty() {
+            return None;
+        }
+        Some((Self::parse_color(&options.color), Self::parse_color(&options.color2)))
+    }
+
+    /// Build a textured rect mesh whose vertex colors go from `start` to `end`
+    fn gradient_mesh(rect: Rect, texture: egui::TextureId, uv: Rect, start: Color32, end: Color32, vertical: bool) -> egui::Mesh {
+        let (top_right, bottom_left) = if vertical { (start, end) } else { (end, start) };
+        let mut mesh = egui::Mesh::with_texture(texture);
+        for (pos, uv, color) in [
+            (rect.left_top(), uv.left_top(), start),
+            (rect.right_top(), uv.right_top(), top_right),
+            (rect.left_bottom(), uv.left_bottom(), bottom_left),
+            (rect.right_bottom(), uv.right_bottom(), end),
+        ] {
+            mesh.vertices.push(egui::epaint::Vertex { pos, uv, color });
+        }
+        mesh.add_triangle(0, 1, 2);
+        mesh.add_triangle(2, 1, 3);
+        mesh
+    }
+
+    /// Get ArknightsOverlayOptions from config, with template variables expanded
+    pub fn get_arknights_options(&self) -> Option<ArknightsOverlayOptions> {
+        let config = self.epconfig.as_ref()?;
+        let mut options = config.arknights_options()?;
+        options.expand_templates(&self.template_vars(config));
+        Some(options)
+    }
+
+    /// Get options of all Image overlays in the stack
+    fn get_image_overlay_options(&self) -> Vec<ImageOverlayOptions> {
+        self.epconfig
+            .as_ref()
+            .map(|c| c.overlay_stack().iter().flat_map(|o| o.image_entries()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get CustomOverlayOptions of an overlay, with template variables expanded
+    fn get_custom_overlay_options(&self, overlay: &Overlay) -> Option<CustomOverlayOptions> {
+        let config = self.epconfig.as_ref()?;
+        let mut options = overlay.custom_options()?;
+        options.apply_template(&self.overlay_templates);
+        options.expand_templates(&self.template_vars(config));
+        Some(options)
+    }
+
+    /// Get transition options for current state (in or loop)
+    fn get_transition_options(&self, is_intro: bool) -> Option<&TransitionOptions> {
+        self.epconfig.as_ref().and_then(|config| {
+            if is_intro {
+                config.transition_in.as_ref().and_then(|t| t.options.as_ref())
+            } else {
+                config.transition_loop.as_ref().and_then(|t| t.options.as_ref())
+            }
+        })
+    }
+
+    /// Resolve the class icon: explicit path first, then the bundled set by class name
+    ///
+    /// Bundled icons live in `app_dir/resources/class_icons`; the editor refers
+    /// to them as `class_icons/<name>.png`, which is also looked up there.
+    fn resolve_class_icon_path(&self, options: &ArknightsOverlayOptions) -> Option<PathBuf> {
+        let bundled_dir = self.app_dir.join("resources/class_icons");
+
+        if !options.operator_class_icon.is_empty() {
+            let path = self.image_loader.resolve_path(&options.operator_class_icon);
+            if !file_exists(&path) && options.operator_class_icon.starts_with("class_icons/") {
+                if let Some(name) = path.file_name() {
+                    let bundled = bundled_dir.join(name);
+                    if file_exists(&bundled) {
+                        return Some(bundled);
+                    }
+                }
+            }
+            return Some(path);
+        }
+
+        let key = options.operator_class_key()?;
+        Some(bundled_dir.join(format!("{}.png", key)))
+    }
+
+    /// Load textures for the current configuration
+    pub fn load_textures(&mut self, ctx: &egui::Context) {
+        if self.textures_loaded {
+            return;
+        }
+
+        // Load progress bar image: per-material ak_bar_image, else resources/data/ak_bar.png
+        if self.ak_bar_texture.is_none() {
+            let default_path = self.app_dir.join("resources/data/ak_bar.png");
+            let ak_bar_path = self.get_arknights_options()
+                .filter(|opts| !opts.ak_bar_image.is_empty())
+                .map(|opts| self.image_loader.resolve_path(&opts.ak_bar_image))
+                .filter(|path| {
+                    let exists = file_exists(path);
+                    if !exists {
+                        self.image_loader.report_failure("ak_bar_image", path, "not found, using the default");
+                    }
+                    exists
+                })
+                .unwrap_or(default_path);
+            if let Some(img) = self.image_loader.open_image("ak_bar", &ak_bar_path) {
+                let rgba = img.to_rgba8();
+                let size = [rgba.width() as usize, rgba.height() as usize];
+                let pixels: Vec<Color32> = rgba
+                    .pixels()
+                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+                    .collect();
+                let color_image = egui::ColorImage { size, pixels };
+                self.ak_bar_texture = Some(ctx.load_texture(
+                    "ak_bar",
+                    color_image,
+                    egui::TextureOptions::LINEAR,
+                ));
+                info!("Loaded ak_bar.png: {}", ak_bar_path.display());
+            }
+        }
+
+        // Load top_right_arrow.png from resources/data directory
+        if self.top_right_arrow_texture.is_none() {
+            let arrow_path = self.app_dir.join("resources/data/top_right_arrow.png");
+            if let Some(img) = self.image_loader.open_image("top_right_arrow", &arrow_path) {
+                let rgba = img.to_rgba8();
+                let size = [rgba.width() as usize, rgba.height() as usize];
+                let pixels: Vec<Color32> = rgba
+                    .pixels()
+                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+                    .collect();
+                let color_image = egui::ColorImage { size, pixels };
+                self.top_right_arrow_texture = Some(ctx.load_texture(
+                    "top_right_arrow",
+                    color_image,
+                    egui::TextureOptions::LINEAR,
+                ));
+                info!("Loaded top_right_arrow.png: {}", arrow_path.display());
+            }
+        }
+
+        // Load modular decoration textures
+
+        // Load top_left_rect.png (L-shape black decoration at top-left)
+        if self.top_left_rect_texture.is_none() {
+            let path = self.app_dir.join("resources/data/top_left_rect.png");
+            if let Some(img) = self.image_loader.open_image("top_left_rect", &path) {
+                let rgba = img.to_rgba8();
+                let size = [rgba.width() as usize, rgba.height() as usize];
+                let pixels: Vec<Color32> = rgba
+                    .pixels()
+                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+                    .collect();
+                let color_image = egui::ColorImage { size, pixels };
+                self.top_left_rect_texture = Some(ctx.load_texture(
+                    "top_left_rect",
+                    color_image,
+                    egui::TextureOptions::LINEAR,
+                ));
+                info!("Loaded top_left_rect.png: {}", path.display());
+            }
+        }
+
+        // Load top_left_rhodes.png (Rhodes decoration below L-shape)
+        if self.top_left_rhodes_texture.is_none() {
+            let path = self.app_dir.join("resources/data/top_left_rhodes.png");
+            if let Some(img) = self.image_loader.open_image("top_left_rhodes", &path) {
+                let rgba = img.to_rgba8();
+                let size = [rgba.width() as usize, rgba.height() as usize];
+                let pixels: Vec<Color32> = rgba
+                    .pixels()
+                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+                    .collect();
+                let color_image = egui::ColorImage { size, pixels };
+                self.top_left_rhodes_texture = Some(ctx.load_texture(
+                    "top_left_rhodes",
+                    color_image,
+                    egui::TextureOptions::LINEAR,
+                ));
+                info!("Loaded top_left_rhodes.png: {}", path.display());
+            }
+        }
+
+        // Load top_right_bar.png (yellow bar + full vertical bar on right)
+        if self.top_right_bar_texture.is_none() {
+            let path = self.app_dir.join("resources/data/top_right_bar.png");
+            if let Some(img) = self.image_loader.open_image("top_right_bar", &path) {
+                let rgba = img.to_rgba8();
+                let size = [rgba.width() as usize, rgba.height() as usize];
+                let pixels: Vec<Color32> = rgba
+                    .pixels()
+                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+                    .collect();
+                let color_image = egui::ColorImage { size, pixels };
+                self.top_right_bar_texture = Some(ctx.load_texture(
+                    "top_right_bar",
+                    color_image,
+                    egui::TextureOptions::LINEAR,
+                ));
+                info!("Loaded top_right_bar.png: {}", path.display());
+            }
+        }
+
+        // Load btm_left_bar.png (colorful gradient bar on left side)
+        if self.btm_left_bar_texture.is_none() {
+            let path = self.app_dir.join("resources/data/btm_left_bar.png");
+            if let Some(img) = self.image_loader.open_image("btm_left_bar", &path) {
+                let rgba = img.to_rgba8();
+                let size = [rgba.width() as usize, rgba.height() as usize];
+                let pixels: Vec<Color32> = rgba
+                    .pixels()
+                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+                    .collect();
+                let color_image = egui::ColorImage { size, pixels };
+                self.btm_left_bar_texture = Some(ctx.load_texture(
+                    "btm_left_bar",
+                    color_image,
+                    egui::TextureOptions::LINEAR,
+                ));
+                info!("Loaded btm_left_bar.png: {}", path.display());
+            }
+        }
+
+        // Load image overlay textures for all Image overlays
+        for image_opts in self.get_image_overlay_options() {
+            if !image_opts.image.is_empty() && !self.image_overlay_textures.contains_key(&image_opts.image) {
+                let image_path = self.image_loader.resolve_path(&image_opts.image);
+                if let Some(img) = self.image_loader.open_image("image overlay", &image_path) {
+                    let rgba = img.to_rgba8();
+                    let size = [rgba.width() as usize, rgba.height() as usize];
+                    let pixels: Vec<Color32> = rgba
+                        .pixels()
+                        .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+                        .collect();
+                    let color_image = egui::ColorImage { size, pixels };
+                    let texture = ctx.load_texture(
+                        format!("image_overlay:{}", image_opts.image),
+                        color_image,
+                        egui::TextureOptions::LINEAR,
+                    );
+                    self.image_overlay_textures.insert(image_opts.image.clone(), texture);
+                    info!("Loaded image overlay: {}", image_path.display());
+                }
+            }
+        }
+
+        // Load transition image texture if specified in transition_in or transition_loop
+        if self.transition_image_texture.is_none() {
+            // Check transition_in first, then transition_loop
+            let image_path = self.get_transition_options(true)
+                .filter(|opts| !opts.image.is_empty())
+                .map(|opts| opts.image.clone())
+                .or_else(|| {
+                    self.get_transition_options(false)
+                        .filter(|opts| !opts.image.is_empty())
+                        .map(|opts| opts.image.clone())
+                });
+
+            if let Some(image_file) = image_path {
+                let resolved_path = self.image_loader.resolve_path(&image_file);
+                if let Some(img) = self.image_loader.open_image("transition image", &resolved_path) {
+                    let rgba = img.to_rgba8();
+                    let img_width = rgba.width() as usize;
+                    let img_height = rgba.height() as usize;
+                    let size = [img_width, img_height];
+                    let pixels: Vec<Color32> = rgba
+                        .pixels()
+                        .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+                        .collect();
+
+                    // Store raw pixel data for direct access during transition
+                    self.transition_image_data = Some((pixels.clone(), img_width, img_height));
+
+                    let color_image = egui::ColorImage { size, pixels };
+                    self.transition_image_texture = Some(ctx.load_texture(
+                        "transition_image",
+                        color_image,
+                        egui::TextureOptions::LINEAR,
+                    ));
+                    info!("Loaded transition image: {}", resolved_path.display());
+                }
+            }
+        }
+
+        // Load Arknights-specific textures
+        let options = match self.get_arknights_options() {
+            Some(opts) => opts,
+            None => {
+                self.textures_loaded = true;
+                return;
+            }
+        };
+
+        // Generate barcode texture from barcode_text (with gradient colors)
+        if !options.barcode_text.is_empty() && self.barcode_texture.is_none() {
+            let barcode_width = self.firmware_config.layout.barcode.width;
+            // Use gradient colors for barcode (purple → blue → cyan → yellow),
+            // or plain bars tinted with the theme gradient at draw time
+            let use_gradient = Self::theme_gradient(&options).is_none();
+            if let Some(barcode_image) = generate_vertical_barcode_gradient(&options.barcode_text, barcode_width, use_gradient) {
+                self.barcode_texture = Some(ctx.load_texture(
+                    "barcode",
+                    barcode_image,
+                    egui::TextureOptions::NEAREST,
+                ));
+                info!("Generated gradient barcode texture");
+            }
+        }
+
+        // Generate secondary barcode texture (plain white bars)
+        if let Some(ref secondary) = options.secondary_barcode {
+            if !secondary.text.is_empty() && self.secondary_barcode_texture.is_none() {
+                let image = if secondary.vertical {
+                    generate_vertical_barcode(&secondary.text, secondary.width)
+                } else {
+                    generate_barcode(&secondary.text, secondary.height)
+                };
+                if let Some(image) = image {
+                    self.secondary_barcode_texture = Some(ctx.load_texture(
+                        "secondary_barcode",
+                        image,
+                        egui::TextureOptions::NEAREST,
+                    ));
+                    info!("Generated secondary barcode texture");
+                }
+            }
+        }
+
+        // Load class icon texture
+        let class_icon_path = self.resolve_class_icon_path(&options)
+            .filter(|_| self.class_icon_texture.is_none());
+        if let Some(icon_path) = class_icon_path {
+            if let Some(img) = self.image_loader.open_image("class icon", &icon_path) {
+                let size = [img.width() as usize, img.height() as usize];
+                let pixels: Vec<Color32> = img
+                    .to_rgba8()
+                    .pixels()
+                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+                    .collect();
+                let color_image = egui::ColorImage { size, pixels };
+                self.class_icon_texture = Some(ctx.load_texture(
+                    "class_icon",
+                    color_image,
+                    egui::TextureOptions::LINEAR,
+                ));
+                info!("Loaded class icon: {}", icon_path.display());
+            }
+        }
+
+        // Load logo texture
+        if !options.logo.is_empty() && self.logo_texture.is_none() {
+            let logo_path = self.image_loader.resolve_path(&options.logo);
+            if let Some(img) = self.image_loader.open_image("logo", &logo_path) {
+                let size = [img.width() as usize, img.height() as usize];
+                let pixels: Vec<Color32> = img
+                    .to_rgba8()
+                    .pixels()
+                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+                    .collect();
+                let color_image = egui::ColorImage { size, pixels };
+                self.logo_texture = Some(ctx.load_texture(
+                    "logo",
+                    color_image,
+                    egui::TextureOptions::LINEAR,
+                ));
+                info!("Loaded logo: {}", logo_path.display());
+            }
+        }
+
+        self.textures_loaded = true;
+        self.frame_dirty = true;
+    }
+
+    /// Paint the current frame into `rect`, with the overlays over it
+    ///
+    /// `load_textures` and `render_frame` must have run for the frame.
+    pub fn paint(&mut self, painter: &egui::Painter, rect: Rect) {
+        self.paint_frame(painter, rect);
+        self.paint_overlays(painter, rect);
+    }
+
+    /// Paint the video frame, with its transition effects, into `rect`
+    pub fn paint_frame(&self, painter: &egui::Painter, rect: Rect) {
+        if let Some(ref texture) = self.frame_texture {
+            let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+            painter.image(texture.id(), rect, uv, Color32::WHITE);
+        }
+    }
+
+    /// Paint the overlays of a frame shown in `rect`; they only show in the loop
+    pub fn paint_overlays(&mut self, painter: &egui::Painter, rect: Rect) {
+        if self.state.play_state == PlayState::Loop {
+            self.render_overlays(painter, rect);
+        }
+    }
+
+    /// A frame has been rendered to paint
+    pub fn has_frame(&self) -> bool {
+        self.frame_texture.is_some()
+    }
+
+    /// Render complete overlay UI using egui Painter
+    fn render_overlay_ui(&mut self, painter: &egui::Painter, image_rect: Rect) {
+        let anim = &self.state.animation;
+        let options = match self.get_arknights_options() {
+            Some(opts) => opts,
+            None => return,
+        };
+
+        // Calculate scaling factor (image might be scaled)
+        let fw_width = self.firmware_config.overlay_width() as f32;
+        let fw_height = self.firmware_config.overlay_height() as f32;
+        let scale_x = image_rect.width() / fw_width;
+        let scale_y = image_rect.height() / fw_height;
+
+        // Calculate Y offset for entry animation; whole physical pixels keep
+        // the overlay from shimmering while it slides in
+        let y_offset = painter.round_to_pixel(anim.entry_y_offset as f32 * scale_y);
+
+        // Get layout offsets
+        let offsets = &self.firmware_config.layout.offsets;
+        let btm_info_x = painter.round_to_pixel(offsets.btm_info_x as f32 * scale_x + image_rect.min.x);
+        let theme_color = self.get_theme_color();
+        let entry_alpha = (anim.entry_progress * 255.0) as u8;
+
+        // ============================================
+        // 1. Render modular static decorations
+        // ============================================
+        if options.show_decorations {
+            self.render_modular_decorations(painter, image_rect, scale_x, scale_y, y_offset, entry_alpha, &options);
+        }
+
+        // ============================================
+        // 2. Render dynamic elements
+        // ============================================
+
+        // Arrow indicator (3 yellow chevrons pointing upward with scrolling animation)
+        if options.show_arrow {
+            self.render_arrow_indicator(painter, image_rect, scale_x, scale_y, y_offset, theme_color);
+        }
+
+        // Typewriter texts (operator name, code, staff_text, etc.)
+        self.render_typewriter_texts(painter, image_rect, scale_x, scale_y, y_offset, &options, theme_color);
+
+        // EINK areas (barcode with gradient, class icon)
+        self.render_eink_areas(painter, image_rect, scale_x, scale_y, y_offset, &options);
+
+        // Divider lines (white color per C reference)
+        if options.show_divider_lines {
+            let gradient = Self::theme_gradient(&options);
+            self.render_divider_lines(painter, image_rect, scale_x, scale_y, y_offset, btm_info_x, gradient);
+        }
+
+        // Progress bar (AK bar)
+        if options.show_ak_bar {
+            self.render_progress_bar(painter, image_rect, scale_x, scale_y, y_offset, btm_info_x, theme_color);
+        }
+
+        // Logo image (dynamic fade-in)
+        if options.show_logo {
+            self.render_logo_image(painter, image_rect, scale_x, scale_y, y_offset);
+        }
+    }
+
+
+    /// Texture sampling for pre-rendered texts (nearest keeps aliased text crisp)
+    fn text_texture_options(&self) -> egui::TextureOptions {
+        if self.text_quality.antialias {
+            egui::TextureOptions::LINEAR
+        } else {
+            egui::TextureOptions::NEAREST
+        }
+    }
+
+    /// Apply a new text quality and re-render cached text textures
+    pub fn apply_text_quality(&mut self, quality: TextRenderQuality) {
+        if self.text_quality == quality {
+            return;
+        }
+        self.text_quality = quality;
+        set_text_quality(quality);
+        self.cached_rhodes_text.clear();
+        self.cached_top_right_bar_text.clear();
+        self.frame_dirty = true;
+    }
+
+    /// Render modular static decorations (replaces overlay_template.png)
+    ///
+    /// Positions are based on hardware implementation (opinfo.c):
+    /// - top_left_rhodes: (0, 0) - left upper corner origin
+    /// - top_left_rect: (60, 0) - L-shape black decoration offset from left
+    /// - top_right_bar: (360-width, 0) - right-aligned
+    /// - btm_left_bar: (0, 640-height) - bottom-aligned
+    fn render_modular_decorations(
+        &mut self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        scale_x: f32,
+        scale_y: f32,
+        y_offset: f32,
+        entry_alpha: u8,
+        options: &ArknightsOverlayOptions,
+    ) {
+        let tint = Color32::from_rgba_unmultiplied(255, 255, 255, entry_alpha);
+        let uv_full = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+        let fw_height = self.firmware_config.overlay_height() as f32; // Firmware screen height
+
+        // 1. top_left_rhodes - custom text or default image
+        if !options.top_left_rhodes.is_empty() {
+            // Custom text mode: render rotated text replacing default Rhodes logo
+            // Per firmware opinfo.c:687-693: rect=(0, 5, 67, OPNAME_Y-5=410)
+            let orientation = options.top_left_rhodes_orientation;
+            if self.cached_rhodes_text != options.top_left_rhodes
+                || self.cached_rhodes_orientation != orientation
+            {
+                let img = render_text_oriented(
+                    &options.top_left_rhodes,
+                    48.0, // Font size (scaled down from firmware's 72px for display)
+                    Color32::WHITE,
+                    false,
+                    orientation,
+                );
+                self.top_left_rhodes_text_texture = Some(
+                    painter.ctx().load_texture("rhodes_text", img, self.text_texture_options())
+                );
+                self.cached_rhodes_text = options.top_left_rhodes.clone();
+                self.cached_rhodes_orientation = orientation;
+            }
+            if let Some(ref tex) = self.top_left_rhodes_text_texture {
+                let tex_w = tex.size()[0] as f32;
+                let tex_h = tex.size()[1] as f32;
+                // Position at (0, 5), constrain to area 67x410
+                let max_w = 67.0;
+                let max_h = 410.0;
+                let (display_w, display_h) = match orientation {
+                    // Firmware clips the rotated text to the area
+                    TextOrientation::Rot90 => (tex_w.min(max_w), tex_h.min(max_h)),
+                    // Other layouts are scaled down to fit, keeping aspect ratio
+                    _ => {
+                        let fit = (max_w / tex_w).min(max_h / tex_h).min(1.0);
+                        (tex_w * fit, tex_h * fit)
+                    }
+                };
+                let rect = Rect::from_min_size(
+                    Pos2::new(image_rect.min.x, image_rect.min.y + 5.0 * scale_y + y_offset),
+                    egui::vec2(display_w * scale_x, display_h * scale_y),
+                );
+                painter.image(tex.id(), rect, uv_full, tint);
+            }
+        } else {
+            // Default: use top_left_rhodes.png image
+            if let Some(ref tex) = self.top_left_rhodes_texture {
+                let tex_w = tex.size()[0] as f32;
+                let tex_h = tex.size()[1] as f32;
+                let rect = Rect::from_min_size(
+                    Pos2::new(image_rect.min.x, image_rect.min.y + y_offset),
+                    egui::vec2(tex_w * scale_x, tex_h * scale_y),
+                );
+                painter.image(tex.id(), rect, uv_full, tint);
+            }
+        }
+
+        // 2. top_left_rect - L-shape black decoration, positioned right after top_left_rhodes
+        if let Some(ref tex) = self.top_left_rect_texture {
+            let tex_w = tex.size()[0] as f32;
+            let tex_h = tex.size()[1] as f32;
+
+            // Use actual rhodes texture width for positioning
+            let rhodes_width = if !options.top_left_rhodes.is_empty() {
+                // When using custom text, use the text texture width
+                self.top_left_rhodes_text_texture
+                    .as_ref()
+                    .map(|t| (t.size()[0] as f32).min(67.0))
+                    .unwrap_or(60.0)
+            } else {
+                self.top_left_rhodes_texture
+                    .as_ref()
+                    .map(|t| t.size()[0] as f32)
+                    .unwrap_or(60.0)
+            };
+
+            let rect = Rect::from_min_size(
+                Pos2::new(
+                    image_rect.min.x + rhodes_width * scale_x,
+                    image_rect.min.y + y_offset,
+                ),
+                egui::vec2(tex_w * scale_x, tex_h * scale_y),
+            );
+            painter.image(tex.id(), rect, uv_full, tint);
+        }
+
+        // 3. top_right_bar (360-width, 0) - right-aligned
+        if let Some(ref tex) = self.top_right_bar_texture {
+            let tex_w = tex.size()[0] as f32;
+            let tex_h = tex.size()[1] as f32;
+            let bar_x = image_rect.max.x - tex_w * scale_x;
+            let rect = Rect::from_min_size(
+                Pos2::new(bar_x, image_rect.min.y + y_offset),
+                egui::vec2(tex_w * scale_x, tex_h * scale_y),
+            );
+            painter.image(tex.id(), rect, uv_full, tint);
+
+            // Custom top_right_bar_text: overlay on top of bar image
+            if !options.top_right_bar_text.is_empty() {
+                // Per firmware opinfo.c:643-683:
+                // 1. Black rect to cover embedded text at (bar_x+42, 314, 10, 102)
+                let cover_x = bar_x + 42.0 * scale_x;
+                let cover_y = image_rect.min.y + 314.0 * scale_y + y_offset;
+                let cover_rect = Rect::from_min_size(
+                    Pos2::new(cover_x, cover_y),
+                    egui::vec2(10.0 * scale_x, 102.0 * scale_y),
+                );
+                let black_tint = Color32::from_rgba_unmultiplied(0, 0, 0, entry_alpha);
+                painter.rect_filled(cover_rect, 0.0, black_tint);
+
+                // 2. Render custom text (split at space: bold + regular)
+                if self.cached_top_right_bar_text != options.top_right_bar_text {
+                    let img = render_top_right_bar_text_rotated(
+                        &options.top_right_bar_text,
+                        10.0,
+                        Color32::WHITE,
+                    );
+                    self.top_right_bar_text_texture = Some(
+                        painter.ctx().load_texture("top_right_bar_text", img, self.text_texture_options())
+                    );
+                    self.cached_top_right_bar_text = options.top_right_bar_text.clone();
+                }
+                if let Some(ref text_tex) = self.top_right_bar_text_texture {
+                    let text_w = text_tex.size()[0] as f32;
+                    let text_h = text_tex.size()[1] as f32;
+                    // Constrain to the covered area
+                    let display_w = text_w.min(10.0);
+                    let display_h = text_h.min(102.0);
+                    let text_rect = Rect::from_min_size(
+                        Pos2::new(cover_x, cover_y),
+                        egui::vec2(display_w * scale_x, display_h * scale_y),
+                    );
+                    painter.image(text_tex.id(), text_rect, uv_full, tint);
+                }
+            }
+        }
+
+        // 4. btm_left_bar (0, 640-height) - bottom-aligned
+        if let Some(ref tex) = self.btm_left_bar_texture {
+            let tex_w = tex.size()[0] as f32;
+            let tex_h = tex.size()[1] as f32;
+            let rect = Rect::from_min_size(
+                Pos2::new(
+                    image_rect.min.x,
+                    image_rect.min.y + (fw_height - tex_h) * scale_y + y_offset,
+                ),
+                egui::vec2(tex_w * scale_x, tex_h * scale_y),
+            );
+            painter.image(tex.id(), rect, uv_full, tint);
+        }
+    }
+
+    /// Render all overlays in z-order (bottom to top)
+    fn render_overlays(&mut self, painter: &egui::Painter, image_rect: Rect) {
+        let stack: Vec<Overlay> = match self.epconfig.as_ref() {
+            Some(config) => config.overlay_stack().into_iter().cloned().collect(),
+            None => return,
+        };
+
+        // Animation state is shared, so only the first Arknights overlay is drawn
+        let mut arknights_drawn = false;
+        for (index, overlay) in stack.iter().enumerate() {
+            match overlay.overlay_type {
+                OverlayType::Arknights if !arknights_drawn => {
+                    self.render_overlay_ui(painter, image_rect);
+                    arknights_drawn = true;
+                }
+                OverlayType::Image => {
+                    // Entries are timed independently, drawn in list order
+                    for options in overlay.image_entries() {
+                        self.render_image_overlay(painter, image_rect, &options);
+                    }
+                }
+                OverlayType::Custom => self.render_custom_overlay(painter, image_rect, index, overlay),
+                OverlayType::Arknights | OverlayType::None => {}
+            }
+        }
+    }
+
+    /// Render layered overlay (for OverlayType::Custom)
+    ///
+    /// `index` is the overlay's position in the stack; each gets its own renderer.
+    fn render_custom_overlay(&mut self, painter: &egui::Painter, image_rect: Rect, index: usize, overlay: &Overlay) {
+        let Some(options) = self.get_custom_overlay_options(overlay) else {
+            return;
+        };
+
+        // Layer times are relative to Loop state start, like the image overlay
+        let fps = self.firmware_config.fps();
+        let current_time_us = (self.state.animation.frame_counter as i64 * 1_000_000) / fps as i64;
+        let fw_size = Vec2::new(
+            self.firmware_config.overlay_width() as f32,
+            self.firmware_config.overlay_height() as f32,
+        );
+
+        self.layer_renderers.entry(index).or_default().paint(
+            painter,
+            image_rect,
+            fw_size,
+            current_time_us,
+            &options,
+            &self.image_loader,
+        );
+    }
+
+    /// Render image overlay (for OverlayType::Image)
+    fn render_image_overlay(&self, painter: &egui::Painter, image_rect: Rect, options: &ImageOverlayOptions) {
+        // Calculate current time in microseconds since Loop state started
+        let fps = self.firmware_config.fps();
+        let current_time_us = (self.state.animation.frame_counter as i64 * 1_000_000) / fps as i64;
+
+        // Check if we're within the display window
+        // appear_time: when overlay starts showing (relative to Loop state start)
+        // duration: how long to show the overlay (0 means show indefinitely)
+        let Some(visual) = image_overlay_visual(options, current_time_us) else {
+            return;
+        };
+
+        // Draw the image overlay - use original size, don't stretch
+        if let Some(texture) = self.image_overlay_textures.get(&options.image) {
+            // Get texture original size
+            let tex_size = texture.size();
+            let img_width = tex_size[0] as f32;
+            let img_height = tex_size[1] as f32;
+
+            // Calculate scale factor (based on hardware resolution)
+            let fw_size = Vec2::new(
+                self.firmware_config.overlay_width() as f32,
+                self.firmware_config.overlay_height() as f32,
+            );
+            let scale = image_rect.size() / fw_size;
+
+            // Use uniform scale factor to maintain aspect ratio (consistent with C reference)
+            let uniform_scale = scale.x.min(scale.y);
+
+            // Position: anchored rect in firmware pixels, plus slide offset
+            let fw_rect = image_overlay_rect(options, Vec2::new(img_width, img_height), fw_size)
+                .translate(visual.offset);
+            let overlay_rect = Rect::from_min_size(
+                image_rect.min + fw_rect.min.to_vec2() * scale,
+                fw_rect.size() * uniform_scale,
+            );
+
+            let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+            let tint = Color32::from_white_alpha((visual.alpha * 255.0) as u8);
+            painter.image(texture.id(), overlay_rect, uv, tint);
+        }
+    }
+
+    /// Render typewriter effect texts
+    fn render_typewriter_texts(
+        &self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        scale_x: f32,
+        scale_y: f32,
+        y_offset: f32,
+        options: &ArknightsOverlayOptions,
+        theme_color: Color32,
+    ) {
+        let anim = &self.state.animation;
+        let offsets = &self.firmware_config.layout.offsets;
+        let btm_info_x = offsets.btm_info_x as f32 * scale_x + image_rect.min.x;
+
+        // Operator name (large text, white by default)
+        if options.show_operator_name && anim.name_chars > 0 {
+            let name: String = options.operator_name.chars().take(anim.name_chars).collect();
+            let y = offsets.opname_y as f32 * scale_y + image_rect.min.y + y_offset;
+
+            if y >= image_rect.min.y && y <= image_rect.max.y {
+                let pos = Pos2::new(btm_info_x, y);
+                painter.text(pos, Align2::LEFT_TOP, &name, FontId::proportional(32.0 * scale_y), Self::element_color(&options.operator_name_color, Color32::WHITE));
+            }
+        }
+
+        // Operator code (smaller text, theme color by default)
+        if options.show_operator_code && anim.code_chars > 0 {
+            let code: String = options.operator_code.chars().take(anim.code_chars).collect();
+            let y = offsets.opcode_y as f32 * scale_y + image_rect.min.y + y_offset;
+
+            if y >= image_rect.min.y && y <= image_rect.max.y {
+                let pos = Pos2::new(btm_info_x, y);
+                painter.text(pos, Align2::LEFT_TOP, &code, FontId::proportional(14.0 * scale_y), Self::element_color(&options.operator_code_color, theme_color));
+            }
+        }
+
+        // Staff text
+        if options.show_staff_text && anim.staff_chars > 0 {
+            let staff: String = options.staff_text.chars().take(anim.staff_chars).collect();
+            let y = offsets.staff_text_y as f32 * scale_y + image_rect.min.y + y_offset;
+
+            if y >= image_rect.min.y && y <= image_rect.max.y {
+                let pos = Pos2::new(btm_info_x, y);
+                painter.text(pos, Align2::LEFT_TOP, &staff, FontId::proportional(12.0 * scale_y), Self::element_color(&options.staff_text_color, Color32::WHITE));
+            }
+        }
+
+        // Auxiliary text (multiline)
+        if options.show_aux_text && anim.aux_chars > 0 {
+            let aux: String = options.aux_text.chars().take(anim.aux_chars).collect();
+            let base_y = offsets.aux_text_y as f32 * scale_y + image_rect.min.y + y_offset;
+            let line_height = offsets.aux_text_line_height as f32 * scale_y;
+
+            let aux_color = Self::element_color(&options.aux_text_color, Color32::GRAY);
+
+            for (i, line) in aux.lines().enumerate() {
+                let y = base_y + (i as f32 * line_height);
+
+                if y >= image_rect.min.y && y <= image_rect.max.y {
+                    let pos = Pos2::new(btm_info_x, y);
+                    painter.text(pos, Align2::LEFT_TOP, line, FontId::proportional(10.0 * scale_y), aux_color);
+                }
+            }
+        }
+    }
+
+    /// Render EINK effect areas (barcode, secondary barcode, class icon)
+    fn render_eink_areas(
+        &self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        scale_x: f32,
+        scale_y: f32,
+        y_offset: f32,
+        options: &ArknightsOverlayOptions,
+    ) {
+        let anim = &self.state.animation;
+        let barcode_layout = &self.firmware_config.layout.barcode;
+        let class_icon_size = &self.firmware_config.layout.class_icon;
+        let offsets = &self.firmware_config.layout.offsets;
+        let gradient = Self::theme_gradient(options);
+
+        // Barcode area
+        let barcode_x = barcode_layout.x as f32 * scale_x + image_rect.min.x;
+        let barcode_y = barcode_layout.y as f32 * scale_y + image_rect.min.y + y_offset;
+        let barcode_w = barcode_layout.width as f32 * scale_x;
+        let barcode_h = barcode_layout.height as f32 * scale_y;
+
+        if options.show_barcode && barcode_y + barcode_h >= image_rect.min.y && barcode_y <= image_rect.max.y {
+            let barcode_rect = Rect::from_min_size(
+                Pos2::new(barcode_x, barcode_y),
+                egui::vec2(barcode_w, barcode_h),
+            );
+
+            self.render_eink_barcode(painter, barcode_rect, anim.barcode_state, self.barcode_texture.as_ref(), gradient);
+        }
+
+        // Secondary barcode area (own layout rect and timing)
+        if let Some(ref secondary) = options.secondary_barcode {
+            let secondary_rect = Rect::from_min_size(
+                Pos2::new(
+                    secondary.x as f32 * scale_x + image_rect.min.x,
+                    secondary.y as f32 * scale_y + image_rect.min.y + y_offset,
+                ),
+                egui::vec2(secondary.width as f32 * scale_x, secondary.height as f32 * scale_y),
+            );
+            if options.show_barcode && secondary_rect.intersects(image_rect) {
+                self.render_eink_barcode(
+                    painter,
+                    secondary_rect,
+                    anim.secondary_barcode_state,
+                    self.secondary_barcode_texture.as_ref(),
+                    gradient,
+                );
+            }
+        }
+
+        // Class icon area
+        let btm_info_x = offsets.btm_info_x as f32 * scale_x + image_rect.min.x;
+        let classicon_x = btm_info_x;
+        let classicon_y = offsets.class_icon_y as f32 * scale_y + image_rect.min.y + y_offset;
+        let classicon_w = class_icon_size.width as f32 * scale_x;
+        let classicon_h = class_icon_size.height as f32 * scale_y;
+
+        if options.show_class_icon && classicon_y + classicon_h >= image_rect.min.y && classicon_y <= image_rect.max.y {
+            let classicon_rect = Rect::from_min_size(
+                Pos2::new(classicon_x, classicon_y),
+                egui::vec2(classicon_w, classicon_h),
+            );
+
+            match anim.classicon_state {
+                EinkState::FirstBlack | EinkState::SecondBlack => {
+                    painter.rect_filled(classicon_rect, 0.0, Color32::BLACK);
+                }
+                EinkState::FirstWhite | EinkState::SecondWhite => {
+                    painter.rect_filled(classicon_rect, 0.0, Color32::WHITE);
+                }
+                EinkState::Content => {
+                    // Draw real class icon texture if available
+                    if let Some(ref texture) = self.class_icon_texture {
+                        let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
+                        painter.image(texture.id(), classicon_rect, uv, Color32::WHITE);
+                    } else {
+                        // Fallback to placeholder (X shape)
+                        painter.rect_stroke(classicon_rect, 0.0, Stroke::new(1.0, Color32::WHITE));
+                        let center = classicon_rect.center();
+                        let half = classicon_w.min(classicon_h) * 0.3;
+                        painter.line_segment(
+                            [Pos2::new(center.x - half, center.y - half), Pos2::new(center.x + half, center.y + half)],
+                            Stroke::new(2.0, Color32::WHITE),
+                        );
+                        painter.line_segment(
+                            [Pos2::new(center.x + half, center.y - half), Pos2::new(center.x - half, center.y + half)],
+                            Stroke::new(2.0, Color32::WHITE),
+                        );
+                    }
+                }
+                EinkState::Idle => {}
+            }
+        }
+    }
+
+    /// Render a barcode area for the given EINK state
+    ///
+    /// `gradient` tints the bars top to bottom for dual-tone themes.
+    fn render_eink_barcode(
+        &self,
+        painter: &egui::Painter,
+        rect: Rect,
+        state: EinkState,
+        texture: Option<&egui::TextureHandle>,
+        gradient: Option<(Color32, Color32)>,
+    ) {
+        match state {
+            EinkState::FirstBlack | EinkState::SecondBlack => {
+                painter.rect_filled(rect, 0.0, Color32::BLACK);
+            }
+            EinkState::FirstWhite | EinkState::SecondWhite => {
+                painter.rect_filled(rect, 0.0, Color32::WHITE);
+            }
+            EinkState::Content => {
+                // Draw real barcode texture if available
+                if let Some(texture) = texture {
+                    let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
+                    match gradient {
+                        Some((start, end)) => {
+                            painter.add(Self::gradient_mesh(rect, texture.id(), uv, start, end, true));
+                        }
+                        None => {
+                            painter.image(texture.id(), rect, uv, Color32::WHITE);
+                        }
+                    }
+                } else {
+                    // Fallback to simplified barcode pattern
+                    self.render_barcode_pattern(painter, rect);
+                }
+            }
+            EinkState::Idle => {}
+        }
+    }
+
+    /// Render simplified barcode pattern
+    fn render_barcode_pattern(&self, painter: &egui::Painter, rect: Rect) {
+        // Draw a simplified barcode pattern (vertical stripes)
+        let stripe_count = 20;
+        let stripe_width = rect.width() / stripe_count as f32;
+
+        for i in 0..stripe_count {
+            // Alternate black and white stripes with some variation
+            if (i % 3 != 0) && (i % 5 != 2) {
+                let x = rect.min.x + i as f32 * stripe_width;
+                let stripe_rect = Rect::from_min_size(
+                    Pos2::new(x, rect.min.y),
+                    egui::vec2(stripe_width * 0.7, rect.height()),
+                );
+                painter.rect_filled(stripe_rect, 0.0, Color32::WHITE);
+            }
+        }
+    }
+
+    /// Render divider lines (upper and lower)
+    /// Note: C reference uses white (0xFFFFFFFF) for divider lines, not theme color;
+    /// dual-tone themes draw them as a left-to-right gradient accent instead
+    fn render_divider_lines(
+        &self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        scale_x: f32,
+        scale_y: f32,
+        y_offset: f32,
+        btm_info_x: f32,
+        gradient: Option<(Color32, Color32)>,
+    ) {
+        let anim = &self.state.animation;
+        let offsets = &self.firmware_config.layout.offsets;
+        let line_width = self.firmware_config.animation.bars_lines.line_width.max(1);
+        let draw_line = |y: f32, width_px: u32| {
+            let width = width_px as f32 * scale_x;
+            match gradient {
+                Some((start, end)) => {
+                    // Gradient spans the full line, revealed as it grows
+                    let t = (width_px * 255 / line_width).min(255) as u8;
+                    let end = Self::blend_colors(start, end, t);
+                    let rect = Rect::from_min_size(Pos2::new(btm_info_x, y - 0.5), egui::vec2(width, 1.0));
+                    let uv = Rect::from_min_max(egui::epaint::WHITE_UV, egui::epaint::WHITE_UV);
+                    painter.add(Self::gradient_mesh(rect, egui::TextureId::default(), uv, start, end, false));
+                }
+                None => {
+                    painter.line_segment(
+                        [Pos2::new(btm_info_x, y), Pos2::new(btm_info_x + width, y)],
+                        Stroke::new(1.0, Color32::WHITE),
+                    );
+                }
+            }
+        };
+
+        // Upper divider line (white per C reference: fbdraw_fill_rect(&fbdst, &dst_rect, 0xFFFFFFFF))
+        if anim.upper_line_width > 0 {
+            let y = offsets.upperline_y as f32 * scale_y + image_rect.min.y + y_offset;
+            if y >= image_rect.min.y && y <= image_rect.max.y {
+                draw_line(y, anim.upper_line_width);
+            }
+        }
+
+        // Lower divider line (white per C reference)
+        if anim.lower_line_width > 0 {
+            let y = offsets.lowerline_y as f32 * scale_y + image_rect.min.y + y_offset;
+            if y >= image_rect.min.y && y <= image_rect.max.y {
+                draw_line(y, anim.lower_line_width);
+            }
+        }
+    }
+
+    /// Render progress bar (AK bar)
+    fn render_progress_bar(
+        &self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        scale_x: f32,
+        scale_y: f32,
+        y_offset: f32,
+        btm_info_x: f32,
+        theme_color: Color32,
+    ) {
+        let anim = &self.state.animation;
+        let offsets = &self.firmware_config.layout.offsets;
+
+        if anim.ak_bar_width == 0 {
+            return;
+        }
+
+        // Use uniform scale factor to preserve aspect ratio (avoid stretching)
+        let uniform_scale = scale_x.min(scale_y);
+
+        let y = offsets.ak_bar_y as f32 * scale_y + image_rect.min.y + y_offset;
+        let width = anim.ak_bar_width as f32 * uniform_scale;
+
+        if y < image_rect.min.y || y > image_rect.max.y {
+            return;
+        }
+
+        // Use AK bar image texture if available
+        if let Some(ref ak_bar_texture) = self.ak_bar_texture {
+            // Get actual texture dimensions
+            let tex_width = ak_bar_texture.size()[0] as f32;
+            let tex_height = ak_bar_texture.size()[1] as f32;
+
+            // Calculate reveal ratio for sweep-in animation
+            let max_bar_width = 280.0;
+            let reveal_ratio = (anim.ak_bar_width as f32 / max_bar_width).min(1.0);
+
+            // Use original texture height, only scale for display
+            let displayed_width = tex_width * reveal_ratio * uniform_scale;
+            let displayed_height = tex_height * uniform_scale;
+
+            let bar_rect = Rect::from_min_size(
+                Pos2::new(btm_info_x, y),
+                egui::vec2(displayed_width, displayed_height),
+            );
+
+            let uv = Rect::from_min_max(
+                Pos2::new(0.0, 0.0),
+                Pos2::new(reveal_ratio, 1.0),
+            );
+
+            painter.image(ak_bar_texture.id(), bar_rect, uv, Color32::WHITE);
+        } else {
+            // Fallback: solid color rectangle
+            let bar_height = 3.0 * scale_y;
+            if y + bar_height <= image_rect.max.y {
+                let bar_rect = Rect::from_min_size(
+                    Pos2::new(btm_info_x, y),
+                    egui::vec2(width, bar_height),
+                );
+                painter.rect_filled(bar_rect, 0.0, theme_color);
+            }
+        }
+    }
+
+    /// Render arrow animation indicator (3 chevrons pointing UP on the right side)
+    /// Uses dark gray color to match C reference design
+    /// Per C reference (opinfo.c:553): arrows scroll upward via Y decrement
+    fn render_arrow_indicator(
+        &self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        scale_x: f32,
+        scale_y: f32,
+        y_offset: f32,
+        _theme_color: Color32,
+    ) {
+        let anim = &self.state.animation;
+
+        // Only show arrows when entry animation is complete
+        if !anim.is_entry_complete() {
+            return;
+        }
+
+        // Use image texture if available
+        if let Some(ref arrow_texture) = self.top_right_arrow_texture {
+            // Arrow image dimensions: 24x100, positioned at Y=100 per opinfo.c reference
+            let arrow_width = 24.0 * scale_x;
+            let arrow_height = 100.0 * scale_y;
+            let arrow_x = image_rect.max.x - arrow_width;  // Right-aligned
+            let arrow_y = image_rect.min.y + 100.0 * scale_y + y_offset;  // Y=100
+
+            // UV scrolling to implement upward loop animation
+            // anim.arrow_y cycles 0-99, use it as scroll offset
+            let scroll_offset = (anim.arrow_y as f32 / 100.0).fract();
+            let uv = Rect::from_min_max(
+                Pos2::new(0.0, scroll_offset),
+                Pos2::new(1.0, scroll_offset + 1.0),
+            );
+
+            let arrow_rect = Rect::from_min_size(
+                Pos2::new(arrow_x, arrow_y),
+                egui::vec2(arrow_width, arrow_height),
+            );
+
+            painter.image(arrow_texture.id(), arrow_rect, uv, Color32::WHITE);
+        } else {
+            // Fallback: draw programmatic dark gray chevrons
+            let offsets = &self.firmware_config.layout.offsets;
+            let base_y = offsets.arrow_y as f32 * scale_y + image_rect.min.y + y_offset;
+            let arrow_offset = anim.arrow_y as f32 * scale_y;
+
+            // Position on the right side of the screen
+            let x = image_rect.max.x - 40.0 * scale_x;
+
+            // Draw 3 chevrons pointing UPWARD (^ shape)
+            let chevron_spacing = 12.0 * scale_y;
+            let chevron_size = 8.0 * scale_x;
+
+            // Use dark gray color for chevrons (matching C reference opinfo.c)
+            let chevron_color = Color32::from_rgb(40, 40, 40);
+            let stroke = Stroke::new(2.0 * scale_x.min(scale_y), chevron_color);
+
+            for i in 0..3 {
+                let y = base_y + arrow_offset + (i as f32 * chevron_spacing);
+
+                if y >= image_rect.min.y && y <= image_rect.max.y {
+                    // Draw chevron (^ shape pointing UP)
+                    let left = Pos2::new(x - chevron_size, y + chevron_size);
+                    let top = Pos2::new(x, y);
+                    let right = Pos2::new(x + chevron_size, y + chevron_size);
+
+                    painter.line_segment([left, top], stroke);
+                    painter.line_segment([top, right], stroke);
+                }
+            }
+        }
+    }
+
+    // ============================================
+    // Legacy static decoration rendering functions
+    // These functions are no longer used since we now use modular assets
+    // Kept for reference only
+    // ============================================
+
+    /// Render top-left black L-shape decoration
+    #[allow(dead_code)]
+    fn render_top_left_corner(
+        &self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        scale_x: f32,
+        scale_y: f32,
+        alpha: u8,
+    ) {
+        let black = Color32::from_rgba_unmultiplied(0, 0, 0, alpha);
+
+        // Horizontal bar: 95x25 pixels (original template precise value)
+        let h_rect = Rect::from_min_size(
+            image_rect.min,
+            egui::vec2(95.0 * scale_x, 25.0 * scale_y),
+        );
+        painter.rect_filled(h_rect, 0.0, black);
+
+        // Vertical bar: 25x105 pixels (original template precise value)
+        let v_rect = Rect::from_min_size(
+            image_rect.min,
+            egui::vec2(25.0 * scale_x, 105.0 * scale_y),
+        );
+        painter.rect_filled(v_rect, 0.0, black);
+
+        // White triangle cutout (at inner corner of L-shape)
+        let white = Color32::from_rgba_unmultiplied(255, 255, 255, alpha);
+        let triangle_size = 15.0;
+        let tri_x = image_rect.min.x + 25.0 * scale_x;
+        let tri_y = image_rect.min.y + 25.0 * scale_y;
+
+        // Triangle points: right-angle triangle pointing to bottom-right
+        let points = vec![
+            Pos2::new(tri_x, tri_y),
+            Pos2::new(tri_x + triangle_size * scale_x, tri_y),
+            Pos2::new(tri_x, tri_y + triangle_size * scale_y),
+        ];
+        painter.add(egui::Shape::convex_polygon(points, white, Stroke::NONE));
+    }
+
+    /// Render top-right black background with gold stripes and dark chevron arrows
+    #[allow(dead_code)]
+    fn render_top_right_gradient(
+        &self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        scale_x: f32,
+        scale_y: f32,
+        alpha: u8,
+    ) {
+        // 1. Black background rectangle (X=280-360, Y=0-145)
+        let bg_rect = Rect::from_min_max(
+            Pos2::new(image_rect.min.x + 280.0 * scale_x, image_rect.min.y),
+            Pos2::new(image_rect.max.x, image_rect.min.y + 145.0 * scale_y),
+        );
+        let black = Color32::from_rgba_unmultiplied(0, 0, 0, alpha);
+        painter.rect_filled(bg_rect, 0.0, black);
+
+        // 2. Gold yellow stripes (uniform color #FFD700, starting from right edge)
+        let gold = Color32::from_rgba_unmultiplied(255, 215, 0, alpha);
+
+        // Define stripes (Y offset, height, width) - stripes get shorter from top to bottom
+        let stripes: [(f32, f32, f32); 12] = [
+            (0.0, 6.0, 76.0),   // Top stripe - longest
+            (8.0, 8.0, 72.0),
+            (18.0, 10.0, 68.0),
+            (30.0, 8.0, 64.0),
+            (40.0, 12.0, 60.0),
+            (54.0, 10.0, 56.0),
+            (66.0, 8.0, 52.0),
+            (76.0, 10.0, 48.0),
+            (88.0, 8.0, 44.0),
+            (98.0, 6.0, 40.0),
+            (106.0, 6.0, 36.0),
+            (114.0, 4.0, 32.0), // Bottom stripe - shortest
+        ];
+
+        for (y_off, height, stripe_width) in stripes.iter() {
+            let y = image_rect.min.y + y_off * scale_y;
+            let x = image_rect.max.x - stripe_width * scale_x;
+
+            let rect = Rect::from_min_size(
+                Pos2::new(x, y),
+                egui::vec2(*stripe_width * scale_x, *height * scale_y),
+            );
+            painter.rect_filled(rect, 0.0, gold);
+        }
+
+        // 3. Dark/black chevron arrows (outline style)
+        let arrow_x = image_rect.min.x + 320.0 * scale_x;
+        let dark = Color32::from_rgba_unmultiplied(40, 40, 40, alpha); // Dark gray/black
+        let stroke = Stroke::new(2.5 * scale_x.min(scale_y), dark);
+        let chevron_size = 10.0;
+
+        for i in 0..3 {
+            let arrow_y = image_rect.min.y + (58.0 + i as f32 * 22.0) * scale_y;
+            // Upward chevron (^)
+            let left = Pos2::new(
+                arrow_x - chevron_size * scale_x,
+                arrow_y + chevron_size * scale_y,
+            );
+            let top = Pos2::new(arrow_x, arrow_y);
+            let right = Pos2::new(
+                arrow_x + chevron_size * scale_x,
+                arrow_y + chevron_size * scale_y,
+            );
+
+            painter.line_segment([left, top], stroke);
+            painter.line_segment([top, right], stroke);
+        }
+    }
+
+    /// Render right side black vertical bar with "RHODES ISLAND INC." text
+    /// RHODES is yellow (#FFD700), ISLAND INC. is white
+    #[allow(dead_code)]
+    fn render_right_side_bar(
+        &self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        scale_x: f32,
+        scale_y: f32,
+        y_offset: f32,
+        alpha: u8,
+    ) {
+        // Black vertical bar: width 30px, from Y=145 to Y=600 (original template precise values)
+        let bar_width = 30.0 * scale_x;
+        let bar_x = image_rect.max.x - bar_width;
+        let bar_start_y = image_rect.min.y + 145.0 * scale_y + y_offset;
+        let bar_end_y = image_rect.min.y + 600.0 * scale_y + y_offset;
+
+        // Clamp to image bounds
+        let clamped_start = bar_start_y.max(image_rect.min.y);
+        let clamped_end = bar_end_y.min(image_rect.max.y);
+
+        if clamped_end > clamped_start {
+            let bar_rect = Rect::from_min_max(
+                Pos2::new(bar_x, clamped_start),
+                Pos2::new(image_rect.max.x, clamped_end),
+            );
+            let bar_color = Color32::from_rgba_unmultiplied(0, 0, 0, alpha);
+            painter.rect_filled(bar_rect, 0.0, bar_color);
+
+            // Text colors
+            let yellow = Color32::from_rgba_unmultiplied(255, 215, 0, alpha); // #FFD700 for RHODES
+            let white = Color32::from_rgba_unmultiplied(255, 255, 255, alpha);
+            let text_x = bar_x + bar_width / 2.0;
+            let text_start_y = clamped_start + 30.0 * scale_y;
+
+            // RHODES (yellow, bold - simulated with slightly larger font)
+            let rhodes = "RHODES";
+            for (i, ch) in rhodes.chars().enumerate() {
+                let char_y = text_start_y + i as f32 * 18.0 * scale_y;
+                if char_y < image_rect.max.y {
+                    painter.text(
+                        Pos2::new(text_x, char_y),
+                        Align2::CENTER_TOP,
+                        ch.to_string(),
+                        FontId::proportional(14.0 * scale_y), // Larger for bold effect
+                        yellow, // RHODES is yellow
+                    );
+                }
+            }
+
+            // ISLAND INC. (white, normal)
+            let island_inc = " ISLAND INC.";
+            let island_start_y = text_start_y + rhodes.len() as f32 * 18.0 * scale_y;
+            for (i, ch) in island_inc.chars().enumerate() {
+                let char_y = island_start_y + i as f32 * 14.0 * scale_y;
+                if char_y < image_rect.max.y {
+                    painter.text(
+                        Pos2::new(text_x, char_y),
+                        Align2::CENTER_TOP,
+                        ch.to_string(),
+                        FontId::proportional(10.0 * scale_y),
+                        white, // ISLAND INC. is white
+                    );
+                }
+            }
+        }
+    }
+
+    /// Render left colorful gradient stripe (yellow → orange → purple → blue → cyan)
+    #[allow(dead_code)]
+    fn render_left_gradient_stripe(
+        &self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        scale_x: f32,
+        scale_y: f32,
+        y_offset: f32,
+        alpha: u8,
+    ) {
+        // Position X=58, width 7px (original template precise values)
+        let stripe_x = image_rect.min.x + 58.0 * scale_x;
+        let stripe_width = 7.0 * scale_x;
+        let stripe_start_y = image_rect.min.y + 110.0 * scale_y + y_offset;
+        let stripe_end_y = image_rect.max.y;
+
+        // More refined gradient: yellow → orange → purple → blue → cyan
+        let segment_count = 30;
+        let total_height = stripe_end_y - stripe_start_y;
+        let segment_height = total_height / segment_count as f32;
+
+        for i in 0..segment_count {
+            let y = stripe_start_y + i as f32 * segment_height;
+            if y < image_rect.min.y || y > image_rect.max.y {
+                continue;
+            }
+
+            let t = i as f32 / segment_count as f32;
+            let (r, g, b) = if t < 0.15 {
+                // Yellow (255, 220, 50)
+                (255, 220, 50)
+            } else if t < 0.30 {
+                // Yellow → orange
+                let t2 = (t - 0.15) / 0.15;
+                (255, (220.0 - t2 * 80.0) as u8, (50.0 + t2 * 30.0) as u8)
+            } else if t < 0.50 {
+                // Orange → purple
+                let t2 = (t - 0.30) / 0.20;
+                (
+                    (255.0 - t2 * 100.0) as u8,
+                    (140.0 - t2 * 40.0) as u8,
+                    (80.0 + t2 * 120.0) as u8,
+                )
+            } else if t < 0.75 {
+                // Purple → blue
+                let t2 = (t - 0.50) / 0.25;
+                (
+                    (155.0 - t2 * 80.0) as u8,
+                    (100.0 + t2 * 80.0) as u8,
+                    (200.0 + t2 * 40.0) as u8,
+                )
+            } else {
+                // Blue → cyan
+                let t2 = (t - 0.75) / 0.25;
+                (
+                    (75.0 + t2 * 25.0) as u8,
+                    (180.0 + t2 * 40.0) as u8,
+                    (240.0 - t2 * 20.0) as u8,
+                )
+            };
+
+            let color = Color32::from_rgba_unmultiplied(r, g, b, alpha);
+            let rect = Rect::from_min_size(
+                Pos2::new(stripe_x, y),
+                egui::vec2(stripe_width, segment_height + 1.0), // +1 to avoid gaps
+            );
+            painter.rect_filled(rect, 0.0, color);
+        }
+    }
+
+    /// Render bottom-left light cyan-blue gradient
+    #[allow(dead_code)]
+    fn render_bottom_left_gradient(
+        &self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        scale_x: f32,
+        scale_y: f32,
+        y_offset: f32,
+        alpha: u8,
+    ) {
+        // Larger, softer gradient starting from left edge and bottom
+        let center_x = image_rect.min.x; // Start from left edge
+        let center_y = image_rect.max.y + y_offset; // Start from bottom edge
+        let max_radius = 280.0 * scale_x.min(scale_y);
+
+        // Use more layers for softer gradient effect
+        let layer_count = 20;
+        for i in (0..layer_count).rev() {
+            let t = i as f32 / layer_count as f32;
+            let radius = max_radius * (1.0 - t * 0.3);
+            // Lower opacity for softer effect
+            let layer_alpha = ((alpha as f32) * 0.08 * (1.0 - t * 0.5)) as u8;
+            let color = Color32::from_rgba_unmultiplied(180, 230, 240, layer_alpha);
+
+            painter.circle_filled(Pos2::new(center_x, center_y), radius, color);
+        }
+    }
+
+    /// Render bottom-right logo background with diagonal cut
+    #[allow(dead_code)]
+    fn render_logo_background(
+        &self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        scale_x: f32,
+        scale_y: f32,
+        y_offset: f32,
+        alpha: u8,
+    ) {
+        // Size: 200x40, with diagonal cut (original template precise values)
+        let bg_width = 200.0 * scale_x;
+        let bg_height = 40.0 * scale_y;
+        let bg_x = image_rect.max.x - bg_width;
+        let bg_y = image_rect.max.y - bg_height + y_offset;
+        let cut_size = 20.0 * scale_x; // Top-left diagonal cut size
+
+        if bg_y >= image_rect.min.y && bg_y + bg_height <= image_rect.max.y {
+            let black = Color32::from_rgba_unmultiplied(0, 0, 0, alpha);
+
+            // Draw polygon with diagonal cut at top-left corner
+            let points = vec![
+                Pos2::new(bg_x + cut_size, bg_y),           // Top-left (after cut)
+                Pos2::new(image_rect.max.x, bg_y),          // Top-right
+                Pos2::new(image_rect.max.x, bg_y + bg_height), // Bottom-right
+                Pos2::new(bg_x, bg_y + bg_height),          // Bottom-left
+                Pos2::new(bg_x, bg_y + cut_size),           // Cut point on left edge
+            ];
+            painter.add(egui::Shape::convex_polygon(points, black, Stroke::NONE));
+
+            // "Rhodes Island" text
+            let white = Color32::from_rgba_unmultiplied(255, 255, 255, alpha);
+            let text_y = bg_y + 8.0 * scale_y;
+            painter.text(
+                Pos2::new(bg_x + 30.0 * scale_x, text_y),
+                Align2::LEFT_TOP,
+                "Rhodes Island",
+                FontId::proportional(10.0 * scale_y),
+                white,
+            );
+
+            // Note: "明日方舟" requires Chinese font support, logo_image handles this separately
+        }
+    }
+
+    /// Render logo image in the bottom-right corner
+    fn render_logo_image(
+        &self,
+        painter: &egui::Painter,
+        image_rect: Rect,
+        scale_x: f32,
+        scale_y: f32,
+        y_offset: f32,
+    ) {
+        let anim = &self.state.animation;
+
+        // Only show when logo alpha > 0
+        if anim.logo_alpha == 0 {
+            return;
+        }
+
+        // Check if we have a logo texture
+        if let Some(ref texture) = self.logo_texture {
+            // Logo position (bottom-right corner)
+            let logo_width = 80.0 * scale_x;
+            let logo_height = 30.0 * scale_y;
+            let logo_x = image_rect.max.x - logo_width - 10.0 * scale_x;
+            let logo_y = image_rect.max.y - logo_height - 10.0 * scale_y + y_offset;
+
+            if logo_y >= image_rect.min.y && logo_y + logo_height <= image_rect.max.y {
+                let logo_rect = Rect::from_min_size(
+                    Pos2::new(logo_x, logo_y),
+                    egui::vec2(logo_width, logo_height),
+                );
+
+                let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
+                // Apply logo alpha
+                let tint = Color32::from_rgba_unmultiplied(255, 255, 255, anim.logo_alpha);
+                painter.image(texture.id(), logo_rect, uv, tint);
+            }
+        }
+    }
+
+    // NOTE: render_left_rhodes_island() removed - C reference uses image resource at (0,0), not text rendering
+    // NOTE: render_bottom_right_logo_text() removed - C reference does not have this element
+    // NOTE: render_staff_section() removed - C reference renders staff_text via typewriter effect at X=70, Y=480
+    //       (already handled in render_typewriter_texts), not as centered "STAFF" with line and subtitle
+}
+
+/// Convert microseconds to frame count
+fn microseconds_to_frames(us: i64, fps: u32) -> u32 {
+    ((us * fps as i64) / 1_000_000).max(1) as u32
+}
+
+/// A frame step, limited to `MAX_SEEK_FRAMES` either way
+fn clamp_step(frames: i32) -> i32 {
+    let limit = MAX_SEEK_FRAMES as i32;
+    if frames.unsigned_abs() > MAX_SEEK_FRAMES {
+        warn!("Stepping {} frames instead of {}", limit * frames.signum(), frames);
+    }
+    frames.clamp(-limit, limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_microseconds_to_frames() {
+        // 1 second at 50fps = 50 frames
+        assert_eq!(microseconds_to_frames(1_000_000, 50), 50);
+        // 0.5 seconds at 50fps = 25 frames
+        assert_eq!(microseconds_to_frames(500_000, 50), 25);
+        // Very small value should return at least 1
+        assert_eq!(microseconds_to_frames(1, 50), 1);
+    }
+
+    #[test]
+    fn test_element_color_fallback() {
+        assert_eq!(Simulator::element_color("", Color32::GRAY), Color32::GRAY);
+        assert_eq!(
+            Simulator::element_color("#FF8000", Color32::GRAY),
+            Color32::from_rgb(255, 128, 0)
+        );
+    }
+
+    #[test]
+    fn test_theme_gradient() {
+        let mut options = ArknightsOverlayOptions::default();
+        assert_eq!(Simulator::theme_gradient(&options), None);
+
+        options.color = "#FF0000".to_string();
+        options.color2 = "#0000FF".to_string();
+        assert_eq!(
+            Simulator::theme_gradient(&options),
+            Some((Color32::from_rgb(255, 0, 0), Color32::from_rgb(0, 0, 255)))
+        );
+    }
+
+    #[test]
+    fn test_clamp_step() {
+        assert_eq!(clamp_step(-3), -3);
+        assert_eq!(clamp_step(i32::MAX), MAX_SEEK_FRAMES as i32);
+        assert_eq!(clamp_step(i32::MIN), -(MAX_SEEK_FRAMES as i32));
+    }
+}
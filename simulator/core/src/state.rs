@@ -155,6 +155,7 @@ pub struct AnimationState {
     // EINK states
     pub barcode_state: EinkState,
     pub classicon_state: EinkState,
+    pub secondary_barcode_state: EinkState,
 
     // Color fade radius
     pub color_fade_radius: u32,
@@ -233,6 +234,28 @@ impl TransitionState {
     }
 }
 
+/// Restarting playback without limit, for unattended demo displays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AutoReplay {
+    #[default]
+    Off,
+    /// Restart this long after reaching the loop state (microseconds)
+    AfterLoop(i64),
+    /// Restart this long after playback started (microseconds)
+    AfterTotal(i64),
+}
+
+impl AutoReplay {
+    /// Whether `state` has played long enough to restart
+    pub fn is_due(self, state: &SimulatorState) -> bool {
+        match self {
+            AutoReplay::Off => false,
+            AutoReplay::AfterLoop(us) => state.play_state == PlayState::Loop && state.loop_played_us >= us,
+            AutoReplay::AfterTotal(us) => state.played_us >= us,
+        }
+    }
+}
+
 /// Complete simulator state
 #[derive(Debug, Clone, Default)]
 pub struct SimulatorState {
@@ -259,6 +282,12 @@ pub struct SimulatorState {
     pub loop_frame_accumulator: i64,
     /// Intro video frame accumulator (microseconds) for FPS sync
     pub intro_frame_accumulator: i64,
+    /// Intro video time shown so far (microseconds)
+    pub intro_played_us: i64,
+    /// Time spent in the loop state so far (microseconds), for auto-replay
+    pub loop_played_us: i64,
+    /// Time played since playback started (microseconds), for auto-replay
+    pub played_us: i64,
 
     /// Wall-clock time remainder for logic frame pacing (microseconds)
     pub logic_time_remainder_us: i64,
@@ -284,10 +313,35 @@ impl SimulatorState {
         self.appear_time_frames = appear_time;
     }
 
+    /// Account for one more intro frame of `frame_duration_us`
+    ///
+    /// Returns false once the frame would run past `limit_us`, the configured
+    /// intro duration (None = play until the video ends).
+    pub fn advance_intro_clock(&mut self, frame_duration_us: i64, limit_us: Option<i64>) -> bool {
+        if limit_us.is_some_and(|limit| self.intro_played_us + frame_duration_us > limit) {
+            return false;
+        }
+        self.intro_played_us += frame_duration_us;
+        true
+    }
+
+    /// Account for `elapsed_us` more of the loop state
+    ///
+    /// Returns true once `replay_after_us` has been spent there.
+    pub fn advance_loop_clock(&mut self, elapsed_us: i64, replay_after_us: i64) -> bool {
+        if self.play_state != PlayState::Loop {
+            return false;
+        }
+        self.loop_played_us += elapsed_us;
+        self.loop_played_us >= replay_after_us
+    }
+
     /// Start playback
     pub fn start_playback(&mut self, has_intro: bool, transition_type: TransitionType, total_frames: u32) {
         self.is_playing = true;
         self.frame_counter = 0;
+        self.loop_played_us = 0;
+        self.played_us = 0;
         self.animation.reset();
 
         // Determine initial state based on whether intro exists
@@ -337,6 +391,39 @@ mod tests {
         assert_eq!(TransitionPhase::from_progress(1.0), TransitionPhase::PhaseDone);
     }
 
+    #[test]
+    fn test_advance_intro_clock() {
+        let mut state = SimulatorState::new();
+        // 30fps intro cut at 100ms: three frames fit, the fourth does not
+        for _ in 0..3 {
+            assert!(state.advance_intro_clock(33_333, Some(100_000)));
+        }
+        assert!(!state.advance_intro_clock(33_333, Some(100_000)));
+        assert!(state.advance_intro_clock(33_333, None));
+    }
+
+    #[test]
+    fn test_advance_loop_clock() {
+        let mut state = SimulatorState::new();
+        assert!(!state.advance_loop_clock(1_000_000, 1_500_000));
+        state.play_state = PlayState::Loop;
+        assert!(!state.advance_loop_clock(1_000_000, 1_500_000));
+        assert!(state.advance_loop_clock(1_000_000, 1_500_000));
+    }
+
+    #[test]
+    fn test_auto_replay() {
+        let mut state = SimulatorState::new();
+        state.played_us = 3_000_000;
+        assert!(!AutoReplay::Off.is_due(&state));
+        assert!(AutoReplay::AfterTotal(2_000_000).is_due(&state));
+        assert!(!AutoReplay::AfterLoop(1_000_000).is_due(&state));
+
+        state.play_state = PlayState::Loop;
+        state.loop_played_us = 1_000_000;
+        assert!(AutoReplay::AfterLoop(1_000_000).is_due(&state));
+    }
+
     #[test]
     fn test_eink_state() {
         // Before start
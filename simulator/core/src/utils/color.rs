@@ -0,0 +1,175 @@
+//! Color utilities
+//!
+//! Helper functions for color conversion and manipulation.
+
+type Rgba = (u8, u8, u8, u8);
+
+/// Named colors accepted in configs (CSS names)
+const NAMED_COLORS: &[(&str, Rgba)] = &[
+    ("black", (0, 0, 0, 255)),
+    ("white", (255, 255, 255, 255)),
+    ("red", (255, 0, 0, 255)),
+    ("green", (0, 128, 0, 255)),
+    ("blue", (0, 0, 255, 255)),
+    ("yellow", (255, 255, 0, 255)),
+    ("orange", (255, 165, 0, 255)),
+    ("purple", (128, 0, 128, 255)),
+    ("cyan", (0, 255, 255, 255)),
+    ("magenta", (255, 0, 255, 255)),
+    ("gray", (128, 128, 128, 255)),
+    ("grey", (128, 128, 128, 255)),
+    ("transparent", (0, 0, 0, 0)),
+];
+
+/// Parse a config color to an RGBA tuple (straight alpha)
+///
+/// Accepts "#RGB", "#RRGGBB", "#RRGGBBAA" (the "#" is optional),
+/// "rgb(r, g, b)", "rgba(r, g, b, a)" with a in 0..1, and a few color names.
+pub fn parse_color(color: &str) -> Option<(u8, u8, u8, u8)> {
+    let color = color.trim();
+    if let Some(&(_, rgba)) = NAMED_COLORS.iter().find(|(name, _)| name.eq_ignore_ascii_case(color)) {
+        return Some(rgba);
+    }
+    let lower = color.to_ascii_lowercase();
+    if let Some(args) = lower.strip_prefix("rgba(").and_then(|s| s.strip_suffix(')')) {
+        let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+        let [r, g, b, a] = parts.as_slice() else {
+            return None;
+        };
+        let alpha: f32 = a.parse().ok()?;
+        if !(0.0..=1.0).contains(&alpha) {
+            return None;
+        }
+        return Some((r.parse().ok()?, g.parse().ok()?, b.parse().ok()?, (alpha * 255.0).round() as u8));
+    }
+    if let Some(args) = lower.strip_prefix("rgb(").and_then(|s| s.strip_suffix(')')) {
+        let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+        let [r, g, b] = parts.as_slice() else {
+            return None;
+        };
+        return Some((r.parse().ok()?, g.parse().ok()?, b.parse().ok()?, 255));
+    }
+    parse_hex_color_rgba(color)
+}
+
+/// Parse hex color string to RGB tuple, dropping any alpha
+///
+/// Accepts formats: "#RGB", "#RRGGBB" or "#RRGGBBAA" ("#" optional)
+pub fn parse_hex_color(hex: &str) -> Option<(u8, u8, u8)> {
+    let (r, g, b, _) = parse_hex_color_rgba(hex)?;
+    Some((r, g, b))
+}
+
+/// Parse hex color string to RGBA tuple (alpha = 255 unless given)
+pub fn parse_hex_color_rgba(hex: &str) -> Option<(u8, u8, u8, u8)> {
+    let hex = hex.trim_start_matches('#');
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok();
+
+    match hex.len() {
+        3 => {
+            // #RGB: each digit doubled
+            let digits: Vec<u8> = hex.chars().filter_map(|c| c.to_digit(16)).map(|d| d as u8 * 17).collect();
+            Some((digits[0], digits[1], digits[2], 255))
+        }
+        6 => Some((channel(0)?, channel(1)?, channel(2)?, 255)),
+        8 => Some((channel(0)?, channel(1)?, channel(2)?, channel(3)?)),
+        _ => None,
+    }
+}
+
+/// Convert RGB to hex string
+pub fn rgb_to_hex(r: u8, g: u8, b: u8) -> String {
+    format!("#{:02X}{:02X}{:02X}", r, g, b)
+}
+
+/// Blend two colors with alpha
+///
+/// result = fg * alpha + bg * (1 - alpha)
+pub fn blend_colors(
+    bg: (u8, u8, u8),
+    fg: (u8, u8, u8),
+    alpha: f32,
+) -> (u8, u8, u8) {
+    let alpha = alpha.clamp(0.0, 1.0);
+    let inv_alpha = 1.0 - alpha;
+
+    let r = (fg.0 as f32 * alpha + bg.0 as f32 * inv_alpha) as u8;
+    let g = (fg.1 as f32 * alpha + bg.1 as f32 * inv_alpha) as u8;
+    let b = (fg.2 as f32 * alpha + bg.2 as f32 * inv_alpha) as u8;
+
+    (r, g, b)
+}
+
+/// Blend with alpha premultiplied
+pub fn blend_rgba(
+    bg: (u8, u8, u8, u8),
+    fg: (u8, u8, u8, u8),
+) -> (u8, u8, u8, u8) {
+    let fg_alpha = fg.3 as f32 / 255.0;
+    let bg_alpha = bg.3 as f32 / 255.0;
+
+    // Standard alpha compositing
+    let out_alpha = fg_alpha + bg_alpha * (1.0 - fg_alpha);
+
+    if out_alpha < 0.001 {
+        return (0, 0, 0, 0);
+    }
+
+    let r = ((fg.0 as f32 * fg_alpha + bg.0 as f32 * bg_alpha * (1.0 - fg_alpha)) / out_alpha) as u8;
+    let g = ((fg.1 as f32 * fg_alpha + bg.1 as f32 * bg_alpha * (1.0 - fg_alpha)) / out_alpha) as u8;
+    let b = ((fg.2 as f32 * fg_alpha + bg.2 as f32 * bg_alpha * (1.0 - fg_alpha)) / out_alpha) as u8;
+    let a = (out_alpha * 255.0) as u8;
+
+    (r, g, b, a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#FF0000"), Some((255, 0, 0)));
+        assert_eq!(parse_hex_color("00FF00"), Some((0, 255, 0)));
+        assert_eq!(parse_hex_color("#0000FF"), Some((0, 0, 255)));
+        assert_eq!(parse_hex_color("#000000"), Some((0, 0, 0)));
+        assert_eq!(parse_hex_color("#FFFFFF"), Some((255, 255, 255)));
+    }
+
+    #[test]
+    fn test_parse_color_formats() {
+        assert_eq!(parse_color("#F80"), Some((255, 136, 0, 255)));
+        assert_eq!(parse_color("#FF000080"), Some((255, 0, 0, 128)));
+        assert_eq!(parse_color("rgb(10, 20, 30)"), Some((10, 20, 30, 255)));
+        assert_eq!(parse_color("RGBA(10,20,30,0.5)"), Some((10, 20, 30, 128)));
+        assert_eq!(parse_color(" White "), Some((255, 255, 255, 255)));
+        assert_eq!(parse_color("transparent"), Some((0, 0, 0, 0)));
+        assert_eq!(parse_hex_color("#FF000080"), Some((255, 0, 0)));
+
+        assert_eq!(parse_color("#12345"), None);
+        assert_eq!(parse_color("rgb(256, 0, 0)"), None);
+        assert_eq!(parse_color("rgba(0, 0, 0, 2)"), None);
+        assert_eq!(parse_color("black-ish"), None);
+    }
+
+    #[test]
+    fn test_rgb_to_hex() {
+        assert_eq!(rgb_to_hex(255, 0, 0), "#FF0000");
+        assert_eq!(rgb_to_hex(0, 255, 0), "#00FF00");
+        assert_eq!(rgb_to_hex(0, 0, 255), "#0000FF");
+    }
+
+    #[test]
+    fn test_blend_colors() {
+        // Full opacity foreground
+        assert_eq!(blend_colors((0, 0, 0), (255, 255, 255), 1.0), (255, 255, 255));
+        // Full opacity background
+        assert_eq!(blend_colors((255, 255, 255), (0, 0, 0), 0.0), (255, 255, 255));
+        // 50% blend
+        let result = blend_colors((0, 0, 0), (200, 200, 200), 0.5);
+        assert!(result.0 >= 90 && result.0 <= 110);
+    }
+}
@@ -0,0 +1,61 @@
+//! Duration parsing
+//!
+//! Command line times are written like "20s", "1500ms" or "1m30s"; a bare
+//! number counts as seconds.
+
+/// Parse a duration into microseconds
+pub fn parse_duration_us(s: &str) -> Result<i64, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("empty duration".to_string());
+    }
+    if let Ok(secs) = s.parse::<f64>() {
+        return secs_to_us(secs, s);
+    }
+
+    let mut total_us = 0i64;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(|| format!("missing unit in {:?}", s))?;
+        let (number, tail) = rest.split_at(number_len);
+        let unit_len = tail.find(|c: char| c.is_ascii_digit() || c == '.').unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        let value: f64 = number.parse().map_err(|_| format!("invalid number in {:?}", s))?;
+        let scale = match unit {
+            "h" => 3600.0,
+            "m" | "min" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            "us" | "µs" => 0.000_001,
+            _ => return Err(format!("unknown unit {:?} in {:?} (use h, m, s, ms or us)", unit, s)),
+        };
+        total_us += secs_to_us(value * scale, s)?;
+        rest = tail;
+    }
+    Ok(total_us)
+}
+
+fn secs_to_us(secs: f64, s: &str) -> Result<i64, String> {
+    if !secs.is_finite() || secs < 0.0 {
+        return Err(format!("invalid duration {:?}", s));
+    }
+    Ok((secs * 1_000_000.0).round() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_us() {
+        assert_eq!(parse_duration_us("20s"), Ok(20_000_000));
+        assert_eq!(parse_duration_us("12.5"), Ok(12_500_000));
+        assert_eq!(parse_duration_us("1500ms"), Ok(1_500_000));
+        assert_eq!(parse_duration_us("1m30s"), Ok(90_000_000));
+        assert!(parse_duration_us("").is_err());
+        assert!(parse_duration_us("5 parsecs").is_err());
+        assert!(parse_duration_us("-1").is_err());
+    }
+}
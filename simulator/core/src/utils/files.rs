@@ -0,0 +1,39 @@
+//! Asset file access
+//!
+//! Assets are read through here so builds without a filesystem (the web
+//! preview) can serve them from memory: files mounted with `mount_files`
+//! shadow the disk.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Files served from memory, by path
+static MOUNTED: RwLock<Option<HashMap<PathBuf, Arc<[u8]>>>> = RwLock::new(None);
+
+/// Serve `files` from memory, replacing the files mounted before
+pub fn mount_files(files: HashMap<PathBuf, Vec<u8>>) {
+    let files = files.into_iter().map(|(path, data)| (path, Arc::from(data))).collect();
+    if let Ok(mut guard) = MOUNTED.write() {
+        *guard = Some(files);
+    }
+}
+
+fn mounted(path: &Path) -> Option<Arc<[u8]>> {
+    MOUNTED.read().ok()?.as_ref()?.get(path).cloned()
+}
+
+/// Read a whole asset file, from memory if mounted
+pub fn read_file(path: &Path) -> io::Result<Vec<u8>> {
+    match mounted(path) {
+        Some(data) => Ok(data.to_vec()),
+        None => std::fs::read(path),
+    }
+}
+
+/// Whether an asset file exists, in memory or on disk
+pub fn file_exists(path: &Path) -> bool {
+    mounted(path).is_some() || path.exists()
+}
+
@@ -0,0 +1,40 @@
+//! JSON helpers
+//!
+//! Deep merge used to apply partial overrides over full configurations.
+
+use serde_json::Value;
+
+/// Merge `patch` into `base` recursively
+///
+/// Objects are merged key by key; any other value in `patch` (including
+/// arrays) replaces the value in `base`. `null` in `patch` is ignored.
+pub fn merge_json(base: &mut Value, patch: &Value) {
+    match (base, patch) {
+        (Value::Object(base_map), Value::Object(patch_map)) => {
+            for (key, patch_value) in patch_map {
+                match base_map.get_mut(key) {
+                    Some(base_value) => merge_json(base_value, patch_value),
+                    None if !patch_value.is_null() => {
+                        base_map.insert(key.clone(), patch_value.clone());
+                    }
+                    None => {}
+                }
+            }
+        }
+        (_, Value::Null) => {}
+        (base, patch) => *base = patch.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_nested() {
+        let mut base = json!({"a": {"x": 1, "y": 2}, "b": [1, 2], "c": "keep"});
+        merge_json(&mut base, &json!({"a": {"y": 5}, "b": [3], "c": null, "d": true}));
+        assert_eq!(base, json!({"a": {"x": 1, "y": 5}, "b": [3], "c": "keep", "d": true}));
+    }
+}
@@ -0,0 +1,17 @@
+//! Utility module
+//!
+//! Contains helper functions and types.
+
+mod color;
+mod duration;
+mod files;
+mod json;
+mod path;
+mod template;
+
+pub use color::*;
+pub use duration::*;
+pub use files::*;
+pub use json::*;
+pub use path::*;
+pub use template::*;
@@ -0,0 +1,91 @@
+//! Asset path resolution
+//!
+//! Asset paths may contain `${APP_DIR}`, `${BASE_DIR}`, `${HOME}` or any
+//! environment variable as `${NAME}`, so a config works across machines.
+
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Application directory substituted for `${APP_DIR}`
+static APP_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Set the directory `${APP_DIR}` expands to
+pub fn set_app_dir(dir: PathBuf) {
+    if let Ok(mut guard) = APP_DIR.write() {
+        *guard = Some(dir);
+    }
+}
+
+fn lookup_var(name: &str, base_dir: &Path) -> Option<String> {
+    match name {
+        "APP_DIR" => APP_DIR
+            .read()
+            .ok()
+            .and_then(|dir| dir.as_ref().map(|d| d.display().to_string())),
+        "BASE_DIR" => Some(base_dir.display().to_string()),
+        "HOME" => std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")).ok(),
+        _ => std::env::var(name).ok(),
+    }
+}
+
+/// Expand `${NAME}` references in a path
+///
+/// Unknown variables are left in place so the resulting "file not found"
+/// points at the culprit.
+pub fn expand_path_vars(path: &str, base_dir: &Path) -> String {
+    let mut result = String::with_capacity(path.len());
+    let mut rest = path;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find('}') {
+            Some(end) => {
+                let name = &after[..end];
+                match lookup_var(name, base_dir) {
+                    Some(value) => result.push_str(&value),
+                    None => result.push_str(&rest[start..start + 2 + end + 1]),
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Resolve an asset path: expand variables, then resolve relative paths against `base_dir`
+pub fn resolve_asset_path(path: &str, base_dir: &Path) -> PathBuf {
+    let expanded = PathBuf::from(expand_path_vars(path, base_dir));
+    if expanded.is_absolute() {
+        expanded
+    } else {
+        base_dir.join(expanded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_path_vars() {
+        let base = Path::new("/materials/amiya");
+        assert_eq!(expand_path_vars("${BASE_DIR}/loop.mp4", base), "/materials/amiya/loop.mp4");
+        assert_eq!(expand_path_vars("${NO_SUCH_VAR_42}/a.png", base), "${NO_SUCH_VAR_42}/a.png");
+        assert_eq!(expand_path_vars("plain/${unterminated", base), "plain/${unterminated");
+
+        std::env::set_var("EPASS_TEST_ASSETS", "/shared");
+        assert_eq!(expand_path_vars("${EPASS_TEST_ASSETS}/logo.png", base), "/shared/logo.png");
+    }
+
+    #[test]
+    fn test_resolve_asset_path() {
+        let base = Path::new("/materials/amiya");
+        assert_eq!(resolve_asset_path("logo.png", base), base.join("logo.png"));
+        assert_eq!(resolve_asset_path("${BASE_DIR}/logo.png", base), PathBuf::from("/materials/amiya/logo.png"));
+    }
+}
@@ -0,0 +1,181 @@
+//! Template variable expansion
+//!
+//! Expands `{name}`-style placeholders in overlay text fields at render time,
+//! so generated passes can show per-unit data in the preview.
+
+use std::collections::HashMap;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::EPConfig;
+
+/// Values available to `{...}` placeholders
+#[derive(Debug, Clone, Default)]
+pub struct TemplateVars {
+    values: HashMap<String, String>,
+}
+
+impl TemplateVars {
+    /// Build variables from an EPConfig and the system clock
+    ///
+    /// Supported: `{name}`, `{description}`, `{uuid}`, `{serial}`,
+    /// `{date}`, `{time}`, `{year}` (clock values are UTC). `{serial}` is the
+    /// config's `serial`; without one it falls back to the first 8 hex digits
+    /// of the uuid, which only tells materials apart, not units.
+    pub fn from_config(config: &EPConfig) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self::from_config_at(config, now)
+    }
+
+    /// Build variables from an EPConfig at a fixed UNIX timestamp (seconds)
+    pub fn from_config_at(config: &EPConfig, unix_secs: u64) -> Self {
+        let mut vars = Self::default();
+
+        vars.set("name", &config.name);
+        vars.set("description", &config.description);
+        vars.set("uuid", &config.uuid);
+
+        if config.serial.is_empty() {
+            let prefix: String = config
+                .uuid
+                .chars()
+                .filter(|c| c.is_ascii_hexdigit())
+                .take(8)
+                .collect::<String>()
+                .to_uppercase();
+            vars.set("serial", &prefix);
+        } else {
+            vars.set("serial", &config.serial);
+        }
+
+        let (year, month, day) = civil_from_days((unix_secs / 86_400) as i64);
+        let secs_of_day = unix_secs % 86_400;
+        vars.set("date", &format!("{:04}-{:02}-{:02}", year, month, day));
+        vars.set("year", &format!("{:04}", year));
+        vars.set(
+            "time",
+            &format!("{:02}:{:02}", secs_of_day / 3600, (secs_of_day / 60) % 60),
+        );
+
+        vars
+    }
+
+    /// Set a variable value
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    /// Get a variable value
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|s| s.as_str())
+    }
+}
+
+/// Expand `{var}` placeholders in text
+///
+/// Unknown placeholders are kept as-is; `{{` and `}}` produce literal braces.
+pub fn expand_template(text: &str, vars: &TemplateVars) -> String {
+    if !text.contains('{') && !text.contains('}') {
+        return text.to_string();
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(pos) = rest.find(['{', '}']) {
+        result.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            result.push_str(&tail[..1]);
+            rest = &tail[2..];
+        } else if tail.starts_with('{') {
+            match tail.find('}') {
+                Some(end) => {
+                    let key = &tail[1..end];
+                    match vars.get(key.trim()) {
+                        Some(value) => result.push_str(value),
+                        None => result.push_str(&tail[..=end]),
+                    }
+                    rest = &tail[end + 1..];
+                }
+                None => {
+                    result.push_str(tail);
+                    rest = "";
+                }
+            }
+        } else {
+            result.push('}');
+            rest = &tail[1..];
+        }
+    }
+    result.push_str(rest);
+
+    result
+}
+
+/// Convert days since 1970-01-01 to (year, month, day)
+///
+/// Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_vars() -> TemplateVars {
+        let config = EPConfig {
+            name: "Amiya".to_string(),
+            uuid: "1a2b3c4d-0000-4000-8000-000000000000".to_string(),
+            ..Default::default()
+        };
+        // 2024-03-01 12:34:00 UTC
+        TemplateVars::from_config_at(&config, 1_709_296_440)
+    }
+
+    #[test]
+    fn test_expand_known_variables() {
+        let vars = test_vars();
+        assert_eq!(expand_template("{name} #{serial}", &vars), "Amiya #1A2B3C4D");
+        assert_eq!(expand_template("{date} {time}", &vars), "2024-03-01 12:34");
+        assert_eq!(expand_template("{year}", &vars), "2024");
+    }
+
+    #[test]
+    fn test_serial() {
+        let mut config = EPConfig { uuid: "1a2b3c4d-0000-4000-8000-000000000000".to_string(), ..Default::default() };
+        // Without a serial, materials sharing a uuid prefix look the same
+        assert_eq!(TemplateVars::from_config_at(&config, 0).get("serial"), Some("1A2B3C4D"));
+        config.serial = "EP-000042".to_string();
+        assert_eq!(TemplateVars::from_config_at(&config, 0).get("serial"), Some("EP-000042"));
+    }
+
+    #[test]
+    fn test_expand_unknown_and_escaped() {
+        let vars = test_vars();
+        assert_eq!(expand_template("{unknown}", &vars), "{unknown}");
+        assert_eq!(expand_template("{{name}}", &vars), "{name}");
+        assert_eq!(expand_template("open {name", &vars), "open {name");
+        assert_eq!(expand_template("plain text", &vars), "plain text");
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+    }
+}
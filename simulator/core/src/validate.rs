@@ -0,0 +1,103 @@
+//! Batch config validation
+//!
+//! Runs everything the window would check on load over many configs at once
+//! and collects the findings in a report for material CI pipelines.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::config::{Diagnostic, EPConfig, Severity};
+
+use crate::assets::asset_statuses;
+
+/// Findings for one config
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigReport {
+    pub path: PathBuf,
+    /// No errors (warnings are fine)
+    pub ok: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Findings for all configs checked
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationReport {
+    /// No config has errors
+    pub ok: bool,
+    pub configs: Vec<ConfigReport>,
+}
+
+impl ValidationReport {
+    /// Validate each config (epconfig.json or .eppkg package)
+    pub fn run(paths: &[PathBuf], strict: bool) -> Self {
+        let configs: Vec<ConfigReport> = paths.iter().map(|path| validate_file(path, strict)).collect();
+        Self { ok: configs.iter().all(|c| c.ok), configs }
+    }
+}
+
+/// Load and validate the config at `path`
+///
+/// Besides `EPConfig::validate`, every asset that exists is opened (videos
+/// probed, image headers read), so corrupt files are caught as well.
+pub fn validate_file(path: &Path, strict: bool) -> ConfigReport {
+    let diagnostics = match EPConfig::load_with_base_dir(path) {
+        Ok((config, base_dir)) => validate_loaded(&config, &base_dir, strict),
+        Err(e) => vec![Diagnostic {
+            severity: Severity::Error,
+            path: String::new(),
+            message: format!("failed to load: {:#}", e),
+        }],
+    };
+    ConfigReport {
+        path: path.to_path_buf(),
+        ok: !diagnostics.iter().any(|d| d.severity == Severity::Error),
+        diagnostics,
+    }
+}
+
+fn validate_loaded(config: &EPConfig, base_dir: &Path, strict: bool) -> Vec<Diagnostic> {
+    let mut diagnostics = if strict {
+        config.validate_strict(base_dir)
+    } else {
+        config.validate(base_dir)
+    };
+    // Missing files are already reported; these exist but cannot be read
+    diagnostics.extend(asset_statuses(config, base_dir).into_iter().filter_map(|status| {
+        status.error.map(|error| Diagnostic {
+            severity: Severity::Error,
+            path: status.field,
+            message: format!("cannot read {}: {}", status.resolved.display(), error),
+        })
+    }));
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_report() {
+        let dir = std::env::temp_dir().join(format!("validate_report_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("logo.png"), b"not a png").unwrap();
+        let config = dir.join("epconfig.json");
+        std::fs::write(
+            &config,
+            r#"{"loop": {"file": "missing.mp4"}, "overlay": {"type": "arknights", "options": {"logo": "logo.png"}}}"#,
+        )
+        .unwrap();
+
+        let report = ValidationReport::run(&[config, dir.join("absent.json")], false);
+        assert!(!report.ok);
+        assert!(report.configs.iter().all(|c| !c.ok));
+        let paths: Vec<&str> = report.configs[0].diagnostics.iter().map(|d| d.path.as_str()).collect();
+        assert!(paths.contains(&"loop.file"));
+        // Exists, so only the read check catches it
+        assert!(paths.contains(&"overlay.options.logo"));
+        assert!(report.configs[1].diagnostics[0].message.starts_with("failed to load"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
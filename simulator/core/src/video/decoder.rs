@@ -14,6 +14,8 @@ use ffmpeg::software::scaling::{Context as Scaler, Flags};
 use ffmpeg::util::frame::video::Video as VideoFrame;
 use ffmpeg::format::Pixel;
 
+use super::VideoInfo;
+
 /// Video decoder that extracts frames from video files using FFmpeg
 pub struct VideoDecoder {
     /// FFmpeg format context
@@ -151,18 +153,8 @@ impl VideoDecoder {
             let (final_w, final_h) = if let Some((_, _, w, h)) = cropbox {
                 // cropbox dimensions are already in rotated space
                 (w, h)
-            } else if rotation == 90 || rotation == 270 {
-                // No cropbox, but rotation swaps dimensions
-                (src_height, src_width)
             } else {
-                // Arbitrary angle: bounding box is larger than original
-                let rad = (rotation as f64).to_radians();
-                let abs_cos = rad.cos().abs();
-                let abs_sin = rad.sin().abs();
-                (
-                    (src_width as f64 * abs_cos + src_height as f64 * abs_sin).ceil() as u32,
-                    (src_width as f64 * abs_sin + src_height as f64 * abs_cos).ceil() as u32,
-                )
+                rotated_dimensions(src_width, src_height, rotation)
             };
 
             // Create final scaler from crop size to target size
@@ -205,6 +197,11 @@ impl VideoDecoder {
         })
     }
 
+    /// Source size after rotation, i.e. the space the cropbox is given in
+    pub fn rotated_source_size(&self) -> (u32, u32) {
+        rotated_dimensions(self.src_width, self.src_height, self.rotation)
+    }
+
     /// Decode up to 100 packets to discover the actual pixel format and resolution.
     /// Used as fallback when the decoder context reports an unusable format before decoding.
     fn probe_first_frame(
@@ -555,6 +552,77 @@ impl VideoDecoder {
     }
 }
 
+/// Read a video's stream parameters without decoding any frames
+pub fn probe_video(path: &Path) -> Result<VideoInfo> {
+    ffmpeg::init().context("Failed to initialize FFmpeg")?;
+    let input_ctx = input(&path).context("Failed to open video file")?;
+    let stream = input_ctx
+        .streams()
+        .best(Type::Video)
+        .ok_or_else(|| anyhow::anyhow!("No video stream found in file"))?;
+    let rate = stream.rate();
+    let fps = if rate.1 != 0 { rate.0 as f64 / rate.1 as f64 } else { 30.0 };
+    let decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())
+        .context("Failed to create decoder context")?
+        .decoder()
+        .video()
+        .context("Failed to create video decoder")?;
+    let duration = input_ctx.duration();
+    Ok(VideoInfo {
+        width: decoder.width(),
+        height: decoder.height(),
+        fps,
+        duration_secs: (duration > 0).then(|| duration as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE)),
+        codec: decoder.id().name().to_string(),
+        rotation: display_rotation(&stream.parameters()),
+    })
+}
+
+/// Clockwise rotation from a stream's display matrix, 0 if it has none
+fn display_rotation(parameters: &ffmpeg::codec::Parameters) -> i32 {
+    use ffmpeg::ffi::{av_display_rotation_get, av_packet_side_data_get, AVPacketSideDataType};
+
+    // SAFETY: the side data belongs to `parameters`, which outlives this call;
+    // a display matrix is nine i32 values
+    let degrees = unsafe {
+        let raw = parameters.as_ptr();
+        let side_data = av_packet_side_data_get(
+            (*raw).coded_side_data,
+            (*raw).nb_coded_side_data,
+            AVPacketSideDataType::AV_PKT_DATA_DISPLAYMATRIX,
+        );
+        if side_data.is_null() || (*side_data).size < 9 * std::mem::size_of::<i32>() {
+            return 0;
+        }
+        av_display_rotation_get((*side_data).data as *const i32)
+    };
+    // FFmpeg gives the angle counterclockwise
+    if degrees.is_finite() {
+        (-degrees.round() as i32).rem_euclid(360)
+    } else {
+        0
+    }
+}
+
+/// Frame size after rotating a w x h frame by `rotation` degrees
+fn rotated_dimensions(w: u32, h: u32, rotation: i32) -> (u32, u32) {
+    match rotation {
+        0 | 180 => (w, h),
+        // Rotation swaps dimensions
+        90 | 270 => (h, w),
+        _ => {
+            // Arbitrary angle: bounding box is larger than original
+            let rad = (rotation as f64).to_radians();
+            let abs_cos = rad.cos().abs();
+            let abs_sin = rad.sin().abs();
+            (
+                (w as f64 * abs_cos + h as f64 * abs_sin).ceil() as u32,
+                (w as f64 * abs_sin + h as f64 * abs_cos).ceil() as u32,
+            )
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -564,5 +632,7 @@ mod tests {
         // Test that decoder returns error for nonexistent file
         let result = VideoDecoder::open("nonexistent.mp4", 360, 640, None, 0);
         assert!(result.is_err());
+        assert!(probe_video(Path::new("nonexistent.mp4")).is_err());
     }
 }
+
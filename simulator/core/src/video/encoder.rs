@@ -0,0 +1,147 @@
+//! Video encoder module
+//!
+//! Writes rendered preview frames to a video file using FFmpeg.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use image::RgbaImage;
+
+use ffmpeg_next as ffmpeg;
+use ffmpeg::codec;
+use ffmpeg::format::Pixel;
+use ffmpeg::software::scaling::{Context as Scaler, Flags};
+use ffmpeg::util::frame::video::Video as VideoFrame;
+use ffmpeg::Rational;
+
+/// Video encoder writing RGBA frames as H.264 (MPEG-4 Part 2 if H.264 is unavailable)
+pub struct VideoEncoder {
+    output: ffmpeg::format::context::Output,
+    encoder: ffmpeg::encoder::Video,
+    /// Scaler converting RGBA frames to the encoder's YUV 4:2:0
+    scaler: Scaler,
+    stream_index: usize,
+    /// One tick per frame
+    time_base: Rational,
+    width: u32,
+    height: u32,
+    frames_written: i64,
+}
+
+impl VideoEncoder {
+    /// Create `path` for a `width`x`height` video at `fps`; the container
+    /// follows the file extension
+    pub fn create(path: &Path, width: u32, height: u32, fps: u32) -> Result<Self> {
+        if !width.is_multiple_of(2) || !height.is_multiple_of(2) {
+            anyhow::bail!("Video size must be even, got {}x{}", width, height);
+        }
+        ffmpeg::init().context("Failed to initialize FFmpeg")?;
+
+        let mut output = ffmpeg::format::output(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        let global_header = output.format().flags().contains(ffmpeg::format::Flags::GLOBAL_HEADER);
+
+        let codec = ffmpeg::encoder::find(codec::Id::H264)
+            .or_else(|| ffmpeg::encoder::find(codec::Id::MPEG4))
+            .ok_or_else(|| anyhow::anyhow!("No H.264 or MPEG-4 encoder available"))?;
+        let mut stream = output.add_stream(codec).context("Failed to add video stream")?;
+        let stream_index = stream.index();
+
+        let time_base = Rational::new(1, fps as i32);
+        let mut encoder = codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .video()
+            .context("Failed to create video encoder")?;
+        encoder.set_width(width);
+        encoder.set_height(height);
+        encoder.set_format(Pixel::YUV420P);
+        encoder.set_time_base(time_base);
+        encoder.set_frame_rate(Some(Rational::new(fps as i32, 1)));
+        if global_header {
+            encoder.set_flags(codec::Flags::GLOBAL_HEADER);
+        }
+        let encoder = encoder.open_as(codec).context("Failed to open video encoder")?;
+        stream.set_parameters(&encoder);
+        stream.set_time_base(time_base);
+
+        output.write_header().context("Failed to write video header")?;
+
+        let scaler = Scaler::get(Pixel::RGBA, width, height, Pixel::YUV420P, width, height, Flags::BILINEAR)
+            .context("Failed to create scaler")?;
+
+        Ok(Self {
+            output,
+            encoder,
+            scaler,
+            stream_index,
+            time_base,
+            width,
+            height,
+            frames_written: 0,
+        })
+    }
+
+    /// Append a frame; it must have the size the video was created with
+    pub fn write_frame(&mut self, image: &RgbaImage) -> Result<()> {
+        if image.dimensions() != (self.width, self.height) {
+            anyhow::bail!(
+                "Frame is {}x{}, video is {}x{}",
+                image.width(),
+                image.height(),
+                self.width,
+                self.height
+            );
+        }
+
+        let mut rgba = VideoFrame::new(Pixel::RGBA, self.width, self.height);
+        let row_len = self.width as usize * 4;
+        let stride = rgba.stride(0);
+        let data = rgba.data_mut(0);
+        for (y, row) in image.as_raw().chunks_exact(row_len).enumerate() {
+            data[y * stride..y * stride + row_len].copy_from_slice(row);
+        }
+
+        let mut yuv = VideoFrame::empty();
+        self.scaler.run(&rgba, &mut yuv).context("Failed to convert frame")?;
+        yuv.set_pts(Some(self.frames_written));
+        self.frames_written += 1;
+
+        self.encoder.send_frame(&yuv).context("Failed to encode frame")?;
+        self.write_packets()
+    }
+
+    /// Flush the encoder and finish the file
+    pub fn finish(mut self) -> Result<()> {
+        self.encoder.send_eof().context("Failed to flush encoder")?;
+        self.write_packets()?;
+        self.output.write_trailer().context("Failed to finish video")?;
+        Ok(())
+    }
+
+    /// Write out the packets the encoder has ready
+    fn write_packets(&mut self) -> Result<()> {
+        let stream_time_base = self
+            .output
+            .stream(self.stream_index)
+            .map(|stream| stream.time_base())
+            .unwrap_or(self.time_base);
+        let mut packet = ffmpeg::Packet::empty();
+        while self.encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(self.stream_index);
+            packet.rescale_ts(self.time_base, stream_time_base);
+            packet.write_interleaved(&mut self.output).context("Failed to write video")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoder_rejects_odd_size() {
+        let path = std::env::temp_dir().join("odd.mp4");
+        assert!(VideoEncoder::create(&path, 361, 640, 30).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+//! Video decoder for builds without FFmpeg
+//!
+//! Plays still images and animated GIFs in place of videos, read through
+//! `read_file` so packages mounted in memory work too. Used where FFmpeg
+//! is not available, such as the web preview; real video files fail to open.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use image::codecs::gif::GifDecoder;
+use image::imageops::{self, FilterType};
+use image::{AnimationDecoder, DynamicImage, ImageFormat, RgbImage};
+use tracing::{info, warn};
+
+use crate::utils::read_file;
+
+use super::VideoInfo;
+
+/// Frame rate of still images and GIFs without frame delays
+const DEFAULT_FPS: f64 = 30.0;
+
+/// Decoded frames of an image, played like a video
+pub struct VideoDecoder {
+    /// Frames after rotation, crop and resize
+    frames: Vec<RgbImage>,
+    next_frame: usize,
+    fps: f64,
+    target_width: u32,
+    target_height: u32,
+    /// Source size after rotation
+    rotated_size: (u32, u32),
+}
+
+impl VideoDecoder {
+    /// Open an image for decoding, like `VideoDecoder::open` with FFmpeg
+    ///
+    /// Rotations other than multiples of 90 degrees are rounded to one.
+    pub fn open(
+        path: &str,
+        target_width: u32,
+        target_height: u32,
+        cropbox: Option<(u32, u32, u32, u32)>,
+        rotation: i32,
+    ) -> Result<Self> {
+        let (frames, fps, _) = decode_frames(Path::new(path))?;
+        let (width, height) = frames
+            .first()
+            .map(|frame| (frame.width(), frame.height()))
+            .context("Image has no frames")?;
+        let rotation = right_angle(rotation);
+        let rotated_size = match rotation {
+            90 | 270 => (height, width),
+            _ => (width, height),
+        };
+        let frames: Vec<RgbImage> = frames
+            .into_iter()
+            .map(|frame| {
+                let frame = match rotation {
+                    90 => frame.rotate90(),
+                    180 => frame.rotate180(),
+                    270 => frame.rotate270(),
+                    _ => frame,
+                };
+                let frame = match cropbox {
+                    Some((x, y, w, h)) => frame.crop_imm(x, y, w, h),
+                    None => frame,
+                };
+                imageops::resize(&frame.to_rgb8(), target_width, target_height, FilterType::Triangle)
+            })
+            .collect();
+        info!("Opened image as video: {} ({} frames @ {:.1}fps)", path, frames.len(), fps);
+
+        Ok(Self {
+            frames,
+            next_frame: 0,
+            fps,
+            target_width,
+            target_height,
+            rotated_size,
+        })
+    }
+
+    /// Source size after rotation, i.e. the space the cropbox is given in
+    pub fn rotated_source_size(&self) -> (u32, u32) {
+        self.rotated_size
+    }
+
+    /// Read the next frame; None after the last one
+    pub fn read_frame(&mut self) -> Option<RgbImage> {
+        let frame = self.frames.get(self.next_frame).cloned();
+        self.next_frame += 1;
+        frame
+    }
+
+    /// Seek to the first frame
+    pub fn seek_to_start(&mut self) {
+        self.next_frame = 0;
+    }
+
+    /// Get the video FPS
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+
+    /// Get the target (output) width
+    pub fn target_width(&self) -> u32 {
+        self.target_width
+    }
+
+    /// Get the target (output) height
+    pub fn target_height(&self) -> u32 {
+        self.target_height
+    }
+}
+
+/// Size, frame rate and length of an image played as a video
+pub fn probe_video(path: &Path) -> Result<VideoInfo> {
+    let (frames, fps, format) = decode_frames(path)?;
+    let first = frames.first().context("Image has no frames")?;
+    Ok(VideoInfo {
+        width: first.width(),
+        height: first.height(),
+        fps,
+        duration_secs: Some(frames.len() as f64 / fps),
+        codec: format.extensions_str().first().unwrap_or(&"image").to_string(),
+        rotation: 0,
+    })
+}
+
+/// All frames of an image, their rate and the image format
+fn decode_frames(path: &Path) -> Result<(Vec<DynamicImage>, f64, ImageFormat)> {
+    let data = read_file(path).with_context(|| format!("Video file not found: {}", path.display()))?;
+    let Ok(format) = image::guess_format(&data) else {
+        bail!("{} is a video, which this build cannot play (no FFmpeg); use a GIF or still image", path.display());
+    };
+    if format != ImageFormat::Gif {
+        let image = image::load_from_memory_with_format(&data, format)?;
+        return Ok((vec![image], DEFAULT_FPS, format));
+    }
+
+    let frames = GifDecoder::new(Cursor::new(&data))?.into_frames().collect_frames()?;
+    let delay_ms = frames
+        .first()
+        .map(|frame| std::time::Duration::from(frame.delay()).as_secs_f64() * 1000.0)
+        .unwrap_or(0.0);
+    let fps = if delay_ms > 0.0 { 1000.0 / delay_ms } else { DEFAULT_FPS };
+    let frames = frames.into_iter().map(|frame| DynamicImage::ImageRgba8(frame.into_buffer())).collect();
+    Ok((frames, fps, format))
+}
+
+/// `rotation` rounded to a multiple of 90 degrees in 0..360
+fn right_angle(rotation: i32) -> i32 {
+    let rounded = ((rotation as f64 / 90.0).round() as i32 * 90).rem_euclid(360);
+    if rounded != rotation.rem_euclid(360) {
+        warn!("Rotation {} is not supported without FFmpeg, using {}", rotation, rounded);
+    }
+    rounded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_as_video() {
+        let path = std::env::temp_dir().join(format!("image_video_{}.png", std::process::id()));
+        RgbImage::from_pixel(40, 20, image::Rgb([255, 0, 0])).save(&path).unwrap();
+
+        let mut decoder = VideoDecoder::open(&path.to_string_lossy(), 10, 10, None, 90).unwrap();
+        assert_eq!(decoder.rotated_source_size(), (20, 40));
+        assert_eq!(decoder.read_frame().unwrap().get_pixel(5, 5).0, [255, 0, 0]);
+        assert!(decoder.read_frame().is_none());
+        decoder.seek_to_start();
+        assert!(decoder.read_frame().is_some());
+        let info = probe_video(&path).unwrap();
+        assert_eq!((info.width, info.codec.as_str()), (40, "png"));
+        assert_eq!(info.decoded_bytes(), Some(40 * 20 * 3));
+
+        std::fs::remove_file(&path).ok();
+        assert!(VideoDecoder::open("missing.mp4", 10, 10, None, 0).is_err());
+
+        let video = std::env::temp_dir().join(format!("image_video_{}.mp4", std::process::id()));
+        std::fs::write(&video, b"\0\0\0\x18ftypmp42").unwrap();
+        let error = probe_video(&video).unwrap_err().to_string();
+        assert!(error.contains("is a video"), "{}", error);
+        std::fs::remove_file(&video).ok();
+    }
+}
@@ -0,0 +1,63 @@
+//! Video module
+//!
+//! Provides video decoding and playback functionality using FFmpeg. Without
+//! the `ffmpeg` feature, images and animated GIFs stand in for videos.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! use video::VideoPlayer;
+//!
+//! let mut player = VideoPlayer::new(360, 640, None, 0);
+//! player.load_from_config(&config, &base_dir);
+//!
+//! // Read frames
+//! if let Some(frame) = player.read_loop_frame() {
+//!     // Use the frame
+//! }
+//! ```
+
+#[cfg(feature = "ffmpeg")]
+mod decoder;
+#[cfg(feature = "ffmpeg")]
+mod encoder;
+#[cfg(not(feature = "ffmpeg"))]
+mod image_decoder;
+mod player;
+
+#[cfg(feature = "ffmpeg")]
+pub use decoder::{probe_video, VideoDecoder};
+#[cfg(feature = "ffmpeg")]
+pub use encoder::VideoEncoder;
+#[cfg(not(feature = "ffmpeg"))]
+pub use image_decoder::{probe_video, VideoDecoder};
+pub use player::VideoPlayer;
+
+use serde::Serialize;
+
+/// Stream parameters of a video file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VideoInfo {
+    pub width: u32,
+    pub height: u32,
+    pub fps: f64,
+    /// Length in seconds, if the container records it
+    pub duration_secs: Option<f64>,
+    /// Codec name (e.g. `h264`), or the image format without FFmpeg
+    pub codec: String,
+    /// Clockwise rotation players should apply, in degrees
+    pub rotation: i32,
+}
+
+impl VideoInfo {
+    /// Bytes of one frame decoded to RGB24, as the player holds it
+    pub fn frame_bytes(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height) * 3
+    }
+
+    /// Bytes of every frame decoded to RGB24, if the length is known
+    pub fn decoded_bytes(&self) -> Option<u64> {
+        let frames = (self.duration_secs? * self.fps).round() as u64;
+        Some(frames * self.frame_bytes())
+    }
+}
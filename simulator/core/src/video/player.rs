@@ -7,7 +7,9 @@ use image::RgbImage;
 use tracing::{info, warn, error};
 
 use crate::config::EPConfig;
-use super::decoder::VideoDecoder;
+use crate::render::AssetIssue;
+use crate::utils::{file_exists, resolve_asset_path};
+use super::VideoDecoder;
 
 /// Video player that manages playback of loop and intro videos
 pub struct VideoPlayer {
@@ -27,6 +29,8 @@ pub struct VideoPlayer {
     loop_cropbox: Option<(u32, u32, u32, u32)>,
     /// Rotation for loop video in degrees (0, 90, 180, 270)
     loop_rotation: i32,
+    /// Load failures not yet collected by `take_failures`
+    failures: Vec<AssetIssue>,
 }
 
 impl VideoPlayer {
@@ -46,9 +50,30 @@ impl VideoPlayer {
             target_height,
             loop_cropbox: cropbox,
             loop_rotation: rotation,
+            failures: Vec::new(),
         }
     }
 
+    /// Change the frame size videos are decoded to
+    ///
+    /// Drops the open videos and cached frames; call `load_from_config` afterwards.
+    pub fn set_target_size(&mut self, target_width: u32, target_height: u32) {
+        self.target_width = target_width;
+        self.target_height = target_height;
+        self.loop_video = None;
+        self.intro_video = None;
+        self.loop_current_frame = None;
+        self.intro_last_frame = None;
+    }
+
+    /// Close both videos and drop the cached frames
+    pub fn unload(&mut self) {
+        self.loop_video = None;
+        self.intro_video = None;
+        self.loop_current_frame = None;
+        self.intro_last_frame = None;
+    }
+
     /// Load videos from EPConfig, returns error description if loop video failed
     ///
     /// # Arguments
@@ -58,32 +83,8 @@ impl VideoPlayer {
         info!("Loading videos from config, base_dir: {:?}", base_dir);
 
         // Load loop video
-        if !config.loop_config.file.is_empty() {
-            let loop_path = Self::resolve_path(&config.loop_config.file, base_dir);
-            info!("Loop video path: {:?} (exists: {})", loop_path, loop_path.exists());
-            info!("Loop video cropbox: {:?}, rotation: {}", self.loop_cropbox, self.loop_rotation);
-            match VideoDecoder::open(
-                &loop_path.to_string_lossy(),
-                self.target_width,
-                self.target_height,
-                self.loop_cropbox,
-                self.loop_rotation,
-            ) {
-                Ok(decoder) => {
-                    info!("Loaded loop video successfully: {}", loop_path.display());
-                    self.loop_video = Some(decoder);
-                }
-                Err(e) => {
-                    let msg = format!(
-                        "循环视频加载失败\n路径: {}\n原因: {}",
-                        loop_path.display(), e
-                    );
-                    error!("{}", msg);
-                    return Some(msg);
-                }
-            }
-        } else {
-            return Some("未配置循环视频文件路径".to_string());
+        if let Some(msg) = self.load_loop_video(config, base_dir) {
+            return Some(msg);
         }
 
         // Load intro video if enabled (no cropbox/rotation for intro)
@@ -103,24 +104,70 @@ impl VideoPlayer {
                     }
                     Err(e) => {
                         warn!("Failed to load intro video: {}", e);
+                        self.failures.push(AssetIssue::new("intro video", &intro_path, e));
                     }
                 }
             }
         }
 
-        // Read first frame of loop video for initial display
+        None
+    }
+
+    /// Open the loop video with the current cropbox and rotation, returns
+    /// error description if it failed
+    pub fn load_loop_video(&mut self, config: &EPConfig, base_dir: &Path) -> Option<String> {
+        self.loop_video = None;
+        self.loop_current_frame = None;
+        if config.loop_config.file.is_empty() {
+            return Some("未配置循环视频文件路径".to_string());
+        }
+
+        let loop_path = Self::resolve_path(&config.loop_config.file, base_dir);
+        info!("Loop video path: {:?} (exists: {})", loop_path, file_exists(&loop_path));
+        info!("Loop video cropbox: {:?}, rotation: {}", self.loop_cropbox, self.loop_rotation);
+        match VideoDecoder::open(
+            &loop_path.to_string_lossy(),
+            self.target_width,
+            self.target_height,
+            self.loop_cropbox,
+            self.loop_rotation,
+        ) {
+            Ok(decoder) => {
+                info!("Loaded loop video successfully: {}", loop_path.display());
+                self.loop_video = Some(decoder);
+            }
+            Err(e) => {
+                let msg = format!(
+                    "循环视频加载失败\n路径: {}\n原因: {}",
+                    loop_path.display(), e
+                );
+                error!("{}", msg);
+                self.failures.push(AssetIssue::new("loop video", &loop_path, e));
+                return Some(msg);
+            }
+        }
+
+        // Read first frame of loop video for initial display; a video that
+        // opens but yields nothing would otherwise just preview black
         self.read_first_loop_frame();
+        if self.loop_current_frame.is_none() {
+            warn!("Loop video has no decodable frames: {}", loop_path.display());
+            self.failures.push(AssetIssue::new("loop video", &loop_path, "no decodable frames"));
+        }
         None
     }
 
+    /// Change the loop video cropbox and rotation
+    ///
+    /// Takes effect when the loop video is next opened (`load_loop_video`).
+    pub fn set_loop_transform(&mut self, cropbox: Option<(u32, u32, u32, u32)>, rotation: i32) {
+        self.loop_cropbox = cropbox;
+        self.loop_rotation = rotation;
+    }
+
     /// Resolve a potentially relative path against the base directory
     fn resolve_path(file_path: &str, base_dir: &Path) -> PathBuf {
-        let path = Path::new(file_path);
-        if path.is_absolute() {
-            path.to_path_buf()
-        } else {
-            base_dir.join(path)
-        }
+        resolve_asset_path(file_path, base_dir)
     }
 
     /// Read and cache the first frame of the loop video
@@ -134,6 +181,11 @@ impl VideoPlayer {
         }
     }
 
+    /// Take the failures recorded since the last call
+    pub fn take_failures(&mut self) -> Vec<AssetIssue> {
+        std::mem::take(&mut self.failures)
+    }
+
     /// Check if intro video is available
     pub fn has_intro(&self) -> bool {
         self.intro_video.is_some()
@@ -144,6 +196,21 @@ impl VideoPlayer {
         self.loop_video.is_some()
     }
 
+    /// Cropbox applied to the loop video
+    pub fn loop_cropbox(&self) -> Option<(u32, u32, u32, u32)> {
+        self.loop_cropbox
+    }
+
+    /// Rotation applied to the loop video (degrees)
+    pub fn loop_rotation(&self) -> i32 {
+        self.loop_rotation
+    }
+
+    /// Rotated source size of the loop video, if loaded
+    pub fn loop_source_size(&self) -> Option<(u32, u32)> {
+        self.loop_video.as_ref().map(|v| v.rotated_source_size())
+    }
+
     /// Advance to the next frame in the loop video
     ///
     /// Updates the internal cache without returning a clone.
@@ -0,0 +1,55 @@
+/*
+ * C API of the Arknights Electronic Pass Simulator
+ *
+ * Link against epass_simulator (cdylib or staticlib, built with
+ * `cargo build --release -p epass-simulator-ffi` in simulator/).
+ *
+ * Failing calls return NULL or -1; epass_last_error() then describes the
+ * failure. A simulator is paused between calls, so the same calls always
+ * produce the same frames.
+ */
+
+#ifndef EPASS_SIMULATOR_H
+#define EPASS_SIMULATOR_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct EpassSimulator EpassSimulator;
+
+/* Message of the last failed call on this thread, or NULL */
+const char *epass_last_error(void);
+
+/* Load a config (epconfig.json or .eppkg) and its videos; app_dir may be NULL */
+EpassSimulator *epass_simulator_load(const char *config_path, const char *app_dir);
+
+/* Free a simulator; NULL is ignored */
+void epass_simulator_free(EpassSimulator *sim);
+
+/* Frame size in pixels */
+int32_t epass_simulator_size(EpassSimulator *sim, uint32_t *width, uint32_t *height);
+
+/* Replay from the start to time_us of playback */
+int32_t epass_simulator_seek(EpassSimulator *sim, int64_t time_us);
+
+/* Play on for `frames` logic frames */
+int32_t epass_simulator_step(EpassSimulator *sim, uint32_t frames);
+
+/* Show serial as {serial} instead of the config's; NULL shows the config's own */
+int32_t epass_simulator_set_serial(EpassSimulator *sim, const char *serial);
+
+/*
+ * Render the current frame; RGBA8888 rows without padding, width * height * 4
+ * bytes (written to len if not NULL). Valid until the next call with sim.
+ */
+const uint8_t *epass_simulator_framebuffer(EpassSimulator *sim, size_t *len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* EPASS_SIMULATOR_H */
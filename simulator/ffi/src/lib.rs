@@ -0,0 +1,258 @@
+//! C API of the simulator
+//!
+//! Lets the firmware team's C test harness load a config, step it frame by
+//! frame and read the composed framebuffer, to diff it against what the
+//! device shows. See `include/epass_simulator.h` for the C declarations.
+//!
+//! Failing calls return NULL or a negative value; `epass_last_error` then
+//! describes the failure. Panics are caught and reported the same way.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+
+use simulator_core::config::EPConfig;
+use simulator_core::utils::set_app_dir;
+use simulator_core::HeadlessRenderer;
+
+thread_local! {
+    /// Message of the last failed call on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Run `f`, turning errors and panics into `on_error` and a last error
+fn guard<T>(on_error: T, f: impl FnOnce() -> Result<T, String>) -> T {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(message)) => {
+            set_last_error(message);
+            on_error
+        }
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            set_last_error(format!("panic: {}", message));
+            on_error
+        }
+    }
+}
+
+/// Text of a C string, `None` for NULL; `what` names it in errors
+///
+/// # Safety
+///
+/// `s` must be NULL or a valid NUL-terminated string.
+unsafe fn str_arg<'a>(s: *const c_char, what: &str) -> Result<Option<&'a str>, String> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    // SAFETY: checked for NULL, the caller guarantees NUL termination
+    let s = unsafe { CStr::from_ptr(s) };
+    s.to_str().map(Some).map_err(|_| format!("{} is not valid UTF-8", what))
+}
+
+/// Path from a C string, `None` for NULL
+///
+/// # Safety
+///
+/// `s` must be NULL or a valid NUL-terminated string.
+unsafe fn path_arg(s: *const c_char) -> Result<Option<PathBuf>, String> {
+    // SAFETY: forwarded from the caller
+    Ok(unsafe { str_arg(s, "path") }?.map(PathBuf::from))
+}
+
+/// A loaded config, paused between calls
+pub struct EpassSimulator {
+    renderer: HeadlessRenderer,
+    /// Last rendered frame, RGBA row by row
+    frame: Vec<u8>,
+}
+
+/// Message of the last failed call on this thread, or NULL
+///
+/// Valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn epass_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Load a config (epconfig.json or .eppkg) and its videos, at the start of playback
+///
+/// `app_dir` is where `${APP_DIR}` asset paths point and may be NULL.
+/// Returns NULL on failure.
+///
+/// # Safety
+///
+/// `config_path` and `app_dir` must be NULL or valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn epass_simulator_load(config_path: *const c_char, app_dir: *const c_char) -> *mut EpassSimulator {
+    guard(ptr::null_mut(), || {
+        // SAFETY: forwarded from the caller
+        let config_path = unsafe { path_arg(config_path) }?.ok_or("config path is NULL")?;
+        // SAFETY: forwarded from the caller
+        let app_dir = unsafe { path_arg(app_dir) }?.unwrap_or_else(|| PathBuf::from("."));
+        set_app_dir(app_dir.clone());
+        let (config, base_dir) = EPConfig::load_with_base_dir(&config_path).map_err(|e| format!("{:#}", e))?;
+        let renderer =
+            HeadlessRenderer::new(config, base_dir, app_dir, None, 0).map_err(|e| format!("{:#}", e))?;
+        Ok(Box::into_raw(Box::new(EpassSimulator { renderer, frame: Vec::new() })))
+    })
+}
+
+/// Free a simulator; NULL is ignored
+///
+/// # Safety
+///
+/// `sim` must be NULL or come from `epass_simulator_load`, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn epass_simulator_free(sim: *mut EpassSimulator) {
+    if !sim.is_null() {
+        // SAFETY: the caller hands back ownership of a pointer from Box::into_raw
+        drop(unsafe { Box::from_raw(sim) });
+    }
+}
+
+/// Borrow a simulator handle
+///
+/// # Safety
+///
+/// `sim` must be NULL or a live pointer from `epass_simulator_load`.
+unsafe fn simulator<'a>(sim: *mut EpassSimulator) -> Result<&'a mut EpassSimulator, String> {
+    // SAFETY: the caller guarantees the pointer is live if not NULL
+    unsafe { sim.as_mut() }.ok_or_else(|| "simulator is NULL".to_string())
+}
+
+/// Frame size in pixels; returns 0, or -1 on failure
+///
+/// # Safety
+///
+/// `sim` must come from `epass_simulator_load`; `width` and `height` must be
+/// NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn epass_simulator_size(sim: *mut EpassSimulator, width: *mut u32, height: *mut u32) -> i32 {
+    guard(-1, || {
+        // SAFETY: forwarded from the caller
+        let [w, h] = unsafe { simulator(sim) }?.renderer.size();
+        // SAFETY: the caller guarantees the pointers are writable if not NULL
+        unsafe {
+            if let Some(width) = width.as_mut() {
+                *width = w;
+            }
+            if let Some(height) = height.as_mut() {
+                *height = h;
+            }
+        }
+        Ok(0)
+    })
+}
+
+/// Replay from the start to `time_us` of playback; returns 0, or -1 on failure
+///
+/// # Safety
+///
+/// `sim` must come from `epass_simulator_load`.
+#[no_mangle]
+pub unsafe extern "C" fn epass_simulator_seek(sim: *mut EpassSimulator, time_us: i64) -> i32 {
+    guard(-1, || {
+        // SAFETY: forwarded from the caller
+        unsafe { simulator(sim) }?.renderer.seek(time_us.max(0));
+        Ok(0)
+    })
+}
+
+/// Play on for `frames` logic frames; returns 0, or -1 on failure
+///
+/// # Safety
+///
+/// `sim` must come from `epass_simulator_load`.
+#[no_mangle]
+pub unsafe extern "C" fn epass_simulator_step(sim: *mut EpassSimulator, frames: u32) -> i32 {
+    guard(-1, || {
+        // SAFETY: forwarded from the caller
+        unsafe { simulator(sim) }?.renderer.step(frames);
+        Ok(0)
+    })
+}
+
+/// Show `serial` as `{serial}` instead of the config's, or the config's own
+/// if NULL; returns 0, or -1 on failure
+///
+/// # Safety
+///
+/// `sim` must come from `epass_simulator_load`; `serial` must be NULL or a
+/// valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn epass_simulator_set_serial(sim: *mut EpassSimulator, serial: *const c_char) -> i32 {
+    guard(-1, || {
+        // SAFETY: forwarded from the caller
+        let serial = unsafe { str_arg(serial, "serial") }?.map(str::to_string);
+        // SAFETY: forwarded from the caller
+        unsafe { simulator(sim) }?.renderer.set_serial(serial);
+        Ok(0)
+    })
+}
+
+/// Render the current frame and return its pixels, or NULL on failure
+///
+/// Pixels are RGBA8888, row by row without padding, `width * height * 4`
+/// bytes (also written to `len` if not NULL). They stay valid until the
+/// next call with this simulator.
+///
+/// # Safety
+///
+/// `sim` must come from `epass_simulator_load`; `len` must be NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn epass_simulator_framebuffer(sim: *mut EpassSimulator, len: *mut usize) -> *const u8 {
+    guard(ptr::null(), || {
+        // SAFETY: forwarded from the caller
+        let sim = unsafe { simulator(sim) }?;
+        sim.frame = sim.renderer.render().into_raw();
+        // SAFETY: the caller guarantees the pointer is writable if not NULL
+        if let Some(len) = unsafe { len.as_mut() } {
+            *len = sim.frame.len();
+        }
+        Ok(sim.frame.as_ptr())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> String {
+        let error = epass_last_error();
+        assert!(!error.is_null());
+        // SAFETY: non-NULL errors are NUL-terminated strings we own
+        unsafe { CStr::from_ptr(error) }.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_errors_are_reported() {
+        unsafe {
+            assert!(epass_simulator_load(ptr::null(), ptr::null()).is_null());
+            assert_eq!(last_error(), "config path is NULL");
+
+            let missing = CString::new("missing/epconfig.json").unwrap();
+            assert!(epass_simulator_load(missing.as_ptr(), ptr::null()).is_null());
+            assert!(last_error().contains("missing/epconfig.json"));
+
+            assert_eq!(epass_simulator_step(ptr::null_mut(), 1), -1);
+            assert_eq!(last_error(), "simulator is NULL");
+            let invalid = [0xffu8, 0];
+            assert_eq!(epass_simulator_set_serial(ptr::null_mut(), invalid.as_ptr().cast()), -1);
+            assert_eq!(last_error(), "serial is not valid UTF-8");
+            assert!(epass_simulator_framebuffer(ptr::null_mut(), ptr::null_mut()).is_null());
+            epass_simulator_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Python bindings for the simulator
+//!
+//! Lets the editor load a config, step the simulation and render frames
+//! in-process, instead of starting a simulator and talking to it over IPC.
+//!
+//! ```python
+//! import epass_simulator
+//!
+//! sim = epass_simulator.Simulator("epconfig.json")
+//! sim.seek(2.5)
+//! frame = sim.render_array()  # (height, width, 4) uint8 RGBA
+//! sim.step(10)
+//! ```
+
+use std::path::PathBuf;
+
+use numpy::ndarray::Array3;
+use numpy::{IntoPyArray, PyArray3};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use simulator_core::config::EPConfig;
+use simulator_core::utils::set_app_dir;
+use simulator_core::{HeadlessRenderer, ValidationReport};
+
+/// Seconds from Python to microseconds of playback
+fn seconds_to_us(seconds: f64) -> PyResult<i64> {
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(PyValueError::new_err(format!("expected a time in seconds >= 0, got {}", seconds)));
+    }
+    Ok((seconds * 1_000_000.0).round() as i64)
+}
+
+/// A simulator playing one config, paused between calls
+///
+/// Playback only moves when told to, so the same calls always give the
+/// same frames.
+#[pyclass(unsendable)]
+struct Simulator {
+    renderer: HeadlessRenderer,
+}
+
+#[pymethods]
+impl Simulator {
+    /// Load `config` (epconfig.json or .eppkg) and its videos
+    ///
+    /// `app_dir` is where `${APP_DIR}` asset paths point, `cropbox` is
+    /// (x, y, w, h) in rotated video coordinates and `serial` is shown as
+    /// `{serial}` instead of the config's.
+    #[new]
+    #[pyo3(signature = (config, app_dir=None, cropbox=None, rotation=0, serial=None))]
+    fn new(
+        config: PathBuf,
+        app_dir: Option<PathBuf>,
+        cropbox: Option<(u32, u32, u32, u32)>,
+        rotation: i32,
+        serial: Option<String>,
+    ) -> PyResult<Self> {
+        let (config, base_dir) = EPConfig::load_with_base_dir(&config)?;
+        let app_dir = app_dir.unwrap_or_else(|| PathBuf::from("."));
+        set_app_dir(app_dir.clone());
+        let mut renderer = HeadlessRenderer::new(config, base_dir, app_dir, cropbox, rotation)?;
+        renderer.set_serial(serial);
+        Ok(Self { renderer })
+    }
+
+    /// Show `serial` as `{serial}` instead of the config's, or the config's own with None
+    #[pyo3(signature = (serial=None))]
+    fn set_serial(&mut self, serial: Option<String>) {
+        self.renderer.set_serial(serial);
+    }
+
+    /// Frame size as (width, height)
+    #[getter]
+    fn size(&self) -> (u32, u32) {
+        let [width, height] = self.renderer.size();
+        (width, height)
+    }
+
+    /// Logic frame rate of the firmware
+    #[getter]
+    fn fps(&self) -> u32 {
+        self.renderer.fps()
+    }
+
+    /// Replay from the start to `seconds` of playback
+    fn seek(&mut self, seconds: f64) -> PyResult<()> {
+        self.renderer.seek(seconds_to_us(seconds)?);
+        Ok(())
+    }
+
+    /// Play on for `seconds`
+    fn advance(&mut self, seconds: f64) -> PyResult<()> {
+        self.renderer.advance(seconds_to_us(seconds)?);
+        Ok(())
+    }
+
+    /// Play on for `frames` logic frames
+    #[pyo3(signature = (frames=1))]
+    fn step(&mut self, frames: u32) {
+        self.renderer.step(frames);
+    }
+
+    /// Current frame as RGBA bytes, row by row
+    fn render<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        let image = self.renderer.render();
+        PyBytes::new_bound(py, image.as_raw())
+    }
+
+    /// Current frame as a (height, width, 4) uint8 RGBA array
+    fn render_array<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyArray3<u8>>> {
+        let image = self.renderer.render();
+        let shape = (image.height() as usize, image.width() as usize, 4);
+        let array = Array3::from_shape_vec(shape, image.into_raw()).map_err(anyhow::Error::from)?;
+        Ok(array.into_pyarray_bound(py))
+    }
+}
+
+/// Check configs and their assets, returning the report as JSON
+///
+/// Same report as `arknights_pass_simulator validate`.
+#[pyfunction]
+#[pyo3(signature = (configs, strict=false))]
+fn validate(configs: Vec<PathBuf>, strict: bool) -> PyResult<String> {
+    let report = ValidationReport::run(&configs, strict);
+    Ok(serde_json::to_string(&report).map_err(anyhow::Error::from)?)
+}
+
+#[pymodule]
+fn epass_simulator(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Simulator>()?;
+    m.add_function(wrap_pyfunction!(validate, m)?)?;
+    Ok(())
+}
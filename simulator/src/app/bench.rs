@@ -0,0 +1,135 @@
+//! Pipeline benchmark
+//!
+//! Plays a config headlessly as fast as the machine allows and times each
+//! stage of producing a frame, to show where optimization pays off and
+//! whether a user's machine can keep up with the firmware frame rate.
+
+use web_time::{Duration, Instant};
+
+use serde::Serialize;
+
+use super::HeadlessRenderer;
+
+/// Timing of one pipeline stage over a benchmark
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StageReport {
+    pub name: &'static str,
+    pub total_ms: f64,
+    /// Average per frame
+    pub avg_ms: f64,
+    /// Slowest frame
+    pub max_ms: f64,
+    /// Frame rate this stage alone could sustain
+    pub fps: f64,
+}
+
+/// Result of a benchmark run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BenchReport {
+    pub frames: u32,
+    /// Playback time covered
+    pub playback_secs: f64,
+    /// Wall-clock time taken
+    pub wall_secs: f64,
+    /// Frame rate of the whole pipeline
+    pub fps: f64,
+    /// Firmware frame rate the device plays at
+    pub target_fps: u32,
+    pub stages: Vec<StageReport>,
+}
+
+/// Accumulated time of one stage
+#[derive(Default)]
+struct StageTimer {
+    total: Duration,
+    max: Duration,
+}
+
+impl StageTimer {
+    fn record(&mut self, time: Duration) {
+        self.total += time;
+        self.max = self.max.max(time);
+    }
+
+    fn report(&self, name: &'static str, frames: u32) -> StageReport {
+        let total_ms = self.total.as_secs_f64() * 1000.0;
+        let avg_ms = if frames > 0 { total_ms / frames as f64 } else { 0.0 };
+        StageReport {
+            name,
+            total_ms,
+            avg_ms,
+            max_ms: self.max.as_secs_f64() * 1000.0,
+            fps: if avg_ms > 0.0 { 1000.0 / avg_ms } else { f64::INFINITY },
+        }
+    }
+}
+
+impl BenchReport {
+    /// Play `playback_us` from the start, one logic frame at a time, rendering each
+    pub fn run(renderer: &mut HeadlessRenderer, playback_us: i64) -> Self {
+        let target_fps = renderer.fps().max(1);
+        let frames = (playback_us.max(0) as f64 * target_fps as f64 / 1_000_000.0).ceil() as u32;
+        let mut decode = StageTimer::default();
+        let mut texture = StageTimer::default();
+        let mut composite = StageTimer::default();
+
+        renderer.seek(0);
+        let started = Instant::now();
+        for _ in 0..frames {
+            let step_started = Instant::now();
+            renderer.step(1);
+            decode.record(step_started.elapsed());
+            let (_, times) = renderer.render_timed();
+            texture.record(times.texture);
+            composite.record(times.composite);
+        }
+        let wall_secs = started.elapsed().as_secs_f64();
+
+        Self {
+            frames,
+            playback_secs: frames as f64 / target_fps as f64,
+            wall_secs,
+            fps: if wall_secs > 0.0 { frames as f64 / wall_secs } else { f64::INFINITY },
+            target_fps,
+            stages: vec![
+                decode.report("decode", frames),
+                texture.report("texture", frames),
+                composite.report("composite", frames),
+            ],
+        }
+    }
+
+    /// Report as a text table
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "{} frames ({:.1}s of playback) in {:.2}s: {:.1} fps (device plays at {} fps)",
+                self.frames, self.playback_secs, self.wall_secs, self.fps, self.target_fps
+            ),
+            format!("{:<10} {:>10} {:>10} {:>10} {:>10}", "stage", "total ms", "avg ms", "max ms", "fps"),
+        ];
+        lines.extend(self.stages.iter().map(|stage| {
+            format!(
+                "{:<10} {:>10.1} {:>10.3} {:>10.3} {:>10.1}",
+                stage.name, stage.total_ms, stage.avg_ms, stage.max_ms, stage.fps
+            )
+        }));
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_report() {
+        let mut timer = StageTimer::default();
+        timer.record(Duration::from_millis(10));
+        timer.record(Duration::from_millis(30));
+        let report = timer.report("decode", 2);
+        assert_eq!((report.total_ms, report.avg_ms, report.max_ms), (40.0, 20.0, 30.0));
+        assert_eq!(report.fps, 50.0);
+        assert_eq!(StageTimer::default().report("idle", 0).avg_ms, 0.0);
+    }
+}
@@ -0,0 +1,268 @@
+//! Preview frame capture
+//!
+//! Frames are cut from an egui screenshot of the window, so they show the
+//! composed output (video, transitions and overlays) exactly as displayed,
+//! scaled back to the device resolution.
+
+use std::io::Cursor;
+use std::path::PathBuf;
+use web_time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use egui::{ColorImage, Rect};
+use image::codecs::jpeg::JpegEncoder;
+use image::{imageops, DynamicImage, ImageFormat, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+/// Highest frame rate a stream or sequence render may request
+pub const MAX_STREAM_FPS: f32 = 60.0;
+
+/// Most frames a single sequence render may produce
+pub const MAX_SEQUENCE_FRAMES: u32 = 10_000;
+
+/// JPEG quality of captured frames
+const JPEG_QUALITY: u8 = 85;
+
+/// Encoding of captured frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameFormat {
+    #[default]
+    Jpeg,
+    Png,
+    /// Uncompressed RGBA, row by row
+    Raw,
+}
+
+impl FrameFormat {
+    /// Encode an image in this format
+    pub fn encode(self, image: &RgbaImage) -> Result<Vec<u8>> {
+        match self {
+            FrameFormat::Jpeg => {
+                // JPEG has no alpha channel
+                let rgb = DynamicImage::ImageRgba8(image.clone()).to_rgb8();
+                let mut data = Vec::new();
+                JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY).encode_image(&rgb)?;
+                Ok(data)
+            }
+            FrameFormat::Png => {
+                let mut data = Cursor::new(Vec::new());
+                image.write_to(&mut data, ImageFormat::Png)?;
+                Ok(data.into_inner())
+            }
+            FrameFormat::Raw => Ok(image.as_raw().clone()),
+        }
+    }
+
+    /// File extension for frames written to disk
+    pub fn extension(self) -> &'static str {
+        match self {
+            FrameFormat::Jpeg => "jpg",
+            FrameFormat::Png => "png",
+            FrameFormat::Raw => "rgba",
+        }
+    }
+}
+
+/// Cut the preview area out of a window screenshot
+///
+/// `rect` is in points; the result is resized to `width`x`height`.
+/// Returns None if the rect lies outside the screenshot.
+pub fn crop_screenshot(
+    screenshot: &ColorImage,
+    rect: Rect,
+    pixels_per_point: f32,
+    width: u32,
+    height: u32,
+) -> Option<RgbaImage> {
+    let [screen_w, screen_h] = screenshot.size;
+    let x0 = ((rect.min.x * pixels_per_point).round().max(0.0) as usize).min(screen_w);
+    let y0 = ((rect.min.y * pixels_per_point).round().max(0.0) as usize).min(screen_h);
+    let x1 = ((rect.max.x * pixels_per_point).round().max(0.0) as usize).min(screen_w);
+    let y1 = ((rect.max.y * pixels_per_point).round().max(0.0) as usize).min(screen_h);
+    if x1 <= x0 || y1 <= y0 || width == 0 || height == 0 {
+        return None;
+    }
+
+    let mut raw = Vec::with_capacity((x1 - x0) * (y1 - y0) * 4);
+    for y in y0..y1 {
+        for pixel in &screenshot.pixels[y * screen_w + x0..y * screen_w + x1] {
+            raw.extend_from_slice(&pixel.to_srgba_unmultiplied());
+        }
+    }
+    let cropped = RgbaImage::from_raw((x1 - x0) as u32, (y1 - y0) as u32, raw)?;
+    if cropped.dimensions() == (width, height) {
+        return Some(cropped);
+    }
+    Some(imageops::resize(&cropped, width, height, imageops::FilterType::Triangle))
+}
+
+/// Timing of a frame stream subscription
+pub struct FrameStream {
+    /// Encoding of streamed frames
+    pub format: FrameFormat,
+    interval: Duration,
+    next_due: Instant,
+}
+
+impl FrameStream {
+    /// Start a stream, or None if `fps` is not positive
+    pub fn new(fps: f32, format: FrameFormat) -> Option<Self> {
+        if fps.is_nan() || fps <= 0.0 {
+            return None;
+        }
+        let fps = fps.min(MAX_STREAM_FPS);
+        Some(Self {
+            format,
+            interval: Duration::from_secs_f32(1.0 / fps),
+            next_due: Instant::now(),
+        })
+    }
+
+    /// Time between frames
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Whether a frame is due at `now`, scheduling the next one if so
+    pub fn poll(&mut self, now: Instant) -> bool {
+        if now < self.next_due {
+            return false;
+        }
+        // Skip missed frames rather than bursting to catch up
+        self.next_due = (self.next_due + self.interval).max(now);
+        true
+    }
+}
+
+/// Progress of a sequence render: frames at `fps` over a span of playback,
+/// written to `dir` as `frame_00000.<ext>`, ...
+pub struct SequenceRender {
+    /// Directory the frames are written to
+    pub dir: PathBuf,
+    format: FrameFormat,
+    start_us: i64,
+    fps: f32,
+    frames: u32,
+    written: u32,
+}
+
+impl SequenceRender {
+    /// Plan a render of playback time `start_us..end_us`, creating `dir`
+    pub fn new(start_us: i64, end_us: i64, fps: f32, format: FrameFormat, dir: PathBuf) -> Result<Self> {
+        if !(fps.is_finite() && fps > 0.0 && fps <= MAX_STREAM_FPS) {
+            bail!("fps must be between 0 and {}, got {}", MAX_STREAM_FPS, fps);
+        }
+        if start_us < 0 || end_us <= start_us {
+            bail!("Invalid time span {}..{} us", start_us, end_us);
+        }
+        let frames = ((end_us - start_us) as f64 * fps as f64 / 1_000_000.0).ceil();
+        if frames > MAX_SEQUENCE_FRAMES as f64 {
+            bail!("{} frames requested, at most {} allowed", frames, MAX_SEQUENCE_FRAMES);
+        }
+        std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        Ok(Self {
+            dir,
+            format,
+            start_us,
+            fps,
+            frames: frames as u32,
+            written: 0,
+        })
+    }
+
+    /// Number of frames in the sequence
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Index of the next frame to capture
+    pub fn next_index(&self) -> u32 {
+        self.written
+    }
+
+    /// Whether every frame has been written
+    pub fn is_done(&self) -> bool {
+        self.written >= self.frames
+    }
+
+    /// Playback time of frame `index`, in microseconds from the start
+    pub fn frame_time_us(&self, index: u32) -> i64 {
+        self.start_us + (index as f64 * 1_000_000.0 / self.fps as f64).round() as i64
+    }
+
+    /// Write the next frame
+    pub fn write_frame(&mut self, image: &RgbaImage) -> Result<()> {
+        let path = self.dir.join(format!("frame_{:05}.{}", self.written, self.format.extension()));
+        std::fs::write(&path, self.format.encode(image)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        self.written += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::{Color32, Pos2};
+
+    #[test]
+    fn test_crop_screenshot() {
+        let mut screenshot = ColorImage::new([8, 8], Color32::BLACK);
+        screenshot.pixels[2 * 8 + 2] = Color32::RED;
+
+        // 2x pixels per point: the 1x1 point rect at (1, 1) covers pixels (2..4, 2..4)
+        let rect = Rect::from_min_max(Pos2::new(1.0, 1.0), Pos2::new(2.0, 2.0));
+        let frame = crop_screenshot(&screenshot, rect, 2.0, 2, 2).unwrap();
+        assert_eq!(frame.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(frame.get_pixel(1, 1).0, [0, 0, 0, 255]);
+
+        let outside = Rect::from_min_max(Pos2::new(10.0, 10.0), Pos2::new(12.0, 12.0));
+        assert!(crop_screenshot(&screenshot, outside, 1.0, 2, 2).is_none());
+    }
+
+    #[test]
+    fn test_frame_format_encode() {
+        let image = RgbaImage::from_pixel(4, 2, image::Rgba([10, 20, 30, 255]));
+        assert_eq!(FrameFormat::Raw.encode(&image).unwrap().len(), 4 * 2 * 4);
+
+        let png = FrameFormat::Png.encode(&image).unwrap();
+        assert_eq!(image::load_from_memory(&png).unwrap().to_rgba8(), image);
+
+        let jpeg = FrameFormat::Jpeg.encode(&image).unwrap();
+        assert_eq!(image::guess_format(&jpeg).unwrap(), ImageFormat::Jpeg);
+    }
+
+    #[test]
+    fn test_frame_stream() {
+        assert!(FrameStream::new(0.0, FrameFormat::Png).is_none());
+
+        let mut stream = FrameStream::new(10.0, FrameFormat::Png).unwrap();
+        let start = Instant::now();
+        assert!(stream.poll(start));
+        assert!(!stream.poll(start));
+        assert!(stream.poll(start + stream.interval()));
+    }
+
+    #[test]
+    fn test_sequence_render() {
+        let dir = std::env::temp_dir().join(format!("epass_sequence_{}", std::process::id()));
+        assert!(SequenceRender::new(0, 1_000_000, 0.0, FrameFormat::Png, dir.clone()).is_err());
+        assert!(SequenceRender::new(500, 500, 30.0, FrameFormat::Png, dir.clone()).is_err());
+        assert!(SequenceRender::new(0, i64::MAX, 30.0, FrameFormat::Png, dir.clone()).is_err());
+
+        // 100ms at 30 fps: frames at 0, 33.3 and 66.7ms
+        let mut render = SequenceRender::new(1_000_000, 1_100_000, 30.0, FrameFormat::Png, dir.clone()).unwrap();
+        assert_eq!(render.frames(), 3);
+        assert_eq!(render.frame_time_us(0), 1_000_000);
+        assert_eq!(render.frame_time_us(2), 1_066_667);
+
+        let image = RgbaImage::from_pixel(2, 2, image::Rgba([0, 0, 0, 255]));
+        while !render.is_done() {
+            render.write_frame(&image).unwrap();
+        }
+        assert!(dir.join("frame_00002.png").is_file());
+        assert!(!dir.join("frame_00003.png").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,179 @@
+//! Crash reports
+//!
+//! A panic hook that writes what the simulator was doing to
+//! `<app_dir>/crash/crash-<time>.txt`, so a crash leaves something to attach
+//! to an issue, and tells a connected editor where the report is.
+
+use std::backtrace::Backtrace;
+use std::fmt::Write;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::ipc::{notify_editor, IpcMessage};
+
+/// Most characters of the last IPC message kept in a report
+const MAX_MESSAGE_CHARS: usize = 4096;
+
+/// How long the hook waits for the IPC connection to send the crash notice
+const NOTIFY_FLUSH: Duration = Duration::from_millis(200);
+
+/// What the app was doing, as far as a report is concerned
+struct CrashContext {
+    config: Option<String>,
+    last_message: Option<IpcMessage>,
+}
+
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext { config: None, last_message: None });
+
+/// Note the config being shown, e.g. its path
+pub fn set_config(config: impl Into<String>) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        context.config = Some(config.into());
+    }
+}
+
+/// Note the last message received over IPC
+pub fn set_last_message(msg: &IpcMessage) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        context.last_message = Some(msg.clone());
+    }
+}
+
+/// Everything written to a crash report
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+    pub message: String,
+    /// `file:line:column` of the panic
+    pub location: Option<String>,
+    pub thread: String,
+    pub backtrace: String,
+    pub config: Option<String>,
+    /// Last IPC message received, as JSON
+    pub last_message: Option<String>,
+}
+
+impl CrashReport {
+    /// Report for a panic, with the context noted so far
+    pub fn from_panic(info: &PanicHookInfo<'_>) -> Self {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        // The panic may have happened while the context was locked
+        let (config, last_message) = match CONTEXT.try_lock() {
+            Ok(context) => (context.config.clone(), context.last_message.as_ref().map(message_json)),
+            Err(_) => (None, None),
+        };
+        Self {
+            message,
+            location: info.location().map(|l| l.to_string()),
+            thread: std::thread::current().name().unwrap_or("<unnamed>").to_string(),
+            backtrace: Backtrace::force_capture().to_string(),
+            config,
+            last_message,
+        }
+    }
+
+    /// Plain text of the report
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "Arknights Pass Simulator crash report");
+        let _ = writeln!(text);
+        let _ = writeln!(text, "Version:  {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(text, "Platform: {} {}", std::env::consts::OS, std::env::consts::ARCH);
+        let _ = writeln!(text, "Thread:   {}", self.thread);
+        let _ = writeln!(text, "Location: {}", self.location.as_deref().unwrap_or("unknown"));
+        let _ = writeln!(text, "Config:   {}", self.config.as_deref().unwrap_or("none"));
+        let _ = writeln!(text);
+        let _ = writeln!(text, "Panic: {}", self.message);
+        let _ = writeln!(text);
+        let _ = writeln!(text, "Last IPC message: {}", self.last_message.as_deref().unwrap_or("none"));
+        let _ = writeln!(text);
+        let _ = writeln!(text, "Backtrace:");
+        let _ = writeln!(text, "{}", self.backtrace);
+        text
+    }
+
+    /// Write the report to a new file in `dir`, returning its path
+    pub fn write(&self, dir: &Path) -> std::io::Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let millis = web_time::SystemTime::now()
+            .duration_since(web_time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis());
+        let path = dir.join(format!("crash-{}.txt", millis));
+        std::fs::write(&path, self.to_text())?;
+        Ok(path)
+    }
+}
+
+/// A message as JSON, cut to `MAX_MESSAGE_CHARS`
+fn message_json(msg: &IpcMessage) -> String {
+    let json = msg.to_json().unwrap_or_else(|e| format!("<not serializable: {}>", e));
+    match json.char_indices().nth(MAX_MESSAGE_CHARS) {
+        Some((end, _)) => format!("{}... ({} bytes)", &json[..end], json.len()),
+        None => json,
+    }
+}
+
+/// Write a crash report to `<app_dir>/crash` whenever a thread panics
+///
+/// The default hook still runs afterwards, printing the panic as usual.
+pub fn install_panic_hook(app_dir: &Path) {
+    let dir = app_dir.join("crash");
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let report = CrashReport::from_panic(info);
+        let path = match report.write(&dir) {
+            Ok(path) => {
+                eprintln!("Crash report written to {}", path.display());
+                Some(path)
+            }
+            Err(e) => {
+                eprintln!("Failed to write crash report to {}: {}", dir.display(), e);
+                None
+            }
+        };
+        let notice = IpcMessage::Crashed {
+            message: report.message.clone(),
+            report: path.map(|p| p.to_string_lossy().into_owned()),
+        };
+        if notify_editor(notice) {
+            // Outgoing messages are sent by the connection thread
+            std::thread::sleep(NOTIFY_FLUSH);
+        }
+        previous(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crash_report_file() {
+        let report = CrashReport {
+            message: "index out of bounds".to_string(),
+            location: Some("src/app/simulator_app.rs:10:5".to_string()),
+            thread: "main".to_string(),
+            backtrace: "0: main".to_string(),
+            config: Some("material/epconfig.json".to_string()),
+            last_message: Some(message_json(&IpcMessage::ready())),
+        };
+        let dir = std::env::temp_dir().join(format!("crash_report_{}", std::process::id()));
+        let path = report.write(&dir).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with("crash-"));
+        assert!(text.contains("Panic: index out of bounds"));
+        assert!(text.contains("Config:   material/epconfig.json"));
+        assert!(text.contains(r#"Last IPC message: {"type":"ready"}"#));
+        assert!(text.contains(env!("CARGO_PKG_VERSION")));
+        std::fs::remove_dir_all(&dir).ok();
+
+        let long = IpcMessage::error(0, "x".repeat(MAX_MESSAGE_CHARS * 2));
+        assert!(message_json(&long).ends_with(" bytes)"));
+    }
+}
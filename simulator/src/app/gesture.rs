@@ -0,0 +1,61 @@
+//! Swipe gestures on the preview
+//!
+//! The device reacts to swipes on its touch screen; dragging the mouse over
+//! the preview stands in for them.
+
+use egui::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Shortest drag counted as a swipe, as a fraction of the preview width
+const MIN_SWIPE_FRACTION: f32 = 0.2;
+
+/// Direction of a swipe, by where the finger moved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl SwipeDirection {
+    /// The swipe made by dragging `delta` over a preview of `size`, if it
+    /// was long enough to count
+    ///
+    /// The longer axis of the drag decides the direction.
+    pub fn from_drag(delta: Vec2, size: Vec2) -> Option<Self> {
+        let min_length = size.x * MIN_SWIPE_FRACTION;
+        if delta.x.abs() >= delta.y.abs() {
+            if delta.x.abs() < min_length {
+                None
+            } else if delta.x < 0.0 {
+                Some(SwipeDirection::Left)
+            } else {
+                Some(SwipeDirection::Right)
+            }
+        } else if delta.y.abs() < min_length {
+            None
+        } else if delta.y < 0.0 {
+            Some(SwipeDirection::Up)
+        } else {
+            Some(SwipeDirection::Down)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swipe_from_drag() {
+        let size = Vec2::new(360.0, 640.0);
+        assert_eq!(SwipeDirection::from_drag(Vec2::new(-100.0, 20.0), size), Some(SwipeDirection::Left));
+        assert_eq!(SwipeDirection::from_drag(Vec2::new(100.0, -20.0), size), Some(SwipeDirection::Right));
+        assert_eq!(SwipeDirection::from_drag(Vec2::new(10.0, -200.0), size), Some(SwipeDirection::Up));
+        assert_eq!(SwipeDirection::from_drag(Vec2::new(0.0, 80.0), size), Some(SwipeDirection::Down));
+        // Too short to be more than a tap
+        assert_eq!(SwipeDirection::from_drag(Vec2::new(30.0, 10.0), size), None);
+    }
+}
@@ -0,0 +1,266 @@
+//! Overlay element inspector
+//!
+//! Firmware-space geometry of the Arknights overlay elements, derived from
+//! the same layout config the renderer uses, so the preview can report which
+//! element is under the cursor and outline every element for debugging.
+//! The animation inspector lists the animated values next to the firmware
+//! frames their animations start at, for checking timing against a device.
+
+use std::fmt;
+
+use egui::{Pos2, Rect, Vec2};
+
+use crate::config::{ArknightsOverlayOptions, EinkElementConfig, FirmwareConfig};
+
+use super::state::AnimationState;
+
+/// An overlay element and where it is drawn
+#[derive(Debug, Clone)]
+pub struct OverlayElement {
+    /// Element name
+    pub name: &'static str,
+    /// Config fields the element comes from
+    pub source: &'static str,
+    /// Rect in firmware pixels (entry animation offset applied)
+    pub rect: Rect,
+    /// Current animation values
+    pub animation: String,
+}
+
+impl fmt::Display for OverlayElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} [{}] rect=({:.0}, {:.0}, {:.0}x{:.0}) {}",
+            self.name,
+            self.source,
+            self.rect.min.x,
+            self.rect.min.y,
+            self.rect.width(),
+            self.rect.height(),
+            self.animation
+        )
+    }
+}
+
+/// All visible Arknights overlay elements, bottom to top
+pub fn overlay_elements(
+    config: &FirmwareConfig,
+    options: &ArknightsOverlayOptions,
+    anim: &AnimationState,
+) -> Vec<OverlayElement> {
+    let layout = &config.layout;
+    let offsets = &layout.offsets;
+    let width = config.overlay_width() as f32;
+    let height = config.overlay_height() as f32;
+    let btm_x = offsets.btm_info_x as f32;
+    let text_width = width - btm_x;
+    let line_width = config.animation.bars_lines.line_width as f32;
+    let y_offset = anim.entry_y_offset as f32;
+    let rect = |x: f32, y: f32, w: f32, h: f32| Rect::from_min_size(Pos2::new(x, y + y_offset), Vec2::new(w, h));
+    let chars = |shown: usize, text: &str| format!("chars={}/{}", shown.min(text.chars().count()), text.chars().count());
+
+    let mut elements = Vec::new();
+    let mut push = |name, source, rect, animation: String| {
+        elements.push(OverlayElement { name, source, rect, animation });
+    };
+
+    if options.show_decorations {
+        push(
+            "top_left_rhodes",
+            "top_left_rhodes, top_left_rhodes_orientation",
+            rect(0.0, 5.0, 67.0, 410.0),
+            format!("entry={:.0}%", anim.entry_progress * 100.0),
+        );
+    }
+    if options.show_arrow {
+        push(
+            "arrow",
+            "show_arrow, animation.arrow",
+            rect(width - 24.0, offsets.arrow_y as f32, 24.0, 100.0),
+            format!("arrow_y={}", anim.arrow_y),
+        );
+    }
+    if options.show_operator_name {
+        push(
+            "operator_name",
+            "operator_name, layout.offsets.opname_y",
+            rect(btm_x, offsets.opname_y as f32, text_width, 32.0),
+            chars(anim.name_chars, &options.operator_name),
+        );
+    }
+    if options.show_operator_code {
+        push(
+            "operator_code",
+            "operator_code, layout.offsets.opcode_y",
+            rect(btm_x, offsets.opcode_y as f32, text_width, 14.0),
+            chars(anim.code_chars, &options.operator_code),
+        );
+    }
+    if options.show_staff_text {
+        push(
+            "staff_text",
+            "staff_text, layout.offsets.staff_text_y",
+            rect(btm_x, offsets.staff_text_y as f32, text_width, 12.0),
+            chars(anim.staff_chars, &options.staff_text),
+        );
+    }
+    if options.show_aux_text {
+        let lines = options.aux_text.lines().count().max(1) as f32;
+        push(
+            "aux_text",
+            "aux_text, layout.offsets.aux_text_y",
+            rect(btm_x, offsets.aux_text_y as f32, text_width, lines * offsets.aux_text_line_height as f32),
+            chars(anim.aux_chars, &options.aux_text),
+        );
+    }
+    if options.show_barcode {
+        let barcode = &layout.barcode;
+        push(
+            "barcode",
+            "barcode_text, layout.barcode",
+            rect(barcode.x as f32, barcode.y as f32, barcode.width as f32, barcode.height as f32),
+            format!("eink={:?}", anim.barcode_state),
+        );
+        if let Some(ref secondary) = options.secondary_barcode {
+            push(
+                "secondary_barcode",
+                "secondary_barcode",
+                rect(secondary.x as f32, secondary.y as f32, secondary.width as f32, secondary.height as f32),
+                format!("eink={:?}", anim.secondary_barcode_state),
+            );
+        }
+    }
+    if options.show_class_icon {
+        let size = &layout.class_icon;
+        push(
+            "class_icon",
+            "operator_class_icon, operator_class, layout.class_icon",
+            rect(btm_x, offsets.class_icon_y as f32, size.width as f32, size.height as f32),
+            format!("eink={:?}", anim.classicon_state),
+        );
+    }
+    if options.show_divider_lines {
+        push(
+            "upper_line",
+            "layout.offsets.upperline_y, animation.bars_lines.upper_line",
+            rect(btm_x, offsets.upperline_y as f32 - 1.0, line_width, 2.0),
+            format!("width={}", anim.upper_line_width),
+        );
+        push(
+            "lower_line",
+            "layout.offsets.lowerline_y, animation.bars_lines.lower_line",
+            rect(btm_x, offsets.lowerline_y as f32 - 1.0, line_width, 2.0),
+            format!("width={}", anim.lower_line_width),
+        );
+    }
+    if options.show_ak_bar {
+        push(
+            "ak_bar",
+            "ak_bar_image, layout.offsets.ak_bar_y, animation.bars_lines.ak_bar",
+            rect(btm_x, offsets.ak_bar_y as f32, line_width, 4.0),
+            format!("width={}", anim.ak_bar_width),
+        );
+    }
+    if options.show_logo {
+        push(
+            "logo",
+            "logo, animation.logo_fade",
+            rect(width - 90.0, height - 40.0, 80.0, 30.0),
+            format!("alpha={}", anim.logo_alpha),
+        );
+    }
+    if options.show_color_fade {
+        // Drawn into the frame itself, so not moved by the entry animation
+        let radius = config.color_fade_end_value() as f32;
+        push(
+            "color_fade",
+            "color, color2, animation.color_fade",
+            Rect::from_min_size(Pos2::new(width - radius, height - radius), Vec2::splat(radius)),
+            format!("radius={}", anim.color_fade_radius),
+        );
+    }
+
+    elements
+}
+
+/// Topmost element containing `pos` (firmware pixels)
+pub fn element_at(elements: &[OverlayElement], pos: Pos2) -> Option<&OverlayElement> {
+    elements.iter().rev().find(|e| e.rect.contains(pos))
+}
+
+/// An animated overlay value
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimatedValue {
+    pub name: &'static str,
+    /// Current value
+    pub value: String,
+    /// Animation frame its animation starts at, for values with a fixed start
+    pub start_frame: Option<u32>,
+}
+
+/// Every animated value of the overlay, with the firmware start frames
+pub fn animated_values(
+    config: &FirmwareConfig,
+    secondary_barcode: Option<&EinkElementConfig>,
+    anim: &AnimationState,
+) -> Vec<AnimatedValue> {
+    let bars = &config.animation.bars_lines;
+    let row = |name, value: String, start_frame| AnimatedValue { name, value, start_frame };
+    vec![
+        row("frame_counter", anim.frame_counter.to_string(), None),
+        row("entry_progress", format!("{:.3}", anim.entry_progress), None),
+        row("entry_y_offset", anim.entry_y_offset.to_string(), None),
+        row("name_chars", anim.name_chars.to_string(), Some(config.name_start_frame())),
+        row("code_chars", anim.code_chars.to_string(), Some(config.code_start_frame())),
+        row("staff_chars", anim.staff_chars.to_string(), Some(config.staff_start_frame())),
+        row("aux_chars", anim.aux_chars.to_string(), Some(config.aux_start_frame())),
+        row("barcode_state", format!("{:?}", anim.barcode_state), Some(config.barcode_start_frame())),
+        row("classicon_state", format!("{:?}", anim.classicon_state), Some(config.classicon_start_frame())),
+        row(
+            "secondary_barcode_state",
+            format!("{:?}", anim.secondary_barcode_state),
+            secondary_barcode.map(|eink| eink.start_frame),
+        ),
+        row("color_fade_radius", anim.color_fade_radius.to_string(), Some(config.color_fade_start_frame())),
+        row("logo_alpha", anim.logo_alpha.to_string(), Some(config.logo_fade_start_frame())),
+        row("ak_bar_width", anim.ak_bar_width.to_string(), Some(bars.ak_bar.start_frame)),
+        row("upper_line_width", anim.upper_line_width.to_string(), Some(bars.upper_line.start_frame)),
+        row("lower_line_width", anim.lower_line_width.to_string(), Some(bars.lower_line.start_frame)),
+        row("arrow_y", anim.arrow_y.to_string(), None),
+        row("arrow_direction", anim.arrow_direction.to_string(), None),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_element_at() {
+        let config = FirmwareConfig::get_default();
+        let mut options = ArknightsOverlayOptions::default();
+        let anim = AnimationState::default();
+
+        let elements = overlay_elements(&config, &options, &anim);
+        let barcode = &config.layout.barcode;
+        let pos = Pos2::new(barcode.x as f32 + 1.0, barcode.y as f32 + 1.0);
+        assert_eq!(element_at(&elements, pos).unwrap().name, "barcode");
+
+        options.show_barcode = false;
+        let elements = overlay_elements(&config, &options, &anim);
+        assert!(element_at(&elements, pos).is_none());
+    }
+
+    #[test]
+    fn test_animated_values() {
+        let config = FirmwareConfig::get_default();
+        let anim = AnimationState { name_chars: 3, ..Default::default() };
+
+        let values = animated_values(&config, None, &anim);
+        let name = values.iter().find(|v| v.name == "name_chars").unwrap();
+        assert_eq!((name.value.as_str(), name.start_frame), ("3", Some(config.name_start_frame())));
+        let secondary = values.iter().find(|v| v.name == "secondary_barcode_state").unwrap();
+        assert_eq!(secondary.start_frame, None);
+    }
+}
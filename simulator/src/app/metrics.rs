@@ -0,0 +1,163 @@
+//! Playback performance metrics
+//!
+//! Rendered frames, video decode time and dropped frames are summed over a
+//! reporting interval, so the editor can tell when the machine cannot keep
+//! up with real-time playback.
+
+use web_time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Time between metrics reports
+pub const METRICS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Performance over one reporting interval
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricsReport {
+    /// Preview frames rendered per second
+    pub render_fps: f32,
+    /// Average time to decode one video frame
+    pub decode_ms: f32,
+    /// Longest time to decode one video frame
+    pub decode_max_ms: f32,
+    /// Video frames decoded but never shown because updates came too late
+    pub dropped_frames: u32,
+    /// Updates that fell so far behind that playback time was skipped
+    pub stalls: u32,
+    /// Resident memory of the simulator process, if known on this platform
+    pub memory_bytes: Option<u64>,
+}
+
+/// Metrics gathered since the start of the current interval
+pub struct PerfMetrics {
+    started: Instant,
+    rendered: u32,
+    decoded: u32,
+    decode_time: Duration,
+    decode_max: Duration,
+    dropped: u32,
+    stalls: u32,
+}
+
+impl PerfMetrics {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            rendered: 0,
+            decoded: 0,
+            decode_time: Duration::ZERO,
+            decode_max: Duration::ZERO,
+            dropped: 0,
+            stalls: 0,
+        }
+    }
+
+    /// Start a new interval, discarding what was gathered so far
+    pub fn restart(&mut self, now: Instant) {
+        *self = Self::new(now);
+    }
+
+    /// Count a rendered preview frame
+    pub fn record_render(&mut self) {
+        self.rendered += 1;
+    }
+
+    /// Count one video frame decode taking `time`
+    pub fn record_decode(&mut self, time: Duration) {
+        self.decoded += 1;
+        self.decode_time += time;
+        self.decode_max = self.decode_max.max(time);
+    }
+
+    /// Count video frames skipped without being shown
+    pub fn record_dropped(&mut self, frames: u32) {
+        self.dropped += frames;
+    }
+
+    /// Count an update that skipped playback time to catch up
+    pub fn record_stall(&mut self) {
+        self.stalls += 1;
+    }
+
+    /// The report for the finished interval, if `METRICS_INTERVAL` has passed
+    ///
+    /// Starts the next interval when it returns a report.
+    pub fn poll(&mut self, now: Instant) -> Option<MetricsReport> {
+        let elapsed = now.saturating_duration_since(self.started);
+        if elapsed < METRICS_INTERVAL {
+            return None;
+        }
+        let decode_ms = if self.decoded > 0 {
+            self.decode_time.as_secs_f32() * 1000.0 / self.decoded as f32
+        } else {
+            0.0
+        };
+        let report = MetricsReport {
+            render_fps: self.rendered as f32 / elapsed.as_secs_f32(),
+            decode_ms,
+            decode_max_ms: self.decode_max.as_secs_f32() * 1000.0,
+            dropped_frames: self.dropped,
+            stalls: self.stalls,
+            memory_bytes: resident_memory_bytes(),
+        };
+        self.restart(now);
+        Some(report)
+    }
+}
+
+/// Resident memory of this process
+#[cfg(target_os = "linux")]
+pub fn resident_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Resident memory (working set) of this process
+#[cfg(windows)]
+pub fn resident_memory_bytes() -> Option<u64> {
+    use windows::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows::Win32::System::Threading::GetCurrentProcess;
+
+    let mut counters = PROCESS_MEMORY_COUNTERS::default();
+    let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+    // SAFETY: `counters` is a valid PROCESS_MEMORY_COUNTERS of `size` bytes
+    unsafe { GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, size) }.ok()?;
+    Some(counters.WorkingSetSize as u64)
+}
+
+/// Resident memory of this process (unknown on this platform)
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn resident_memory_bytes() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_report() {
+        let start = Instant::now();
+        let mut metrics = PerfMetrics::new(start);
+        for _ in 0..30 {
+            metrics.record_render();
+        }
+        metrics.record_decode(Duration::from_millis(2));
+        metrics.record_decode(Duration::from_millis(6));
+        metrics.record_dropped(3);
+        assert!(metrics.poll(start + Duration::from_millis(500)).is_none());
+
+        let report = metrics.poll(start + Duration::from_secs(2)).unwrap();
+        assert_eq!(report.render_fps, 15.0);
+        assert!((report.decode_ms - 4.0).abs() < 1e-3);
+        assert!((report.decode_max_ms - 6.0).abs() < 1e-3);
+        assert_eq!((report.dropped_frames, report.stalls), (3, 0));
+
+        // The next interval starts empty
+        let report = metrics.poll(start + Duration::from_secs(3)).unwrap();
+        assert_eq!(report.render_fps, 0.0);
+        assert_eq!(report.dropped_frames, 0);
+    }
+}
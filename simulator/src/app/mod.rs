@@ -2,8 +2,25 @@
 //!
 //! Contains the main egui application and state management.
 
+mod bench;
+pub mod capture;
+pub mod crash;
+mod gesture;
+mod inspector;
+mod metrics;
+mod placement;
 mod simulator_app;
-pub mod state;
+mod toasts;
 
-pub use simulator_app::SimulatorApp;
+pub use bench::{BenchReport, StageReport};
+pub use capture::FrameFormat;
+pub use gesture::SwipeDirection;
+pub use metrics::MetricsReport;
+pub use placement::{parse_position, WindowPlacement};
+pub use simulator_app::{window_size_for_screen, SimulatorApp};
+pub use simulator_core::clock::Clock;
+pub use simulator_core::render::{HeadlessRenderer, RenderTimes};
+pub use simulator_core::state;
 pub use state::*;
+pub use toasts::ToastLayer;
+pub use simulator_core::validate::ValidationReport;
@@ -0,0 +1,148 @@
+//! Initial window placement
+//!
+//! Launch scripts and the editor place the preview window from the command
+//! line, which needs to be deterministic with several monitors.
+
+use egui::{Pos2, Rect, Vec2, ViewportBuilder};
+use tracing::warn;
+
+/// Where and how large the window opens
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowPlacement {
+    /// Zoom of the window and everything in it
+    pub scale: f32,
+    /// Top left corner of the window, relative to the monitor if one is chosen
+    pub position: Option<Pos2>,
+    pub maximized: bool,
+    /// Monitor to open on, 0 being the first one the system reports
+    pub monitor: Option<usize>,
+}
+
+impl Default for WindowPlacement {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            position: None,
+            maximized: false,
+            monitor: None,
+        }
+    }
+}
+
+impl WindowPlacement {
+    /// Apply to the viewport of a window of `size` and `min_size` at a scale of 1
+    ///
+    /// The window is created before the zoom takes effect, so the sizes
+    /// are scaled here; later resizes are scaled by egui.
+    pub fn apply(&self, builder: ViewportBuilder, size: [f32; 2], min_size: [f32; 2]) -> ViewportBuilder {
+        let size = Vec2::from(size) * self.scale;
+        let mut builder = builder
+            .with_inner_size(size)
+            .with_min_inner_size(Vec2::from(min_size) * self.scale)
+            .with_maximized(self.maximized);
+
+        let monitor = self.monitor.and_then(|index| {
+            let monitors = monitor_rects();
+            let monitor = monitors.get(index).copied();
+            if monitor.is_none() {
+                warn!("Monitor {} not found ({} available), using the default placement", index, monitors.len());
+            }
+            monitor
+        });
+        if let Some(position) = place_on(monitor, self.position, size) {
+            builder = builder.with_position(position);
+        }
+        builder
+    }
+}
+
+/// Window position for `position` on `monitor`
+///
+/// A position is relative to the monitor; without one the window is
+/// centered on it. Without a monitor the position is used as is.
+fn place_on(monitor: Option<Rect>, position: Option<Pos2>, size: Vec2) -> Option<Pos2> {
+    match (monitor, position) {
+        (Some(monitor), Some(position)) => Some(monitor.min + position.to_vec2()),
+        (Some(monitor), None) => Some((monitor.center() - size / 2.0).max(monitor.min)),
+        (None, position) => position,
+    }
+}
+
+/// Parse a window position given as "x,y"
+pub fn parse_position(s: &str) -> Result<Pos2, String> {
+    let (x, y) = s
+        .split_once(',')
+        .ok_or_else(|| format!("expected \"x,y\", got {:?}", s))?;
+    let parse = |v: &str| v.trim().parse::<f32>().map_err(|e| format!("{:?}: {}", v, e));
+    Ok(Pos2::new(parse(x)?, parse(y)?))
+}
+
+/// Desktop area of each monitor, in the order the system reports them
+///
+/// Queried before the window makes the process DPI aware, so Windows
+/// reports them in points at the primary monitor's scale.
+#[cfg(windows)]
+fn monitor_rects() -> Vec<Rect> {
+    use windows::Win32::Foundation::{BOOL, LPARAM, RECT};
+    use windows::Win32::Graphics::Gdi::{EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO};
+
+    unsafe extern "system" fn collect(monitor: HMONITOR, _dc: HDC, _clip: *mut RECT, data: LPARAM) -> BOOL {
+        // SAFETY: `data` points to the Vec passed to EnumDisplayMonitors below
+        let monitors = unsafe { &mut *(data.0 as *mut Vec<HMONITOR>) };
+        monitors.push(monitor);
+        true.into()
+    }
+
+    let mut monitors: Vec<HMONITOR> = Vec::new();
+    // SAFETY: the callback only runs during the call, while `monitors` is borrowed
+    let enumerated = unsafe {
+        EnumDisplayMonitors(HDC::default(), None, Some(collect), LPARAM(&mut monitors as *mut _ as isize))
+    };
+    if !enumerated.as_bool() {
+        return Vec::new();
+    }
+
+    monitors
+        .into_iter()
+        .filter_map(|monitor| {
+            let mut info = MONITORINFO {
+                cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+                ..Default::default()
+            };
+            // SAFETY: `info` is a MONITORINFO with its size set
+            unsafe { GetMonitorInfoW(monitor, &mut info) }.as_bool().then(|| {
+                let r = info.rcMonitor;
+                Rect::from_min_max(
+                    Pos2::new(r.left as f32, r.top as f32),
+                    Pos2::new(r.right as f32, r.bottom as f32),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Desktop area of each monitor (not available on this platform)
+#[cfg(not(windows))]
+fn monitor_rects() -> Vec<Rect> {
+    warn!("--monitor is only supported on Windows");
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_placement() {
+        assert_eq!(parse_position("100, -20"), Ok(Pos2::new(100.0, -20.0)));
+        assert!(parse_position("100").is_err());
+        assert!(parse_position("a,b").is_err());
+
+        let size = Vec2::new(400.0, 800.0);
+        let second = Rect::from_min_size(Pos2::new(1920.0, 0.0), Vec2::new(1920.0, 1080.0));
+        assert_eq!(place_on(Some(second), Some(Pos2::new(10.0, 20.0)), size), Some(Pos2::new(1930.0, 20.0)));
+        assert_eq!(place_on(Some(second), None, size), Some(Pos2::new(2680.0, 140.0)));
+        assert_eq!(place_on(None, Some(Pos2::new(10.0, 20.0)), size), Some(Pos2::new(10.0, 20.0)));
+        assert_eq!(place_on(None, None, size), None);
+    }
+}
@@ -2,2328 +2,1923 @@
 //!
 //! Implements the egui App trait for the pass simulator.
 
-use std::path::PathBuf;
-use std::time::{Duration, Instant};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use web_time::{Duration, Instant};
 
 use egui::{Color32, RichText, Vec2, Rect, Pos2, Stroke, FontId, Align2};
-use image::RgbImage;
 use tracing::{info, warn};
 
-use crate::config::{EPConfig, FirmwareConfig, TransitionType, TransitionOptions, OverlayType, ArknightsOverlayOptions, ImageOverlayOptions};
-use crate::app::state::EinkState;
-use crate::render::{TransitionRenderer, OverlayRenderer, ImageLoader, generate_vertical_barcode_gradient, render_text_rotated_90, render_top_right_bar_text_rotated};
-use crate::animation::AnimationController;
+use crate::config::{EPConfig, ScreenType, Diagnostic, Severity, validate_cropbox, write_template, config_for_video, is_package};
+use crate::render::{AssetIssue, StatusBar};
 use crate::video::VideoPlayer;
-use crate::ipc::{start_ipc_server, IpcMessage, IpcReceiver, IpcSender, ControlCommand};
+use simulator_core::assets::{asset_statuses, AssetStatus};
+use simulator_core::simulator::{PlaybackEvent, Simulator, MAX_SEEK_FRAMES};
+use crate::ipc::{error_codes, ConfigSlot, Event, IpcMessage, IpcReceiver, IpcSender, Bytes, ReplyTo, ControlCommand, StateUpdateRate};
+
+use super::Clock;
+use super::crash;
+use super::capture::{crop_screenshot, FrameFormat, FrameStream, SequenceRender};
+use super::inspector::{animated_values, element_at, overlay_elements};
+use super::metrics::PerfMetrics;
+use super::gesture::SwipeDirection;
+use super::toasts::Toasts;
+use super::state::{AutoReplay, PlayState, SimulatorState};
+
+/// Speeds offered in the playback speed dropdown
+const SPEED_PRESETS: [f32; 4] = [0.25, 0.5, 1.0, 2.0];
+
+/// Longest wait for a shutdown acknowledgement to go out before closing anyway
+const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Keyboard shortcuts, as listed in the help window
+const SHORTCUTS: &[(&str, &str)] = &[
+    ("Space", "Play / pause"),
+    ("R", "Reset"),
+    ("S", "Save a screenshot next to the config"),
+    ("Ctrl+C", "Copy the preview to the clipboard"),
+    ("Left / Right", "Step one frame back / forward"),
+    ("1 / 2 / 3", "Transitions: fade / move / swipe"),
+    ("Esc", "Leave presentation mode"),
+    ("F1", "Show these shortcuts"),
+];
+
+/// Video files that open as a minimal config when dropped onto the window
+const DROPPED_VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "mkv", "webm", "avi"];
+
+/// Window size for the default 360x640 screen
+const BASE_WINDOW_SIZE: [f32; 2] = [420.0, 860.0];
+
+/// Initial window size for a screen of `width`x`height`
+///
+/// Keeps the preview at the height it has on a 360x640 screen and widens or
+/// narrows the window to the screen's aspect ratio.
+pub fn window_size_for_screen(width: u32, height: u32) -> [f32; 2] {
+    let margin = BASE_WINDOW_SIZE[0] - 360.0;
+    [margin + 640.0 * width as f32 / height.max(1) as f32, BASE_WINDOW_SIZE[1]]
+}
 
-use super::state::{PlayState, SimulatorState, TransitionPhase};
+/// Largest size a `frame` sized preview fits into `available`, in points
+/// covering whole physical pixels at `pixels_per_point`
+///
+/// On scaled displays a size in whole points can end mid-pixel, which
+/// smears every frame pixel over its neighbours.
+fn fit_preview(available: Vec2, frame: Vec2, pixels_per_point: f32) -> Vec2 {
+    let scale = (available.x / frame.x).min(available.y / frame.y).max(0.0);
+    let pixels = (frame * scale * pixels_per_point).floor();
+    pixels / pixels_per_point
+}
 
-/// Main simulator application
-pub struct SimulatorApp {
-    /// Firmware configuration
-    firmware_config: FirmwareConfig,
-    /// Current EP configuration
-    epconfig: Option<EPConfig>,
-    /// Base directory for assets
-    base_dir: PathBuf,
-    /// Application directory for program resources (modular assets, etc.)
-    app_dir: PathBuf,
+/// Move `rect` onto the physical pixel grid at `pixels_per_point`
+fn align_to_pixels(rect: Rect, pixels_per_point: f32) -> Rect {
+    let min = (rect.min.to_vec2() * pixels_per_point).round() / pixels_per_point;
+    Rect::from_min_size(min.to_pos2(), rect.size())
+}
 
-    /// Simulator state
-    state: SimulatorState,
+/// Where a requested screenshot goes
+enum ScreenshotTarget {
+    /// PNG file
+    File(PathBuf),
+    /// PNG bytes in the reply
+    Reply,
+    /// System clipboard
+    Clipboard,
+}
 
-    /// Video player
+/// A config variant with its videos already opened
+struct PreparedConfig {
+    config: EPConfig,
+    base_dir: PathBuf,
     video_player: VideoPlayer,
+    /// Why the loop video failed to load, if it did
+    video_error: Option<String>,
+}
 
-    /// Transition renderer
-    transition_renderer: TransitionRenderer,
-    /// Overlay renderer
-    overlay_renderer: OverlayRenderer,
-    /// Animation controller
-    animation_controller: AnimationController,
+/// Videos being opened on a background thread
+struct VideoLoad {
+    /// The loaded player and the loop video error, if any
+    receiver: mpsc::Receiver<(VideoPlayer, Option<String>)>,
+    /// Start playback once they are ready
+    autoplay: bool,
+}
 
-    /// Last frame time for timing control
-    last_frame_time: Instant,
+/// A config open in a tab
+struct ConfigTab {
+    /// Label shown on the tab
+    title: String,
+    /// The config and its videos while the tab is in the background
+    prepared: Option<PreparedConfig>,
+    /// Where playback was when the tab was left: state, logic frame, playing
+    position: (PlayState, u64, bool),
+}
 
-    /// Current frame texture
-    frame_texture: Option<egui::TextureHandle>,
+/// What the right side of the split view shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareSource {
+    /// The config of another tab
+    Tab(usize),
+    /// The shown config laid out for another screen
+    Screen(ScreenType),
+}
 
-    /// Reusable color buffer to avoid allocations every frame
-    color_image_buffer: Vec<Color32>,
+/// Main simulator application
+pub struct SimulatorApp {
+    /// The simulator shown in the preview
+    sim: Simulator,
+    /// Preloaded config variants, by slot; the shown one is taken out while shown
+    slots: [Option<PreparedConfig>; 2],
+    /// Slot the current config was switched in from
+    active_slot: Option<ConfigSlot>,
+    /// Open configs; the shown one is taken out of its tab while shown
+    tabs: Vec<ConfigTab>,
+    /// Tab of the shown config
+    active_tab: usize,
+    /// Split view: the compared simulator, following this one's playback clock
+    compare: Option<(CompareSource, Box<SimulatorApp>)>,
+    /// Time source for playback
+    clock: Clock,
+    /// Last frame time for timing control
+    last_frame_time: Instant,
 
-    /// Whether the frame content has changed and needs re-rendering
-    frame_dirty: bool,
+    /// Videos of the current config still being opened
+    video_load: Option<VideoLoad>,
+    /// Requests waiting for a config's videos to load, in arrival order
+    deferred_requests: VecDeque<(ReplyTo, IpcMessage)>,
+
+    /// Window width needed after the screen size changed, applied on the next update
+    pending_window_width: Option<f32>,
+    /// Window changes requested over IPC, applied on the next update
+    pending_viewport_commands: Vec<egui::ViewportCommand>,
+    /// The window stays above other windows
+    always_on_top: bool,
+    /// Simulated device clock and battery strip over the preview
+    status_bar: StatusBar,
+    /// Only the preview is shown
+    presentation_mode: bool,
+    /// Mouse drag over the preview so far, while one is in progress
+    swipe_drag: Option<Vec2>,
+    /// Screen area of the preview image (points), as of the last update
+    preview_rect: Option<Rect>,
+    /// Frame stream requested over IPC
+    frame_stream: Option<(ReplyTo, FrameStream)>,
+    /// A screenshot was requested and has not arrived yet
+    screenshot_pending: bool,
+    /// Frames requested over IPC, rendered one at a time
+    frame_requests: VecDeque<(ReplyTo, PlayState, u64)>,
+    /// Client whose frame request the current screenshot answers
+    frame_request_in_flight: Option<ReplyTo>,
+    /// Sequence render requested over IPC
+    sequence_render: Option<(ReplyTo, SequenceRender)>,
+    /// The current screenshot captures a sequence render frame
+    sequence_frame_in_flight: bool,
+    /// Playback position to return to once the frame requests are done
+    position_before_requests: Option<(PlayState, u64, bool)>,
+    /// Screenshots requested over IPC, or with the copy hotkey (no client)
+    screenshot_requests: Vec<(Option<ReplyTo>, ScreenshotTarget)>,
+    /// System clipboard, kept open as on X11 copied images vanish with it
+    #[cfg(not(target_arch = "wasm32"))]
+    clipboard: Option<arboard::Clipboard>,
+    /// How often state updates are sent during playback
+    state_update_rate: StateUpdateRate,
+    /// Play state and playing flag of the last state update
+    last_state_update: Option<(PlayState, bool)>,
+    /// Close the window once the shutdown acknowledgement is out, or at this time
+    shutdown_deadline: Option<Instant>,
+    /// Performance of live playback, reported over IPC
+    metrics: PerfMetrics,
+
+    /// Delay shown in the auto-replay controls while it is off (seconds)
+    auto_replay_secs: f64,
 
     /// Whether to use dark theme
     is_dark_theme: bool,
 
-    /// UI state
-    selected_transition_in: usize,
-    selected_transition_loop: usize,
-
-    /// Is first transition (forces SWIPE)
-    is_first_transition: bool,
-
     /// IPC receiver
     ipc_rx: Option<IpcReceiver>,
     /// IPC sender
     ipc_tx: Option<IpcSender>,
 
-    /// Image loader for textures
-    image_loader: ImageLoader,
-
-    /// Barcode texture (dynamically generated)
-    barcode_texture: Option<egui::TextureHandle>,
-
-    /// Class icon texture
-    class_icon_texture: Option<egui::TextureHandle>,
-
-    /// Logo texture
-    logo_texture: Option<egui::TextureHandle>,
-
-    /// Image overlay texture (for OverlayType::Image)
-    image_overlay_texture: Option<egui::TextureHandle>,
-
-    /// Transition image texture (for transition effect)
-    transition_image_texture: Option<egui::TextureHandle>,
-
-    /// Transition image raw pixel data (for direct pixel access during transition)
-    transition_image_data: Option<(Vec<Color32>, usize, usize)>, // (pixels, width, height)
-
-    /// AK progress bar image texture (from res/ak_bar.png)
-    ak_bar_texture: Option<egui::TextureHandle>,
-
-    /// Top-right arrow image texture (from res/top_right_arrow.png)
-    top_right_arrow_texture: Option<egui::TextureHandle>,
-
-    /// Left upper L-shape black decoration (modular asset)
-    top_left_rect_texture: Option<egui::TextureHandle>,
-
-    /// Left upper Rhodes decoration below L-shape (modular asset)
-    top_left_rhodes_texture: Option<egui::TextureHandle>,
-
-    /// Right upper yellow bar + full vertical bar (modular asset)
-    top_right_bar_texture: Option<egui::TextureHandle>,
-
-    /// Left side colorful gradient bar (modular asset)
-    btm_left_bar_texture: Option<egui::TextureHandle>,
-
-    /// Pre-rendered rotated text texture for top_left_rhodes custom text
-    top_left_rhodes_text_texture: Option<egui::TextureHandle>,
-    /// Pre-rendered rotated text texture for top_right_bar custom text
-    top_right_bar_text_texture: Option<egui::TextureHandle>,
-    /// Cached text value to detect changes
-    cached_rhodes_text: String,
-    /// Cached text value to detect changes
-    cached_top_right_bar_text: String,
-
-    /// Whether textures have been loaded for current config
-    textures_loaded: bool,
-
+    /// Report the overlay element under the cursor when the preview is clicked
+    inspector_enabled: bool,
+    /// Last inspected element, formatted for display
+    inspected_element: Option<String>,
+    /// Outline every overlay element with a labelled rect
+    show_bounding_boxes: bool,
+    /// Keyboard shortcut help window is open
+    show_shortcuts: bool,
+    /// Overlay field editor side panel is open
+    show_overlay_editor: bool,
+    /// Animation inspector window is open
+    show_animation_inspector: bool,
+    /// Asset status window is open
+    show_asset_panel: bool,
+    /// Assets of the current config, looked up when the asset window needs them
+    asset_statuses: Option<Vec<AssetStatus>>,
+
+    /// Warnings and errors logged while running, shown until they time out or are dismissed
+    toasts: Toasts,
     /// Error message to display in UI
     error_message: Option<String>,
+    /// Problems found in the current config
+    diagnostics: Vec<Diagnostic>,
+    /// Report unknown overlay option keys as errors
+    strict_validation: bool,
+    /// Assets of the current config that failed to load
+    asset_issues: Vec<AssetIssue>,
 }
 
 impl SimulatorApp {
     /// Create new simulator application
     pub fn new(
-        _cc: &eframe::CreationContext<'_>,
+        cc: &eframe::CreationContext<'_>,
         initial_config: Option<EPConfig>,
         base_dir: PathBuf,
         app_dir: PathBuf,
-        pipe_name: Option<String>,
-        use_stdio: bool,
+        ipc: Option<(IpcReceiver, IpcSender)>,
         cropbox: Option<(u32, u32, u32, u32)>,
         rotation: i32,
         is_dark_theme: bool,
         config_error: Option<String>,
     ) -> Self {
-        let firmware_config = FirmwareConfig::get_default();
-        let width = firmware_config.overlay_width();
-        let height = firmware_config.overlay_height();
-
-        let mut state = SimulatorState::new();
-
-        // Set appear time from config if available
-        if let Some(ref config) = initial_config {
-            let appear_us = config.get_appear_time();
-            state.appear_time_frames = microseconds_to_frames(appear_us, firmware_config.fps());
-        }
+        let app = Self::build(
+            initial_config,
+            base_dir,
+            app_dir,
+            ipc,
+            cropbox,
+            rotation,
+            is_dark_theme,
+            config_error,
+        );
 
-        // Create video player with cropbox and rotation
-        let mut video_player = VideoPlayer::new(width, height, cropbox, rotation);
+        // Apply Fluent Design theme
+        Self::setup_theme(&cc.egui_ctx, is_dark_theme);
+        app
+    }
 
-        // Load videos from config
-        let load_error = if let Some(ref config) = initial_config {
-            video_player.load_from_config(config, &base_dir)
-        } else {
-            None
-        };
-        let error_message = config_error.or(load_error);
-
-        // Start IPC server if requested
-        let (ipc_rx, ipc_tx) = if use_stdio || pipe_name.is_some() {
-            match start_ipc_server(pipe_name.clone(), use_stdio) {
-                Some((rx, tx)) => {
-                    info!("IPC server started");
-                    (Some(rx), Some(tx))
-                }
-                None => (None, None),
-            }
-        } else {
-            (None, None)
+    /// Set up a simulator, starting playback if there is a config
+    ///
+    /// Also used for the right side of the split view, which has no window
+    /// of its own.
+    #[allow(clippy::too_many_arguments)]
+    fn build(
+        initial_config: Option<EPConfig>,
+        base_dir: PathBuf,
+        app_dir: PathBuf,
+        ipc: Option<(IpcReceiver, IpcSender)>,
+        cropbox: Option<(u32, u32, u32, u32)>,
+        rotation: i32,
+        is_dark_theme: bool,
+        config_error: Option<String>,
+    ) -> Self {
+        let first_tab = ConfigTab {
+            title: initial_config.as_ref().map_or_else(|| "Untitled".to_string(), |c| Self::tab_title(c, &base_dir)),
+            prepared: None,
+            position: (PlayState::Idle, 0, false),
         };
 
-        info!(
-            "Simulator initialized: {}x{} @ {}fps",
-            width, height,
-            firmware_config.fps()
-        );
-
-        // Auto-start playback if config was provided via command line
-        let auto_start = initial_config.is_some();
-
-        // Read transition settings from config
-        let (selected_transition_in, selected_transition_loop) = if let Some(ref config) = initial_config {
-            let trans_in = config.get_transition_in_type();
-            let trans_loop = config.get_transition_loop_type();
-            info!("Transition settings from config: in={:?}, loop={:?}", trans_in, trans_loop);
-            (
-                Self::transition_type_to_index(trans_in),
-                Self::transition_type_to_index(trans_loop),
-            )
-        } else {
-            (0, 0) // Default to Fade
-        };
+        // Videos are opened in the background once the app is set up
+        let mut sim = Simulator::new(initial_config, base_dir, app_dir, cropbox, rotation);
+        sim.set_record_events(ipc.is_some());
 
-        // Pre-allocate color buffer for frame rendering
-        let buffer_size = (width * height) as usize;
+        // Connection to the editor, if the IPC server was started
+        let (ipc_rx, ipc_tx) = ipc.unzip();
 
         let mut app = Self {
-            firmware_config: firmware_config.clone(),
-            epconfig: initial_config,
-            base_dir: base_dir.clone(),
-            app_dir,
-            state,
-            video_player,
-            transition_renderer: TransitionRenderer::new(firmware_config.clone()),
-            overlay_renderer: OverlayRenderer::new(firmware_config.clone()),
-            animation_controller: AnimationController::new(firmware_config),
+            sim,
+            slots: [None, None],
+            active_slot: None,
+            tabs: vec![first_tab],
+            active_tab: 0,
+            compare: None,
+            clock: Clock::default(),
             last_frame_time: Instant::now(),
-            frame_texture: None,
-            color_image_buffer: Vec::with_capacity(buffer_size),
-            frame_dirty: true,
+            video_load: None,
+            deferred_requests: VecDeque::new(),
             is_dark_theme,
-            selected_transition_in,
-            selected_transition_loop,
-            is_first_transition: true,
             ipc_rx,
             ipc_tx,
-            image_loader: ImageLoader::new(base_dir),
-            barcode_texture: None,
-            class_icon_texture: None,
-            logo_texture: None,
-            image_overlay_texture: None,
-            transition_image_texture: None,
-            transition_image_data: None,
-            ak_bar_texture: None,
-            top_right_arrow_texture: None,
-            top_left_rect_texture: None,
-            top_left_rhodes_texture: None,
-            top_right_bar_texture: None,
-            btm_left_bar_texture: None,
-            top_left_rhodes_text_texture: None,
-            top_right_bar_text_texture: None,
-            cached_rhodes_text: String::new(),
-            cached_top_right_bar_text: String::new(),
-            textures_loaded: false,
-            error_message,
+            inspector_enabled: false,
+            inspected_element: None,
+            show_bounding_boxes: false,
+            show_shortcuts: false,
+            show_overlay_editor: false,
+            show_animation_inspector: false,
+            show_asset_panel: false,
+            asset_statuses: None,
+            toasts: Toasts::default(),
+            error_message: config_error,
+            diagnostics: Vec::new(),
+            strict_validation: false,
+            asset_issues: Vec::new(),
+            pending_window_width: None,
+            pending_viewport_commands: Vec::new(),
+            always_on_top: false,
+            status_bar: StatusBar::default(),
+            swipe_drag: None,
+            presentation_mode: false,
+            preview_rect: None,
+            frame_stream: None,
+            screenshot_pending: false,
+            frame_requests: VecDeque::new(),
+            frame_request_in_flight: None,
+            sequence_render: None,
+            sequence_frame_in_flight: false,
+            position_before_requests: None,
+            screenshot_requests: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            clipboard: None,
+            state_update_rate: StateUpdateRate::default(),
+            last_state_update: None,
+            shutdown_deadline: None,
+            metrics: PerfMetrics::new(Instant::now()),
+            auto_replay_secs: 5.0,
         };
 
-        // Apply Fluent Design theme
-        Self::setup_theme(&_cc.egui_ctx, is_dark_theme);
+        if app.sim.epconfig.is_some() {
+            app.validate_config();
 
-        // Auto-start playback if config was provided
-        if auto_start && app.video_player.has_loop() {
-            info!("Auto-starting playback...");
-            app.start_playback();
+            // Auto-start playback once the videos of a config given on the command line are open
+            let config_error = app.error_message.take();
+            app.load_videos_in_background(true);
+            app.error_message = config_error;
         }
 
         app
     }
 
-    /// Load a new configuration
-    pub fn load_config(&mut self, config: EPConfig, base_dir: PathBuf) {
-        // Update appear time
-        let appear_us = config.get_appear_time();
-        self.state.appear_time_frames = microseconds_to_frames(appear_us, self.firmware_config.fps());
-
-        // Load videos
-        self.error_message = self.video_player.load_from_config(&config, &base_dir);
-
-        // Apply transition settings from config
-        let trans_in = config.get_transition_in_type();
-        if trans_in != TransitionType::None {
-            self.selected_transition_in = Self::transition_type_to_index(trans_in);
-        }
-        let trans_loop = config.get_transition_loop_type();
-        if trans_loop != TransitionType::None {
-            self.selected_transition_loop = Self::transition_type_to_index(trans_loop);
-        }
-
-        self.epconfig = Some(config);
-        self.base_dir = base_dir.clone();
-        self.reset_playback();
-
-        // Reset textures for new config
-        self.image_loader.set_base_dir(base_dir);
-        self.barcode_texture = None;
-        self.class_icon_texture = None;
-        self.logo_texture = None;
-        self.image_overlay_texture = None;
-        self.transition_image_texture = None;
-        self.transition_image_data = None;
-        self.ak_bar_texture = None;
-        self.top_right_arrow_texture = None;
-        self.top_left_rect_texture = None;
-        self.top_left_rhodes_texture = None;
-        self.top_right_bar_texture = None;
-        self.btm_left_bar_texture = None;
-        self.top_left_rhodes_text_texture = None;
-        self.top_right_bar_text_texture = None;
-        self.cached_rhodes_text.clear();
-        self.cached_top_right_bar_text.clear();
-        self.textures_loaded = false;
-        self.frame_dirty = true;
-
-        info!("Configuration loaded");
+    /// Open the videos of `config` on a background thread, so large files
+    /// do not freeze the window
+    ///
+    /// The current videos are closed right away. Requests arriving
+    /// meanwhile wait until the new ones are ready (see `poll_video_load`).
+    fn load_videos_in_background(&mut self, autoplay: bool) {
+        let Some(config) = self.sim.epconfig.clone() else {
+            return;
+        };
+        let mut video_player = VideoPlayer::new(
+            self.sim.firmware_config().overlay_width(),
+            self.sim.firmware_config().overlay_height(),
+            self.sim.video_player.loop_cropbox(),
+            self.sim.video_player.loop_rotation(),
+        );
+        self.sim.video_player.unload();
+        self.error_message = None;
+
+        let base_dir = self.sim.base_dir.clone();
+        let (tx, rx) = mpsc::channel();
+        let load = move || {
+            let error = video_player.load_from_config(&config, &base_dir);
+            // The load was superseded if nobody is listening any more
+            let _ = tx.send((video_player, error));
+        };
+        // Browsers give us no threads; the videos are then ready by the next frame
+        #[cfg(target_arch = "wasm32")]
+        load();
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(load);
+        self.video_load = Some(VideoLoad { receiver: rx, autoplay });
     }
 
-    /// Setup Fluent Design theme to match QFluentWidgets
-    fn setup_theme(ctx: &egui::Context, is_dark: bool) {
-        let mut visuals = if is_dark {
-            egui::Visuals::dark()
-        } else {
-            egui::Visuals::light()
+    /// Take over the videos opened in the background once they are ready
+    fn poll_video_load(&mut self) {
+        let Some(ref load) = self.video_load else {
+            return;
         };
-
-        if is_dark {
-            // Match QFluentWidgets dark theme colors
-            visuals.panel_fill = Color32::from_rgb(0x2d, 0x2d, 0x2d);
-            visuals.window_fill = Color32::from_rgb(0x1e, 0x1e, 0x1e);
-            visuals.widgets.noninteractive.bg_fill = Color32::from_rgb(0x33, 0x33, 0x33);
-            visuals.widgets.inactive.bg_fill = Color32::from_rgb(0x3a, 0x3a, 0x3a);
-            visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(0x3a, 0x3a, 0x3a);
-            visuals.widgets.hovered.bg_fill = Color32::from_rgb(0x44, 0x44, 0x44);
-            visuals.widgets.active.bg_fill = Color32::from_rgb(0x55, 0x55, 0x55);
-            visuals.override_text_color = Some(Color32::from_rgb(0xee, 0xee, 0xee));
-            visuals.widgets.noninteractive.bg_stroke.color = Color32::from_rgb(0x55, 0x55, 0x55);
-        } else {
-            // Match QFluentWidgets light theme colors
-            visuals.panel_fill = Color32::from_rgb(0xf8, 0xf9, 0xfa);
-            visuals.window_fill = Color32::WHITE;
-            visuals.widgets.noninteractive.bg_fill = Color32::from_rgb(0xf0, 0xf0, 0xf0);
-            visuals.widgets.inactive.bg_fill = Color32::from_rgb(0xe8, 0xe8, 0xe8);
-            visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(0xe8, 0xe8, 0xe8);
-            visuals.widgets.hovered.bg_fill = Color32::from_rgb(0xd8, 0xd8, 0xd8);
-            visuals.widgets.active.bg_fill = Color32::from_rgb(0xcc, 0xcc, 0xcc);
-            visuals.override_text_color = Some(Color32::from_rgb(0x33, 0x33, 0x33));
-            visuals.widgets.noninteractive.bg_stroke.color = Color32::from_rgb(0xdd, 0xdd, 0xdd);
+        match load.receiver.try_recv() {
+            Ok(loaded) => self.finish_video_load(loaded),
+            Err(mpsc::TryRecvError::Empty) => {}
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.video_load = None;
+                self.error_message = Some("视频加载失败".to_string());
+            }
         }
-
-        // Fluent Design rounded corners
-        let rounding = egui::Rounding::same(4.0);
-        visuals.widgets.inactive.rounding = rounding;
-        visuals.widgets.hovered.rounding = rounding;
-        visuals.widgets.active.rounding = rounding;
-        visuals.window_rounding = egui::Rounding::same(8.0);
-
-        ctx.set_visuals(visuals);
     }
 
-    /// Get transition type from index
-    fn transition_type_from_index(index: usize) -> TransitionType {
-        match index {
-            0 => TransitionType::Fade,
-            1 => TransitionType::Move,
-            2 => TransitionType::Swipe,
-            _ => TransitionType::None,
+    /// Block until the videos opened in the background are ready
+    ///
+    /// For operations that move the video player elsewhere or need its frames.
+    fn wait_for_videos(&mut self) {
+        let Some(ref load) = self.video_load else {
+            return;
+        };
+        match load.receiver.recv() {
+            Ok(loaded) => self.finish_video_load(loaded),
+            Err(_) => {
+                self.video_load = None;
+                self.error_message = Some("视频加载失败".to_string());
+            }
         }
     }
 
-    /// Get index from transition type
-    fn transition_type_to_index(trans_type: TransitionType) -> usize {
-        match trans_type {
-            TransitionType::Fade => 0,
-            TransitionType::Move => 1,
-            TransitionType::Swipe => 2,
-            TransitionType::None => 3,
+    fn finish_video_load(&mut self, (video_player, error): (VideoPlayer, Option<String>)) {
+        let autoplay = self.video_load.take().is_some_and(|load| load.autoplay);
+        self.sim.video_player = video_player;
+        if let Some(message) = error {
+            self.emit_event(Event::Error { code: error_codes::VIDEO_LOAD_FAILED, message: message.clone() });
+            // A config error found meanwhile takes precedence
+            self.error_message.get_or_insert(message);
         }
-    }
-
-    /// Get transition frames
-    fn get_transition_frames(&self, is_intro: bool) -> u32 {
-        let fps = self.firmware_config.fps();
-        let default_frames = self.firmware_config.transition.default_frames;
-
-        if let Some(ref config) = self.epconfig {
-            let duration = if is_intro {
-                config.get_transition_in_duration()
-            } else {
-                config.get_transition_loop_duration()
-            };
+        self.sim.frame_dirty = true;
+        info!("Videos loaded");
 
-            if duration > 0 {
-                // Total duration = 3 × stage duration
-                let stage_frames = microseconds_to_frames(duration, fps);
-                return stage_frames * 3;
-            }
+        if autoplay && self.sim.video_player.has_loop() {
+            info!("Auto-starting playback...");
+            self.sim.start_playback();
         }
+    }
 
-        default_frames
+    /// Load a new configuration
+    ///
+    /// It belongs to no slot; a config shown from a slot is dropped.
+    pub fn load_config(&mut self, config: EPConfig, base_dir: PathBuf) {
+        self.active_slot = None;
+        self.apply_config(config, base_dir, false);
     }
 
-    /// Start playback
-    fn start_playback(&mut self) {
-        let has_intro = self.video_player.has_intro();
+    /// Show a configuration
+    ///
+    /// With `videos_loaded`, the video player already holds the config's
+    /// videos and `error_message` their load error.
+    fn apply_config(&mut self, config: EPConfig, base_dir: PathBuf, videos_loaded: bool) {
+        if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+            tab.title = Self::tab_title(&config, &base_dir);
+        }
+        crash::set_config(format!("{} in {}", Self::tab_title(&config, &base_dir), base_dir.display()));
+        self.asset_issues.clear();
+        self.asset_statuses = None;
+        if self.sim.set_config(config, base_dir, videos_loaded) {
+            let [width, height] = self.sim.frame_size();
+            self.pending_window_width = Some(window_size_for_screen(width, height)[0]);
+        }
 
-        // Firmware behavior: first transition is always SWIPE
-        let transition_type = if self.is_first_transition {
-            self.is_first_transition = false;
-            TransitionType::Swipe
-        } else {
-            Self::transition_type_from_index(
-                if has_intro { self.selected_transition_in } else { self.selected_transition_loop }
-            )
-        };
+        // Load videos
+        if !videos_loaded {
+            self.load_videos_in_background(false);
+        } else if let Some(ref message) = self.error_message {
+            self.emit_event(Event::Error { code: error_codes::VIDEO_LOAD_FAILED, message: message.clone() });
+        }
+        self.validate_config();
 
-        let total_frames = self.get_transition_frames(has_intro);
+        // A screen comparison shows the same config
+        if let Some((source @ CompareSource::Screen(_), _)) = self.compare {
+            self.set_compare(Some(source));
+        }
 
-        self.state.start_playback(has_intro, transition_type, total_frames);
-        self.animation_controller.reset();
+        info!("Configuration loaded");
+    }
 
-        // Reset frame accumulators for FPS sync
-        self.state.loop_frame_accumulator = 0;
-        self.state.intro_frame_accumulator = 0;
+    /// Preload a config variant into `slot`, returning the loop video error if any
+    ///
+    /// Reloading the shown slot replaces the shown config.
+    fn load_config_slot(&mut self, slot: ConfigSlot, config: EPConfig, base_dir: PathBuf) -> Option<String> {
+        let firmware_config = Simulator::firmware_config_for(self.sim.base_firmware_config(), &config);
+        let mut video_player = VideoPlayer::new(
+            firmware_config.overlay_width(),
+            firmware_config.overlay_height(),
+            self.sim.video_player.loop_cropbox(),
+            self.sim.video_player.loop_rotation(),
+        );
+        let video_error = video_player.load_from_config(&config, &base_dir);
+        info!("Preloaded config into slot {:?}", slot);
+        self.slots[slot as usize] = Some(PreparedConfig { config, base_dir, video_player, video_error: video_error.clone() });
 
-        // Prepare videos
-        if has_intro {
-            self.video_player.seek_intro_to_start();
+        if self.active_slot == Some(slot) {
+            self.active_slot = None;
+            // Cannot fail: the slot was just filled
+            let _ = self.switch_slot(slot);
         }
-        self.video_player.seek_loop_to_start();
+        video_error
+    }
 
-        self.frame_dirty = true;
-        info!("Playback started: has_intro={}, transition={:?}", has_intro, transition_type);
+    /// Show the config preloaded in `slot`
+    ///
+    /// The shown config goes back to its own slot, with its videos still
+    /// open, so switching back is just as quick.
+    fn switch_slot(&mut self, slot: ConfigSlot) -> anyhow::Result<()> {
+        if self.active_slot == Some(slot) {
+            return Ok(());
+        }
+        self.wait_for_videos();
+        let prepared = self.slots[slot as usize]
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Slot {:?} is empty", slot))?;
+
+        let shown_video_player = std::mem::replace(&mut self.sim.video_player, prepared.video_player);
+        let shown_video_error = std::mem::replace(&mut self.error_message, prepared.video_error);
+        let shown_base_dir = self.sim.base_dir.clone();
+        let shown_config = self.sim.epconfig.take();
+        self.apply_config(prepared.config, prepared.base_dir, true);
+
+        if let (Some(previous), Some(config)) = (self.active_slot, shown_config) {
+            self.slots[previous as usize] = Some(PreparedConfig {
+                config,
+                base_dir: shown_base_dir,
+                video_player: shown_video_player,
+                video_error: shown_video_error,
+            });
+        }
+        self.active_slot = Some(slot);
+        info!("Switched to slot {:?}", slot);
+        Ok(())
     }
 
-    /// Reset playback
-    fn reset_playback(&mut self) {
-        self.state.reset();
-        self.animation_controller.reset();
-        self.video_player.reset();
-        self.is_first_transition = true;
-        self.frame_dirty = true;
-        info!("Playback reset");
+    /// Label for a config's tab: its name, or else its directory
+    fn tab_title(config: &EPConfig, base_dir: &Path) -> String {
+        if !config.name.trim().is_empty() {
+            return config.name.clone();
+        }
+        base_dir
+            .file_name()
+            .map_or_else(|| "Untitled".to_string(), |name| name.to_string_lossy().into_owned())
     }
 
-    /// Handle IPC messages
-    fn handle_ipc_messages(&mut self) {
-        // Collect messages first to avoid borrow issues
-        let messages: Vec<IpcMessage> = if let Some(ref rx) = self.ipc_rx {
-            let mut msgs = Vec::new();
-            while let Some(msg) = rx.try_recv() {
-                msgs.push(msg);
-            }
-            msgs
-        } else {
-            return;
+    /// Put the shown config away in its tab, leaving a fresh video player
+    ///
+    /// Returns false if nothing is shown, in which case the tab is reused.
+    fn stash_active_tab(&mut self) -> bool {
+        self.wait_for_videos();
+        let Some(config) = self.sim.epconfig.take() else {
+            return false;
         };
-
-        for msg in messages {
-            match msg {
-                IpcMessage::LoadConfig { config, base_dir } => {
-                    self.load_config(config, PathBuf::from(base_dir));
-                }
-                IpcMessage::Control(cmd) => match cmd {
-                    ControlCommand::Play => {
-                        if self.state.play_state == PlayState::Idle {
-                            self.start_playback();
-                        } else {
-                            self.state.resume();
-                        }
-                    }
-                    ControlCommand::Pause => {
-                        self.state.pause();
-                        self.frame_dirty = true;
-                    }
-                    ControlCommand::Stop | ControlCommand::Reset => {
-                        self.reset_playback();
-                    }
-                    ControlCommand::SeekTo(state) => {
-                        // Seek to specific state
-                        if let Some(play_state) = PlayState::from_u8(state) {
-                            self.state.play_state = play_state;
-                        }
-                    }
-                },
-                IpcMessage::SetTransition { transition_in, transition_loop } => {
-                    self.selected_transition_in = match transition_in.as_str() {
-                        "fade" => 0,
-                        "move" => 1,
-                        "swipe" => 2,
-                        _ => 3,
-                    };
-                    self.selected_transition_loop = match transition_loop.as_str() {
-                        "fade" => 0,
-                        "move" => 1,
-                        "swipe" => 2,
-                        _ => 3,
-                    };
-                }
-                IpcMessage::Shutdown => {
-                    info!("Received shutdown command");
-                    std::process::exit(0);
-                }
-                _ => {}
-            }
-        }
+        let video_player = VideoPlayer::new(
+            self.sim.firmware_config().overlay_width(),
+            self.sim.firmware_config().overlay_height(),
+            self.sim.video_player.loop_cropbox(),
+            self.sim.video_player.loop_rotation(),
+        );
+        let tab = &mut self.tabs[self.active_tab];
+        tab.position = (self.sim.state.play_state, self.sim.state.frame_counter, self.sim.state.is_playing);
+        tab.prepared = Some(PreparedConfig {
+            config,
+            base_dir: self.sim.base_dir.clone(),
+            video_player: std::mem::replace(&mut self.sim.video_player, video_player),
+            video_error: self.error_message.take(),
+        });
+        self.active_slot = None;
+        true
     }
 
-    /// Send state update via IPC
-    fn send_state_update(&self) {
-        if let Some(ref tx) = self.ipc_tx {
-            let msg = IpcMessage::state_update(
-                self.state.play_state,
-                self.state.frame_counter as u64,
-                self.state.is_playing,
-            );
-            tx.send(msg);
+    /// Open a config in a new tab and show it
+    fn open_tab(&mut self, config: EPConfig, base_dir: PathBuf) {
+        if self.stash_active_tab() {
+            self.tabs.push(ConfigTab {
+                title: String::new(),
+                prepared: None,
+                position: (PlayState::Idle, 0, false),
+            });
+            self.active_tab = self.tabs.len() - 1;
         }
+        self.load_config(config, base_dir);
     }
 
-    /// Update simulation state
-    fn update_simulation(&mut self, elapsed_us: i64) {
-        if !self.state.is_playing {
+    /// Show the config of tab `index`, where its playback was left
+    fn switch_tab(&mut self, index: usize) {
+        if index == self.active_tab || index >= self.tabs.len() {
             return;
         }
-
-        let step_us = self.firmware_config.animation.step_time_us as i64;
-
-        // Accumulate wall-clock time, step N logic frames
-        self.state.logic_time_remainder_us += elapsed_us;
-        let logic_ticks = (self.state.logic_time_remainder_us / step_us) as u32;
-        self.state.logic_time_remainder_us %= step_us;
-
-        for _ in 0..logic_ticks {
-            self.state.frame_counter += 1;
-
-            match self.state.play_state {
-                PlayState::TransitionIn => self.process_transition_in(),
-                PlayState::Intro => {} // video advanced below via wall-clock
-                PlayState::TransitionLoop => self.process_transition_loop(),
-                PlayState::PreOpinfo => {
-                    self.state.pre_opinfo_counter += 1;
-                    if self.state.pre_opinfo_counter >= self.state.appear_time_frames {
-                        self.state.play_state = PlayState::Loop;
-                        self.animation_controller.reset();
-                        self.animation_controller.start_entry_animation();
-                    }
-                }
-                PlayState::Loop => {
-                    self.animation_controller.update(&mut self.state.animation);
-                }
-                PlayState::Idle => {}
-            }
-
-            // Send state update every 10 logic frames
-            if self.state.frame_counter % 10 == 0 {
-                self.send_state_update();
-            }
+        let Some(prepared) = self.tabs[index].prepared.take() else {
+            return;
+        };
+        // Tab numbers are about to change
+        if matches!(self.compare, Some((CompareSource::Tab(_), _))) {
+            self.compare = None;
         }
-
-        // Video frame advancement uses wall-clock elapsed (not logic ticks)
-        match self.state.play_state {
-            PlayState::Intro => self.advance_intro_video(elapsed_us),
-            PlayState::PreOpinfo | PlayState::Loop => self.advance_loop_video(elapsed_us),
-            _ => {}
+        if !self.stash_active_tab() {
+            // An empty tab is not worth keeping
+            self.tabs.remove(self.active_tab);
         }
-    }
+        self.active_tab = self.tabs.iter().position(|tab| tab.prepared.is_none()).unwrap_or(0);
 
-    fn process_transition_in(&mut self) {
-        self.state.transition.frame += 1;
-        let phase = self.state.transition.phase();
+        self.sim.video_player = prepared.video_player;
+        self.error_message = prepared.video_error;
+        self.apply_config(prepared.config, prepared.base_dir, true);
 
-        // Switch video during hold phase
-        if phase == TransitionPhase::PhaseHold && !self.state.transition.video_switched {
-            self.state.transition.video_switched = true;
-            self.video_player.seek_intro_to_start();
+        let (play_state, frame_counter, is_playing) = self.tabs[self.active_tab].position;
+        if play_state != PlayState::Idle {
+            self.fast_forward(|state| state.frame_counter >= frame_counter);
+            self.sim.state.is_playing = is_playing;
         }
+        info!("Switched to tab {}", self.active_tab);
+        self.send_state_update();
+    }
 
-        // Transition complete
-        if self.state.transition.is_complete() {
-            self.state.play_state = PlayState::Intro;
-            self.state.intro_frame_accumulator = 0;  // Reset for FPS sync
-            self.video_player.seek_intro_to_start();
+    /// Close tab `index`, showing a neighbouring tab if it was shown
+    fn close_tab(&mut self, index: usize) {
+        if self.tabs.len() <= 1 || index >= self.tabs.len() {
+            return;
+        }
+        if index == self.active_tab {
+            let neighbour = if index + 1 < self.tabs.len() { index + 1 } else { index - 1 };
+            self.switch_tab(neighbour);
+        }
+        if matches!(self.compare, Some((CompareSource::Tab(_), _))) {
+            self.compare = None;
+        }
+        self.tabs.remove(index);
+        if self.active_tab > index {
+            self.active_tab -= 1;
         }
     }
 
-    /// Advance intro video frames based on wall-clock elapsed time
-    fn advance_intro_video(&mut self, elapsed_us: i64) {
-        let video_fps = self.video_player.intro_fps();
-        let frame_duration_us = (1_000_000.0 / video_fps) as i64;
-
-        self.state.intro_frame_accumulator += elapsed_us;
-
-        while self.state.intro_frame_accumulator >= frame_duration_us {
-            self.state.intro_frame_accumulator -= frame_duration_us;
-            if !self.video_player.advance_intro_frame() {
-                self.start_transition_loop();
-                return;
+    /// Label of a split view choice
+    fn compare_label(&self, source: CompareSource) -> String {
+        match source {
+            CompareSource::Tab(index) => {
+                format!("Tab: {}", self.tabs.get(index).map_or("?", |tab| tab.title.as_str()))
+            }
+            CompareSource::Screen(screen) => {
+                let (width, height) = screen.dimensions();
+                format!("Screen: {}x{}", width, height)
             }
         }
     }
 
-    fn start_transition_loop(&mut self) {
-        self.state.play_state = PlayState::TransitionLoop;
-        let transition_type = Self::transition_type_from_index(self.selected_transition_loop);
-        let total_frames = self.get_transition_frames(false);
-        self.state.transition.reset(transition_type, total_frames);
+    /// Show `source` next to the preview, or close the split view
+    ///
+    /// The compared side opens its own videos and follows this side's
+    /// playback clock, so both show the same moment.
+    fn set_compare(&mut self, source: Option<CompareSource>) {
+        self.compare = None;
+        let Some(source) = source else {
+            return;
+        };
+        let loaded = match source {
+            CompareSource::Tab(index) => self
+                .tabs
+                .get(index)
+                .and_then(|tab| tab.prepared.as_ref())
+                .map(|prepared| (prepared.config.clone(), prepared.base_dir.clone())),
+            CompareSource::Screen(screen) => self.sim.epconfig.as_ref().map(|config| {
+                let mut config = config.clone();
+                config.screen = screen;
+                (config, self.sim.base_dir.clone())
+            }),
+        };
+        let Some((config, base_dir)) = loaded else {
+            warn!("Nothing to compare for {:?}", source);
+            return;
+        };
+        let mut other = Self::build(
+            Some(config),
+            base_dir,
+            self.sim.app_dir().to_path_buf(),
+            None,
+            self.sim.video_player.loop_cropbox(),
+            self.sim.video_player.loop_rotation(),
+            self.is_dark_theme,
+            None,
+        );
+        other.sim.apply_text_quality(self.sim.text_quality());
+        other.sim.reset_playback();
+        info!("Comparing with {:?}", source);
+        self.compare = Some((source, Box::new(other)));
     }
 
-    fn process_transition_loop(&mut self) {
-        self.state.transition.frame += 1;
-        let phase = self.state.transition.phase();
-
-        // Switch video during hold phase
-        if phase == TransitionPhase::PhaseHold && !self.state.transition.video_switched {
-            self.state.transition.video_switched = true;
-            self.video_player.seek_loop_to_start();
+    /// Advance by `elapsed_us` of playback, staying at the same time as `leader`
+    ///
+    /// Restarts, seeks and auto-replays on the leading side show up as a
+    /// different play time, which this side catches up with by seeking.
+    fn follow_clock(&mut self, ctx: &egui::Context, leader: &SimulatorState, loop_only: bool, elapsed_us: i64) {
+        self.poll_video_load();
+        self.sim.load_textures(ctx);
+        self.sim.loop_only = loop_only;
+        self.sim.replays_remaining = 0;
+        self.sim.auto_replay = AutoReplay::Off;
+
+        if leader.play_state == PlayState::Idle {
+            if self.sim.state.play_state != PlayState::Idle {
+                self.sim.reset_playback();
+            }
+        } else {
+            if elapsed_us > 0 && self.sim.state.play_state != PlayState::Idle {
+                self.sim.state.resume();
+                self.sim.update_simulation(elapsed_us);
+                self.sim.frame_dirty = true;
+            }
+            if self.sim.state.play_state == PlayState::Idle || self.sim.state.played_us != leader.played_us {
+                self.seek_to_time(leader.played_us);
+                self.sim.frame_dirty = true;
+            }
+            self.sim.state.is_playing = leader.is_playing;
         }
 
-        // Transition complete
-        if self.state.transition.is_complete() {
-            self.state.play_state = PlayState::PreOpinfo;
-            self.state.pre_opinfo_counter = 0;
-            self.state.loop_frame_accumulator = 0;  // Reset for FPS sync
-            self.video_player.seek_loop_to_start();
+        if self.sim.frame_dirty {
+            self.sim.render_frame(ctx);
+            self.sim.frame_dirty = false;
         }
     }
 
-    /// Advance loop video frames based on wall-clock elapsed time
-    fn advance_loop_video(&mut self, elapsed_us: i64) {
-        let video_fps = self.video_player.loop_fps();
-        let frame_duration_us = (1_000_000.0 / video_fps) as i64;
-
-        self.state.loop_frame_accumulator += elapsed_us;
-
-        while self.state.loop_frame_accumulator >= frame_duration_us {
-            self.state.loop_frame_accumulator -= frame_duration_us;
-            self.video_player.advance_loop_frame();
+    /// Show the preview image, as large as fits, with the overlays on top
+    fn preview_ui(&mut self, ui: &mut egui::Ui) {
+        if self.video_load.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Loading videos…");
+            });
         }
-    }
 
-    /// Update a color buffer from an RgbImage
-    /// Takes the buffer as a separate parameter to avoid borrow checker issues
-    fn update_color_buffer(buffer: &mut Vec<Color32>, img: &RgbImage) {
-        let pixels = img.as_raw();
-        let len = img.width() as usize * img.height() as usize;
+        // Calculate adaptive image size to fit available space, in whole
+        // physical pixels so the frame stays sharp on scaled displays
+        let available = ui.available_size();
+        let fw_width = self.sim.firmware_config().overlay_width() as f32;
+        let fw_height = self.sim.firmware_config().overlay_height() as f32;
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        let img_size = fit_preview(available, Vec2::new(fw_width, fw_height), pixels_per_point);
+
+        // Display area, centered horizontally
+        let image_response = self.sim.has_frame().then(|| {
+            let (row, _) = ui.allocate_exact_size(Vec2::new(available.x, img_size.y), egui::Sense::hover());
+            let rect = Rect::from_min_size(Pos2::new(row.center().x - img_size.x / 2.0, row.min.y), img_size);
+            let rect = align_to_pixels(rect, pixels_per_point);
+            self.sim.paint_frame(ui.painter(), rect);
+            ui.interact(rect, ui.id().with("preview"), egui::Sense::click_and_drag())
+        });
+
+        self.preview_rect = image_response.as_ref().map(|r| r.rect);
+        if let Some(ref response) = image_response {
+            if self.inspector_enabled && response.clicked() {
+                if let Some(pos) = response.interact_pointer_pos() {
+                    self.inspect_at(pos, response.rect);
+                }
+            }
 
-        // Clear and reuse the existing buffer
-        buffer.clear();
+            // Dragging over the preview stands in for swiping the touch screen
+            if response.drag_started() {
+                self.swipe_drag = Some(Vec2::ZERO);
+            }
+            if let Some(ref mut drag) = self.swipe_drag {
+                *drag += response.drag_delta();
+            }
+            if response.drag_stopped() {
+                let swipe = self.swipe_drag.take().and_then(|drag| SwipeDirection::from_drag(drag, response.rect.size()));
+                if let Some(direction) = swipe {
+                    self.handle_swipe(direction);
+                }
+            }
+        }
 
-        // Reserve capacity if needed (only allocates if buffer is too small)
-        if buffer.capacity() < len {
-            buffer.reserve(len - buffer.capacity());
+        // Render overlay UI on top of the image when in Loop state
+        if self.sim.state.play_state == PlayState::Loop {
+            if let Some(image_rect) = self.preview_rect {
+                let painter = ui.painter_at(image_rect);
+                self.sim.paint_overlays(&painter, image_rect);
+                if self.show_bounding_boxes {
+                    self.render_bounding_boxes(&painter, image_rect);
+                }
+            }
         }
 
-        // Convert RGB pixels to Color32
-        for i in 0..len {
-            let idx = i * 3;
-            buffer.push(Color32::from_rgb(
-                pixels[idx],
-                pixels[idx + 1],
-                pixels[idx + 2],
-            ));
+        // The firmware draws its status bar above everything
+        if let Some(image_rect) = self.preview_rect {
+            self.status_bar.paint(&ui.painter_at(image_rect), image_rect);
         }
     }
 
-    /// Fill color buffer with black pixels
-    fn fill_color_buffer_black(buffer: &mut Vec<Color32>, width: usize, height: usize) {
-        let len = width * height;
-        buffer.clear();
-        if buffer.capacity() < len {
-            buffer.reserve(len - buffer.capacity());
-        }
-        buffer.resize(len, Color32::BLACK);
+    /// Apply a partial Arknights overlay options patch without resetting playback
+    fn update_overlay(&mut self, patch: &serde_json::Value) -> anyhow::Result<()> {
+        self.sim.update_overlay(patch)?;
+        self.asset_statuses = None;
+        Ok(())
     }
 
-    /// Render the current frame
-    fn render_frame(&mut self, ctx: &egui::Context) {
-        let width = self.firmware_config.overlay_width() as usize;
-        let height = self.firmware_config.overlay_height() as usize;
-
-        // Determine frame source based on current state (avoids multiple borrows)
-        enum FrameSource {
-            Loop,
-            Intro,
-            Black,
-        }
-
-        let source = match self.state.play_state {
-            PlayState::Idle => FrameSource::Loop,
-            PlayState::TransitionIn => FrameSource::Loop,
-            PlayState::Intro => FrameSource::Intro,
-            PlayState::TransitionLoop => {
-                if self.state.transition.video_switched {
-                    FrameSource::Loop
-                } else if self.video_player.has_intro() {
-                    FrameSource::Intro
-                } else {
-                    FrameSource::Loop
-                }
-            }
-            PlayState::PreOpinfo | PlayState::Loop => FrameSource::Loop,
+    /// Editable fields of the Arknights overlay; changes apply to the preview
+    /// at once and are sent to the editor as `overlay_edited`
+    fn overlay_editor_ui(&mut self, ui: &mut egui::Ui) {
+        // Unexpanded, so template variables stay editable
+        let Some(options) = self.sim.epconfig.as_ref().and_then(|c| c.arknights_options()) else {
+            ui.label("The loaded config has no Arknights overlay");
+            return;
         };
-
-        // Update color buffer from the appropriate frame source (using references, no clone)
-        let has_frame = match source {
-            FrameSource::Loop => {
-                if let Some(frame) = self.video_player.get_loop_current_frame() {
-                    Self::update_color_buffer(&mut self.color_image_buffer, frame);
-                    true
+        let mut patch = serde_json::Map::new();
+        egui::Grid::new("overlay_fields").num_columns(2).show(ui, |ui| {
+            for (label, key, value, multiline) in [
+                ("Name", "operator_name", &options.operator_name, false),
+                ("Code", "operator_code", &options.operator_code, false),
+                ("Barcode", "barcode_text", &options.barcode_text, false),
+                ("Staff", "staff_text", &options.staff_text, false),
+                ("Top left", "top_left_rhodes", &options.top_left_rhodes, false),
+                ("Top right bar", "top_right_bar_text", &options.top_right_bar_text, false),
+                ("Aux text", "aux_text", &options.aux_text, true),
+            ] {
+                ui.label(label);
+                let mut text = value.clone();
+                let edit = if multiline {
+                    egui::TextEdit::multiline(&mut text).desired_rows(3)
                 } else {
-                    false
+                    egui::TextEdit::singleline(&mut text)
+                };
+                if ui.add(edit.desired_width(180.0)).changed() {
+                    patch.insert(key.to_string(), text.into());
                 }
+                ui.end_row();
             }
-            FrameSource::Intro => {
-                if let Some(frame) = self.video_player.get_intro_last_frame() {
-                    Self::update_color_buffer(&mut self.color_image_buffer, frame);
-                    true
-                } else {
-                    false
-                }
+            for (label, key, value) in [
+                ("Theme color", "color", &options.color),
+                ("Second color", "color2", &options.color2),
+                ("Name color", "operator_name_color", &options.operator_name_color),
+                ("Code color", "operator_code_color", &options.operator_code_color),
+                ("Staff color", "staff_text_color", &options.staff_text_color),
+            ] {
+                ui.label(label);
+                ui.horizontal(|ui| {
+                    let mut color = Simulator::parse_color(value);
+                    if ui.color_edit_button_srgba(&mut color).changed() {
+                        let [r, g, b, _] = color.to_srgba_unmultiplied();
+                        let hex = format!("#{:02X}{:02X}{:02X}", r, g, b);
+                        patch.insert(key.to_string(), hex.into());
+                    }
+                    // Empty means the element's default color
+                    let mut text = value.clone();
+                    if ui.add(egui::TextEdit::singleline(&mut text).desired_width(90.0)).changed() {
+                        patch.insert(key.to_string(), text.into());
+                    }
+                });
+                ui.end_row();
             }
-            FrameSource::Black => false,
-        };
-
-        // Fill with black if no frame available
-        if !has_frame {
-            Self::fill_color_buffer_black(&mut self.color_image_buffer, width, height);
-        }
-
-        // Create ColorImage from the buffer
-        // We clone here because egui needs ownership, but the buffer retains its capacity for reuse
-        // The main memory savings come from not cloning RgbImage (2.7MB per frame saved)
-        let mut image = egui::ColorImage {
-            size: [width, height],
-            pixels: self.color_image_buffer.clone(),
-        };
-
-        // Apply transition effect if in transition state
-        if matches!(self.state.play_state, PlayState::TransitionIn | PlayState::TransitionLoop) {
-            self.apply_transition_overlay(&mut image);
+        });
+        if patch.is_empty() {
+            return;
         }
 
-        // If in loop state with arknights overlay, render color fade at pixel level
-        if self.state.play_state == PlayState::Loop {
-            if let Some(ref config) = self.epconfig {
-                if let Some(ref overlay) = config.overlay {
-                    if overlay.overlay_type == OverlayType::Arknights {
-                        self.render_color_fade(&mut image.pixels, width, height);
-                    }
+        let patch = serde_json::Value::Object(patch);
+        match self.update_overlay(&patch) {
+            Ok(()) => {
+                if let Some(ref tx) = self.ipc_tx {
+                    tx.send(IpcMessage::OverlayEdited { patch });
                 }
             }
+            Err(e) => warn!("Failed to update overlay: {}", e),
         }
+    }
 
-        // Update texture
-        if let Some(ref mut texture) = self.frame_texture {
-            texture.set(image, egui::TextureOptions::NEAREST);
-        } else {
-            self.frame_texture = Some(ctx.load_texture(
-                "frame",
-                image,
-                egui::TextureOptions::NEAREST,
-            ));
-        }
+    /// Save the current configuration, returning the path written
+    fn save_config(&self, path: &str) -> anyhow::Result<PathBuf> {
+        let config = self
+            .sim
+            .epconfig
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No configuration loaded"))?;
+        let path = self.sim.base_dir.join(path);
+        config.save_to_file(&path)?;
+        info!("Configuration saved: {}", path.display());
+        Ok(path)
     }
 
-    /// Apply transition overlay effect to the image
-    fn apply_transition_overlay(&self, image: &mut egui::ColorImage) {
-        let progress = self.state.transition.progress();
-        let trans_type = self.state.transition.transition_type;
-        let phase = self.state.transition.phase();
-        let width = image.size[0];
-        let height = image.size[1];
-
-        // Get transition options based on current state
-        let is_intro = self.state.play_state == PlayState::TransitionIn;
-        let options = self.get_transition_options(is_intro);
-
-        // Get background color from config (default black)
-        let bg_color = options
-            .map(|o| Self::parse_hex_color(&o.background_color))
-            .unwrap_or(Color32::BLACK);
-
-        // Check if we have a transition image and we're in Hold phase
-        let has_transition_image = options
-            .map(|o| !o.image.is_empty())
-            .unwrap_or(false);
-
-        match trans_type {
-            TransitionType::Fade => {
-                // Calculate fade alpha based on progress
-                let alpha = self.transition_renderer.calculate_fade_alpha(progress);
-
-                // During Hold phase with transition image, show the image
-                if phase == TransitionPhase::PhaseHold && has_transition_image {
-                    if let Some((ref trans_pixels, trans_width, trans_height)) = self.transition_image_data {
-                        // Calculate aspect-ratio-preserving scale (contain mode, centered)
-                        let screen_aspect = width as f32 / height as f32;
-                        let image_aspect = trans_width as f32 / trans_height as f32;
-
-                        let (scaled_w, scaled_h, offset_x, offset_y) = if image_aspect > screen_aspect {
-                            // Image is wider - fit to width
-                            let scaled_w = width as f32;
-                            let scaled_h = width as f32 / image_aspect;
-                            let offset_y = ((height as f32 - scaled_h) / 2.0) as i32;
-                            (scaled_w, scaled_h, 0i32, offset_y)
-                        } else {
-                            // Image is taller - fit to height
-                            let scaled_h = height as f32;
-                            let scaled_w = height as f32 * image_aspect;
-                            let offset_x = ((width as f32 - scaled_w) / 2.0) as i32;
-                            (scaled_w, scaled_h, offset_x, 0i32)
-                        };
-
-                        for (i, pixel) in image.pixels.iter_mut().enumerate() {
-                            let x = i % width;
-                            let y = i / width;
-
-                            // Map screen coordinates to source image coordinates
-                            let src_x = ((x as i32 - offset_x) as f32 * trans_width as f32 / scaled_w) as i32;
-                            let src_y = ((y as i32 - offset_y) as f32 * trans_height as f32 / scaled_h) as i32;
-
-                            if src_x >= 0 && src_x < trans_width as i32 && src_y >= 0 && src_y < trans_height as i32 {
-                                let tex_idx = src_y as usize * trans_width + src_x as usize;
-                                if tex_idx < trans_pixels.len() {
-                                    let trans_pixel = trans_pixels[tex_idx];
-                                    let blend = alpha as f32 / 255.0;
-                                    let inv_blend = 1.0 - blend;
-                                    *pixel = Color32::from_rgb(
-                                        ((trans_pixel.r() as f32 * blend) + (pixel.r() as f32 * inv_blend)) as u8,
-                                        ((trans_pixel.g() as f32 * blend) + (pixel.g() as f32 * inv_blend)) as u8,
-                                        ((trans_pixel.b() as f32 * blend) + (pixel.b() as f32 * inv_blend)) as u8,
-                                    );
-                                }
-                            } else {
-                                // Outside bounds - fill with background color
-                                let blend = alpha as f32 / 255.0;
-                                let inv_blend = 1.0 - blend;
-                                *pixel = Color32::from_rgb(
-                                    ((bg_color.r() as f32 * blend) + (pixel.r() as f32 * inv_blend)) as u8,
-                                    ((bg_color.g() as f32 * blend) + (pixel.g() as f32 * inv_blend)) as u8,
-                                    ((bg_color.b() as f32 * blend) + (pixel.b() as f32 * inv_blend)) as u8,
-                                );
-                            }
-                        }
-                        return;
-                    }
-                }
-
-                // Apply background color overlay with alpha (instead of hardcoded black)
-                for pixel in image.pixels.iter_mut() {
-                    let blend = alpha as f32 / 255.0;
-                    let inv_blend = 1.0 - blend;
+    /// Export the current configuration as a package, returning the path written
+    fn export_package(&self, path: &str) -> anyhow::Result<PathBuf> {
+        let config = self
+            .sim
+            .epconfig
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No configuration loaded"))?;
+        let path = self.sim.base_dir.join(path);
+        config.export_package(&self.sim.base_dir, &path)?;
+        Ok(path)
+    }
 
-                    *pixel = Color32::from_rgb(
-                        ((bg_color.r() as f32 * blend) + (pixel.r() as f32 * inv_blend)) as u8,
-                        ((bg_color.g() as f32 * blend) + (pixel.g() as f32 * inv_blend)) as u8,
-                        ((bg_color.b() as f32 * blend) + (pixel.b() as f32 * inv_blend)) as u8,
-                    );
-                }
-            }
-            TransitionType::Move => {
-                // Calculate move offset
-                let offset = self.transition_renderer.calculate_move_offset(progress);
-
-                // During Hold phase with transition image, fill above the line with bg_color
-                if phase == TransitionPhase::PhaseHold {
-                    // Fill area above the offset line with background color
-                    for y in 0..(offset as usize).min(height) {
-                        for x in 0..width {
-                            let idx = y * width + x;
-                            image.pixels[idx] = bg_color;
-                        }
-                    }
-                }
+    /// Setup Fluent Design theme to match QFluentWidgets
+    fn setup_theme(ctx: &egui::Context, is_dark: bool) {
+        let mut visuals = if is_dark {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        };
 
-                // Draw line at the offset position
-                if offset > 0 && (offset as usize) < height {
-                    for x in 0..width {
-                        image.pixels[offset as usize * width + x] = Color32::WHITE;
-                    }
-                }
-            }
-            TransitionType::Swipe => {
-                // Calculate swipe progress (0.0 to 1.0)
-                let swipe_progress = self.transition_renderer.calculate_swipe_progress(progress);
-                let swipe_y = (swipe_progress * height as f32) as usize;
+        if is_dark {
+            // Match QFluentWidgets dark theme colors
+            visuals.panel_fill = Color32::from_rgb(0x2d, 0x2d, 0x2d);
+            visuals.window_fill = Color32::from_rgb(0x1e, 0x1e, 0x1e);
+            visuals.widgets.noninteractive.bg_fill = Color32::from_rgb(0x33, 0x33, 0x33);
+            visuals.widgets.inactive.bg_fill = Color32::from_rgb(0x3a, 0x3a, 0x3a);
+            visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(0x3a, 0x3a, 0x3a);
+            visuals.widgets.hovered.bg_fill = Color32::from_rgb(0x44, 0x44, 0x44);
+            visuals.widgets.active.bg_fill = Color32::from_rgb(0x55, 0x55, 0x55);
+            visuals.override_text_color = Some(Color32::from_rgb(0xee, 0xee, 0xee));
+            visuals.widgets.noninteractive.bg_stroke.color = Color32::from_rgb(0x55, 0x55, 0x55);
+        } else {
+            // Match QFluentWidgets light theme colors
+            visuals.panel_fill = Color32::from_rgb(0xf8, 0xf9, 0xfa);
+            visuals.window_fill = Color32::WHITE;
+            visuals.widgets.noninteractive.bg_fill = Color32::from_rgb(0xf0, 0xf0, 0xf0);
+            visuals.widgets.inactive.bg_fill = Color32::from_rgb(0xe8, 0xe8, 0xe8);
+            visuals.widgets.inactive.weak_bg_fill = Color32::from_rgb(0xe8, 0xe8, 0xe8);
+            visuals.widgets.hovered.bg_fill = Color32::from_rgb(0xd8, 0xd8, 0xd8);
+            visuals.widgets.active.bg_fill = Color32::from_rgb(0xcc, 0xcc, 0xcc);
+            visuals.override_text_color = Some(Color32::from_rgb(0x33, 0x33, 0x33));
+            visuals.widgets.noninteractive.bg_stroke.color = Color32::from_rgb(0xdd, 0xdd, 0xdd);
+        }
 
-                // Draw swipe line
-                if swipe_y > 0 && swipe_y < height {
-                    for x in 0..width {
-                        image.pixels[swipe_y * width + x] = Color32::from_rgb(200, 200, 200);
-                    }
+        // Fluent Design rounded corners
+        let rounding = egui::Rounding::same(4.0);
+        visuals.widgets.inactive.rounding = rounding;
+        visuals.widgets.hovered.rounding = rounding;
+        visuals.widgets.active.rounding = rounding;
+        visuals.window_rounding = egui::Rounding::same(8.0);
 
-                    // Fill area above swipe line with background color (or darkened if no bg specified)
-                    for y in 0..swipe_y.min(height) {
-                        for x in 0..width {
-                            let idx = y * width + x;
-                            if bg_color != Color32::BLACK {
-                                // Use configured background color
-                                image.pixels[idx] = bg_color;
-                            } else {
-                                // Default: darken the existing pixels
-                                let p = image.pixels[idx];
-                                image.pixels[idx] = Color32::from_rgb(
-                                    p.r() / 3,
-                                    p.g() / 3,
-                                    p.b() / 3,
-                                );
-                            }
-                        }
-                    }
-                }
-            }
-            TransitionType::None => {}
-        }
+        ctx.set_visuals(visuals);
     }
 
-    /// Render color fade effect at pixel level (blends with video)
-    fn render_color_fade(&self, pixels: &mut [Color32], width: usize, height: usize) {
-        let anim = &self.state.animation;
-        let radius = anim.color_fade_radius as usize;
-
-        if radius == 0 {
+    /// Change the loop video cropbox and rotation, reopening its decoder
+    ///
+    /// Playback keeps its state; the loop video restarts from its first frame.
+    fn set_loop_transform(&mut self, cropbox: Option<(u32, u32, u32, u32)>, rotation: i32) {
+        let error = self.sim.set_loop_transform(cropbox, rotation);
+        if self.sim.epconfig.is_none() {
             return;
         }
-
-        // Get theme color
-        let theme_color = self.get_theme_color();
-
-        // Draw color fade in bottom-right corner (matching C firmware draw_color_fade)
-        for x in 0..radius.min(width) {
-            for y in 0..radius.min(height) {
-                // C firmware: if(x+y > radius - 2) break;
-                if x + y > radius.saturating_sub(2) {
-                    break;
-                }
-
-                // Calculate alpha: 255 - ((x+y)*255 / radius)
-                let alpha = 255.0 - ((x + y) as f32 * 255.0 / radius as f32);
-                let alpha = (alpha * 0.8).clamp(0.0, 255.0) as u8; // Slightly reduce opacity
-
-                // Calculate real coordinates (bottom-right corner)
-                let real_x = width - x - 1;
-                let real_y = height - y - 1;
-
-                if real_y < height && real_x < width {
-                    let idx = real_y * width + real_x;
-                    // Blend with existing pixel
-                    let bg = pixels[idx];
-                    pixels[idx] = Self::blend_colors(bg, theme_color, alpha);
-                }
-            }
+        self.asset_issues.retain(|issue| issue.asset != "loop video");
+        self.error_message = error;
+        if let Some(ref message) = self.error_message {
+            self.emit_event(Event::Error { code: error_codes::VIDEO_LOAD_FAILED, message: message.clone() });
         }
+        self.validate_config();
     }
 
-    /// Blend two colors with alpha
-    fn blend_colors(bg: Color32, fg: Color32, alpha: u8) -> Color32 {
-        let a = alpha as f32 / 255.0;
-        let inv_a = 1.0 - a;
-
-        Color32::from_rgb(
-            ((fg.r() as f32 * a) + (bg.r() as f32 * inv_a)) as u8,
-            ((fg.g() as f32 * a) + (bg.g() as f32 * inv_a)) as u8,
-            ((fg.b() as f32 * a) + (bg.b() as f32 * inv_a)) as u8,
-        )
+    /// Show `serial` as `{serial}` whatever config is loaded, or the config's own with None
+    pub fn set_serial(&mut self, serial: Option<String>) {
+        self.sim.set_serial(serial);
     }
 
-    /// Parse hex color string to Color32
-    fn parse_hex_color(hex: &str) -> Color32 {
-        let hex = hex.trim_start_matches('#');
-
-        if hex.len() >= 6 {
-            let r = u8::from_str_radix(&hex[0..2], 16).unwrap_or(0);
-            let g = u8::from_str_radix(&hex[2..4], 16).unwrap_or(0);
-            let b = u8::from_str_radix(&hex[4..6], 16).unwrap_or(0);
-            Color32::from_rgb(r, g, b)
-        } else {
-            Color32::WHITE
+    /// Switch strict validation on or off, revalidating the current config
+    pub fn set_strict_validation(&mut self, strict: bool) {
+        if self.strict_validation != strict {
+            self.strict_validation = strict;
+            self.validate_config();
         }
     }
 
-    /// Get theme color from config
-    fn get_theme_color(&self) -> Color32 {
-        self.get_arknights_options()
-            .map(|opts| Self::parse_hex_color(&opts.color))
-            .unwrap_or(Color32::from_rgb(255, 100, 100))
-    }
-
-    /// Get ArknightsOverlayOptions from config
-    fn get_arknights_options(&self) -> Option<ArknightsOverlayOptions> {
-        self.epconfig
-            .as_ref()
-            .and_then(|c| c.overlay.as_ref())
-            .and_then(|o| o.arknights_options())
+    /// Keep restarting playback, e.g. for a demo display left running
+    pub fn set_auto_replay(&mut self, auto_replay: AutoReplay) {
+        if let AutoReplay::AfterLoop(us) | AutoReplay::AfterTotal(us) = auto_replay {
+            self.auto_replay_secs = us as f64 / 1_000_000.0;
+        }
+        self.sim.auto_replay = auto_replay;
     }
 
-    /// Get ImageOverlayOptions from config
-    fn get_image_overlay_options(&self) -> Option<ImageOverlayOptions> {
-        self.epconfig
-            .as_ref()
-            .and_then(|c| c.overlay.as_ref())
-            .and_then(|o| o.image_options())
+    /// Validate the current config and the loop video cropbox
+    ///
+    /// Results are logged, shown in the UI and sent to the editor.
+    fn validate_config(&mut self) {
+        let Some(ref config) = self.sim.epconfig else {
+            return;
+        };
+        let mut diagnostics = if self.strict_validation {
+            config.validate_strict(&self.sim.base_dir)
+        } else {
+            config.validate(&self.sim.base_dir)
+        };
+        if let (Some(cropbox), Some(source)) = (self.sim.video_player.loop_cropbox(), self.sim.video_player.loop_source_size()) {
+            diagnostics.extend(validate_cropbox(cropbox, source));
+        }
+        for diagnostic in &diagnostics {
+            warn!("Config {}", diagnostic);
+        }
+        if let Some(ref tx) = self.ipc_tx {
+            tx.send(IpcMessage::Validation { diagnostics: diagnostics.clone() });
+        }
+        self.diagnostics = diagnostics;
     }
 
-    /// Get transition options for current state (in or loop)
-    fn get_transition_options(&self, is_intro: bool) -> Option<&TransitionOptions> {
-        self.epconfig.as_ref().and_then(|config| {
-            if is_intro {
-                config.transition_in.as_ref().and_then(|t| t.options.as_ref())
-            } else {
-                config.transition_loop.as_ref().and_then(|t| t.options.as_ref())
-            }
-        })
+    /// Collect asset load failures and report new ones to the editor
+    fn collect_asset_issues(&mut self) {
+        let mut new_issues = self.sim.take_asset_failures();
+        new_issues.retain(|issue| !self.asset_issues.contains(issue));
+        if new_issues.is_empty() {
+            return;
+        }
+        new_issues.dedup();
+        for issue in &new_issues {
+            self.emit_event(Event::Error {
+                code: error_codes::ASSET_LOAD_FAILED,
+                message: format!("Failed to load {} ({}): {}", issue.asset, issue.path, issue.reason),
+            });
+        }
+        self.asset_issues.extend(new_issues);
+        if let Some(ref tx) = self.ipc_tx {
+            tx.send(IpcMessage::asset_errors(self.asset_issues.clone()));
+        }
     }
 
-    /// Load textures for the current configuration
-    fn load_textures(&mut self, ctx: &egui::Context) {
-        if self.textures_loaded {
+    /// Handle IPC messages
+    fn handle_ipc_messages(&mut self) {
+        // Collect messages first to avoid borrow issues
+        let Some(ref rx) = self.ipc_rx else {
             return;
+        };
+        while let Some(msg) = rx.try_recv() {
+            crash::set_last_message(&msg.1);
+            self.deferred_requests.push_back(msg);
         }
 
-        // Load ak_bar.png from resources/data directory
-        if self.ak_bar_texture.is_none() {
-            let ak_bar_path = self.app_dir.join("resources/data/ak_bar.png");
-            if let Ok(img) = image::open(&ak_bar_path) {
-                let rgba = img.to_rgba8();
-                let size = [rgba.width() as usize, rgba.height() as usize];
-                let pixels: Vec<Color32> = rgba
-                    .pixels()
-                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-                    .collect();
-                let color_image = egui::ColorImage { size, pixels };
-                self.ak_bar_texture = Some(ctx.load_texture(
-                    "ak_bar",
-                    color_image,
-                    egui::TextureOptions::LINEAR,
-                ));
-                info!("Loaded ak_bar.png: {}", ak_bar_path.display());
-            } else {
-                warn!("Failed to load ak_bar.png: {}", ak_bar_path.display());
-            }
+        // Requests after a config load wait for its videos
+        while self.video_load.is_none() {
+            let Some((client, msg)) = self.deferred_requests.pop_front() else {
+                break;
+            };
+            self.handle_ipc_message(client, msg);
+            self.forward_events();
         }
+    }
 
-        // Load top_right_arrow.png from resources/data directory
-        if self.top_right_arrow_texture.is_none() {
-            let arrow_path = self.app_dir.join("resources/data/top_right_arrow.png");
-            if let Ok(img) = image::open(&arrow_path) {
-                let rgba = img.to_rgba8();
-                let size = [rgba.width() as usize, rgba.height() as usize];
-                let pixels: Vec<Color32> = rgba
-                    .pixels()
-                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-                    .collect();
-                let color_image = egui::ColorImage { size, pixels };
-                self.top_right_arrow_texture = Some(ctx.load_texture(
-                    "top_right_arrow",
-                    color_image,
-                    egui::TextureOptions::LINEAR,
-                ));
-                info!("Loaded top_right_arrow.png: {}", arrow_path.display());
-            } else {
-                warn!("Failed to load top_right_arrow.png: {}", arrow_path.display());
+    /// Apply one request from a client
+    fn handle_ipc_message(&mut self, client: ReplyTo, msg: IpcMessage) {
+        match msg {
+            IpcMessage::LoadConfig { config, base_dir } => {
+                self.load_config(*config, PathBuf::from(base_dir));
             }
-        }
-
-        // Load modular decoration textures
-
-        // Load top_left_rect.png (L-shape black decoration at top-left)
-        if self.top_left_rect_texture.is_none() {
-            let path = self.app_dir.join("resources/data/top_left_rect.png");
-            if let Ok(img) = image::open(&path) {
-                let rgba = img.to_rgba8();
-                let size = [rgba.width() as usize, rgba.height() as usize];
-                let pixels: Vec<Color32> = rgba
-                    .pixels()
-                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-                    .collect();
-                let color_image = egui::ColorImage { size, pixels };
-                self.top_left_rect_texture = Some(ctx.load_texture(
-                    "top_left_rect",
-                    color_image,
-                    egui::TextureOptions::LINEAR,
-                ));
-                info!("Loaded top_left_rect.png: {}", path.display());
-            } else {
-                warn!("Failed to load top_left_rect.png: {}", path.display());
+            IpcMessage::LoadConfigSlot { slot, config, base_dir } => {
+                let reply = match self.load_config_slot(slot, *config, PathBuf::from(base_dir)) {
+                    None => IpcMessage::SlotLoaded { slot },
+                    Some(message) => IpcMessage::error(error_codes::VIDEO_LOAD_FAILED, message),
+                };
+                if let Some(ref tx) = self.ipc_tx {
+                    tx.reply(&client, reply);
+                }
             }
-        }
-
-        // Load top_left_rhodes.png (Rhodes decoration below L-shape)
-        if self.top_left_rhodes_texture.is_none() {
-            let path = self.app_dir.join("resources/data/top_left_rhodes.png");
-            if let Ok(img) = image::open(&path) {
-                let rgba = img.to_rgba8();
-                let size = [rgba.width() as usize, rgba.height() as usize];
-                let pixels: Vec<Color32> = rgba
-                    .pixels()
-                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-                    .collect();
-                let color_image = egui::ColorImage { size, pixels };
-                self.top_left_rhodes_texture = Some(ctx.load_texture(
-                    "top_left_rhodes",
-                    color_image,
-                    egui::TextureOptions::LINEAR,
-                ));
-                info!("Loaded top_left_rhodes.png: {}", path.display());
-            } else {
-                warn!("Failed to load top_left_rhodes.png: {}", path.display());
+            IpcMessage::SwitchSlot { slot } => {
+                if let Err(e) = self.switch_slot(slot) {
+                    warn!("Failed to switch slot: {}", e);
+                    if let Some(ref tx) = self.ipc_tx {
+                        tx.reply(&client, IpcMessage::error(error_codes::INVALID_REQUEST, e.to_string()));
+                    }
+                }
             }
-        }
-
-        // Load top_right_bar.png (yellow bar + full vertical bar on right)
-        if self.top_right_bar_texture.is_none() {
-            let path = self.app_dir.join("resources/data/top_right_bar.png");
-            if let Ok(img) = image::open(&path) {
-                let rgba = img.to_rgba8();
-                let size = [rgba.width() as usize, rgba.height() as usize];
-                let pixels: Vec<Color32> = rgba
-                    .pixels()
-                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-                    .collect();
-                let color_image = egui::ColorImage { size, pixels };
-                self.top_right_bar_texture = Some(ctx.load_texture(
-                    "top_right_bar",
-                    color_image,
-                    egui::TextureOptions::LINEAR,
-                ));
-                info!("Loaded top_right_bar.png: {}", path.display());
-            } else {
-                warn!("Failed to load top_right_bar.png: {}", path.display());
+            IpcMessage::OpenPackage { path } => match EPConfig::load_package(&path) {
+                Ok((config, base_dir)) => self.load_config(config, base_dir),
+                Err(e) => {
+                    warn!("Failed to open package: {}", e);
+                    if let Some(ref tx) = self.ipc_tx {
+                        tx.reply(&client, IpcMessage::error(error_codes::INVALID_CONFIG, e.to_string()));
+                    }
+                }
+            },
+            IpcMessage::SaveConfig { path } => {
+                let reply = match self.save_config(&path) {
+                    Ok(saved) => IpcMessage::ConfigSaved { path: saved.to_string_lossy().into_owned() },
+                    Err(e) => {
+                        warn!("Failed to save config: {}", e);
+                        IpcMessage::error(error_codes::SAVE_FAILED, e.to_string())
+                    }
+                };
+                if let Some(ref tx) = self.ipc_tx {
+                    tx.reply(&client, reply);
+                }
             }
-        }
-
-        // Load btm_left_bar.png (colorful gradient bar on left side)
-        if self.btm_left_bar_texture.is_none() {
-            let path = self.app_dir.join("resources/data/btm_left_bar.png");
-            if let Ok(img) = image::open(&path) {
-                let rgba = img.to_rgba8();
-                let size = [rgba.width() as usize, rgba.height() as usize];
-                let pixels: Vec<Color32> = rgba
-                    .pixels()
-                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-                    .collect();
-                let color_image = egui::ColorImage { size, pixels };
-                self.btm_left_bar_texture = Some(ctx.load_texture(
-                    "btm_left_bar",
-                    color_image,
-                    egui::TextureOptions::LINEAR,
-                ));
-                info!("Loaded btm_left_bar.png: {}", path.display());
-            } else {
-                warn!("Failed to load btm_left_bar.png: {}", path.display());
-            }
-        }
-
-        // Load image overlay texture if type is Image
-        if let Some(image_opts) = self.get_image_overlay_options() {
-            if !image_opts.image.is_empty() && self.image_overlay_texture.is_none() {
-                let image_path = self.image_loader.resolve_path(&image_opts.image);
-                if let Ok(img) = image::open(&image_path) {
-                    let rgba = img.to_rgba8();
-                    let size = [rgba.width() as usize, rgba.height() as usize];
-                    let pixels: Vec<Color32> = rgba
-                        .pixels()
-                        .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-                        .collect();
-                    let color_image = egui::ColorImage { size, pixels };
-                    self.image_overlay_texture = Some(ctx.load_texture(
-                        "image_overlay",
-                        color_image,
-                        egui::TextureOptions::LINEAR,
-                    ));
-                    info!("Loaded image overlay: {}", image_path.display());
-                } else {
-                    warn!("Failed to load image overlay: {}", image_path.display());
+            IpcMessage::ExportPackage { path } => {
+                let reply = match self.export_package(&path) {
+                    Ok(exported) => IpcMessage::PackageExported { path: exported.to_string_lossy().into_owned() },
+                    Err(e) => {
+                        warn!("Failed to export package: {}", e);
+                        IpcMessage::error(error_codes::SAVE_FAILED, e.to_string())
+                    }
+                };
+                if let Some(ref tx) = self.ipc_tx {
+                    tx.reply(&client, reply);
                 }
             }
-        }
-
-        // Load transition image texture if specified in transition_in or transition_loop
-        if self.transition_image_texture.is_none() {
-            // Check transition_in first, then transition_loop
-            let image_path = self.get_transition_options(true)
-                .filter(|opts| !opts.image.is_empty())
-                .map(|opts| opts.image.clone())
-                .or_else(|| {
-                    self.get_transition_options(false)
-                        .filter(|opts| !opts.image.is_empty())
-                        .map(|opts| opts.image.clone())
-                });
-
-            if let Some(image_file) = image_path {
-                let resolved_path = self.image_loader.resolve_path(&image_file);
-                if let Ok(img) = image::open(&resolved_path) {
-                    let rgba = img.to_rgba8();
-                    let img_width = rgba.width() as usize;
-                    let img_height = rgba.height() as usize;
-                    let size = [img_width, img_height];
-                    let pixels: Vec<Color32> = rgba
-                        .pixels()
-                        .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-                        .collect();
-
-                    // Store raw pixel data for direct access during transition
-                    self.transition_image_data = Some((pixels.clone(), img_width, img_height));
-
-                    let color_image = egui::ColorImage { size, pixels };
-                    self.transition_image_texture = Some(ctx.load_texture(
-                        "transition_image",
-                        color_image,
-                        egui::TextureOptions::LINEAR,
-                    ));
-                    info!("Loaded transition image: {}", resolved_path.display());
+            IpcMessage::ConvertLegacy { config } => {
+                let reply = match EPConfig::from_legacy(&config) {
+                    Ok(converted) => IpcMessage::LegacyConverted { config: Box::new(converted) },
+                    Err(e) => {
+                        warn!("Failed to convert legacy config: {}", e);
+                        IpcMessage::error(error_codes::INVALID_CONFIG, e.to_string())
+                    }
+                };
+                if let Some(ref tx) = self.ipc_tx {
+                    tx.reply(&client, reply);
+                }
+            }
+            IpcMessage::GetSchema => {
+                if let Some(ref tx) = self.ipc_tx {
+                    tx.reply(&client, IpcMessage::Schema { schema: EPConfig::schema_json() });
+                }
+            }
+            IpcMessage::CreateTemplate { dir } => {
+                let reply = match write_template(&self.sim.base_dir.join(dir)) {
+                    Ok(path) => IpcMessage::TemplateCreated { path: path.to_string_lossy().into_owned() },
+                    Err(e) => {
+                        warn!("Failed to create template: {}", e);
+                        IpcMessage::error(error_codes::SAVE_FAILED, e.to_string())
+                    }
+                };
+                if let Some(ref tx) = self.ipc_tx {
+                    tx.reply(&client, reply);
+                }
+            }
+            IpcMessage::UpdateOverlay { patch } => {
+                if let Err(e) = self.update_overlay(&patch) {
+                    warn!("Failed to update overlay: {}", e);
+                    if let Some(ref tx) = self.ipc_tx {
+                        tx.reply(&client, IpcMessage::error(error_codes::INVALID_CONFIG, e.to_string()));
+                    }
+                }
+            }
+            IpcMessage::Control(cmd) => match cmd {
+                ControlCommand::Play => {
+                    if self.sim.state.play_state == PlayState::Idle {
+                        self.sim.start_playback();
+                    } else {
+                        self.sim.state.resume();
+                    }
+                }
+                ControlCommand::Pause => {
+                    self.sim.state.pause();
+                    self.sim.frame_dirty = true;
+                }
+                ControlCommand::Stop | ControlCommand::Reset => {
+                    self.sim.reset_playback();
+                }
+                ControlCommand::SeekTo(state) => {
+                    // Seek to specific state
+                    if let Some(play_state) = PlayState::from_u8(state) {
+                        self.sim.state.play_state = play_state;
+                    }
+                }
+                ControlCommand::StepFrame(frames) => {
+                    self.step_frames(frames);
+                }
+                ControlCommand::SetSpeed(speed) => {
+                    self.sim.set_playback_speed(speed);
+                    info!("Playback speed: {}x", self.sim.playback_speed);
+                }
+            },
+            IpcMessage::SetTransition { transition_in, transition_loop } => {
+                self.sim.selected_transition_in = match transition_in.as_str() {
+                    "fade" => 0,
+                    "move" => 1,
+                    "swipe" => 2,
+                    _ => 3,
+                };
+                self.sim.selected_transition_loop = match transition_loop.as_str() {
+                    "fade" => 0,
+                    "move" => 1,
+                    "swipe" => 2,
+                    _ => 3,
+                };
+            }
+            IpcMessage::SetCropbox { cropbox } => match cropbox {
+                Some((_, _, w, h)) if w == 0 || h == 0 => {
+                    if let Some(ref tx) = self.ipc_tx {
+                        tx.reply(&client, IpcMessage::error(error_codes::INVALID_REQUEST, "Cropbox must not be empty"));
+                    }
+                }
+                _ => self.set_loop_transform(cropbox, self.sim.video_player.loop_rotation()),
+            },
+            IpcMessage::SetRotation { rotation } => {
+                self.set_loop_transform(self.sim.video_player.loop_cropbox(), rotation);
+            }
+            IpcMessage::SetStrictValidation { enabled } => {
+                self.set_strict_validation(enabled);
+            }
+            IpcMessage::SetStateUpdates { rate } => {
+                if rate == StateUpdateRate::EveryFrames(0) {
+                    if let Some(ref tx) = self.ipc_tx {
+                        tx.reply(&client, IpcMessage::error(error_codes::INVALID_REQUEST, "every_frames must be at least 1"));
+                    }
                 } else {
-                    warn!("Failed to load transition image: {}", resolved_path.display());
+                    info!("State updates: {:?}", rate);
+                    self.state_update_rate = rate;
                 }
             }
-        }
-
-        // Load Arknights-specific textures
-        let options = match self.get_arknights_options() {
-            Some(opts) => opts,
-            None => {
-                self.textures_loaded = true;
-                return;
+            IpcMessage::SetWindowSize { width, height } => {
+                if width.is_finite() && height.is_finite() && width > 0.0 && height > 0.0 {
+                    // An explicit size wins over following the config's screen
+                    self.pending_window_width = None;
+                    self.pending_viewport_commands.push(egui::ViewportCommand::InnerSize(Vec2::new(width, height)));
+                } else if let Some(ref tx) = self.ipc_tx {
+                    tx.reply(&client, IpcMessage::error(error_codes::INVALID_REQUEST, format!("Invalid window size {}x{}", width, height)));
+                }
             }
-        };
-
-        // Generate barcode texture from barcode_text (with gradient colors)
-        if !options.barcode_text.is_empty() && self.barcode_texture.is_none() {
-            let barcode_width = self.firmware_config.layout.barcode.width;
-            // Use gradient colors for barcode (purple → blue → cyan → yellow)
-            if let Some(barcode_image) = generate_vertical_barcode_gradient(&options.barcode_text, barcode_width, true) {
-                self.barcode_texture = Some(ctx.load_texture(
-                    "barcode",
-                    barcode_image,
-                    egui::TextureOptions::NEAREST,
-                ));
-                info!("Generated gradient barcode texture");
-            }
-        }
-
-        // Load class icon texture
-        if !options.operator_class_icon.is_empty() && self.class_icon_texture.is_none() {
-            let icon_path = self.image_loader.resolve_path(&options.operator_class_icon);
-            if let Ok(img) = image::open(&icon_path) {
-                let size = [img.width() as usize, img.height() as usize];
-                let pixels: Vec<Color32> = img
-                    .to_rgba8()
-                    .pixels()
-                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-                    .collect();
-                let color_image = egui::ColorImage { size, pixels };
-                self.class_icon_texture = Some(ctx.load_texture(
-                    "class_icon",
-                    color_image,
-                    egui::TextureOptions::LINEAR,
-                ));
-                info!("Loaded class icon: {}", icon_path.display());
-            } else {
-                warn!("Failed to load class icon: {}", icon_path.display());
+            IpcMessage::SetWindowPosition { x, y } => {
+                if x.is_finite() && y.is_finite() {
+                    self.pending_viewport_commands.push(egui::ViewportCommand::OuterPosition(Pos2::new(x, y)));
+                } else if let Some(ref tx) = self.ipc_tx {
+                    tx.reply(&client, IpcMessage::error(error_codes::INVALID_REQUEST, "Invalid window position"));
+                }
             }
-        }
-
-        // Load logo texture
-        if !options.logo.is_empty() && self.logo_texture.is_none() {
-            let logo_path = self.image_loader.resolve_path(&options.logo);
-            if let Ok(img) = image::open(&logo_path) {
-                let size = [img.width() as usize, img.height() as usize];
-                let pixels: Vec<Color32> = img
-                    .to_rgba8()
-                    .pixels()
-                    .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
-                    .collect();
-                let color_image = egui::ColorImage { size, pixels };
-                self.logo_texture = Some(ctx.load_texture(
-                    "logo",
-                    color_image,
-                    egui::TextureOptions::LINEAR,
-                ));
-                info!("Loaded logo: {}", logo_path.display());
-            } else {
-                warn!("Failed to load logo: {}", logo_path.display());
+            IpcMessage::SetAlwaysOnTop { enabled } => self.set_always_on_top(enabled),
+            IpcMessage::SetSerial { serial } => self.set_serial(serial),
+            IpcMessage::SetStatusBar(status_bar) => {
+                self.status_bar = StatusBar { battery: status_bar.battery.min(100), ..status_bar };
             }
-        }
-
-        self.textures_loaded = true;
-    }
-
-    /// Render complete overlay UI using egui Painter
-    fn render_overlay_ui(&mut self, painter: &egui::Painter, image_rect: Rect) {
-        let anim = &self.state.animation;
-        let options = match self.get_arknights_options() {
-            Some(opts) => opts,
-            None => return,
-        };
-
-        // Calculate scaling factor (image might be scaled)
-        let fw_width = self.firmware_config.overlay_width() as f32;
-        let fw_height = self.firmware_config.overlay_height() as f32;
-        let scale_x = image_rect.width() / fw_width;
-        let scale_y = image_rect.height() / fw_height;
-
-        // Calculate Y offset for entry animation
-        let y_offset = anim.entry_y_offset as f32 * scale_y;
-
-        // Get layout offsets
-        let offsets = &self.firmware_config.layout.offsets;
-        let btm_info_x = offsets.btm_info_x as f32 * scale_x + image_rect.min.x;
-        let theme_color = self.get_theme_color();
-        let entry_alpha = (anim.entry_progress * 255.0) as u8;
-
-        // ============================================
-        // 1. Render modular static decorations
-        // ============================================
-        self.render_modular_decorations(painter, image_rect, scale_x, scale_y, y_offset, entry_alpha, &options);
-
-        // ============================================
-        // 2. Render dynamic elements
-        // ============================================
-
-        // Arrow indicator (3 yellow chevrons pointing upward with scrolling animation)
-        self.render_arrow_indicator(painter, image_rect, scale_x, scale_y, y_offset, theme_color);
-
-        // Typewriter texts (operator name, code, staff_text, etc.)
-        self.render_typewriter_texts(painter, image_rect, scale_x, scale_y, y_offset, &options, theme_color);
-
-        // EINK areas (barcode with gradient, class icon)
-        self.render_eink_areas(painter, image_rect, scale_x, scale_y, y_offset);
-
-        // Divider lines (white color per C reference)
-        self.render_divider_lines(painter, image_rect, scale_x, scale_y, y_offset, btm_info_x, theme_color);
-
-        // Progress bar (AK bar)
-        self.render_progress_bar(painter, image_rect, scale_x, scale_y, y_offset, btm_info_x, theme_color);
-
-        // Logo image (dynamic fade-in)
-        self.render_logo_image(painter, image_rect, scale_x, scale_y, y_offset);
-    }
-
-    /// Render modular static decorations (replaces overlay_template.png)
-    ///
-    /// Positions are based on hardware implementation (opinfo.c):
-    /// - top_left_rhodes: (0, 0) - left upper corner origin
-    /// - top_left_rect: (60, 0) - L-shape black decoration offset from left
-    /// - top_right_bar: (360-width, 0) - right-aligned
-    /// - btm_left_bar: (0, 640-height) - bottom-aligned
-    fn render_modular_decorations(
-        &mut self,
-        painter: &egui::Painter,
-        image_rect: Rect,
-        scale_x: f32,
-        scale_y: f32,
-        y_offset: f32,
-        entry_alpha: u8,
-        options: &ArknightsOverlayOptions,
-    ) {
-        let tint = Color32::from_rgba_unmultiplied(255, 255, 255, entry_alpha);
-        let uv_full = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
-        let fw_height = 640.0; // Firmware screen height
-
-        // 1. top_left_rhodes - custom text or default image
-        if !options.top_left_rhodes.is_empty() {
-            // Custom text mode: render rotated text replacing default Rhodes logo
-            // Per firmware opinfo.c:687-693: rect=(0, 5, 67, OPNAME_Y-5=410)
-            if self.cached_rhodes_text != options.top_left_rhodes {
-                let img = render_text_rotated_90(
-                    &options.top_left_rhodes,
-                    48.0, // Font size (scaled down from firmware's 72px for display)
-                    Color32::WHITE,
-                    false,
-                );
-                self.top_left_rhodes_text_texture = Some(
-                    painter.ctx().load_texture("rhodes_text", img, egui::TextureOptions::LINEAR)
-                );
-                self.cached_rhodes_text = options.top_left_rhodes.clone();
-            }
-            if let Some(ref tex) = self.top_left_rhodes_text_texture {
-                let tex_w = tex.size()[0] as f32;
-                let tex_h = tex.size()[1] as f32;
-                // Position at (0, 5), constrain to area 67x410
-                let max_w = 67.0;
-                let max_h = 410.0;
-                let display_w = tex_w.min(max_w);
-                let display_h = tex_h.min(max_h);
-                let rect = Rect::from_min_size(
-                    Pos2::new(image_rect.min.x, image_rect.min.y + 5.0 * scale_y + y_offset),
-                    egui::vec2(display_w * scale_x, display_h * scale_y),
-                );
-                painter.image(tex.id(), rect, uv_full, tint);
+            IpcMessage::SetPresentationMode { enabled, fullscreen } => {
+                self.set_presentation_mode(enabled, fullscreen);
             }
-        } else {
-            // Default: use top_left_rhodes.png image
-            if let Some(ref tex) = self.top_left_rhodes_texture {
-                let tex_w = tex.size()[0] as f32;
-                let tex_h = tex.size()[1] as f32;
-                let rect = Rect::from_min_size(
-                    Pos2::new(image_rect.min.x, image_rect.min.y + y_offset),
-                    egui::vec2(tex_w * scale_x, tex_h * scale_y),
-                );
-                painter.image(tex.id(), rect, uv_full, tint);
+            IpcMessage::GetFrame { state, frame } => match PlayState::from_u8(state) {
+                Some(play_state) => self.frame_requests.push_back((client, play_state, frame)),
+                None => {
+                    if let Some(ref tx) = self.ipc_tx {
+                        tx.reply(&client, IpcMessage::error(error_codes::INVALID_REQUEST, format!("Unknown state {}", state)));
+                    }
+                }
+            },
+            IpcMessage::RenderSequence { start_us, end_us, fps, format, dir } => {
+                if let Err(e) = self.start_sequence_render(client.clone(), start_us, end_us, fps, format, dir) {
+                    warn!("Sequence render rejected: {}", e);
+                    if let Some(ref tx) = self.ipc_tx {
+                        tx.reply(&client, IpcMessage::error(error_codes::INVALID_REQUEST, e.to_string()));
+                    }
+                }
             }
-        }
-
-        // 2. top_left_rect - L-shape black decoration, positioned right after top_left_rhodes
-        if let Some(ref tex) = self.top_left_rect_texture {
-            let tex_w = tex.size()[0] as f32;
-            let tex_h = tex.size()[1] as f32;
-
-            // Use actual rhodes texture width for positioning
-            let rhodes_width = if !options.top_left_rhodes.is_empty() {
-                // When using custom text, use the text texture width
-                self.top_left_rhodes_text_texture
-                    .as_ref()
-                    .map(|t| (t.size()[0] as f32).min(67.0))
-                    .unwrap_or(60.0)
-            } else {
-                self.top_left_rhodes_texture
-                    .as_ref()
-                    .map(|t| t.size()[0] as f32)
-                    .unwrap_or(60.0)
-            };
-
-            let rect = Rect::from_min_size(
-                Pos2::new(
-                    image_rect.min.x + rhodes_width * scale_x,
-                    image_rect.min.y + y_offset,
-                ),
-                egui::vec2(tex_w * scale_x, tex_h * scale_y),
-            );
-            painter.image(tex.id(), rect, uv_full, tint);
-        }
-
-        // 3. top_right_bar (360-width, 0) - right-aligned
-        if let Some(ref tex) = self.top_right_bar_texture {
-            let tex_w = tex.size()[0] as f32;
-            let tex_h = tex.size()[1] as f32;
-            let bar_x = image_rect.max.x - tex_w * scale_x;
-            let rect = Rect::from_min_size(
-                Pos2::new(bar_x, image_rect.min.y + y_offset),
-                egui::vec2(tex_w * scale_x, tex_h * scale_y),
-            );
-            painter.image(tex.id(), rect, uv_full, tint);
-
-            // Custom top_right_bar_text: overlay on top of bar image
-            if !options.top_right_bar_text.is_empty() {
-                // Per firmware opinfo.c:643-683:
-                // 1. Black rect to cover embedded text at (bar_x+42, 314, 10, 102)
-                let cover_x = bar_x + 42.0 * scale_x;
-                let cover_y = image_rect.min.y + 314.0 * scale_y + y_offset;
-                let cover_rect = Rect::from_min_size(
-                    Pos2::new(cover_x, cover_y),
-                    egui::vec2(10.0 * scale_x, 102.0 * scale_y),
-                );
-                let black_tint = Color32::from_rgba_unmultiplied(0, 0, 0, entry_alpha);
-                painter.rect_filled(cover_rect, 0.0, black_tint);
-
-                // 2. Render custom text (split at space: bold + regular)
-                if self.cached_top_right_bar_text != options.top_right_bar_text {
-                    let img = render_top_right_bar_text_rotated(
-                        &options.top_right_bar_text,
-                        10.0,
-                        Color32::WHITE,
-                    );
-                    self.top_right_bar_text_texture = Some(
-                        painter.ctx().load_texture("top_right_bar_text", img, egui::TextureOptions::LINEAR)
-                    );
-                    self.cached_top_right_bar_text = options.top_right_bar_text.clone();
+            IpcMessage::Screenshot { path } => {
+                let target = path.map_or(ScreenshotTarget::Reply, |path| ScreenshotTarget::File(PathBuf::from(path)));
+                self.screenshot_requests.push((Some(client), target));
+            }
+            IpcMessage::CopyScreenshot => {
+                self.screenshot_requests.push((Some(client), ScreenshotTarget::Clipboard));
+            }
+            IpcMessage::StreamFrames { fps, format } => {
+                info!("Frame stream: {} fps ({:?})", fps, format);
+                self.frame_stream = FrameStream::new(fps, format).map(|stream| (client, stream));
+            }
+            IpcMessage::GetState => {
+                if let Some(ref tx) = self.ipc_tx {
+                    tx.reply(&client, IpcMessage::State {
+                        state: self.sim.state.play_state as u8,
+                        state_name: self.sim.state.play_state.display_name().to_string(),
+                        frame: self.sim.state.frame_counter,
+                        is_playing: self.sim.state.is_playing,
+                        speed: self.sim.playback_speed,
+                    });
                 }
-                if let Some(ref text_tex) = self.top_right_bar_text_texture {
-                    let text_w = text_tex.size()[0] as f32;
-                    let text_h = text_tex.size()[1] as f32;
-                    // Constrain to the covered area
-                    let display_w = text_w.min(10.0);
-                    let display_h = text_h.min(102.0);
-                    let text_rect = Rect::from_min_size(
-                        Pos2::new(cover_x, cover_y),
-                        egui::vec2(display_w * scale_x, display_h * scale_y),
-                    );
-                    painter.image(text_tex.id(), text_rect, uv_full, tint);
+            }
+            IpcMessage::GetConfig => {
+                if let Some(ref tx) = self.ipc_tx {
+                    tx.reply(&client, IpcMessage::Config {
+                        config: self.sim.epconfig.clone().map(Box::new),
+                        base_dir: self.sim.base_dir.to_string_lossy().to_string(),
+                        firmware: Box::new(self.sim.firmware_config().clone()),
+                    });
                 }
             }
-        }
-
-        // 4. btm_left_bar (0, 640-height) - bottom-aligned
-        if let Some(ref tex) = self.btm_left_bar_texture {
-            let tex_w = tex.size()[0] as f32;
-            let tex_h = tex.size()[1] as f32;
-            let rect = Rect::from_min_size(
-                Pos2::new(
-                    image_rect.min.x,
-                    image_rect.min.y + (fw_height - tex_h) * scale_y + y_offset,
-                ),
-                egui::vec2(tex_w * scale_x, tex_h * scale_y),
-            );
-            painter.image(tex.id(), rect, uv_full, tint);
+            IpcMessage::GetCapabilities => {
+                if let Some(ref tx) = self.ipc_tx {
+                    tx.reply(&client, IpcMessage::capabilities());
+                }
+            }
+            IpcMessage::Shutdown => {
+                info!("Received shutdown command");
+                self.begin_shutdown(client);
+            }
+            IpcMessage::Batch(messages) => {
+                // Replies to batched requests carry the batch's id
+                let mut messages = messages.into_iter();
+                while let Some(msg) = messages.next() {
+                    let deferred = self.deferred_requests.len();
+                    self.handle_ipc_message(client.clone(), msg);
+                    if self.video_load.is_some() {
+                        // The rest waits for the videos too, after what a nested batch left
+                        let rest: Vec<IpcMessage> = messages.collect();
+                        if !rest.is_empty() {
+                            let nested = self.deferred_requests.len() - deferred;
+                            self.deferred_requests.insert(nested, (client, IpcMessage::Batch(rest)));
+                        }
+                        break;
+                    }
+                }
+            }
+            _ => {}
         }
     }
 
-    /// Render image overlay (for OverlayType::Image)
-    fn render_image_overlay(&self, painter: &egui::Painter, image_rect: Rect) {
-        // Get image overlay options
-        let options = match self.get_image_overlay_options() {
-            Some(opts) => opts,
-            None => return,
-        };
+    /// Drive playback from `clock` instead of the wall clock
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.last_frame_time = clock.now();
+        self.clock = clock;
+    }
 
-        // Calculate current time in microseconds since Loop state started
-        let fps = self.firmware_config.fps();
-        let current_time_us = (self.state.animation.frame_counter as i64 * 1_000_000) / fps as i64;
+    /// Keep the window above other windows, or let it be covered again
+    pub fn set_always_on_top(&mut self, enabled: bool) {
+        self.always_on_top = enabled;
+        let level = if enabled { egui::WindowLevel::AlwaysOnTop } else { egui::WindowLevel::Normal };
+        self.pending_viewport_commands.push(egui::ViewportCommand::WindowLevel(level));
+    }
 
-        // Check if we're within the display window
-        // appear_time: when overlay starts showing (relative to Loop state start)
-        // duration: how long to show the overlay (0 means show indefinitely)
-        let should_show = if options.duration > 0 {
-            current_time_us >= options.appear_time && current_time_us < options.appear_time + options.duration
-        } else {
-            // If duration is 0 or negative, show indefinitely after appear_time
-            current_time_us >= options.appear_time
-        };
+    /// Show only the preview (or everything again), fullscreen if asked
+    fn set_presentation_mode(&mut self, enabled: bool, fullscreen: bool) {
+        info!("Presentation mode: {}", enabled);
+        self.presentation_mode = enabled;
+        self.pending_viewport_commands.push(egui::ViewportCommand::Fullscreen(enabled && fullscreen));
+    }
 
-        if !should_show {
-            return;
+    /// Stop playback, release the videos and acknowledge a shutdown request
+    ///
+    /// The window closes once the IPC server has sent the acknowledgement
+    /// and stopped.
+    fn begin_shutdown(&mut self, client: ReplyTo) {
+        self.sim.reset_playback();
+        self.frame_stream = None;
+        self.frame_requests.clear();
+        self.sequence_render = None;
+        self.screenshot_requests.clear();
+        self.sim.video_player.unload();
+        if let Some(ref tx) = self.ipc_tx {
+            tx.reply(&client, IpcMessage::ShutdownAck);
         }
+        self.shutdown_deadline = Some(Instant::now() + SHUTDOWN_FLUSH_TIMEOUT);
+    }
 
-        // Draw the image overlay - use original size, don't stretch
-        if let Some(ref texture) = self.image_overlay_texture {
-            // Get texture original size
-            let tex_size = texture.size();
-            let img_width = tex_size[0] as f32;
-            let img_height = tex_size[1] as f32;
-
-            // Calculate scale factor (based on hardware resolution 360x640)
-            let scale_x = image_rect.width() / 360.0;
-            let scale_y = image_rect.height() / 640.0;
-
-            // Use uniform scale factor to maintain aspect ratio (consistent with C reference)
-            let uniform_scale = scale_x.min(scale_y);
-
-            // Calculate display size (original size × uniform scale)
-            let display_width = img_width * uniform_scale;
-            let display_height = img_height * uniform_scale;
-
-            // Position: start from top-left corner (0, 0) of image_rect
-            let overlay_rect = Rect::from_min_size(
-                image_rect.min, // top-left corner (0, 0)
-                egui::vec2(display_width, display_height),
+    /// Send state update via IPC
+    fn send_state_update(&mut self) {
+        if let Some(ref tx) = self.ipc_tx {
+            let msg = IpcMessage::state_update(
+                self.sim.state.play_state,
+                self.sim.state.frame_counter as u64,
+                self.sim.state.is_playing,
             );
-
-            let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
-            painter.image(texture.id(), overlay_rect, uv, Color32::WHITE);
+            tx.send(msg);
+            self.last_state_update = Some((self.sim.state.play_state, self.sim.state.is_playing));
         }
     }
 
-    /// Render typewriter effect texts
-    fn render_typewriter_texts(
-        &self,
-        painter: &egui::Painter,
-        image_rect: Rect,
-        scale_x: f32,
-        scale_y: f32,
-        y_offset: f32,
-        options: &ArknightsOverlayOptions,
-        theme_color: Color32,
-    ) {
-        let anim = &self.state.animation;
-        let offsets = &self.firmware_config.layout.offsets;
-        let btm_info_x = offsets.btm_info_x as f32 * scale_x + image_rect.min.x;
-
-        // Operator name (large white text)
-        if anim.name_chars > 0 {
-            let name: String = options.operator_name.chars().take(anim.name_chars).collect();
-            let y = offsets.opname_y as f32 * scale_y + image_rect.min.y + y_offset;
-
-            if y >= image_rect.min.y && y <= image_rect.max.y {
-                let pos = Pos2::new(btm_info_x, y);
-                painter.text(
-                    pos,
-                    Align2::LEFT_TOP,
-                    &name,
-                    FontId::proportional(32.0 * scale_y),
-                    Color32::WHITE,
-                );
-            }
-        }
-
-        // Operator code (theme color, smaller text)
-        if anim.code_chars > 0 {
-            let code: String = options.operator_code.chars().take(anim.code_chars).collect();
-            let y = offsets.opcode_y as f32 * scale_y + image_rect.min.y + y_offset;
-
-            if y >= image_rect.min.y && y <= image_rect.max.y {
-                let pos = Pos2::new(btm_info_x, y);
-                painter.text(
-                    pos,
-                    Align2::LEFT_TOP,
-                    &code,
-                    FontId::proportional(14.0 * scale_y),
-                    theme_color,
-                );
-            }
+    /// Send a state update if updates are on change only and the play
+    /// state or playing flag differs from the last one sent
+    fn send_state_update_on_change(&mut self) {
+        if self.state_update_rate != StateUpdateRate::OnChange || self.position_before_requests.is_some() {
+            return;
         }
-
-        // Staff text
-        if anim.staff_chars > 0 {
-            let staff: String = options.staff_text.chars().take(anim.staff_chars).collect();
-            let y = offsets.staff_text_y as f32 * scale_y + image_rect.min.y + y_offset;
-
-            if y >= image_rect.min.y && y <= image_rect.max.y {
-                let pos = Pos2::new(btm_info_x, y);
-                painter.text(
-                    pos,
-                    Align2::LEFT_TOP,
-                    &staff,
-                    FontId::proportional(12.0 * scale_y),
-                    Color32::WHITE,
-                );
-            }
+        if self.last_state_update != Some((self.sim.state.play_state, self.sim.state.is_playing)) {
+            self.send_state_update();
         }
+    }
 
-        // Auxiliary text (multiline)
-        if anim.aux_chars > 0 {
-            let aux: String = options.aux_text.chars().take(anim.aux_chars).collect();
-            let base_y = offsets.aux_text_y as f32 * scale_y + image_rect.min.y + y_offset;
-            let line_height = offsets.aux_text_line_height as f32 * scale_y;
-
-            for (i, line) in aux.lines().enumerate() {
-                let y = base_y + (i as f32 * line_height);
-
-                if y >= image_rect.min.y && y <= image_rect.max.y {
-                    let pos = Pos2::new(btm_info_x, y);
-                    painter.text(
-                        pos,
-                        Align2::LEFT_TOP,
-                        line,
-                        FontId::proportional(10.0 * scale_y),
-                        Color32::GRAY,
-                    );
-                }
-            }
+    /// Send an event to the clients subscribed to its category
+    fn emit_event(&self, event: Event) {
+        if let Some(ref tx) = self.ipc_tx {
+            tx.send(IpcMessage::Event(event));
         }
     }
 
-    /// Render EINK effect areas (barcode, class icon)
-    fn render_eink_areas(
-        &self,
-        painter: &egui::Painter,
-        image_rect: Rect,
-        scale_x: f32,
-        scale_y: f32,
-        y_offset: f32,
-    ) {
-        let anim = &self.state.animation;
-        let barcode_layout = &self.firmware_config.layout.barcode;
-        let class_icon_size = &self.firmware_config.layout.class_icon;
-        let offsets = &self.firmware_config.layout.offsets;
-
-        // Barcode area
-        let barcode_x = barcode_layout.x as f32 * scale_x + image_rect.min.x;
-        let barcode_y = barcode_layout.y as f32 * scale_y + image_rect.min.y + y_offset;
-        let barcode_w = barcode_layout.width as f32 * scale_x;
-        let barcode_h = barcode_layout.height as f32 * scale_y;
-
-        if barcode_y + barcode_h >= image_rect.min.y && barcode_y <= image_rect.max.y {
-            let barcode_rect = Rect::from_min_size(
-                Pos2::new(barcode_x, barcode_y),
-                egui::vec2(barcode_w, barcode_h),
-            );
-
-            match anim.barcode_state {
-                EinkState::FirstBlack | EinkState::SecondBlack => {
-                    painter.rect_filled(barcode_rect, 0.0, Color32::BLACK);
-                }
-                EinkState::FirstWhite | EinkState::SecondWhite => {
-                    painter.rect_filled(barcode_rect, 0.0, Color32::WHITE);
-                }
-                EinkState::Content => {
-                    // Draw real barcode texture if available
-                    if let Some(ref texture) = self.barcode_texture {
-                        let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
-                        painter.image(texture.id(), barcode_rect, uv, Color32::WHITE);
-                    } else {
-                        // Fallback to simplified barcode pattern
-                        self.render_barcode_pattern(painter, barcode_rect);
+    /// Send what happened during playback to event subscribers, state
+    /// update subscribers and the metrics
+    fn forward_events(&mut self) {
+        for event in self.sim.take_events() {
+            match event {
+                PlaybackEvent::Frame { frame } => {
+                    if let StateUpdateRate::EveryFrames(frames) = self.state_update_rate {
+                        if frame.is_multiple_of(u64::from(frames)) {
+                            self.send_state_update();
+                        }
                     }
                 }
-                EinkState::Idle => {}
-            }
-        }
-
-        // Class icon area
-        let btm_info_x = offsets.btm_info_x as f32 * scale_x + image_rect.min.x;
-        let classicon_x = btm_info_x;
-        let classicon_y = offsets.class_icon_y as f32 * scale_y + image_rect.min.y + y_offset;
-        let classicon_w = class_icon_size.width as f32 * scale_x;
-        let classicon_h = class_icon_size.height as f32 * scale_y;
-
-        if classicon_y + classicon_h >= image_rect.min.y && classicon_y <= image_rect.max.y {
-            let classicon_rect = Rect::from_min_size(
-                Pos2::new(classicon_x, classicon_y),
-                egui::vec2(classicon_w, classicon_h),
-            );
-
-            match anim.classicon_state {
-                EinkState::FirstBlack | EinkState::SecondBlack => {
-                    painter.rect_filled(classicon_rect, 0.0, Color32::BLACK);
-                }
-                EinkState::FirstWhite | EinkState::SecondWhite => {
-                    painter.rect_filled(classicon_rect, 0.0, Color32::WHITE);
+                PlaybackEvent::StateChanged { from, to, frame } => {
+                    self.emit_event(Event::StateChanged { from: from as u8, to: to as u8, frame });
                 }
-                EinkState::Content => {
-                    // Draw real class icon texture if available
-                    if let Some(ref texture) = self.class_icon_texture {
-                        let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
-                        painter.image(texture.id(), classicon_rect, uv, Color32::WHITE);
-                    } else {
-                        // Fallback to placeholder (X shape)
-                        painter.rect_stroke(classicon_rect, 0.0, Stroke::new(1.0, Color32::WHITE));
-                        let center = classicon_rect.center();
-                        let half = classicon_w.min(classicon_h) * 0.3;
-                        painter.line_segment(
-                            [Pos2::new(center.x - half, center.y - half), Pos2::new(center.x + half, center.y + half)],
-                            Stroke::new(2.0, Color32::WHITE),
-                        );
-                        painter.line_segment(
-                            [Pos2::new(center.x + half, center.y - half), Pos2::new(center.x - half, center.y + half)],
-                            Stroke::new(2.0, Color32::WHITE),
-                        );
-                    }
+                PlaybackEvent::TransitionStarted { state, transition, frames, frame } => {
+                    self.emit_event(Event::TransitionStarted { state: state as u8, transition, frames, frame });
                 }
-                EinkState::Idle => {}
+                PlaybackEvent::IntroEnded { frame } => self.emit_event(Event::IntroEnded { frame }),
+                PlaybackEvent::AnimationMilestone { milestone, frame } => {
+                    self.emit_event(Event::AnimationMilestone { milestone, frame });
+                }
+                PlaybackEvent::FrameDecoded { time } => self.metrics.record_decode(time),
+                PlaybackEvent::FramesDropped { frames } => self.metrics.record_dropped(frames),
             }
         }
     }
 
-    /// Render simplified barcode pattern
-    fn render_barcode_pattern(&self, painter: &egui::Painter, rect: Rect) {
-        // Draw a simplified barcode pattern (vertical stripes)
-        let stripe_count = 20;
-        let stripe_width = rect.width() / stripe_count as f32;
-
-        for i in 0..stripe_count {
-            // Alternate black and white stripes with some variation
-            if (i % 3 != 0) && (i % 5 != 2) {
-                let x = rect.min.x + i as f32 * stripe_width;
-                let stripe_rect = Rect::from_min_size(
-                    Pos2::new(x, rect.min.y),
-                    egui::vec2(stripe_width * 0.7, rect.height()),
-                );
-                painter.rect_filled(stripe_rect, 0.0, Color32::WHITE);
-            }
-        }
+    /// Cut the device-sized preview out of a window screenshot
+    fn crop_preview(&self, screenshot: &egui::ColorImage, pixels_per_point: f32) -> Option<image::RgbaImage> {
+        let width = self.sim.firmware_config().overlay_width();
+        let height = self.sim.firmware_config().overlay_height();
+        crop_screenshot(screenshot, self.preview_rect?, pixels_per_point, width, height)
     }
 
-    /// Render divider lines (upper and lower)
-    /// Note: C reference uses white (0xFFFFFFFF) for divider lines, not theme color
-    fn render_divider_lines(
-        &self,
-        painter: &egui::Painter,
-        image_rect: Rect,
-        scale_x: f32,
-        scale_y: f32,
-        y_offset: f32,
-        btm_info_x: f32,
-        _theme_color: Color32, // Unused - kept for API compatibility
-    ) {
-        let anim = &self.state.animation;
-        let offsets = &self.firmware_config.layout.offsets;
-
-        // Upper divider line (white per C reference: fbdraw_fill_rect(&fbdst, &dst_rect, 0xFFFFFFFF))
-        if anim.upper_line_width > 0 {
-            let y = offsets.upperline_y as f32 * scale_y + image_rect.min.y + y_offset;
-            let width = anim.upper_line_width as f32 * scale_x;
-
-            if y >= image_rect.min.y && y <= image_rect.max.y {
-                painter.line_segment(
-                    [Pos2::new(btm_info_x, y), Pos2::new(btm_info_x + width, y)],
-                    Stroke::new(1.0, Color32::WHITE),
-                );
+    /// Encode a captured preview and send it to a client
+    fn send_frame(&self, client: &ReplyTo, image: &image::RgbaImage, format: FrameFormat) {
+        let Some(ref tx) = self.ipc_tx else {
+            return;
+        };
+        match format.encode(image) {
+            Ok(data) => {
+                tx.reply(client, IpcMessage::Frame {
+                    width: image.width(),
+                    height: image.height(),
+                    format,
+                    data: Bytes(data),
+                    state: self.sim.state.play_state as u8,
+                    frame: self.sim.state.frame_counter,
+                });
             }
-        }
-
-        // Lower divider line (white per C reference)
-        if anim.lower_line_width > 0 {
-            let y = offsets.lowerline_y as f32 * scale_y + image_rect.min.y + y_offset;
-            let width = anim.lower_line_width as f32 * scale_x;
-
-            if y >= image_rect.min.y && y <= image_rect.max.y {
-                painter.line_segment(
-                    [Pos2::new(btm_info_x, y), Pos2::new(btm_info_x + width, y)],
-                    Stroke::new(1.0, Color32::WHITE),
-                );
+            Err(e) => {
+                warn!("Failed to encode preview frame: {}", e);
+                tx.reply(client, IpcMessage::error(error_codes::INTERNAL_ERROR, e.to_string()));
             }
         }
     }
 
-    /// Render progress bar (AK bar)
-    fn render_progress_bar(
-        &self,
-        painter: &egui::Painter,
-        image_rect: Rect,
-        scale_x: f32,
-        scale_y: f32,
-        y_offset: f32,
-        btm_info_x: f32,
-        theme_color: Color32,
-    ) {
-        let anim = &self.state.animation;
-        let offsets = &self.firmware_config.layout.offsets;
-
-        if anim.ak_bar_width == 0 {
+    /// Hand a window screenshot to whoever requested it
+    fn handle_screenshot(&mut self, screenshot: &egui::ColorImage, pixels_per_point: f32) {
+        self.screenshot_pending = false;
+        let image = self.crop_preview(screenshot, pixels_per_point);
+        if let (Some((client, stream)), Some(image)) = (&self.frame_stream, &image) {
+            self.send_frame(client, image, stream.format);
+        }
+
+        // A capture for a frame request shows that frame rather than the
+        // current one, so screenshot requests wait for the next capture
+        if let Some(client) = self.frame_request_in_flight.take() {
+            match image {
+                Some(ref image) => self.send_frame(&client, image, FrameFormat::Png),
+                None => {
+                    if let Some(ref tx) = self.ipc_tx {
+                        tx.reply(&client, IpcMessage::error(error_codes::INTERNAL_ERROR, "Preview is not visible"));
+                    }
+                }
+            }
             return;
         }
-
-        // Use uniform scale factor to preserve aspect ratio (avoid stretching)
-        let uniform_scale = scale_x.min(scale_y);
-
-        let y = offsets.ak_bar_y as f32 * scale_y + image_rect.min.y + y_offset;
-        let width = anim.ak_bar_width as f32 * uniform_scale;
-
-        if y < image_rect.min.y || y > image_rect.max.y {
+        if self.sequence_frame_in_flight {
+            self.sequence_frame_in_flight = false;
+            self.write_sequence_frame(image.as_ref());
             return;
         }
 
-        // Use AK bar image texture if available
-        if let Some(ref ak_bar_texture) = self.ak_bar_texture {
-            // Get actual texture dimensions
-            let tex_width = ak_bar_texture.size()[0] as f32;
-            let tex_height = ak_bar_texture.size()[1] as f32;
+        for (client, target) in std::mem::take(&mut self.screenshot_requests) {
+            let reply = match image {
+                Some(ref image) => self.save_screenshot(image, target).unwrap_or_else(|e| {
+                    warn!("Failed to save screenshot: {}", e);
+                    IpcMessage::error(error_codes::SAVE_FAILED, e.to_string())
+                }),
+                None => IpcMessage::error(error_codes::INTERNAL_ERROR, "Preview is not visible"),
+            };
+            if let (Some(ref tx), Some(client)) = (&self.ipc_tx, client) {
+                tx.reply(&client, reply);
+            }
+        }
+    }
 
-            // Calculate reveal ratio for sweep-in animation
-            let max_bar_width = 280.0;
-            let reveal_ratio = (anim.ak_bar_width as f32 / max_bar_width).min(1.0);
+    /// Deliver a screenshot to `target`, returning the reply for the requester
+    fn save_screenshot(&mut self, image: &image::RgbaImage, target: ScreenshotTarget) -> anyhow::Result<IpcMessage> {
+        match target {
+            ScreenshotTarget::File(path) => {
+                image.save_with_format(&path, image::ImageFormat::Png)?;
+                info!("Saved screenshot to {}", path.display());
+                Ok(IpcMessage::ScreenshotTaken {
+                    path: Some(path.to_string_lossy().to_string()),
+                    data: None,
+                })
+            }
+            ScreenshotTarget::Reply => Ok(IpcMessage::ScreenshotTaken {
+                path: None,
+                data: Some(Bytes(FrameFormat::Png.encode(image)?)),
+            }),
+            #[cfg(target_arch = "wasm32")]
+            ScreenshotTarget::Clipboard => anyhow::bail!("Copying images is not supported in the browser"),
+            #[cfg(not(target_arch = "wasm32"))]
+            ScreenshotTarget::Clipboard => {
+                let clipboard = match self.clipboard.take() {
+                    Some(clipboard) => clipboard,
+                    None => arboard::Clipboard::new()?,
+                };
+                self.clipboard.insert(clipboard).set_image(arboard::ImageData {
+                    width: image.width() as usize,
+                    height: image.height() as usize,
+                    bytes: image.as_raw().into(),
+                })?;
+                info!("Copied screenshot to the clipboard");
+                Ok(IpcMessage::ScreenshotCopied)
+            }
+        }
+    }
 
-            // Use original texture height, only scale for display
-            let displayed_width = tex_width * reveal_ratio * uniform_scale;
-            let displayed_height = tex_height * uniform_scale;
+    /// Replay from the start until `done` holds, see `Simulator::fast_forward`
+    fn fast_forward(&mut self, done: impl FnMut(&SimulatorState) -> bool) -> bool {
+        self.wait_for_videos();
+        let reached = self.sim.fast_forward(done);
+        self.last_frame_time = self.clock.now();
+        reached
+    }
 
-            let bar_rect = Rect::from_min_size(
-                Pos2::new(btm_info_x, y),
-                egui::vec2(displayed_width, displayed_height),
-            );
+    /// Replay from the start to `time_us` of playback
+    fn seek_to_time(&mut self, time_us: i64) {
+        self.wait_for_videos();
+        self.sim.seek_to_time(time_us);
+        self.last_frame_time = self.clock.now();
+    }
 
-            let uv = Rect::from_min_max(
-                Pos2::new(0.0, 0.0),
-                Pos2::new(reveal_ratio, 1.0),
-            );
+    /// Seek to logic frame `frame` of `play_state`, counted from entering it
+    fn seek_to_frame(&mut self, play_state: PlayState, frame: u64) -> anyhow::Result<()> {
+        self.wait_for_videos();
+        let result = self.sim.seek_to_frame(play_state, frame);
+        self.last_frame_time = self.clock.now();
+        result
+    }
 
-            painter.image(ak_bar_texture.id(), bar_rect, uv, Color32::WHITE);
+    /// Open a file dropped onto the window: a package, a config, or a video
+    /// shown in a minimal config
+    ///
+    /// With `new_tab` it opens next to the shown config instead of replacing it.
+    fn open_dropped_file(&mut self, path: &Path, new_tab: bool) {
+        info!("Opening dropped file {}", path.display());
+        let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        let loaded = if is_package(path) {
+            EPConfig::load_package(path)
+        } else if extension == "json" {
+            EPConfig::load_from_file(path).map(|config| (config, base_dir))
+        } else if DROPPED_VIDEO_EXTENSIONS.contains(&extension.as_str()) {
+            let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            config_for_video(&file_name).map(|config| (config, base_dir))
         } else {
-            // Fallback: solid color rectangle
-            let bar_height = 3.0 * scale_y;
-            if y + bar_height <= image_rect.max.y {
-                let bar_rect = Rect::from_min_size(
-                    Pos2::new(btm_info_x, y),
-                    egui::vec2(width, bar_height),
-                );
-                painter.rect_filled(bar_rect, 0.0, theme_color);
+            Err(anyhow::anyhow!("Unsupported file type"))
+        };
+        match loaded {
+            Ok((config, base_dir)) if new_tab => self.open_tab(config, base_dir),
+            Ok((config, base_dir)) => self.load_config(config, base_dir),
+            Err(e) => {
+                warn!("Failed to open {}: {}", path.display(), e);
+                self.error_message = Some(format!("无法打开拖入的文件\n路径: {}\n原因: {}", path.display(), e));
             }
         }
     }
 
-    /// Render arrow animation indicator (3 chevrons pointing UP on the right side)
-    /// Uses dark gray color to match C reference design
-    /// Per C reference (opinfo.c:553): arrows scroll upward via Y decrement
-    fn render_arrow_indicator(
-        &self,
-        painter: &egui::Painter,
-        image_rect: Rect,
-        scale_x: f32,
-        scale_y: f32,
-        y_offset: f32,
-        _theme_color: Color32,
-    ) {
-        let anim = &self.state.animation;
-
-        // Only show arrows when entry animation is complete
-        if !anim.is_entry_complete() {
-            return;
+    /// React to a swipe on the preview like the device does
+    ///
+    /// Sideways swipes switch to the next or previous material, which the
+    /// device then plays from the start; with a single material it replays.
+    /// Every swipe is reported to event subscribers.
+    fn handle_swipe(&mut self, direction: SwipeDirection) {
+        info!("Swipe {:?}", direction);
+        self.emit_event(Event::Swipe { direction, frame: self.sim.state.frame_counter });
+        let count = self.tabs.len();
+        match direction {
+            SwipeDirection::Left => self.switch_tab((self.active_tab + 1) % count),
+            SwipeDirection::Right => self.switch_tab((self.active_tab + count - 1) % count),
+            SwipeDirection::Up | SwipeDirection::Down => return,
+        }
+        if self.sim.epconfig.is_some() {
+            self.sim.reset_playback();
+            self.sim.start_playback();
+            self.send_state_update();
         }
+    }
 
-        // Use image texture if available
-        if let Some(ref arrow_texture) = self.top_right_arrow_texture {
-            // Arrow image dimensions: 24x100, positioned at Y=100 per opinfo.c reference
-            let arrow_width = 24.0 * scale_x;
-            let arrow_height = 100.0 * scale_y;
-            let arrow_x = image_rect.max.x - arrow_width;  // Right-aligned
-            let arrow_y = image_rect.min.y + 100.0 * scale_y + y_offset;  // Y=100
-
-            // UV scrolling to implement upward loop animation
-            // anim.arrow_y cycles 0-99, use it as scroll offset
-            let scroll_offset = (anim.arrow_y as f32 / 100.0).fract();
-            let uv = Rect::from_min_max(
-                Pos2::new(0.0, scroll_offset),
-                Pos2::new(1.0, scroll_offset + 1.0),
-            );
-
-            let arrow_rect = Rect::from_min_size(
-                Pos2::new(arrow_x, arrow_y),
-                egui::vec2(arrow_width, arrow_height),
-            );
-
-            painter.image(arrow_texture.id(), arrow_rect, uv, Color32::WHITE);
-        } else {
-            // Fallback: draw programmatic dark gray chevrons
-            let offsets = &self.firmware_config.layout.offsets;
-            let base_y = offsets.arrow_y as f32 * scale_y + image_rect.min.y + y_offset;
-            let arrow_offset = anim.arrow_y as f32 * scale_y;
-
-            // Position on the right side of the screen
-            let x = image_rect.max.x - 40.0 * scale_x;
-
-            // Draw 3 chevrons pointing UPWARD (^ shape)
-            let chevron_spacing = 12.0 * scale_y;
-            let chevron_size = 8.0 * scale_x;
-
-            // Use dark gray color for chevrons (matching C reference opinfo.c)
-            let chevron_color = Color32::from_rgb(40, 40, 40);
-            let stroke = Stroke::new(2.0 * scale_x.min(scale_y), chevron_color);
-
-            for i in 0..3 {
-                let y = base_y + arrow_offset + (i as f32 * chevron_spacing);
-
-                if y >= image_rect.min.y && y <= image_rect.max.y {
-                    // Draw chevron (^ shape pointing UP)
-                    let left = Pos2::new(x - chevron_size, y + chevron_size);
-                    let top = Pos2::new(x, y);
-                    let right = Pos2::new(x + chevron_size, y + chevron_size);
+    /// Act on the keys in `SHORTCUTS`; text fields keep their keys
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if self.presentation_mode && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.set_presentation_mode(false, false);
+        }
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let pressed = |key| ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, key));
 
-                    painter.line_segment([left, top], stroke);
-                    painter.line_segment([top, right], stroke);
-                }
+        if pressed(egui::Key::Space) {
+            self.sim.toggle_playback();
+        }
+        if pressed(egui::Key::R) {
+            self.sim.reset_playback();
+        }
+        if pressed(egui::Key::S) {
+            let millis = web_time::SystemTime::now()
+                .duration_since(web_time::UNIX_EPOCH)
+                .map_or(0, |since| since.as_millis());
+            let path = self.sim.base_dir.join(format!("screenshot-{}.png", millis));
+            self.screenshot_requests.push((None, ScreenshotTarget::File(path)));
+        }
+        if ctx.input(|i| i.events.iter().any(|e| matches!(e, egui::Event::Copy))) {
+            self.screenshot_requests.push((None, ScreenshotTarget::Clipboard));
+        }
+        if pressed(egui::Key::ArrowLeft) {
+            self.step_frames(-1);
+        }
+        if pressed(egui::Key::ArrowRight) {
+            self.step_frames(1);
+        }
+        for (key, transition) in [(egui::Key::Num1, 0), (egui::Key::Num2, 1), (egui::Key::Num3, 2)] {
+            if pressed(key) {
+                self.sim.selected_transition_in = transition;
+                self.sim.selected_transition_loop = transition;
             }
         }
+        if pressed(egui::Key::F1) {
+            self.show_shortcuts = !self.show_shortcuts;
+        }
     }
 
-    // ============================================
-    // Legacy static decoration rendering functions
-    // These functions are no longer used since we now use modular assets
-    // Kept for reference only
-    // ============================================
-
-    /// Render top-left black L-shape decoration
-    #[allow(dead_code)]
-    fn render_top_left_corner(
-        &self,
-        painter: &egui::Painter,
-        image_rect: Rect,
-        scale_x: f32,
-        scale_y: f32,
-        alpha: u8,
-    ) {
-        let black = Color32::from_rgba_unmultiplied(0, 0, 0, alpha);
-
-        // Horizontal bar: 95x25 pixels (original template precise value)
-        let h_rect = Rect::from_min_size(
-            image_rect.min,
-            egui::vec2(95.0 * scale_x, 25.0 * scale_y),
-        );
-        painter.rect_filled(h_rect, 0.0, black);
-
-        // Vertical bar: 25x105 pixels (original template precise value)
-        let v_rect = Rect::from_min_size(
-            image_rect.min,
-            egui::vec2(25.0 * scale_x, 105.0 * scale_y),
-        );
-        painter.rect_filled(v_rect, 0.0, black);
-
-        // White triangle cutout (at inner corner of L-shape)
-        let white = Color32::from_rgba_unmultiplied(255, 255, 255, alpha);
-        let triangle_size = 15.0;
-        let tri_x = image_rect.min.x + 25.0 * scale_x;
-        let tri_y = image_rect.min.y + 25.0 * scale_y;
-
-        // Triangle points: right-angle triangle pointing to bottom-right
-        let points = vec![
-            Pos2::new(tri_x, tri_y),
-            Pos2::new(tri_x + triangle_size * scale_x, tri_y),
-            Pos2::new(tri_x, tri_y + triangle_size * scale_y),
-        ];
-        painter.add(egui::Shape::convex_polygon(points, white, Stroke::NONE));
-    }
-
-    /// Render top-right black background with gold stripes and dark chevron arrows
-    #[allow(dead_code)]
-    fn render_top_right_gradient(
-        &self,
-        painter: &egui::Painter,
-        image_rect: Rect,
-        scale_x: f32,
-        scale_y: f32,
-        alpha: u8,
-    ) {
-        // 1. Black background rectangle (X=280-360, Y=0-145)
-        let bg_rect = Rect::from_min_max(
-            Pos2::new(image_rect.min.x + 280.0 * scale_x, image_rect.min.y),
-            Pos2::new(image_rect.max.x, image_rect.min.y + 145.0 * scale_y),
-        );
-        let black = Color32::from_rgba_unmultiplied(0, 0, 0, alpha);
-        painter.rect_filled(bg_rect, 0.0, black);
-
-        // 2. Gold yellow stripes (uniform color #FFD700, starting from right edge)
-        let gold = Color32::from_rgba_unmultiplied(255, 215, 0, alpha);
-
-        // Define stripes (Y offset, height, width) - stripes get shorter from top to bottom
-        let stripes: [(f32, f32, f32); 12] = [
-            (0.0, 6.0, 76.0),   // Top stripe - longest
-            (8.0, 8.0, 72.0),
-            (18.0, 10.0, 68.0),
-            (30.0, 8.0, 64.0),
-            (40.0, 12.0, 60.0),
-            (54.0, 10.0, 56.0),
-            (66.0, 8.0, 52.0),
-            (76.0, 10.0, 48.0),
-            (88.0, 8.0, 44.0),
-            (98.0, 6.0, 40.0),
-            (106.0, 6.0, 36.0),
-            (114.0, 4.0, 32.0), // Bottom stripe - shortest
-        ];
-
-        for (y_off, height, stripe_width) in stripes.iter() {
-            let y = image_rect.min.y + y_off * scale_y;
-            let x = image_rect.max.x - stripe_width * scale_x;
-
-            let rect = Rect::from_min_size(
-                Pos2::new(x, y),
-                egui::vec2(*stripe_width * scale_x, *height * scale_y),
-            );
-            painter.rect_filled(rect, 0.0, gold);
+    /// Replay from the start of `play_state`, continuing to play from there
+    fn jump_to_state(&mut self, play_state: PlayState) {
+        if let Err(e) = self.seek_to_frame(play_state, 0) {
+            warn!("Cannot jump to {}: {}", play_state.display_name(), e);
         }
+        self.sim.state.resume();
+        self.sim.frame_dirty = true;
+        self.send_state_update();
+    }
 
-        // 3. Dark/black chevron arrows (outline style)
-        let arrow_x = image_rect.min.x + 320.0 * scale_x;
-        let dark = Color32::from_rgba_unmultiplied(40, 40, 40, alpha); // Dark gray/black
-        let stroke = Stroke::new(2.5 * scale_x.min(scale_y), dark);
-        let chevron_size = 10.0;
+    /// Pause and move `frames` logic frames forward, or back if negative
+    ///
+    /// Rewinding replays from the start, as the simulation only runs forward.
+    fn step_frames(&mut self, frames: i32) {
+        if frames < 0 {
+            self.wait_for_videos();
+        }
+        self.sim.step_frames(frames);
+        self.last_frame_time = self.clock.now();
+        self.forward_events();
+        self.send_state_update();
+    }
 
-        for i in 0..3 {
-            let arrow_y = image_rect.min.y + (58.0 + i as f32 * 22.0) * scale_y;
-            // Upward chevron (^)
-            let left = Pos2::new(
-                arrow_x - chevron_size * scale_x,
-                arrow_y + chevron_size * scale_y,
-            );
-            let top = Pos2::new(arrow_x, arrow_y);
-            let right = Pos2::new(
-                arrow_x + chevron_size * scale_x,
-                arrow_y + chevron_size * scale_y,
-            );
+    /// Queue a sequence render of `start_us..end_us` for `client`
+    fn start_sequence_render(
+        &mut self,
+        client: ReplyTo,
+        start_us: i64,
+        end_us: i64,
+        fps: f32,
+        format: FrameFormat,
+        dir: Option<String>,
+    ) -> anyhow::Result<()> {
+        if self.sequence_render.is_some() {
+            anyhow::bail!("A sequence render is already running");
+        }
+        if self.sim.epconfig.is_none() {
+            anyhow::bail!("No configuration loaded");
+        }
+        let step_us = self.sim.firmware_config().animation.step_time_us as i64;
+        if end_us > MAX_SEEK_FRAMES as i64 * step_us {
+            anyhow::bail!("Sequence ends after the longest replay ({} frames)", MAX_SEEK_FRAMES);
+        }
+        let dir = match dir {
+            Some(dir) => self.sim.base_dir.join(dir),
+            None => {
+                let stamp = web_time::SystemTime::now()
+                    .duration_since(web_time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_millis());
+                std::env::temp_dir()
+                    .join("arknights_pass_simulator")
+                    .join(format!("render-{}", stamp))
+            }
+        };
+        let render = SequenceRender::new(start_us, end_us, fps, format, dir)?;
+        info!("Rendering {} frames to {}", render.frames(), render.dir.display());
+        self.sequence_render = Some((client, render));
+        Ok(())
+    }
 
-            painter.line_segment([left, top], stroke);
-            painter.line_segment([top, right], stroke);
-        }
+    /// Move playback to the next frame of the sequence render, returning
+    /// false if no render is running
+    fn process_sequence_render(&mut self) -> bool {
+        let Some((_, ref render)) = self.sequence_render else {
+            return false;
+        };
+        let index = render.next_index();
+        let time_us = render.frame_time_us(index);
+        let previous_us = index.checked_sub(1).map(|previous| render.frame_time_us(previous));
+
+        if self.position_before_requests.is_none() {
+            self.position_before_requests =
+                Some((self.sim.state.play_state, self.sim.state.frame_counter, self.sim.state.is_playing));
+        }
+        match previous_us {
+            Some(previous_us) => self.sim.advance_detached(time_us - previous_us),
+            None => self.seek_to_time(time_us),
+        }
+        // Hold the frame until the screenshot arrives
+        self.sim.state.pause();
+        self.sim.frame_dirty = true;
+        self.sequence_frame_in_flight = true;
+        true
     }
 
-    /// Render right side black vertical bar with "RHODES ISLAND INC." text
-    /// RHODES is yellow (#FFD700), ISLAND INC. is white
-    #[allow(dead_code)]
-    fn render_right_side_bar(
-        &self,
-        painter: &egui::Painter,
-        image_rect: Rect,
-        scale_x: f32,
-        scale_y: f32,
-        y_offset: f32,
-        alpha: u8,
-    ) {
-        // Black vertical bar: width 30px, from Y=145 to Y=600 (original template precise values)
-        let bar_width = 30.0 * scale_x;
-        let bar_x = image_rect.max.x - bar_width;
-        let bar_start_y = image_rect.min.y + 145.0 * scale_y + y_offset;
-        let bar_end_y = image_rect.min.y + 600.0 * scale_y + y_offset;
-
-        // Clamp to image bounds
-        let clamped_start = bar_start_y.max(image_rect.min.y);
-        let clamped_end = bar_end_y.min(image_rect.max.y);
-
-        if clamped_end > clamped_start {
-            let bar_rect = Rect::from_min_max(
-                Pos2::new(bar_x, clamped_start),
-                Pos2::new(image_rect.max.x, clamped_end),
-            );
-            let bar_color = Color32::from_rgba_unmultiplied(0, 0, 0, alpha);
-            painter.rect_filled(bar_rect, 0.0, bar_color);
-
-            // Text colors
-            let yellow = Color32::from_rgba_unmultiplied(255, 215, 0, alpha); // #FFD700 for RHODES
-            let white = Color32::from_rgba_unmultiplied(255, 255, 255, alpha);
-            let text_x = bar_x + bar_width / 2.0;
-            let text_start_y = clamped_start + 30.0 * scale_y;
-
-            // RHODES (yellow, bold - simulated with slightly larger font)
-            let rhodes = "RHODES";
-            for (i, ch) in rhodes.chars().enumerate() {
-                let char_y = text_start_y + i as f32 * 18.0 * scale_y;
-                if char_y < image_rect.max.y {
-                    painter.text(
-                        Pos2::new(text_x, char_y),
-                        Align2::CENTER_TOP,
-                        ch.to_string(),
-                        FontId::proportional(14.0 * scale_y), // Larger for bold effect
-                        yellow, // RHODES is yellow
-                    );
+    /// Write a captured sequence frame, replying once the sequence is complete
+    fn write_sequence_frame(&mut self, image: Option<&image::RgbaImage>) {
+        let Some((client, mut render)) = self.sequence_render.take() else {
+            return;
+        };
+        let result = match image {
+            Some(image) => render.write_frame(image),
+            None => Err(anyhow::anyhow!("Preview is not visible")),
+        };
+        let reply = match result {
+            Ok(()) if render.is_done() => {
+                info!("Rendered {} frames to {}", render.frames(), render.dir.display());
+                IpcMessage::SequenceRendered {
+                    path: render.dir.to_string_lossy().into_owned(),
+                    frames: render.frames(),
                 }
             }
+            Ok(()) => {
+                self.sequence_render = Some((client, render));
+                return;
+            }
+            Err(e) => {
+                warn!("Sequence render failed: {}", e);
+                IpcMessage::error(error_codes::SAVE_FAILED, e.to_string())
+            }
+        };
+        if let Some(ref tx) = self.ipc_tx {
+            tx.reply(&client, reply);
+        }
+    }
 
-            // ISLAND INC. (white, normal)
-            let island_inc = " ISLAND INC.";
-            let island_start_y = text_start_y + rhodes.len() as f32 * 18.0 * scale_y;
-            for (i, ch) in island_inc.chars().enumerate() {
-                let char_y = island_start_y + i as f32 * 14.0 * scale_y;
-                if char_y < image_rect.max.y {
-                    painter.text(
-                        Pos2::new(text_x, char_y),
-                        Align2::CENTER_TOP,
-                        ch.to_string(),
-                        FontId::proportional(10.0 * scale_y),
-                        white, // ISLAND INC. is white
-                    );
+    /// Render the next queued frame request or sequence frame, or restore
+    /// playback once all are done
+    fn process_frame_requests(&mut self) {
+        while let Some((client, play_state, frame)) = self.frame_requests.pop_front() {
+            if self.position_before_requests.is_none() {
+                self.position_before_requests =
+                    Some((self.sim.state.play_state, self.sim.state.frame_counter, self.sim.state.is_playing));
+            }
+            match self.seek_to_frame(play_state, frame) {
+                Ok(()) => {
+                    // Hold the frame until the screenshot arrives
+                    self.sim.state.pause();
+                    self.frame_request_in_flight = Some(client);
+                    return;
+                }
+                Err(e) => {
+                    warn!("Frame request failed: {}", e);
+                    if let Some(ref tx) = self.ipc_tx {
+                        tx.reply(&client, IpcMessage::error(error_codes::INVALID_REQUEST, e.to_string()));
+                    }
                 }
             }
         }
-    }
 
-    /// Render left colorful gradient stripe (yellow → orange → purple → blue → cyan)
-    #[allow(dead_code)]
-    fn render_left_gradient_stripe(
-        &self,
-        painter: &egui::Painter,
-        image_rect: Rect,
-        scale_x: f32,
-        scale_y: f32,
-        y_offset: f32,
-        alpha: u8,
-    ) {
-        // Position X=58, width 7px (original template precise values)
-        let stripe_x = image_rect.min.x + 58.0 * scale_x;
-        let stripe_width = 7.0 * scale_x;
-        let stripe_start_y = image_rect.min.y + 110.0 * scale_y + y_offset;
-        let stripe_end_y = image_rect.max.y;
-
-        // More refined gradient: yellow → orange → purple → blue → cyan
-        let segment_count = 30;
-        let total_height = stripe_end_y - stripe_start_y;
-        let segment_height = total_height / segment_count as f32;
-
-        for i in 0..segment_count {
-            let y = stripe_start_y + i as f32 * segment_height;
-            if y < image_rect.min.y || y > image_rect.max.y {
-                continue;
-            }
-
-            let t = i as f32 / segment_count as f32;
-            let (r, g, b) = if t < 0.15 {
-                // Yellow (255, 220, 50)
-                (255, 220, 50)
-            } else if t < 0.30 {
-                // Yellow → orange
-                let t2 = (t - 0.15) / 0.15;
-                (255, (220.0 - t2 * 80.0) as u8, (50.0 + t2 * 30.0) as u8)
-            } else if t < 0.50 {
-                // Orange → purple
-                let t2 = (t - 0.30) / 0.20;
-                (
-                    (255.0 - t2 * 100.0) as u8,
-                    (140.0 - t2 * 40.0) as u8,
-                    (80.0 + t2 * 120.0) as u8,
-                )
-            } else if t < 0.75 {
-                // Purple → blue
-                let t2 = (t - 0.50) / 0.25;
-                (
-                    (155.0 - t2 * 80.0) as u8,
-                    (100.0 + t2 * 80.0) as u8,
-                    (200.0 + t2 * 40.0) as u8,
-                )
-            } else {
-                // Blue → cyan
-                let t2 = (t - 0.75) / 0.25;
-                (
-                    (75.0 + t2 * 25.0) as u8,
-                    (180.0 + t2 * 40.0) as u8,
-                    (240.0 - t2 * 20.0) as u8,
-                )
-            };
+        if self.process_sequence_render() {
+            return;
+        }
 
-            let color = Color32::from_rgba_unmultiplied(r, g, b, alpha);
-            let rect = Rect::from_min_size(
-                Pos2::new(stripe_x, y),
-                egui::vec2(stripe_width, segment_height + 1.0), // +1 to avoid gaps
-            );
-            painter.rect_filled(rect, 0.0, color);
+        if let Some((play_state, frame_counter, is_playing)) = self.position_before_requests.take() {
+            if play_state == PlayState::Idle {
+                self.sim.reset_playback();
+            } else {
+                self.fast_forward(|state| state.frame_counter >= frame_counter);
+            }
+            self.sim.state.is_playing = is_playing;
         }
     }
 
-    /// Render bottom-left light cyan-blue gradient
-    #[allow(dead_code)]
-    fn render_bottom_left_gradient(
-        &self,
-        painter: &egui::Painter,
-        image_rect: Rect,
-        scale_x: f32,
-        scale_y: f32,
-        y_offset: f32,
-        alpha: u8,
-    ) {
-        // Larger, softer gradient starting from left edge and bottom
-        let center_x = image_rect.min.x; // Start from left edge
-        let center_y = image_rect.max.y + y_offset; // Start from bottom edge
-        let max_radius = 280.0 * scale_x.min(scale_y);
-
-        // Use more layers for softer gradient effect
-        let layer_count = 20;
-        for i in (0..layer_count).rev() {
-            let t = i as f32 / layer_count as f32;
-            let radius = max_radius * (1.0 - t * 0.3);
-            // Lower opacity for softer effect
-            let layer_alpha = ((alpha as f32) * 0.08 * (1.0 - t * 0.5)) as u8;
-            let color = Color32::from_rgba_unmultiplied(180, 230, 240, layer_alpha);
-
-            painter.circle_filled(Pos2::new(center_x, center_y), radius, color);
-        }
+    /// Report the overlay element under a clicked screen position
+    fn inspect_at(&mut self, pos: Pos2, image_rect: Rect) {
+        let fw_size = Vec2::new(
+            self.sim.firmware_config().overlay_width() as f32,
+            self.sim.firmware_config().overlay_height() as f32,
+        );
+        let fw_pos = Pos2::ZERO + (pos - image_rect.min) * fw_size / image_rect.size();
+
+        let report = match self.sim.get_arknights_options() {
+            Some(options) if self.sim.state.play_state == PlayState::Loop => {
+                let elements = overlay_elements(self.sim.firmware_config(), &options, &self.sim.state.animation);
+                match element_at(&elements, fw_pos) {
+                    Some(element) => element.to_string(),
+                    None => format!("No overlay element at ({:.0}, {:.0})", fw_pos.x, fw_pos.y),
+                }
+            }
+            Some(_) => "Overlay is not shown in the current state".to_string(),
+            None => "No Arknights overlay configured".to_string(),
+        };
+        info!("Inspector: {}", report);
+        self.inspected_element = Some(report);
     }
 
-    /// Render bottom-right logo background with diagonal cut
-    #[allow(dead_code)]
-    fn render_logo_background(
-        &self,
-        painter: &egui::Painter,
-        image_rect: Rect,
-        scale_x: f32,
-        scale_y: f32,
-        y_offset: f32,
-        alpha: u8,
-    ) {
-        // Size: 200x40, with diagonal cut (original template precise values)
-        let bg_width = 200.0 * scale_x;
-        let bg_height = 40.0 * scale_y;
-        let bg_x = image_rect.max.x - bg_width;
-        let bg_y = image_rect.max.y - bg_height + y_offset;
-        let cut_size = 20.0 * scale_x; // Top-left diagonal cut size
-
-        if bg_y >= image_rect.min.y && bg_y + bg_height <= image_rect.max.y {
-            let black = Color32::from_rgba_unmultiplied(0, 0, 0, alpha);
-
-            // Draw polygon with diagonal cut at top-left corner
-            let points = vec![
-                Pos2::new(bg_x + cut_size, bg_y),           // Top-left (after cut)
-                Pos2::new(image_rect.max.x, bg_y),          // Top-right
-                Pos2::new(image_rect.max.x, bg_y + bg_height), // Bottom-right
-                Pos2::new(bg_x, bg_y + bg_height),          // Bottom-left
-                Pos2::new(bg_x, bg_y + cut_size),           // Cut point on left edge
-            ];
-            painter.add(egui::Shape::convex_polygon(points, black, Stroke::NONE));
-
-            // "Rhodes Island" text
-            let white = Color32::from_rgba_unmultiplied(255, 255, 255, alpha);
-            let text_y = bg_y + 8.0 * scale_y;
-            painter.text(
-                Pos2::new(bg_x + 30.0 * scale_x, text_y),
-                Align2::LEFT_TOP,
-                "Rhodes Island",
-                FontId::proportional(10.0 * scale_y),
-                white,
+    /// Outline every overlay element with a colored rect and its name
+    fn render_bounding_boxes(&self, painter: &egui::Painter, image_rect: Rect) {
+        let Some(options) = self.sim.get_arknights_options() else {
+            return;
+        };
+        let scale = image_rect.size()
+            / Vec2::new(
+                self.sim.firmware_config().overlay_width() as f32,
+                self.sim.firmware_config().overlay_height() as f32,
             );
 
-            // Note: "明日方舟" requires Chinese font support, logo_image handles this separately
+        let elements = overlay_elements(self.sim.firmware_config(), &options, &self.sim.state.animation);
+        for (i, element) in elements.iter().enumerate() {
+            let hue = (i as f32 * 0.13).fract();
+            let color: Color32 = egui::ecolor::Hsva::new(hue, 0.8, 1.0, 1.0).into();
+            let rect = Rect::from_min_size(
+                image_rect.min + element.rect.min.to_vec2() * scale,
+                element.rect.size() * scale,
+            );
+            painter.rect_stroke(rect, 0.0, Stroke::new(1.0, color));
+            painter.text(rect.min, Align2::LEFT_BOTTOM, element.name, FontId::monospace(9.0), color);
         }
     }
+}
 
-    /// Render logo image in the bottom-right corner
-    fn render_logo_image(
-        &self,
-        painter: &egui::Painter,
-        image_rect: Rect,
-        scale_x: f32,
-        scale_y: f32,
-        y_offset: f32,
-    ) {
-        let anim = &self.state.animation;
-
-        // Only show when logo alpha > 0
-        if anim.logo_alpha == 0 {
-            return;
+impl eframe::App for SimulatorApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_video_load();
+        if self.video_load.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(50));
         }
 
-        // Check if we have a logo texture
-        if let Some(ref texture) = self.logo_texture {
-            // Logo position (bottom-right corner)
-            let logo_width = 80.0 * scale_x;
-            let logo_height = 30.0 * scale_y;
-            let logo_x = image_rect.max.x - logo_width - 10.0 * scale_x;
-            let logo_y = image_rect.max.y - logo_height - 10.0 * scale_y + y_offset;
-
-            if logo_y >= image_rect.min.y && logo_y + logo_height <= image_rect.max.y {
-                let logo_rect = Rect::from_min_size(
-                    Pos2::new(logo_x, logo_y),
-                    egui::vec2(logo_width, logo_height),
-                );
+        // Handle IPC messages
+        self.handle_ipc_messages();
 
-                let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
-                // Apply logo alpha
-                let tint = Color32::from_rgba_unmultiplied(255, 255, 255, anim.logo_alpha);
-                painter.image(texture.id(), logo_rect, uv, tint);
+        if let Some(deadline) = self.shutdown_deadline {
+            let flushed = self.ipc_rx.as_ref().is_none_or(|rx| rx.is_closed());
+            if flushed || Instant::now() >= deadline {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            } else {
+                ctx.request_repaint_after(Duration::from_millis(10));
             }
         }
-    }
 
-    // NOTE: render_left_rhodes_island() removed - C reference uses image resource at (0,0), not text rendering
-    // NOTE: render_bottom_right_logo_text() removed - C reference does not have this element
-    // NOTE: render_staff_section() removed - C reference renders staff_text via typewriter effect at X=70, Y=480
-    //       (already handled in render_typewriter_texts), not as centered "STAFF" with line and subtitle
-}
+        // Requested screenshots arrive in a later update
+        let screenshot = ctx.input(|i| {
+            i.raw.events.iter().find_map(|event| match event {
+                egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                _ => None,
+            })
+        });
+        if let Some(screenshot) = screenshot {
+            self.handle_screenshot(&screenshot, ctx.pixels_per_point());
+        }
+        if !self.screenshot_pending && self.frame_request_in_flight.is_none() && !self.sequence_frame_in_flight {
+            self.process_frame_requests();
+        }
 
-impl eframe::App for SimulatorApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Handle IPC messages
-        self.handle_ipc_messages();
+        // Follow the screen size of the loaded config
+        if let Some(width) = self.pending_window_width.take() {
+            let height = ctx
+                .input(|i| i.viewport().inner_rect)
+                .map_or(BASE_WINDOW_SIZE[1], |rect| rect.height());
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(Vec2::new(width, height)));
+        }
+        self.handle_shortcuts(ctx);
+        self.toasts.collect(Instant::now());
+        self.toasts.show(ctx);
 
-        // Load textures for current configuration (lazy loading)
-        let was_textures_loaded = self.textures_loaded;
-        self.load_textures(ctx);
-        if !was_textures_loaded && self.textures_loaded {
-            self.frame_dirty = true;
+        // Files dropped onto the window; several, or any with Ctrl held, open in new tabs
+        let (dropped, ctrl) = ctx.input(|i| {
+            let paths: Vec<PathBuf> = i.raw.dropped_files.iter().filter_map(|file| file.path.clone()).collect();
+            (paths, i.modifiers.command)
+        });
+        let new_tabs = ctrl || dropped.len() > 1;
+        for path in &dropped {
+            self.open_dropped_file(path, new_tabs);
+        }
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("drop_target")));
+            let rect = ctx.screen_rect();
+            painter.rect_filled(rect, 0.0, Color32::from_black_alpha(160));
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "Drop a config, package or video (Ctrl: in a new tab)",
+                egui::FontId::proportional(18.0),
+                Color32::WHITE,
+            );
         }
+        for command in self.pending_viewport_commands.drain(..) {
+            ctx.send_viewport_cmd(command);
+        }
+
+        // Load textures for current configuration (lazy loading)
+        self.sim.load_textures(ctx);
+        self.collect_asset_issues();
 
-        // Wall-clock timing
-        let now = Instant::now();
+        // Wall-clock timing, unless a stepped clock drives playback
+        let now = self.clock.tick();
         let elapsed_us = now.duration_since(self.last_frame_time).as_micros() as i64;
-        if self.state.is_playing && elapsed_us > 0 {
+        let mut played_us = 0;
+        if self.sim.state.is_playing && elapsed_us > 0 {
             self.last_frame_time = now;
             // Cap to prevent spiral-of-death after system stall (max 4 logic frames)
-            let step_us = self.firmware_config.animation.step_time_us as i64;
+            let step_us = self.sim.firmware_config().animation.step_time_us as i64;
             let clamped_us = elapsed_us.min(step_us * 4);
-            self.update_simulation(clamped_us);
-            self.frame_dirty = true;
+            if clamped_us < elapsed_us {
+                self.metrics.record_stall();
+            }
+            played_us = (clamped_us as f64 * self.sim.playback_speed as f64) as i64;
+            self.sim.update_simulation(played_us);
+            self.sim.frame_dirty = true;
+        }
+        if let Some((_, ref mut other)) = self.compare {
+            other.status_bar.clone_from(&self.status_bar);
+            other.follow_clock(ctx, &self.sim.state, self.sim.loop_only, played_us);
+        }
+        // Changes outside logic ticks: controls, the intro end, auto-replay
+        if self.position_before_requests.is_none() {
+            self.sim.emit_state_events();
         }
+        self.forward_events();
+        self.send_state_update_on_change();
 
         // Only re-render frame texture when content actually changed
-        if self.frame_dirty {
-            self.render_frame(ctx);
-            self.frame_dirty = false;
+        if self.sim.frame_dirty {
+            self.sim.render_frame(ctx);
+            self.metrics.record_render();
+            self.sim.frame_dirty = false;
+        }
+
+        // Report performance while playing; idle time would only dilute it
+        match self.ipc_tx {
+            Some(ref tx) if self.sim.state.is_playing => {
+                if let Some(report) = self.metrics.poll(now) {
+                    tx.send(IpcMessage::Metrics(report));
+                }
+            }
+            _ => self.metrics.restart(now),
         }
 
         // Determine text color based on theme
@@ -2339,39 +1934,143 @@ impl eframe::App for SimulatorApp {
         };
 
         // Bottom panel: controls (always visible, never clipped)
-        egui::TopBottomPanel::bottom("controls").show(ctx, |ui| {
+        // Hidden in presentation mode
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut self.show_shortcuts)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("shortcuts").num_columns(2).spacing([16.0, 4.0]).show(ui, |ui| {
+                    for (keys, action) in SHORTCUTS {
+                        ui.label(RichText::new(*keys).strong());
+                        ui.label(*action);
+                        ui.end_row();
+                    }
+                });
+            });
+
+        if self.show_animation_inspector {
+            let secondary_barcode = self.sim.epconfig.as_ref().and_then(Simulator::secondary_barcode_eink);
+            let values = animated_values(self.sim.firmware_config(), secondary_barcode.as_ref(), &self.sim.state.animation);
+            let animation_frame = self.sim.state.animation.frame_counter;
+            egui::Window::new("Animation inspector")
+                .open(&mut self.show_animation_inspector)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    egui::Grid::new("animated_values").num_columns(3).striped(true).show(ui, |ui| {
+                        ui.label(RichText::new("Value").strong());
+                        ui.label(RichText::new("Now").strong());
+                        ui.label(RichText::new("Start frame").strong());
+                        ui.end_row();
+                        for value in values {
+                            // Values whose animation has not started yet are dimmed
+                            let started = value.start_frame.is_none_or(|start| animation_frame >= start);
+                            let color = if started { text_color } else { dim_text_color };
+                            ui.label(RichText::new(value.name).monospace().color(color));
+                            ui.label(RichText::new(value.value).monospace().color(color));
+                            ui.label(RichText::new(value.start_frame.map_or(String::new(), |f| f.to_string())).monospace());
+                            ui.end_row();
+                        }
+                    });
+                });
+        }
+
+        if self.show_asset_panel {
+            if self.asset_statuses.is_none() {
+                self.asset_statuses = self.sim.epconfig.as_ref().map(|config| asset_statuses(config, &self.sim.base_dir));
+            }
+            let statuses = self.asset_statuses.as_deref().unwrap_or_default();
+            let mut refresh = false;
+            egui::Window::new("Assets")
+                .open(&mut self.show_asset_panel)
+                .show(ctx, |ui| {
+                    if statuses.is_empty() {
+                        ui.label(RichText::new("The config references no assets").color(dim_text_color));
+                    }
+                    egui::ScrollArea::both().show(ui, |ui| {
+                        egui::Grid::new("asset_statuses").num_columns(4).striped(true).show(ui, |ui| {
+                            ui.label(RichText::new("Field").strong());
+                            ui.label(RichText::new("Path").strong());
+                            ui.label(RichText::new("Details").strong());
+                            ui.label(RichText::new("Status").strong());
+                            ui.end_row();
+                            for status in statuses {
+                                let resolved = status.resolved.display().to_string();
+                                let failure = self.asset_issues.iter().find(|issue| issue.path == resolved);
+                                let (text, color) = match (status.exists, failure, &status.error) {
+                                    (false, _, _) => ("Missing".to_string(), Color32::from_rgb(255, 100, 100)),
+                                    (true, Some(issue), _) => (format!("Failed: {}", issue.reason), Color32::from_rgb(255, 100, 100)),
+                                    (true, None, Some(error)) => (error.clone(), Color32::from_rgb(255, 200, 80)),
+                                    (true, None, None) => ("OK".to_string(), Color32::GREEN),
+                                };
+                                ui.label(RichText::new(&status.field).monospace());
+                                ui.label(RichText::new(&resolved).small()).on_hover_text(&status.path);
+                                ui.label(status.details.as_deref().unwrap_or(""));
+                                ui.label(RichText::new(text).color(color));
+                                ui.end_row();
+                            }
+                        });
+                    });
+                    refresh = ui.button("Refresh").clicked();
+                });
+            if refresh {
+                self.asset_statuses = None;
+            }
+        }
+
+        let mut tab_action = None;
+        egui::TopBottomPanel::top("tabs").show_animated(ctx, self.tabs.len() > 1 && !self.presentation_mode, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (index, tab) in self.tabs.iter().enumerate() {
+                    if ui.selectable_label(index == self.active_tab, &tab.title).clicked() {
+                        tab_action = Some((index, false));
+                    }
+                    if ui.small_button("×").on_hover_text("Close tab").clicked() {
+                        tab_action = Some((index, true));
+                    }
+                    ui.separator();
+                }
+            });
+        });
+        match tab_action {
+            Some((index, true)) => self.close_tab(index),
+            Some((index, false)) => self.switch_tab(index),
+            None => {}
+        }
+
+        egui::TopBottomPanel::bottom("controls").show_animated(ctx, !self.presentation_mode, |ui| {
             ui.add_space(4.0);
 
             // Transition selectors
             ui.horizontal(|ui| {
                 ui.label("Transition In:");
                 egui::ComboBox::from_id_salt("trans_in")
-                    .selected_text(match self.selected_transition_in {
+                    .selected_text(match self.sim.selected_transition_in {
                         0 => "fade",
                         1 => "move",
                         2 => "swipe",
                         _ => "none",
                     })
                     .show_ui(ui, |ui| {
-                        ui.selectable_value(&mut self.selected_transition_in, 0, "fade");
-                        ui.selectable_value(&mut self.selected_transition_in, 1, "move");
-                        ui.selectable_value(&mut self.selected_transition_in, 2, "swipe");
-                        ui.selectable_value(&mut self.selected_transition_in, 3, "none");
+                        ui.selectable_value(&mut self.sim.selected_transition_in, 0, "fade");
+                        ui.selectable_value(&mut self.sim.selected_transition_in, 1, "move");
+                        ui.selectable_value(&mut self.sim.selected_transition_in, 2, "swipe");
+                        ui.selectable_value(&mut self.sim.selected_transition_in, 3, "none");
                     });
 
                 ui.label("Transition Loop:");
                 egui::ComboBox::from_id_salt("trans_loop")
-                    .selected_text(match self.selected_transition_loop {
+                    .selected_text(match self.sim.selected_transition_loop {
                         0 => "fade",
                         1 => "move",
                         2 => "swipe",
                         _ => "none",
                     })
                     .show_ui(ui, |ui| {
-                        ui.selectable_value(&mut self.selected_transition_loop, 0, "fade");
-                        ui.selectable_value(&mut self.selected_transition_loop, 1, "move");
-                        ui.selectable_value(&mut self.selected_transition_loop, 2, "swipe");
-                        ui.selectable_value(&mut self.selected_transition_loop, 3, "none");
+                        ui.selectable_value(&mut self.sim.selected_transition_loop, 0, "fade");
+                        ui.selectable_value(&mut self.sim.selected_transition_loop, 1, "move");
+                        ui.selectable_value(&mut self.sim.selected_transition_loop, 2, "swipe");
+                        ui.selectable_value(&mut self.sim.selected_transition_loop, 3, "none");
                     });
             });
 
@@ -2379,75 +2078,252 @@ impl eframe::App for SimulatorApp {
 
             // Control buttons
             ui.horizontal(|ui| {
-                if self.state.is_playing {
-                    if ui.button("Pause").clicked() {
-                        self.state.pause();
-                        self.frame_dirty = true;
-                    }
-                } else {
-                    if ui.button("Play").clicked() {
-                        if self.state.play_state == PlayState::Idle {
-                            self.start_playback();
-                        } else {
-                            self.state.resume();
+                if ui.button(if self.sim.state.is_playing { "Pause" } else { "Play" }).clicked() {
+                    self.sim.toggle_playback();
+                }
+
+                let mut speed = self.sim.playback_speed;
+                egui::ComboBox::from_id_salt("playback_speed")
+                    .width(60.0)
+                    .selected_text(format!("{}x", speed))
+                    .show_ui(ui, |ui| {
+                        for preset in SPEED_PRESETS {
+                            ui.selectable_value(&mut speed, preset, format!("{}x", preset));
                         }
-                    }
+                    })
+                    .response
+                    .on_hover_text("Playback speed");
+                if speed != self.sim.playback_speed {
+                    self.sim.set_playback_speed(speed);
+                    info!("Playback speed: {}x", self.sim.playback_speed);
+                }
+
+                // Single logic frames (one tick, videos included)
+                if ui.button("<").on_hover_text("Step back one frame (Left)").clicked() {
+                    self.step_frames(-1);
+                }
+                if ui.button(">").on_hover_text("Step forward one frame (Right)").clicked() {
+                    self.step_frames(1);
                 }
 
                 if ui.button("Reset").clicked() {
-                    self.reset_playback();
+                    self.sim.reset_playback();
                 }
 
                 // Video status indicator
-                let video_status = if self.video_player.has_loop() {
+                let video_status = if self.sim.video_player.has_loop() {
                     "Video: OK"
                 } else {
                     "Video: None"
                 };
                 ui.label(RichText::new(video_status).color(
-                    if self.video_player.has_loop() { Color32::GREEN } else { Color32::GRAY }
+                    if self.sim.video_player.has_loop() { Color32::GREEN } else { Color32::GRAY }
                 ).small());
+
+                ui.toggle_value(&mut self.show_overlay_editor, "Edit overlay");
+                ui.toggle_value(&mut self.show_asset_panel, "Assets");
+                let mut pinned = self.always_on_top;
+                if ui.toggle_value(&mut pinned, "Pin").on_hover_text("Keep the window on top").changed() {
+                    self.set_always_on_top(pinned);
+                }
+                if ui.small_button("?").on_hover_text("Keyboard shortcuts (F1)").clicked() {
+                    self.show_shortcuts = !self.show_shortcuts;
+                }
+            });
+
+            // Replay a part without watching everything before it
+            ui.horizontal(|ui| {
+                ui.label("Jump:");
+                if ui.add_enabled(self.sim.video_player.has_intro(), egui::Button::new("Replay intro")).clicked() {
+                    self.jump_to_state(PlayState::Intro);
+                }
+                if ui.button("Replay transition").on_hover_text("Transition into the loop video").clicked() {
+                    self.jump_to_state(PlayState::TransitionLoop);
+                }
+                if ui.button("Skip to Loop").clicked() {
+                    self.jump_to_state(PlayState::Loop);
+                }
+                ui.checkbox(&mut self.sim.loop_only, "Loop only")
+                    .on_hover_text("Start playback at the loop, skipping the intro and transitions");
+            });
+
+            // Keep restarting, for demo displays
+            ui.horizontal(|ui| {
+                ui.label("Auto-replay:");
+                let mut mode = match self.sim.auto_replay {
+                    AutoReplay::Off => 0,
+                    AutoReplay::AfterLoop(_) => 1,
+                    AutoReplay::AfterTotal(_) => 2,
+                };
+                let mut secs = self.auto_replay_secs;
+                egui::ComboBox::from_id_salt("auto_replay")
+                    .selected_text(["Off", "After loop", "After total"][mode])
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut mode, 0, "Off");
+                        ui.selectable_value(&mut mode, 1, "After loop");
+                        ui.selectable_value(&mut mode, 2, "After total");
+                    });
+                ui.add_enabled(
+                    mode != 0,
+                    egui::DragValue::new(&mut secs).range(0.5..=3600.0).speed(0.1).suffix(" s"),
+                );
+                self.auto_replay_secs = secs;
+                let us = (secs * 1_000_000.0) as i64;
+                self.sim.auto_replay = match mode {
+                    1 => AutoReplay::AfterLoop(us),
+                    2 => AutoReplay::AfterTotal(us),
+                    _ => AutoReplay::Off,
+                };
+            });
+
+            // Split view against another tab or screen size
+            ui.horizontal(|ui| {
+                ui.label("Compare:");
+                let current = self.compare.as_ref().map(|(source, _)| *source);
+                let mut choices: Vec<CompareSource> = (0..self.tabs.len())
+                    .filter(|&index| self.tabs[index].prepared.is_some())
+                    .map(CompareSource::Tab)
+                    .collect();
+                if let Some(ref config) = self.sim.epconfig {
+                    choices.extend(
+                        [ScreenType::S360x640, ScreenType::S480x854, ScreenType::S720x1080]
+                            .into_iter()
+                            .filter(|&screen| screen != config.screen)
+                            .map(CompareSource::Screen),
+                    );
+                }
+                let mut selected = current;
+                egui::ComboBox::from_id_salt("compare")
+                    .selected_text(current.map_or_else(|| "Off".to_string(), |source| self.compare_label(source)))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut selected, None, "Off");
+                        for choice in choices {
+                            ui.selectable_value(&mut selected, Some(choice), self.compare_label(choice));
+                        }
+                    });
+                if selected != current {
+                    self.set_compare(selected);
+                }
+            });
+
+            // Device status bar
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.status_bar.enabled, "Status bar");
+                ui.add_enabled_ui(self.status_bar.enabled, |ui| {
+                    ui.add(egui::Slider::new(&mut self.status_bar.battery, 0..=100).suffix("%"));
+                    ui.add(egui::TextEdit::singleline(&mut self.status_bar.time).desired_width(48.0));
+                });
+            });
+
+            // Text rendering quality
+            ui.horizontal(|ui| {
+                let mut quality = self.sim.text_quality();
+                ui.label("Text:");
+                ui.checkbox(&mut quality.antialias, "Anti-aliasing");
+                ui.checkbox(&mut quality.hinting, "Hinting");
+                self.sim.apply_text_quality(quality);
             });
 
+            // Debug tools
+            ui.horizontal(|ui| {
+                ui.label("Debug:");
+                ui.checkbox(&mut self.inspector_enabled, "Inspect elements (click preview)");
+                ui.checkbox(&mut self.show_bounding_boxes, "Bounding boxes");
+                ui.checkbox(&mut self.show_animation_inspector, "Animation values");
+            });
+            if self.inspector_enabled {
+                let report = self.inspected_element.as_deref().unwrap_or("Click an overlay element");
+                ui.label(RichText::new(report).color(dim_text_color).small());
+            }
+
+            // Assets that failed to load
+            if !self.asset_issues.is_empty() {
+                egui::CollapsingHeader::new(format!("Assets: {} failed to load", self.asset_issues.len()))
+                    .id_salt("asset_issues")
+                    .show(ui, |ui| {
+                        egui::ScrollArea::vertical().id_salt("asset_issues_list").max_height(120.0).show(ui, |ui| {
+                            for issue in &self.asset_issues {
+                                ui.label(RichText::new(issue.to_string()).color(Color32::from_rgb(255, 100, 100)).small());
+                            }
+                        });
+                    });
+            }
+
+            // Config diagnostics
+            if !self.diagnostics.is_empty() {
+                let errors = self.diagnostics.iter().filter(|d| d.severity == Severity::Error).count();
+                let warnings = self.diagnostics.len() - errors;
+                let mut strict = self.strict_validation;
+                egui::CollapsingHeader::new(format!("Config: {} error(s), {} warning(s)", errors, warnings))
+                    .id_salt("diagnostics")
+                    .show(ui, |ui| {
+                        ui.checkbox(&mut strict, "Strict (unknown options are errors)");
+                        egui::ScrollArea::vertical().id_salt("diagnostics_list").max_height(120.0).show(ui, |ui| {
+                            for diagnostic in &self.diagnostics {
+                                let color = match diagnostic.severity {
+                                    Severity::Error => Color32::from_rgb(255, 100, 100),
+                                    Severity::Warning => Color32::from_rgb(230, 180, 60),
+                                };
+                                ui.label(RichText::new(diagnostic.to_string()).color(color).small());
+                            }
+                        });
+                    });
+                self.set_strict_validation(strict);
+            }
+
             ui.separator();
 
             // Status display
             ui.label(RichText::new(format!(
                 "State: {} | Frame: {} | Animation Frame: {}",
-                self.state.play_state.display_name(),
-                self.state.frame_counter,
-                self.state.animation.frame_counter
+                self.sim.state.play_state.display_name(),
+                self.sim.state.frame_counter,
+                self.sim.state.animation.frame_counter
             )).color(dim_text_color).small());
 
             // Animation state details (debug)
-            if self.state.play_state == PlayState::Loop {
+            if self.sim.state.play_state == PlayState::Loop {
                 ui.label(RichText::new(format!(
                     "Name: {} | Code: {} | Color: {} | Entry: {:.1}%",
-                    self.state.animation.name_chars,
-                    self.state.animation.code_chars,
-                    self.state.animation.color_fade_radius,
-                    self.state.animation.entry_progress * 100.0
+                    self.sim.state.animation.name_chars,
+                    self.sim.state.animation.code_chars,
+                    self.sim.state.animation.color_fade_radius,
+                    self.sim.state.animation.entry_progress * 100.0
                 )).color(dim_text_color).small());
             }
 
             ui.add_space(4.0);
         });
 
+        egui::SidePanel::right("overlay_editor")
+            .resizable(false)
+            .show_animated(ctx, self.show_overlay_editor && !self.presentation_mode, |ui| {
+                ui.heading("Overlay");
+                egui::ScrollArea::vertical().show(ui, |ui| self.overlay_editor_ui(ui));
+            });
+
         // Central panel: title + adaptive image + overlay
-        egui::CentralPanel::default().show(ctx, |ui| {
+        let central_frame = if self.presentation_mode {
+            egui::Frame::none().fill(Color32::BLACK)
+        } else {
+            egui::Frame::central_panel(&ctx.style())
+        };
+        egui::CentralPanel::default().frame(central_frame).show(ctx, |ui| {
             // Title
-            let video_fps = self.video_player.loop_fps();
-            ui.heading(RichText::new(format!(
-                "Pass Simulator ({}x{} @ {:.1}fps)",
-                self.firmware_config.overlay_width(),
-                self.firmware_config.overlay_height(),
-                video_fps
-            )).color(text_color));
-
-            ui.separator();
+            if !self.presentation_mode {
+                let video_fps = self.sim.video_player.loop_fps();
+                ui.heading(RichText::new(format!(
+                    "Pass Simulator ({}x{} @ {:.1}fps)",
+                    self.sim.firmware_config().overlay_width(),
+                    self.sim.firmware_config().overlay_height(),
+                    video_fps
+                )).color(text_color));
+
+                ui.separator();
+            }
 
             // Show error message when no video loaded
-            if !self.video_player.has_loop() {
+            if !self.sim.video_player.has_loop() {
                 if let Some(ref error) = self.error_message {
                     ui.add_space(8.0);
                     ui.colored_label(Color32::from_rgb(255, 100, 100), error);
@@ -2455,57 +2331,47 @@ impl eframe::App for SimulatorApp {
                 }
             }
 
-            // Calculate adaptive image size to fit available space
-            let available = ui.available_size();
-            let fw_width = self.firmware_config.overlay_width() as f32;
-            let fw_height = self.firmware_config.overlay_height() as f32;
-            let aspect = fw_width / fw_height;
-
-            let img_height = available.y.min(available.x / aspect);
-            let img_width = img_height * aspect;
-
-            // Display area
-            let image_response = ui.vertical_centered(|ui| {
-                if let Some(ref texture) = self.frame_texture {
-                    let response = ui.image(egui::ImageSource::Texture(egui::load::SizedTexture::new(
-                        texture.id(),
-                        Vec2::new(img_width, img_height),
-                    )));
-                    Some(response.rect)
-                } else {
-                    None
-                }
-            });
-
-            // Render overlay UI on top of the image when in Loop state
-            if self.state.play_state == PlayState::Loop {
-                let overlay_type = self.epconfig
-                    .as_ref()
-                    .and_then(|c| c.overlay.as_ref())
-                    .map(|o| o.overlay_type)
-                    .unwrap_or(OverlayType::None);
-                if let Some(image_rect) = image_response.inner {
-                    let painter = ui.painter_at(image_rect);
-                    match overlay_type {
-                        OverlayType::Arknights => self.render_overlay_ui(&painter, image_rect),
-                        OverlayType::Image => self.render_image_overlay(&painter, image_rect),
-                        OverlayType::None => {}
-                    }
+            match self.compare.take() {
+                Some((source, mut other)) => {
+                    let label = self.compare_label(source);
+                    let title = self.tabs.get(self.active_tab).map_or(String::new(), |tab| tab.title.clone());
+                    ui.columns(2, |columns| {
+                        columns[0].label(RichText::new(title).color(dim_text_color).small());
+                        self.preview_ui(&mut columns[0]);
+                        columns[1].label(RichText::new(label).color(dim_text_color).small());
+                        other.preview_ui(&mut columns[1]);
+                    });
+                    self.compare = Some((source, other));
                 }
+                None => self.preview_ui(ui),
             }
         });
 
         // Request repaint if playing
-        if self.state.is_playing {
-            let step_ms = self.firmware_config.animation.step_time_us as u64 / 1000;
+        if self.sim.state.is_playing {
+            let step_ms = self.sim.firmware_config().animation.step_time_us as u64 / 1000;
             ctx.request_repaint_after(Duration::from_millis(step_ms));
         }
-    }
-}
 
-/// Convert microseconds to frame count
-fn microseconds_to_frames(us: i64, fps: u32) -> u32 {
-    ((us * fps as i64) / 1_000_000).max(1) as u32
+        // Capture the composed preview for frame requests and the frame stream
+        let stream_due = match self.frame_stream {
+            Some((_, ref mut stream)) => {
+                ctx.request_repaint_after(stream.interval());
+                !self.screenshot_pending && stream.poll(Instant::now())
+            }
+            None => false,
+        };
+        let requested = self.frame_request_in_flight.is_some()
+            || self.sequence_frame_in_flight
+            || !self.screenshot_requests.is_empty();
+        if !self.screenshot_pending && (requested || stream_due) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot);
+            self.screenshot_pending = true;
+        }
+        if !self.frame_requests.is_empty() || self.frame_request_in_flight.is_some() || self.sequence_render.is_some() {
+            ctx.request_repaint();
+        }
+    }
 }
 
 #[cfg(test)]
@@ -2513,12 +2379,31 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_microseconds_to_frames() {
-        // 1 second at 50fps = 50 frames
-        assert_eq!(microseconds_to_frames(1_000_000, 50), 50);
-        // 0.5 seconds at 50fps = 25 frames
-        assert_eq!(microseconds_to_frames(500_000, 50), 25);
-        // Very small value should return at least 1
-        assert_eq!(microseconds_to_frames(1, 50), 1);
+    fn test_window_size_for_screen() {
+        assert_eq!(window_size_for_screen(360, 640), BASE_WINDOW_SIZE);
+        let [wide, _] = window_size_for_screen(720, 1080);
+        assert!(wide > BASE_WINDOW_SIZE[0]);
+        assert!((window_size_for_screen(480, 854)[0] - BASE_WINDOW_SIZE[0]).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_preview_on_physical_pixels() {
+        let frame = Vec2::new(360.0, 640.0);
+        // 150%: 400x700 points fit 393.75x700, i.e. 590.6x1050 pixels
+        let size = fit_preview(Vec2::new(400.0, 700.0), frame, 1.5);
+        assert_eq!(size * 1.5, Vec2::new(590.0, 1050.0));
+        assert_eq!(fit_preview(Vec2::new(360.0, 900.0), frame, 2.0), frame);
+
+        let rect = align_to_pixels(Rect::from_min_size(Pos2::new(10.2, 5.9), size), 1.5);
+        assert_eq!(rect.min * 1.5, Pos2::new(15.0, 9.0));
+        assert_eq!(rect.size(), size);
+    }
+
+    #[test]
+    fn test_tab_title() {
+        let mut config = EPConfig::default();
+        assert_eq!(SimulatorApp::tab_title(&config, Path::new("/passes/amiya")), "amiya");
+        config.name = "Amiya".to_string();
+        assert_eq!(SimulatorApp::tab_title(&config, Path::new("/passes/amiya")), "Amiya");
     }
 }
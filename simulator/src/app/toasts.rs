@@ -0,0 +1,157 @@
+//! Toast notifications
+//!
+//! Warnings and errors are collected by a tracing layer and shown as toasts
+//! in the window, since most users never see the console. Warnings fade out
+//! on their own; errors stay until dismissed.
+
+use std::sync::Mutex;
+use web_time::{Duration, Instant};
+
+use egui::{Color32, RichText};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::ipc::MessageVisitor;
+
+/// How long a warning stays up
+const WARNING_TIMEOUT: Duration = Duration::from_secs(8);
+
+/// Most toasts shown at once; older ones make room for new ones
+const MAX_TOASTS: usize = 5;
+
+/// Records logged since the window last picked them up
+static PENDING: Mutex<Vec<(ToastLevel, String)>> = Mutex::new(Vec::new());
+
+/// Severity of a toast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ToastLevel {
+    Warning,
+    Error,
+}
+
+/// Tracing layer queueing warn and error records as toasts
+pub struct ToastLayer;
+
+impl<S: Subscriber> Layer<S> for ToastLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let level = match *event.metadata().level() {
+            Level::ERROR => ToastLevel::Error,
+            Level::WARN => ToastLevel::Warning,
+            _ => return,
+        };
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        if let Ok(mut pending) = PENDING.lock() {
+            // Nobody is picking them up (e.g. a command without a window)
+            if pending.len() < MAX_TOASTS * 4 {
+                pending.push((level, visitor.message));
+            }
+        }
+    }
+}
+
+struct Toast {
+    level: ToastLevel,
+    message: String,
+    /// Times the same message came in while shown
+    count: u32,
+    shown_at: Instant,
+}
+
+/// Toasts shown in the window
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    /// Show a toast; a message already shown is counted instead of repeated
+    pub fn push(&mut self, level: ToastLevel, message: String, now: Instant) {
+        if let Some(toast) = self.toasts.iter_mut().find(|toast| toast.message == message) {
+            toast.count += 1;
+            toast.shown_at = now;
+            toast.level = toast.level.max(level);
+            return;
+        }
+        if self.toasts.len() >= MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+        self.toasts.push(Toast { level, message, count: 1, shown_at: now });
+    }
+
+    /// Take in the records logged since the last call and drop timed out warnings
+    pub fn collect(&mut self, now: Instant) {
+        let pending = PENDING.lock().map(|mut pending| std::mem::take(&mut *pending)).unwrap_or_default();
+        for (level, message) in pending {
+            self.push(level, message, now);
+        }
+        self.toasts.retain(|toast| {
+            toast.level == ToastLevel::Error || now.saturating_duration_since(toast.shown_at) < WARNING_TIMEOUT
+        });
+    }
+
+    /// Paint the toasts in the bottom right corner, newest at the bottom
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if self.toasts.is_empty() {
+            return;
+        }
+        let mut dismissed = None;
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -8.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(320.0);
+                for (index, toast) in self.toasts.iter().enumerate() {
+                    let color = match toast.level {
+                        ToastLevel::Error => Color32::from_rgb(255, 100, 100),
+                        ToastLevel::Warning => Color32::from_rgb(255, 200, 80),
+                    };
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            let text = if toast.count > 1 {
+                                format!("{} (×{})", toast.message, toast.count)
+                            } else {
+                                toast.message.clone()
+                            };
+                            ui.add(egui::Label::new(RichText::new(text).color(color)).wrap());
+                            if ui.small_button("×").clicked() {
+                                dismissed = Some(index);
+                            }
+                        });
+                    });
+                }
+            });
+        if let Some(index) = dismissed {
+            self.toasts.remove(index);
+        }
+        // Let warnings time out without other input
+        ctx.request_repaint_after(Duration::from_millis(500));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts() {
+        let start = Instant::now();
+        let mut toasts = Toasts::default();
+        toasts.push(ToastLevel::Warning, "Decode failed".to_string(), start);
+        toasts.push(ToastLevel::Warning, "Decode failed".to_string(), start);
+        toasts.push(ToastLevel::Error, "Config invalid".to_string(), start);
+        assert_eq!(toasts.toasts.len(), 2);
+        assert_eq!(toasts.toasts[0].count, 2);
+
+        // Warnings time out, errors stay
+        toasts.collect(start + WARNING_TIMEOUT);
+        assert_eq!(toasts.toasts.len(), 1);
+        assert_eq!(toasts.toasts[0].level, ToastLevel::Error);
+
+        for i in 0..MAX_TOASTS {
+            toasts.push(ToastLevel::Error, format!("Error {}", i), start);
+        }
+        assert_eq!(toasts.toasts.len(), MAX_TOASTS);
+        assert_eq!(toasts.toasts[0].message, "Error 0");
+    }
+}
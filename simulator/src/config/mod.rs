@@ -1,9 +0,0 @@
-//! Configuration module
-//!
-//! Contains data structures for EPConfig and FirmwareConfig.
-
-mod epconfig;
-mod firmware_config;
-
-pub use epconfig::*;
-pub use firmware_config::*;
@@ -0,0 +1,98 @@
+//! Process exit codes
+//!
+//! Every mode exits with one of these, so scripts can tell why a run failed
+//! without parsing its output:
+//!
+//! | Code | Meaning |
+//! |------|---------|
+//! | 0 | Success |
+//! | 1 | Any other failure |
+//! | 2 | Invalid command line |
+//! | 3 | Config could not be loaded, or `validate` found errors |
+//! | 4 | An asset the config references is missing |
+//! | 5 | A video or image could not be decoded |
+//! | 6 | IPC could not be set up |
+//! | 7 | Rendering or writing the output failed (including `init`) |
+
+use std::fmt;
+
+use crate::config::MissingAssets;
+
+/// Exit code for failures without a known cause
+pub const GENERIC: u8 = 1;
+
+/// The `--help` footer listing the exit codes
+pub const HELP: &str = "Exit codes:
+  0  success
+  1  any other failure
+  2  invalid command line
+  3  config could not be loaded, or validate found errors
+  4  an asset the config references is missing
+  5  a video or image could not be decoded
+  6  IPC could not be set up
+  7  rendering or writing the output failed (including init)";
+
+/// Cause of a failed run, attached to errors with `.context(Failure::..)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    Config,
+    AssetMissing,
+    Decode,
+    Ipc,
+    Render,
+}
+
+impl Failure {
+    /// Process exit code for this cause
+    pub fn code(self) -> u8 {
+        match self {
+            Failure::Config => 3,
+            Failure::AssetMissing => 4,
+            Failure::Decode => 5,
+            Failure::Ipc => 6,
+            Failure::Render => 7,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Failure::Config => "config error",
+            Failure::AssetMissing => "missing asset",
+            Failure::Decode => "decode error",
+            Failure::Ipc => "IPC error",
+            Failure::Render => "render error",
+        })
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Exit code for `error`
+///
+/// Missing assets win over the cause they were tagged with, as a folder
+/// export reports them from inside a broader step.
+pub fn for_error(error: &anyhow::Error) -> u8 {
+    if error.downcast_ref::<MissingAssets>().is_some() {
+        return Failure::AssetMissing.code();
+    }
+    error.downcast_ref::<Failure>().map_or(GENERIC, |failure| failure.code())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_for_error() {
+        assert_eq!(for_error(&anyhow::anyhow!("boom")), GENERIC);
+
+        let tagged = Err::<(), _>(anyhow::anyhow!("bad json")).context(Failure::Config).context("loading");
+        assert_eq!(for_error(&tagged.unwrap_err()), 3);
+
+        let missing = Err::<(), _>(MissingAssets(vec!["loop.file".into()])).context(Failure::Config);
+        assert_eq!(for_error(&missing.unwrap_err()), 4);
+    }
+}
@@ -0,0 +1,485 @@
+//! Client connections
+//!
+//! A connection carries one message per frame: a JSON line for stdio, local
+//! sockets and TCP (or a length-prefixed MessagePack frame after switching
+//! to binary framing), a text or binary message for WebSocket. Reads time
+//! out so the server can push messages (state updates, streamed frames)
+//! unprompted.
+//!
+//! Input is untrusted: messages over `MAX_MESSAGE_LEN` and lines that are
+//! not UTF-8 are skipped and reported as malformed, keeping the connection
+//! usable. Any web page in the user's browser can reach a local WebSocket,
+//! so handshakes from non-loopback origins, or without the token when one
+//! is set, are refused.
+
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::net::{IpAddr, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{Message, WebSocket};
+
+use super::protocol::{Envelope, Framing, IpcMessage, RequestId};
+
+/// Largest accepted message, in bytes
+pub const MAX_MESSAGE_LEN: usize = 64 * 1024 * 1024;
+
+/// Why a message could not be read or decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    /// Byte offset into the message where decoding failed, if known
+    pub offset: Option<usize>,
+}
+
+impl ParseError {
+    fn new(message: impl Into<String>, offset: Option<usize>) -> Self {
+        Self { message: message.into(), offset }
+    }
+
+    fn too_large(len: usize, limit: usize) -> Self {
+        Self::new(format!("Message of {} bytes exceeds the limit of {} bytes", len, limit), None)
+    }
+
+    /// A JSON error, with its line and column turned into an offset into `text`
+    fn json(text: &str, e: serde_json::Error) -> Self {
+        let line_start: usize = text.split_inclusive('\n').take(e.line().saturating_sub(1)).map(str::len).sum();
+        let offset = (e.line() > 0).then(|| line_start + e.column().saturating_sub(1));
+        Self::new(e.to_string(), offset)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.offset {
+            Some(offset) => write!(f, "{} (at byte {})", self.message, offset),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// One encoded message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+    /// JSON
+    Text(String),
+    /// MessagePack
+    Binary(Vec<u8>),
+}
+
+impl Payload {
+    /// Encode a message with the given framing
+    pub fn encode(envelope: &Envelope, framing: Framing) -> io::Result<Self> {
+        match framing {
+            Framing::Json => envelope.to_json().map(Payload::Text).map_err(io::Error::other),
+            Framing::Msgpack => envelope.to_msgpack().map(Payload::Binary).map_err(io::Error::other),
+        }
+    }
+
+    /// Decode the message, whichever framing it uses
+    pub fn decode(&self) -> Result<Envelope, ParseError> {
+        match self {
+            Payload::Text(text) => Envelope::from_json(text).map_err(|e| ParseError::json(text, e)),
+            Payload::Binary(data) => Envelope::from_msgpack(data).map_err(|e| ParseError::new(e.to_string(), None)),
+        }
+    }
+
+    /// Request id of a message that failed to decode, if it has a readable one
+    pub fn request_id(&self) -> Option<RequestId> {
+        #[derive(serde::Deserialize)]
+        struct IdOnly {
+            #[serde(default)]
+            id: Option<RequestId>,
+        }
+        let id_only: Option<IdOnly> = match self {
+            Payload::Text(text) => serde_json::from_str(text).ok(),
+            Payload::Binary(data) => rmp_serde::from_slice(data).ok(),
+        };
+        id_only?.id
+    }
+
+    /// Framing the client switches to with this message, if it is `set_framing`
+    fn framing_switch(&self) -> Option<Framing> {
+        let mentions = match self {
+            Payload::Text(text) => text.contains("set_framing"),
+            Payload::Binary(data) => data.windows(11).any(|w| w == b"set_framing"),
+        };
+        match mentions.then(|| self.decode()) {
+            Some(Ok(Envelope { message: IpcMessage::SetFraming { framing }, .. })) => Some(framing),
+            _ => None,
+        }
+    }
+}
+
+/// Result of waiting for a client message
+pub enum Incoming {
+    Message(Payload),
+    /// A message arrived but was skipped; the connection stays usable
+    Malformed(ParseError),
+    /// Nothing arrived within the timeout
+    Idle,
+    /// The client disconnected
+    Closed,
+}
+
+/// A client connection exchanging encoded messages
+pub trait Connection {
+    /// Wait up to `timeout` for the next message
+    ///
+    /// Returning early lets the server send pending outgoing messages while
+    /// the client is quiet.
+    fn recv_timeout(&mut self, timeout: Duration) -> io::Result<Incoming>;
+
+    /// Send one message
+    fn send(&mut self, payload: &Payload) -> io::Result<()>;
+}
+
+/// A message read from a byte stream, or why it was skipped
+type ReadResult = Result<Payload, ParseError>;
+
+/// Discard input up to and including the next newline
+fn skip_line(reader: &mut impl BufRead) -> io::Result<usize> {
+    let mut skipped = 0;
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(skipped);
+        }
+        match buf.iter().position(|&b| b == b'\n') {
+            Some(end) => {
+                reader.consume(end + 1);
+                return Ok(skipped + end);
+            }
+            None => {
+                let len = buf.len();
+                reader.consume(len);
+                skipped += len;
+            }
+        }
+    }
+}
+
+/// Read one non-empty JSON line of at most `limit` bytes, None at EOF
+fn read_line(reader: &mut impl BufRead, limit: usize) -> io::Result<Option<ReadResult>> {
+    loop {
+        // One byte over the limit tells an oversized line from one that fits exactly
+        let mut line = Vec::new();
+        if reader.by_ref().take(limit as u64 + 1).read_until(b'\n', &mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        } else if line.len() > limit {
+            let len = line.len() + skip_line(reader)?;
+            return Ok(Some(Err(ParseError::too_large(len, limit))));
+        }
+        let line = match String::from_utf8(line) {
+            Ok(line) => line,
+            Err(e) => {
+                let offset = e.utf8_error().valid_up_to();
+                return Ok(Some(Err(ParseError::new("Message is not valid UTF-8", Some(offset)))));
+            }
+        };
+        // Kept untrimmed so error offsets point into the line as sent
+        if !line.trim().is_empty() {
+            return Ok(Some(Ok(Payload::Text(line))));
+        }
+    }
+}
+
+/// Read one frame of at most `limit` bytes with a big-endian u32 length prefix, None at EOF
+fn read_frame(reader: &mut impl Read, limit: usize) -> io::Result<Option<ReadResult>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len > limit {
+        // The prefix says where the next frame starts, so only this one is lost
+        io::copy(&mut reader.by_ref().take(len as u64), &mut io::sink())?;
+        return Ok(Some(Err(ParseError::too_large(len, limit))));
+    }
+    let mut data = vec![0; len];
+    match reader.read_exact(&mut data) {
+        Ok(()) => Ok(Some(Ok(Payload::Binary(data)))),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("Connection closed in the middle of a {} byte frame", len),
+        )),
+        Err(e) => Err(e),
+    }
+}
+
+/// Messages over a byte stream: JSON lines, or length-prefixed MessagePack
+///
+/// Messages are read on a separate thread, as stdin and pipes cannot time
+/// out. That thread follows the client's `set_framing` requests itself, so
+/// the switch takes effect exactly after the request.
+pub struct LineConnection<W> {
+    messages: Receiver<io::Result<ReadResult>>,
+    writer: W,
+}
+
+impl<W: Write> LineConnection<W> {
+    pub fn new<R: BufRead + Send + 'static>(mut reader: R, writer: W) -> Self {
+        let (tx, messages) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut framing = Framing::Json;
+            loop {
+                let payload = match framing {
+                    Framing::Json => read_line(&mut reader, MAX_MESSAGE_LEN),
+                    Framing::Msgpack => read_frame(&mut reader, MAX_MESSAGE_LEN),
+                };
+                match payload {
+                    Ok(Some(payload)) => {
+                        if let Some(switch) = payload.as_ref().ok().and_then(Payload::framing_switch) {
+                            framing = switch;
+                        }
+                        if tx.send(Ok(payload)).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        let _ = tx.send(Err(e));
+                        break;
+                    }
+                }
+            }
+        });
+        Self { messages, writer }
+    }
+}
+
+impl<W: Write> Connection for LineConnection<W> {
+    fn recv_timeout(&mut self, timeout: Duration) -> io::Result<Incoming> {
+        match self.messages.recv_timeout(timeout) {
+            Ok(Ok(Ok(payload))) => Ok(Incoming::Message(payload)),
+            Ok(Ok(Err(e))) => Ok(Incoming::Malformed(e)),
+            Ok(Err(e)) => Err(e),
+            Err(RecvTimeoutError::Timeout) => Ok(Incoming::Idle),
+            Err(RecvTimeoutError::Disconnected) => Ok(Incoming::Closed),
+        }
+    }
+
+    fn send(&mut self, payload: &Payload) -> io::Result<()> {
+        match payload {
+            Payload::Text(json) => {
+                self.writer.write_all(json.as_bytes())?;
+                self.writer.write_all(b"\n")?;
+            }
+            Payload::Binary(data) => {
+                let len = u32::try_from(data.len()).map_err(io::Error::other)?;
+                self.writer.write_all(&len.to_be_bytes())?;
+                self.writer.write_all(data)?;
+            }
+        }
+        self.writer.flush()
+    }
+}
+
+/// Messages over a WebSocket: JSON as text, MessagePack as binary messages
+pub struct WebSocketConnection {
+    socket: WebSocket<TcpStream>,
+}
+
+impl WebSocketConnection {
+    /// Perform the server handshake on an accepted TCP stream
+    ///
+    /// With a `token`, the request URI must carry it as `?token=...`.
+    pub fn accept(stream: TcpStream, token: Option<&str>) -> io::Result<Self> {
+        let config = WebSocketConfig {
+            max_message_size: Some(MAX_MESSAGE_LEN),
+            max_frame_size: Some(MAX_MESSAGE_LEN),
+            ..Default::default()
+        };
+        // The callback signature is tungstenite's
+        #[allow(clippy::result_large_err)]
+        let check = |request: &Request, response: Response| match check_handshake(request, token) {
+            Ok(()) => Ok(response),
+            Err(reason) => {
+                let mut error = ErrorResponse::new(Some(reason.to_string()));
+                *error.status_mut() = StatusCode::FORBIDDEN;
+                Err(error)
+            }
+        };
+        let socket = tungstenite::accept_hdr_with_config(stream, check, Some(config))
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        Ok(Self { socket })
+    }
+}
+
+/// Why a WebSocket handshake is refused, if it is
+fn check_handshake(request: &Request, token: Option<&str>) -> Result<(), &'static str> {
+    let origin = request.headers().get("origin").map(|origin| origin.to_str().unwrap_or_default());
+    if !origin_allowed(origin) {
+        return Err("Origin not allowed");
+    }
+    if let Some(token) = token {
+        let query = request.uri().query().unwrap_or_default();
+        if !query.split('&').any(|pair| pair.strip_prefix("token=") == Some(token)) {
+            return Err("Missing or wrong token");
+        }
+    }
+    Ok(())
+}
+
+/// Whether a page at `origin` may connect: none (not a browser), `null` or a loopback host
+fn origin_allowed(origin: Option<&str>) -> bool {
+    let Some(origin) = origin else {
+        return true;
+    };
+    if origin == "null" {
+        return true;
+    }
+    let Some((_, authority)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = match authority.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost") || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+fn ws_error(e: tungstenite::Error) -> io::Error {
+    match e {
+        tungstenite::Error::Io(e) => e,
+        e => io::Error::other(e),
+    }
+}
+
+impl Connection for WebSocketConnection {
+    fn recv_timeout(&mut self, timeout: Duration) -> io::Result<Incoming> {
+        // tungstenite keeps partially received frames across timed out reads
+        self.socket.get_mut().set_read_timeout(Some(timeout))?;
+        loop {
+            // Pings are answered by tungstenite itself
+            match self.socket.read() {
+                Ok(Message::Text(text)) => return Ok(Incoming::Message(Payload::Text(text))),
+                Ok(Message::Binary(data)) => return Ok(Incoming::Message(Payload::Binary(data))),
+                Ok(Message::Close(_)) => return Ok(Incoming::Closed),
+                Ok(_) => continue,
+                Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                    return Ok(Incoming::Closed)
+                }
+                Err(tungstenite::Error::Utf8) => {
+                    return Ok(Incoming::Malformed(ParseError::new("Message is not valid UTF-8", None)))
+                }
+                Err(tungstenite::Error::Io(e))
+                    if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) =>
+                {
+                    return Ok(Incoming::Idle)
+                }
+                Err(e) => return Err(ws_error(e)),
+            }
+        }
+    }
+
+    fn send(&mut self, payload: &Payload) -> io::Result<()> {
+        let message = match payload {
+            Payload::Text(json) => Message::Text(json.clone()),
+            Payload::Binary(data) => Message::Binary(data.clone()),
+        };
+        self.socket.send(message).map_err(ws_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_connection_framing_switch() {
+        // JSON until set_framing, length-prefixed MessagePack after it
+        let mut input = b"\n{\"type\": \"set_framing\", \"payload\": {\"framing\": \"msgpack\"}}\n".to_vec();
+        let packed = Envelope::new(IpcMessage::GetState).to_msgpack().unwrap();
+        input.extend_from_slice(&(packed.len() as u32).to_be_bytes());
+        input.extend_from_slice(&packed);
+
+        let mut output = Vec::new();
+        let mut connection = LineConnection::new(io::Cursor::new(input), &mut output);
+        let timeout = Duration::from_secs(1);
+        let Ok(Incoming::Message(first)) = connection.recv_timeout(timeout) else {
+            panic!("expected set_framing");
+        };
+        assert!(matches!(first.decode().unwrap().message, IpcMessage::SetFraming { framing: Framing::Msgpack }));
+        let Ok(Incoming::Message(second)) = connection.recv_timeout(timeout) else {
+            panic!("expected get_state");
+        };
+        assert!(matches!(second.decode().unwrap().message, IpcMessage::GetState));
+        assert!(matches!(connection.recv_timeout(timeout), Ok(Incoming::Closed)));
+
+        connection.send(&Payload::Binary(vec![1, 2])).unwrap();
+        drop(connection);
+        assert_eq!(output, [0, 0, 0, 2, 1, 2]);
+    }
+
+    #[test]
+    fn test_read_line_limits() {
+        let input = b"0123456789abcdefghij\n{\"type\": \"ready\"}\n\xff\xfe\n{\"type\": \"pong\"}".to_vec();
+        let mut reader = io::Cursor::new(input);
+
+        // Oversized and non-UTF-8 lines are skipped without losing the next message
+        let Ok(Some(Err(e))) = read_line(&mut reader, 17) else {
+            panic!("expected the long line to be rejected");
+        };
+        assert!(e.message.contains("limit of 17 bytes"));
+        assert!(matches!(read_line(&mut reader, 17), Ok(Some(Ok(Payload::Text(ref t)))) if t.contains("ready")));
+        let Ok(Some(Err(e))) = read_line(&mut reader, 17) else {
+            panic!("expected invalid UTF-8 to be rejected");
+        };
+        assert_eq!(e.offset, Some(0));
+        // A last line without newline still counts
+        assert!(matches!(read_line(&mut reader, 17), Ok(Some(Ok(Payload::Text(ref t)))) if t.contains("pong")));
+        assert!(matches!(read_line(&mut reader, 17), Ok(None)));
+    }
+
+    #[test]
+    fn test_read_frame_limits() {
+        let mut input = vec![0, 0, 0, 8];
+        input.extend_from_slice(b"too long");
+        input.extend_from_slice(&[0, 0, 0, 2, 1, 2, 0, 0, 0, 3, 1]);
+        let mut reader = io::Cursor::new(input);
+
+        assert!(matches!(read_frame(&mut reader, 4), Ok(Some(Err(_)))));
+        assert!(matches!(read_frame(&mut reader, 4), Ok(Some(Ok(Payload::Binary(ref d)))) if d == &[1, 2]));
+        // Truncated frame: the client went away mid-message
+        let e = read_frame(&mut reader, 4).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_parse_error_offset() {
+        let text = "{\"type\": \"get_frame\",\n \"payload\": {\"state\": x}}";
+        let e = Payload::Text(text.to_string()).decode().unwrap_err();
+        assert_eq!(&text[e.offset.unwrap()..e.offset.unwrap() + 1], "x");
+        assert!(e.to_string().contains("at byte"));
+
+        // Offsets count the leading whitespace of the line as sent
+        let line = "  \t{\"type\": x}\r\n";
+        let Ok(Some(Ok(payload))) = read_line(&mut io::Cursor::new(line), 64) else {
+            panic!("expected a line");
+        };
+        let e = payload.decode().unwrap_err();
+        assert_eq!(&line[e.offset.unwrap()..e.offset.unwrap() + 1], "x");
+    }
+
+    #[test]
+    fn test_origin_allowed() {
+        assert!(origin_allowed(None));
+        assert!(origin_allowed(Some("null")));
+        assert!(origin_allowed(Some("http://localhost:5173")));
+        assert!(origin_allowed(Some("http://127.0.0.1")));
+        assert!(origin_allowed(Some("http://[::1]:8080")));
+        assert!(!origin_allowed(Some("https://example.com")));
+        assert!(!origin_allowed(Some("http://localhost.example.com")));
+        assert!(!origin_allowed(Some("garbage")));
+    }
+}
@@ -0,0 +1,393 @@
+//! HTTP control endpoint
+//!
+//! A REST face on the IPC protocol for scripts and CI jobs. Each HTTP
+//! request becomes protocol messages tagged with a request id, goes through
+//! a regular client session and is answered from the reply carrying that
+//! id. Connections serve a single request.
+//!
+//! - `GET /state`: playback state
+//! - `POST /play`, `POST /pause`, `POST /stop`: playback state after the command
+//! - `POST /config?base_dir=DIR` with an epconfig.json body: validation
+//!   diagnostics and playback state
+//! - `GET /screenshot`: the composed preview as PNG
+//!
+//! Errors are JSON `{"code", "message"}` objects with a 4xx or 5xx status.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::config::{Diagnostic, EPConfig};
+use super::connection::{Connection, Incoming, Payload, MAX_MESSAGE_LEN};
+use super::protocol::{error_codes, ControlCommand, Envelope, IpcMessage, RequestId};
+
+/// How long a client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for the app to answer
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest accepted request or header line
+const MAX_LINE_LEN: usize = 8 * 1024;
+
+/// Id of the request forwarded to the app; each connection carries one
+const REQUEST_ID: RequestId = RequestId::Number(1);
+
+/// How to turn the app's reply into a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Route {
+    State,
+    Config,
+    Screenshot,
+}
+
+/// A parsed HTTP request
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// An HTTP response
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn json(status: u16, value: &serde_json::Value) -> Self {
+        Self { status, content_type: "application/json", body: value.to_string().into_bytes() }
+    }
+
+    fn error(status: u16, code: i32, message: impl Into<String>) -> Self {
+        Self::json(status, &json!({ "code": code, "message": message.into() }))
+    }
+
+    fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason_phrase(self.status),
+            self.content_type,
+            self.body.len()
+        )?;
+        writer.write_all(&self.body)?;
+        writer.flush()
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        500 => "Internal Server Error",
+        504 => "Gateway Timeout",
+        _ => "",
+    }
+}
+
+/// Progress of the connection's single request
+enum Exchange {
+    /// The request has not been read yet
+    Reading,
+    /// Forwarded to the app, waiting for the reply
+    Waiting {
+        route: Route,
+        since: Instant,
+        /// Diagnostics broadcast while the request was handled
+        diagnostics: Vec<Diagnostic>,
+    },
+    /// The response is out
+    Done,
+}
+
+/// One HTTP request and its response
+pub struct HttpConnection {
+    stream: TcpStream,
+    exchange: Exchange,
+}
+
+impl HttpConnection {
+    pub fn new(stream: TcpStream) -> Self {
+        Self { stream, exchange: Exchange::Reading }
+    }
+
+    fn respond(&mut self, response: &Response) -> io::Result<()> {
+        self.exchange = Exchange::Done;
+        response.write_to(&mut self.stream)
+    }
+}
+
+impl Connection for HttpConnection {
+    fn recv_timeout(&mut self, timeout: Duration) -> io::Result<Incoming> {
+        match self.exchange {
+            Exchange::Reading => {
+                self.stream.set_read_timeout(Some(READ_TIMEOUT))?;
+                let request = {
+                    let mut reader = BufReader::new(&self.stream);
+                    read_request(&mut reader, &mut &self.stream)
+                };
+                match request.and_then(|request| route(&request)) {
+                    Ok((route, message)) => {
+                        self.exchange = Exchange::Waiting { route, since: Instant::now(), diagnostics: Vec::new() };
+                        let json = Envelope::reply(Some(REQUEST_ID), message).to_json().map_err(io::Error::other)?;
+                        Ok(Incoming::Message(Payload::Text(json)))
+                    }
+                    Err(response) => {
+                        // The client may already be gone
+                        let _ = self.respond(&response);
+                        Ok(Incoming::Closed)
+                    }
+                }
+            }
+            Exchange::Waiting { since, .. } if since.elapsed() > REPLY_TIMEOUT => {
+                let response = Response::error(504, error_codes::INTERNAL_ERROR, "The simulator did not answer in time");
+                let _ = self.respond(&response);
+                Ok(Incoming::Closed)
+            }
+            Exchange::Waiting { .. } => {
+                std::thread::sleep(timeout);
+                Ok(Incoming::Idle)
+            }
+            Exchange::Done => Ok(Incoming::Closed),
+        }
+    }
+
+    fn send(&mut self, payload: &Payload) -> io::Result<()> {
+        let Exchange::Waiting { route, ref mut diagnostics, .. } = self.exchange else {
+            return Ok(());
+        };
+        // Everything but the reply (ready, pings, broadcasts) is dropped
+        let Ok(envelope) = payload.decode() else {
+            return Ok(());
+        };
+        match envelope.message {
+            IpcMessage::Validation { diagnostics: found } if envelope.id.is_none() => *diagnostics = found,
+            message if envelope.id == Some(REQUEST_ID) => {
+                let response = reply_response(route, message, std::mem::take(diagnostics));
+                self.respond(&response)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Read one request line, without its line ending
+fn read_line(reader: &mut impl BufRead) -> Result<String, Response> {
+    let mut line = Vec::new();
+    let read = reader
+        .by_ref()
+        .take(MAX_LINE_LEN as u64 + 1)
+        .read_until(b'\n', &mut line)
+        .map_err(|e| Response::error(400, error_codes::PARSE_ERROR, format!("Failed to read request: {}", e)))?;
+    if read == 0 || line.last() != Some(&b'\n') {
+        let message = if line.len() > MAX_LINE_LEN { "Request line too long" } else { "Incomplete request" };
+        return Err(Response::error(400, error_codes::PARSE_ERROR, message));
+    }
+    String::from_utf8(line)
+        .map(|line| line.trim_end().to_string())
+        .map_err(|_| Response::error(400, error_codes::PARSE_ERROR, "Request is not valid UTF-8"))
+}
+
+/// Read a request; `interim` receives `100 Continue` if the client waits for it
+fn read_request(reader: &mut impl BufRead, interim: &mut impl Write) -> Result<Request, Response> {
+    let request_line = read_line(reader)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(Response::error(400, error_codes::PARSE_ERROR, "Malformed request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut content_length = 0;
+    let mut expect_continue = false;
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(Response::error(400, error_codes::PARSE_ERROR, format!("Malformed header: {}", line)));
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value
+                .parse::<usize>()
+                .map_err(|_| Response::error(400, error_codes::PARSE_ERROR, "Invalid Content-Length"))?;
+        } else if name.eq_ignore_ascii_case("expect") {
+            expect_continue = value.eq_ignore_ascii_case("100-continue");
+        }
+    }
+    if content_length > MAX_MESSAGE_LEN {
+        let message = format!("Body of {} bytes exceeds the limit of {} bytes", content_length, MAX_MESSAGE_LEN);
+        return Err(Response::error(413, error_codes::PARSE_ERROR, message));
+    }
+    if expect_continue && content_length > 0 {
+        let _ = interim.write_all(b"HTTP/1.1 100 Continue\r\n\r\n");
+    }
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|_| Response::error(400, error_codes::PARSE_ERROR, "Incomplete request body"))?;
+
+    Ok(Request {
+        method: method.to_string(),
+        path: percent_decode(path),
+        query: query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (percent_decode(key), percent_decode(value))
+            })
+            .collect(),
+        body,
+    })
+}
+
+/// Decode `%XX` escapes and `+` (as space) in a URL component
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            (b'+', _) => decoded.push(b' '),
+            (byte, _) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The protocol message for a request, and how to answer it
+fn route(request: &Request) -> Result<(Route, IpcMessage), Response> {
+    let command = |command| IpcMessage::Batch(vec![IpcMessage::Control(command), IpcMessage::GetState]);
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/state") => Ok((Route::State, IpcMessage::GetState)),
+        ("POST", "/play") => Ok((Route::State, command(ControlCommand::Play))),
+        ("POST", "/pause") => Ok((Route::State, command(ControlCommand::Pause))),
+        ("POST", "/stop") => Ok((Route::State, command(ControlCommand::Stop))),
+        ("POST", "/config") => {
+            let base_dir = request.query_param("base_dir").ok_or_else(|| {
+                Response::error(400, error_codes::INVALID_REQUEST, "Missing base_dir query parameter")
+            })?;
+            let config: EPConfig = serde_json::from_slice(&request.body)
+                .map_err(|e| Response::error(400, error_codes::INVALID_CONFIG, format!("Invalid config: {}", e)))?;
+            let load = IpcMessage::LoadConfig { config: Box::new(config), base_dir: base_dir.to_string() };
+            Ok((Route::Config, IpcMessage::Batch(vec![load, IpcMessage::GetState])))
+        }
+        ("GET", "/screenshot") => Ok((Route::Screenshot, IpcMessage::Screenshot { path: None })),
+        (_, "/state" | "/play" | "/pause" | "/stop" | "/config" | "/screenshot") => Err(Response::error(
+            405,
+            error_codes::INVALID_REQUEST,
+            format!("{} is not allowed on {}", request.method, request.path),
+        )),
+        _ => Err(Response::error(404, error_codes::INVALID_REQUEST, format!("No endpoint {}", request.path))),
+    }
+}
+
+/// The `payload` of a message, as JSON
+fn payload_json(message: &IpcMessage) -> serde_json::Value {
+    serde_json::to_value(message)
+        .ok()
+        .and_then(|mut value| value.get_mut("payload").map(serde_json::Value::take))
+        .unwrap_or(serde_json::Value::Null)
+}
+
+/// The response for the app's reply to a request
+fn reply_response(route: Route, message: IpcMessage, diagnostics: Vec<Diagnostic>) -> Response {
+    match (route, message) {
+        (_, IpcMessage::Error { code, message, .. }) => {
+            let status = match code {
+                error_codes::INVALID_CONFIG | error_codes::INVALID_REQUEST | error_codes::PARSE_ERROR => 400,
+                _ => 500,
+            };
+            Response::error(status, code, message)
+        }
+        (Route::State, state @ IpcMessage::State { .. }) => Response::json(200, &payload_json(&state)),
+        (Route::Config, state @ IpcMessage::State { .. }) => {
+            Response::json(200, &json!({ "diagnostics": diagnostics, "state": payload_json(&state) }))
+        }
+        (Route::Screenshot, IpcMessage::ScreenshotTaken { data: Some(png), .. }) => {
+            Response { status: 200, content_type: "image/png", body: png.0 }
+        }
+        (_, other) => Response::error(500, error_codes::INTERNAL_ERROR, format!("Unexpected reply: {:?}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::protocol::Bytes;
+
+    fn parse(raw: &str) -> Result<Request, Response> {
+        let mut interim = Vec::new();
+        read_request(&mut io::Cursor::new(raw.as_bytes().to_vec()), &mut interim)
+    }
+
+    #[test]
+    fn test_read_request() {
+        let request = parse("POST /config?base_dir=C%3A%5Cmy+material HTTP/1.1\r\nHost: x\r\ncontent-length: 4\r\n\r\nbody").unwrap();
+        assert_eq!((request.method.as_str(), request.path.as_str()), ("POST", "/config"));
+        assert_eq!(request.query_param("base_dir"), Some("C:\\my material"));
+        assert_eq!(request.body, b"body");
+
+        assert_eq!(parse("GET /state HTTP/1.1\r\nHost").unwrap_err().status, 400);
+        assert_eq!(parse("POST /config HTTP/1.1\r\nContent-Length: 999999999999\r\n\r\n").unwrap_err().status, 413);
+    }
+
+    #[test]
+    fn test_routes() {
+        let request = |method: &str, path: &str| Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: Vec::new(),
+            body: Vec::new(),
+        };
+        assert!(matches!(route(&request("GET", "/state")), Ok((Route::State, IpcMessage::GetState))));
+        assert!(matches!(
+            route(&request("POST", "/pause")),
+            Ok((Route::State, IpcMessage::Batch(ref batch))) if matches!(batch[..], [IpcMessage::Control(ControlCommand::Pause), IpcMessage::GetState])
+        ));
+        assert_eq!(route(&request("GET", "/play")).unwrap_err().status, 405);
+        assert_eq!(route(&request("GET", "/nothing")).unwrap_err().status, 404);
+        // Loading needs a base directory and a valid config
+        assert_eq!(route(&request("POST", "/config")).unwrap_err().status, 400);
+        let mut load = request("POST", "/config");
+        load.query.push(("base_dir".to_string(), ".".to_string()));
+        load.body = b"not json".to_vec();
+        assert_eq!(route(&load).unwrap_err().status, 400);
+    }
+
+    #[test]
+    fn test_reply_response() {
+        let error = IpcMessage::error(error_codes::VIDEO_LOAD_FAILED, "no video");
+        assert_eq!(reply_response(Route::Config, error, Vec::new()).status, 500);
+        let png = IpcMessage::ScreenshotTaken { path: None, data: Some(Bytes(vec![1, 2])) };
+        let response = reply_response(Route::Screenshot, png, Vec::new());
+        assert_eq!((response.status, response.content_type, response.body), (200, "image/png", vec![1, 2]));
+    }
+}
@@ -0,0 +1,127 @@
+//! Log forwarding
+//!
+//! A tracing layer that sends warn and error records to the editor as
+//! `log_record` messages, so failures that would only reach the console show
+//! up where the user is looking. Records are dropped until the IPC server
+//! starts forwarding.
+
+use std::fmt::{self, Write};
+use std::sync::Mutex;
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+use super::protocol::IpcMessage;
+
+type Sink = Box<dyn Fn(IpcMessage) + Send>;
+
+/// Where forwarded records go, set once the IPC server is up
+static SINK: Mutex<Option<Sink>> = Mutex::new(None);
+
+/// Send warn and error records to `sink` from now on
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub fn forward_logs(sink: impl Fn(IpcMessage) + Send + 'static) {
+    if let Ok(mut current) = SINK.lock() {
+        *current = Some(Box::new(sink));
+    }
+}
+
+/// Send `msg` to the editor if logs are being forwarded, returning whether it was
+///
+/// Never blocks, so it is safe to call from a panic hook.
+pub fn notify_editor(msg: IpcMessage) -> bool {
+    let Ok(sink) = SINK.try_lock() else {
+        return false;
+    };
+    match *sink {
+        Some(ref sink) => {
+            sink(msg);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Tracing layer forwarding warn and error records over IPC
+pub struct IpcLogLayer;
+
+impl<S: Subscriber> Layer<S> for IpcLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        // The IPC code's own failures would feed back into the connection
+        if *metadata.level() > Level::WARN || is_ipc_target(metadata.target()) {
+            return;
+        }
+        let Ok(sink) = SINK.lock() else {
+            return;
+        };
+        let Some(ref sink) = *sink else {
+            return;
+        };
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        sink(IpcMessage::LogRecord {
+            level: metadata.level().as_str().to_lowercase(),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+        });
+    }
+}
+
+/// Whether a record comes from this module's parent (the IPC code)
+fn is_ipc_target(target: &str) -> bool {
+    let own = module_path!();
+    let ipc = own.rsplit_once("::").map_or(own, |(parent, _)| parent);
+    target.starts_with(ipc)
+}
+
+/// Formats the message followed by any other fields as `key=value`
+#[derive(Default)]
+pub(crate) struct MessageVisitor {
+    pub message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.message);
+            let _ = write!(self.message, "{:?}{}", value, fields);
+        } else {
+            let _ = write!(self.message, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_log_forwarding() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        forward_logs(move |msg| {
+            let _ = tx.lock().unwrap().send(msg);
+        });
+
+        let subscriber = tracing_subscriber::registry().with(IpcLogLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            // Targets of this module count as IPC code
+            tracing::info!(target: "arknights_pass_simulator::video", "not forwarded");
+            tracing::warn!(target: "arknights_pass_simulator::video", path = "loop.mp4", "Failed to load {}", "video");
+            tracing::error!(target: "arknights_pass_simulator::ipc::server", "not forwarded either");
+        });
+
+        match rx.try_recv().unwrap() {
+            IpcMessage::LogRecord { level, message, .. } => {
+                assert_eq!(level, "warn");
+                assert_eq!(message, r#"Failed to load video path="loop.mp4""#);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert!(rx.try_recv().is_err());
+    }
+}
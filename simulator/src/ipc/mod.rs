@@ -1,9 +1,25 @@
 //! IPC communication module
 //!
-//! Handles communication with the Python editor via Named Pipe or stdin/stdout.
+//! Handles communication with the Python editor via a local socket (Named
+//! Pipe / Unix domain socket), TCP, WebSocket or stdin/stdout, and with
+//! scripts through a small HTTP endpoint. Browser builds have none of
+//! these and get a stand-in that never connects.
 
+#[cfg(not(target_arch = "wasm32"))]
+mod connection;
+#[cfg(not(target_arch = "wasm32"))]
+mod http;
+mod logging;
 mod protocol;
+#[cfg(not(target_arch = "wasm32"))]
 mod server;
+#[cfg(target_arch = "wasm32")]
+mod web;
 
+pub use logging::{notify_editor, IpcLogLayer};
+pub(crate) use logging::MessageVisitor;
 pub use protocol::*;
-pub use server::{start_ipc_server, IpcReceiver, IpcSender};
+#[cfg(not(target_arch = "wasm32"))]
+pub use server::{start_ipc_server, IpcOptions, IpcReceiver, IpcSender, IpcTransport, ReplyTo};
+#[cfg(target_arch = "wasm32")]
+pub use web::{start_ipc_server, IpcOptions, IpcReceiver, IpcSender, IpcTransport, ReplyTo};
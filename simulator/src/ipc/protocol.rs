@@ -2,9 +2,188 @@
 //!
 //! Defines message formats for communication with the Python editor.
 
-use serde::{Deserialize, Serialize};
-use crate::config::EPConfig;
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::animation::Milestone;
+use crate::config::{Diagnostic, EPConfig, FirmwareConfig, TransitionType};
 use crate::app::state::PlayState;
+use crate::app::{FrameFormat, MetricsReport, SwipeDirection};
+use crate::render::{AssetIssue, StatusBar};
+
+/// Protocol version, bumped on incompatible message changes
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Optional protocol features this simulator supports
+pub const FEATURES: &[&str] = &[
+    "packages",
+    "templates",
+    "strict_validation",
+    "get_frame",
+    "stream_frames",
+    "step_frame",
+    "set_speed",
+    "screenshot",
+    "heartbeat",
+    "msgpack_framing",
+    "events",
+    "loop_transform",
+    "render_sequence",
+    "log_records",
+    "batch",
+    "window_control",
+    "metrics",
+    "request_ids",
+    "config_slots",
+    "state_update_rate",
+    "clipboard",
+    "overlay_editor",
+    "status_bar",
+    "swipe_events",
+    "crash_reports",
+    "set_serial",
+];
+
+/// Slot holding a preloaded config variant for A/B comparison
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSlot {
+    A,
+    B,
+}
+
+/// Wire encoding of messages, switched with `set_framing`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Framing {
+    /// One JSON message per line (or WebSocket text message)
+    #[default]
+    Json,
+    /// MessagePack, length-prefixed (or one WebSocket binary message)
+    Msgpack,
+}
+
+/// Binary data: base64 in JSON, raw bytes in MessagePack
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bytes(pub Vec<u8>);
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(&self.0))
+        } else {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+            type Value = Bytes;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("base64 string or bytes")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Bytes, E> {
+                base64::engine::general_purpose::STANDARD.decode(v).map(Bytes).map_err(E::custom)
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Bytes, E> {
+                Ok(Bytes(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<Bytes, E> {
+                Ok(Bytes(v))
+            }
+        }
+
+        // Accept either form: buffered enum content may lose the format's
+        // human-readable flag
+        deserializer.deserialize_any(BytesVisitor)
+    }
+}
+
+/// Event categories a client can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    StateChanged,
+    TransitionStarted,
+    IntroEnded,
+    AnimationMilestone,
+    Error,
+    Gesture,
+}
+
+impl EventKind {
+    /// Every category
+    pub const ALL: [EventKind; 6] = [
+        EventKind::StateChanged,
+        EventKind::TransitionStarted,
+        EventKind::IntroEnded,
+        EventKind::AnimationMilestone,
+        EventKind::Error,
+        EventKind::Gesture,
+    ];
+}
+
+/// Playback event, sent to clients subscribed to its category
+///
+/// `frame` is the global logic frame the event happened at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// Playback moved from one state to another
+    StateChanged {
+        from: u8,
+        to: u8,
+        frame: u64,
+    },
+    /// A transition began (`state` is transition in or transition loop)
+    TransitionStarted {
+        state: u8,
+        transition: TransitionType,
+        /// Length of the transition in logic frames
+        frames: u32,
+        frame: u64,
+    },
+    /// The intro finished and the loop transition begins
+    IntroEnded {
+        frame: u64,
+    },
+    /// The overlay animation reached a milestone
+    AnimationMilestone {
+        milestone: Milestone,
+        frame: u64,
+    },
+    /// Something went wrong outside a request (e.g. an asset failed to load)
+    Error {
+        code: i32,
+        message: String,
+    },
+    /// The preview was swiped, as a finger would on the device
+    Swipe {
+        direction: SwipeDirection,
+        frame: u64,
+    },
+}
+
+impl Event {
+    /// Category of this event
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::StateChanged { .. } => EventKind::StateChanged,
+            Event::TransitionStarted { .. } => EventKind::TransitionStarted,
+            Event::IntroEnded { .. } => EventKind::IntroEnded,
+            Event::AnimationMilestone { .. } => EventKind::AnimationMilestone,
+            Event::Error { .. } => EventKind::Error,
+            Event::Swipe { .. } => EventKind::Gesture,
+        }
+    }
+}
 
 /// Control commands from editor to simulator
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +199,26 @@ pub enum ControlCommand {
     Reset,
     /// Seek to specific state
     SeekTo(u8),
+    /// Pause and move by N logic frames (negative rewinds)
+    StepFrame(i32),
+    /// Set the playback speed multiplier (clamped to 0.1..=4.0)
+    SetSpeed(f32),
+}
+
+/// How often `state_update` is sent during playback
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateUpdateRate {
+    /// Every N logic frames (1 = every frame)
+    EveryFrames(u32),
+    /// Only when the play state or the playing flag changes
+    OnChange,
+}
+
+impl Default for StateUpdateRate {
+    fn default() -> Self {
+        StateUpdateRate::EveryFrames(10)
+    }
 }
 
 /// IPC message types
@@ -31,10 +230,67 @@ pub enum IpcMessage {
     /// Load configuration
     #[serde(rename = "load_config")]
     LoadConfig {
-        config: EPConfig,
+        config: Box<EPConfig>,
+        base_dir: String,
+    },
+
+    /// Preload a config variant into `slot`, replied to with `slot_loaded`
+    ///
+    /// Its videos are opened right away, so `switch_slot` shows it without
+    /// decoding from scratch. Reloading the shown slot shows the new config.
+    #[serde(rename = "load_config_slot")]
+    LoadConfigSlot {
+        slot: ConfigSlot,
+        config: Box<EPConfig>,
         base_dir: String,
     },
 
+    /// Show the config preloaded in `slot`; the shown variant goes back to its slot
+    #[serde(rename = "switch_slot")]
+    SwitchSlot {
+        slot: ConfigSlot,
+    },
+
+    /// Open a material package (.eppkg)
+    #[serde(rename = "open_package")]
+    OpenPackage {
+        path: String,
+    },
+
+    /// Partially update the Arknights overlay options (no playback reset)
+    #[serde(rename = "update_overlay")]
+    UpdateOverlay {
+        patch: serde_json::Value,
+    },
+
+    /// Save the current configuration (relative paths resolve against base_dir)
+    #[serde(rename = "save_config")]
+    SaveConfig {
+        path: String,
+    },
+
+    /// Export the current configuration and its assets as a package
+    #[serde(rename = "export_package")]
+    ExportPackage {
+        path: String,
+    },
+
+    /// Convert a legacy flat config (replied to with `legacy_converted`)
+    #[serde(rename = "convert_legacy")]
+    ConvertLegacy {
+        config: serde_json::Value,
+    },
+
+    /// Request the config JSON Schema (replied to with `schema`)
+    #[serde(rename = "get_schema")]
+    GetSchema,
+
+    /// Write a starter material into `dir` (replied to with `template_created`)
+    #[serde(rename = "create_template")]
+    CreateTemplate {
+        dir: String,
+    },
+
     /// Control command
     #[serde(rename = "control")]
     Control(ControlCommand),
@@ -46,10 +302,174 @@ pub enum IpcMessage {
         transition_loop: String,
     },
 
-    /// Shutdown simulator
+    /// Crop the loop video to `[x, y, w, h]` in rotated video coordinates (null = full frame)
+    #[serde(rename = "set_cropbox")]
+    SetCropbox {
+        #[serde(default)]
+        cropbox: Option<(u32, u32, u32, u32)>,
+    },
+
+    /// Rotate the loop video by `rotation` degrees
+    #[serde(rename = "set_rotation")]
+    SetRotation {
+        rotation: i32,
+    },
+
+    /// Resize the window's inner area to `width`x`height` points
+    #[serde(rename = "set_window_size")]
+    SetWindowSize {
+        width: f32,
+        height: f32,
+    },
+
+    /// Move the window's outer top-left corner to `x`, `y` (points, desktop coordinates)
+    #[serde(rename = "set_window_position")]
+    SetWindowPosition {
+        x: f32,
+        y: f32,
+    },
+
+    /// Keep the window above other windows
+    #[serde(rename = "set_always_on_top")]
+    SetAlwaysOnTop {
+        enabled: bool,
+    },
+
+    /// Show `serial` as `{serial}` instead of the config's, or stop overriding it with None
+    #[serde(rename = "set_serial")]
+    SetSerial {
+        #[serde(default)]
+        serial: Option<String>,
+    },
+
+    /// Paint the device status bar over the preview, with this clock and battery level
+    #[serde(rename = "set_status_bar")]
+    SetStatusBar(StatusBar),
+
+    /// Show only the preview, without controls and title, optionally fullscreen
+    ///
+    /// Escape in the window leaves presentation mode.
+    #[serde(rename = "set_presentation_mode")]
+    SetPresentationMode {
+        enabled: bool,
+        #[serde(default)]
+        fullscreen: bool,
+    },
+
+    /// Change how often `state_update` is sent (default every 10 logic frames)
+    #[serde(rename = "set_state_updates")]
+    SetStateUpdates {
+        rate: StateUpdateRate,
+    },
+
+    /// Report unknown overlay option keys as errors (revalidates the current config)
+    #[serde(rename = "set_strict_validation")]
+    SetStrictValidation {
+        enabled: bool,
+    },
+
+    /// Render logic frame `frame` of `state` (counted from entering it), replied to with a PNG `frame`
+    ///
+    /// Replays from the start in fixed steps, so the result does not depend on
+    /// timing. Playback returns to its previous position afterwards.
+    #[serde(rename = "get_frame")]
+    GetFrame {
+        state: u8,
+        #[serde(default)]
+        frame: u64,
+    },
+
+    /// Render playback time `start_us..end_us` at `fps` into numbered image
+    /// files, replied to with `sequence_rendered`
+    ///
+    /// Replays from the start in fixed steps like `get_frame`. Frames go to
+    /// `dir` (relative paths resolve against base_dir), or a new temp
+    /// directory if omitted.
+    #[serde(rename = "render_sequence")]
+    RenderSequence {
+        start_us: i64,
+        end_us: i64,
+        fps: f32,
+        #[serde(default)]
+        format: FrameFormat,
+        #[serde(default)]
+        dir: Option<String>,
+    },
+
+    /// Capture the composed preview as PNG, written to `path` or returned as bytes
+    #[serde(rename = "screenshot")]
+    Screenshot {
+        #[serde(default)]
+        path: Option<String>,
+    },
+
+    /// Put the composed preview on the system clipboard as an image, replied to with `screenshot_copied`
+    #[serde(rename = "copy_screenshot")]
+    CopyScreenshot,
+
+    /// Stream the composed preview at `fps` frames per second (0 stops)
+    #[serde(rename = "stream_frames")]
+    StreamFrames {
+        fps: f32,
+        #[serde(default)]
+        format: FrameFormat,
+    },
+
+    /// Query the playback state, replied to with `state`
+    #[serde(rename = "get_state")]
+    GetState,
+
+    /// Query the loaded config and firmware profile, replied to with `config`
+    #[serde(rename = "get_config")]
+    GetConfig,
+
+    /// Query version and supported features, replied to with `capabilities`
+    #[serde(rename = "get_capabilities")]
+    GetCapabilities,
+
+    /// Receive events of these categories (all if empty), replied to with `subscriptions`
+    #[serde(rename = "subscribe")]
+    Subscribe {
+        #[serde(default)]
+        events: Vec<EventKind>,
+    },
+
+    /// Stop receiving events of these categories (all if empty), replied to with `subscriptions`
+    #[serde(rename = "unsubscribe")]
+    Unsubscribe {
+        #[serde(default)]
+        events: Vec<EventKind>,
+    },
+
+    /// Switch the wire encoding; takes effect right after this message, and
+    /// for replies right after `framing_changed`
+    #[serde(rename = "set_framing")]
+    SetFraming {
+        framing: Framing,
+    },
+
+    /// Apply several requests in the same frame, in order
+    ///
+    /// Also sent as a bare array of messages. Heartbeats, `set_framing`,
+    /// subscriptions and nested batches cannot be batched.
+    #[serde(rename = "batch")]
+    Batch(Vec<IpcMessage>),
+
+    /// Stop playback and close the simulator, replied to with `shutdown_ack`
+    /// right before the connection closes
     #[serde(rename = "shutdown")]
     Shutdown,
 
+    // === Either direction ===
+
+    /// Heartbeat, answered with `pong`
+    #[serde(rename = "ping")]
+    Ping,
+
+    /// Heartbeat reply
+    #[serde(rename = "pong")]
+    Pong,
+
     // === Simulator -> Editor ===
 
     /// State update notification
@@ -64,11 +484,167 @@ pub enum IpcMessage {
     #[serde(rename = "ready")]
     Ready,
 
+    /// Event categories the client is now subscribed to
+    #[serde(rename = "subscriptions")]
+    Subscriptions {
+        events: Vec<EventKind>,
+    },
+
+    /// Playback event for subscribed clients
+    #[serde(rename = "event")]
+    Event(Event),
+
+    /// The simulator stopped playback and is closing
+    #[serde(rename = "shutdown_ack")]
+    ShutdownAck,
+
+    /// Acknowledges `set_framing`; later messages use the new encoding
+    #[serde(rename = "framing_changed")]
+    FramingChanged {
+        framing: Framing,
+    },
+
+    /// Current playback state
+    #[serde(rename = "state")]
+    State {
+        state: u8,
+        state_name: String,
+        frame: u64,
+        is_playing: bool,
+        speed: f32,
+    },
+
+    /// Loaded config (None before the first load) and effective firmware profile
+    #[serde(rename = "config")]
+    Config {
+        config: Option<Box<EPConfig>>,
+        base_dir: String,
+        firmware: Box<FirmwareConfig>,
+    },
+
+    /// Simulator version and supported features
+    #[serde(rename = "capabilities")]
+    Capabilities {
+        version: String,
+        protocol_version: u32,
+        features: Vec<String>,
+        frame_formats: Vec<FrameFormat>,
+    },
+
+    /// Config variant preloaded into `slot`
+    #[serde(rename = "slot_loaded")]
+    SlotLoaded {
+        slot: ConfigSlot,
+    },
+
+    /// Configuration saved
+    #[serde(rename = "config_saved")]
+    ConfigSaved {
+        path: String,
+    },
+
+    /// Config validation results (sent after every load, may be empty)
+    #[serde(rename = "validation")]
+    Validation {
+        diagnostics: Vec<Diagnostic>,
+    },
+
+    /// Overlay fields changed in the simulator's own editor panel, as an `update_overlay` patch
+    #[serde(rename = "overlay_edited")]
+    OverlayEdited {
+        patch: serde_json::Value,
+    },
+
+    /// Package exported
+    #[serde(rename = "package_exported")]
+    PackageExported {
+        path: String,
+    },
+
+    /// Converted legacy config
+    #[serde(rename = "legacy_converted")]
+    LegacyConverted {
+        config: Box<EPConfig>,
+    },
+
+    /// JSON Schema of epconfig.json
+    #[serde(rename = "schema")]
+    Schema {
+        schema: serde_json::Value,
+    },
+
+    /// Starter material written
+    #[serde(rename = "template_created")]
+    TemplateCreated {
+        path: String,
+    },
+
+    /// Preview frame at the device resolution (streamed, or the reply to `get_frame`)
+    #[serde(rename = "frame")]
+    Frame {
+        width: u32,
+        height: u32,
+        format: FrameFormat,
+        /// Encoded image data
+        data: Bytes,
+        state: u8,
+        frame: u64,
+    },
+
+    /// Sequence rendered: `frames` files named `frame_00000.<ext>`, ... in `path`
+    #[serde(rename = "sequence_rendered")]
+    SequenceRendered {
+        path: String,
+        frames: u32,
+    },
+
+    /// Screenshot written to `path`, or its base64 PNG `data` if no path was given
+    #[serde(rename = "screenshot_taken")]
+    ScreenshotTaken {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        data: Option<Bytes>,
+    },
+
+    /// Screenshot placed on the system clipboard
+    #[serde(rename = "screenshot_copied")]
+    ScreenshotCopied,
+
+    /// Playback performance, sent every second while playing
+    #[serde(rename = "metrics")]
+    Metrics(MetricsReport),
+
+    /// Warning or error logged by the simulator
+    #[serde(rename = "log_record")]
+    LogRecord {
+        /// `warn` or `error`
+        level: String,
+        /// Module that logged it
+        target: String,
+        message: String,
+    },
+
+    /// The simulator panicked and is about to exit
+    #[serde(rename = "crashed")]
+    Crashed {
+        message: String,
+        /// Crash report file, if it could be written
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        report: Option<String>,
+    },
+
     /// Error occurred
     #[serde(rename = "error")]
     Error {
         code: i32,
         message: String,
+        /// Assets that failed to load (with `ASSET_LOAD_FAILED`)
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        assets: Vec<AssetIssue>,
+        /// Byte offset into the rejected message (with `PARSE_ERROR`), if known
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        offset: Option<usize>,
     },
 }
 
@@ -87,11 +663,68 @@ impl IpcMessage {
         IpcMessage::Ready
     }
 
+    /// Create a capabilities message for this build
+    pub fn capabilities() -> Self {
+        IpcMessage::Capabilities {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            features: FEATURES.iter().map(|f| f.to_string()).collect(),
+            frame_formats: vec![FrameFormat::Jpeg, FrameFormat::Png, FrameFormat::Raw],
+        }
+    }
+
     /// Create an error message
     pub fn error(code: i32, message: impl Into<String>) -> Self {
         IpcMessage::Error {
             code,
             message: message.into(),
+            assets: Vec::new(),
+            offset: None,
+        }
+    }
+
+    /// Create an error message for a request that could not be read or parsed
+    pub fn parse_error(message: impl Into<String>, offset: Option<usize>) -> Self {
+        IpcMessage::Error {
+            code: error_codes::PARSE_ERROR,
+            message: message.into(),
+            assets: Vec::new(),
+            offset,
+        }
+    }
+
+    /// Create an asset report error message
+    pub fn asset_errors(assets: Vec<AssetIssue>) -> Self {
+        IpcMessage::Error {
+            code: error_codes::ASSET_LOAD_FAILED,
+            message: format!("{} asset(s) failed to load", assets.len()),
+            assets,
+            offset: None,
+        }
+    }
+
+    /// Whether this message may be part of a batch
+    ///
+    /// Batches go to the app as a whole, so messages the connection handles
+    /// itself are left out.
+    pub fn is_batchable(&self) -> bool {
+        !matches!(
+            self,
+            IpcMessage::Batch(_)
+                | IpcMessage::Ping
+                | IpcMessage::Pong
+                | IpcMessage::SetFraming { .. }
+                | IpcMessage::Subscribe { .. }
+                | IpcMessage::Unsubscribe { .. }
+        )
+    }
+
+    /// Whether this message is, or a batch containing, `shutdown`
+    pub fn requests_shutdown(&self) -> bool {
+        match self {
+            IpcMessage::Shutdown => true,
+            IpcMessage::Batch(messages) => messages.iter().any(|msg| matches!(msg, IpcMessage::Shutdown)),
+            _ => false,
         }
     }
 
@@ -100,10 +733,93 @@ impl IpcMessage {
         serde_json::to_string(self)
     }
 
-    /// Deserialize from JSON string
+    /// Deserialize from JSON string; an array of messages is a batch
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        if s.trim_start().starts_with('[') {
+            return serde_json::from_str(s).map(IpcMessage::Batch);
+        }
+        serde_json::from_str(s)
+    }
+
+    /// Serialize to MessagePack, with field names like the JSON form
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        rmp_serde::to_vec_named(self)
+    }
+
+    /// Deserialize from MessagePack; an array of messages is a batch
+    pub fn from_msgpack(data: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        // fixarray, array 16 and array 32 markers
+        if matches!(data.first(), Some(0x90..=0x9f | 0xdc | 0xdd)) {
+            return rmp_serde::from_slice(data).map(IpcMessage::Batch);
+        }
+        rmp_serde::from_slice(data)
+    }
+}
+
+/// Client-chosen request id: a number or a string
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RequestId {
+    Number(i64),
+    Text(String),
+}
+
+/// A message on the wire, with the id of the request it belongs to
+///
+/// The id sits next to `type` and `payload`. Requests may carry one; every
+/// reply to such a request (including errors) carries it back. Broadcasts
+/// have none.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<RequestId>,
+    #[serde(flatten)]
+    pub message: IpcMessage,
+}
+
+impl Envelope {
+    /// A message that belongs to no request
+    pub fn new(message: IpcMessage) -> Self {
+        Self { id: None, message }
+    }
+
+    /// A reply to the request with `id`
+    pub fn reply(id: Option<RequestId>, message: IpcMessage) -> Self {
+        Self { id, message }
+    }
+
+    /// Serialize to JSON string (line-delimited)
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        // Flattening goes through an intermediate map; skip it when there is no id
+        match self.id {
+            None => self.message.to_json(),
+            Some(_) => serde_json::to_string(self),
+        }
+    }
+
+    /// Deserialize from JSON string; an array of messages is a batch without id
     pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        if s.trim_start().starts_with('[') {
+            return IpcMessage::from_json(s).map(Envelope::new);
+        }
         serde_json::from_str(s)
     }
+
+    /// Serialize to MessagePack, with field names like the JSON form
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        match self.id {
+            None => self.message.to_msgpack(),
+            Some(_) => rmp_serde::to_vec_named(self),
+        }
+    }
+
+    /// Deserialize from MessagePack; an array of messages is a batch without id
+    pub fn from_msgpack(data: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        if matches!(data.first(), Some(0x90..=0x9f | 0xdc | 0xdd)) {
+            return IpcMessage::from_msgpack(data).map(Envelope::new);
+        }
+        rmp_serde::from_slice(data)
+    }
 }
 
 /// Error codes
@@ -111,6 +827,10 @@ pub mod error_codes {
     pub const OK: i32 = 0;
     pub const INVALID_CONFIG: i32 = 1;
     pub const VIDEO_LOAD_FAILED: i32 = 2;
+    pub const SAVE_FAILED: i32 = 3;
+    pub const ASSET_LOAD_FAILED: i32 = 4;
+    pub const INVALID_REQUEST: i32 = 5;
+    pub const PARSE_ERROR: i32 = 6;
     pub const INTERNAL_ERROR: i32 = 100;
 }
 
@@ -128,10 +848,296 @@ mod tests {
         assert!(matches!(parsed, IpcMessage::Ready));
     }
 
+    #[test]
+    fn test_update_overlay_message() {
+        let json = r#"{"type": "update_overlay", "payload": {"patch": {"operator_name": "AMIYA"}}}"#;
+        let parsed = IpcMessage::from_json(json).unwrap();
+        match parsed {
+            IpcMessage::UpdateOverlay { patch } => assert_eq!(patch["operator_name"], "AMIYA"),
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validation_message() {
+        let msg = IpcMessage::Validation {
+            diagnostics: vec![Diagnostic {
+                severity: crate::config::Severity::Error,
+                path: "loop.file".to_string(),
+                message: "file not found".to_string(),
+            }],
+        };
+        let json = msg.to_json().unwrap();
+        assert!(json.contains(r#""type":"validation""#));
+        assert!(json.contains(r#""severity":"error""#));
+    }
+
+    #[test]
+    fn test_asset_errors_message() {
+        let msg = IpcMessage::asset_errors(vec![AssetIssue {
+            asset: "logo".to_string(),
+            path: "logo.png".to_string(),
+            reason: "No such file".to_string(),
+        }]);
+        let json = msg.to_json().unwrap();
+        assert!(json.contains(r#""code":4"#));
+        assert!(json.contains(r#""asset":"logo""#));
+
+        // Plain errors keep the old shape
+        let json = IpcMessage::error(error_codes::SAVE_FAILED, "denied").to_json().unwrap();
+        assert!(!json.contains("assets"));
+    }
+
+    #[test]
+    fn test_get_frame_message() {
+        let json = r#"{"type": "get_frame", "payload": {"state": 5, "frame": 30}}"#;
+        let parsed = IpcMessage::from_json(json).unwrap();
+        assert!(matches!(parsed, IpcMessage::GetFrame { state: 5, frame: 30 }));
+    }
+
+    #[test]
+    fn test_render_sequence_message() {
+        let json = r#"{"type": "render_sequence", "payload": {"start_us": 0, "end_us": 2000000, "fps": 30, "format": "png"}}"#;
+        match IpcMessage::from_json(json).unwrap() {
+            IpcMessage::RenderSequence { start_us, end_us, fps, format, dir } => {
+                assert_eq!((start_us, end_us, fps), (0, 2_000_000, 30.0));
+                assert_eq!(format, FrameFormat::Png);
+                assert!(dir.is_none());
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_screenshot_message() {
+        let parsed = IpcMessage::from_json(r#"{"type": "screenshot", "payload": {}}"#).unwrap();
+        assert!(matches!(parsed, IpcMessage::Screenshot { path: None }));
+
+        let msg = IpcMessage::ScreenshotTaken { path: Some("shot.png".to_string()), data: None };
+        assert_eq!(msg.to_json().unwrap(), r#"{"type":"screenshot_taken","payload":{"path":"shot.png"}}"#);
+    }
+
+    #[test]
+    fn test_crashed_message() {
+        let msg = IpcMessage::Crashed { message: "boom".to_string(), report: None };
+        assert_eq!(msg.to_json().unwrap(), r#"{"type":"crashed","payload":{"message":"boom"}}"#);
+    }
+
+    #[test]
+    fn test_stream_frames_message() {
+        let json = r#"{"type": "stream_frames", "payload": {"fps": 15}}"#;
+        match IpcMessage::from_json(json).unwrap() {
+            IpcMessage::StreamFrames { fps, format } => {
+                assert_eq!(fps, 15.0);
+                assert_eq!(format, FrameFormat::Jpeg);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        let json = r#"{"type": "stream_frames", "payload": {"fps": 0, "format": "raw"}}"#;
+        let parsed = IpcMessage::from_json(json).unwrap();
+        assert!(matches!(parsed, IpcMessage::StreamFrames { format: FrameFormat::Raw, .. }));
+    }
+
+    #[test]
+    fn test_capabilities_message() {
+        let json = IpcMessage::capabilities().to_json().unwrap();
+        assert!(json.contains(r#""type":"capabilities""#));
+        assert!(json.contains(r#""get_frame""#));
+        assert!(json.contains(r#""frame_formats":["jpeg","png","raw"]"#));
+
+        let parsed = IpcMessage::from_json(r#"{"type": "get_state"}"#).unwrap();
+        assert!(matches!(parsed, IpcMessage::GetState));
+    }
+
+    #[test]
+    fn test_batch_message() {
+        let json = r#"[
+            {"type": "set_transition", "payload": {"transition_in": "fade", "transition_loop": "move"}},
+            {"type": "update_overlay", "payload": {"patch": {"operator_name": "AMIYA"}}},
+            {"type": "control", "payload": "play"}
+        ]"#;
+        let IpcMessage::Batch(messages) = IpcMessage::from_json(json).unwrap() else {
+            panic!("expected a batch");
+        };
+        assert_eq!(messages.len(), 3);
+        assert!(matches!(messages[2], IpcMessage::Control(ControlCommand::Play)));
+        assert!(messages.iter().all(IpcMessage::is_batchable));
+
+        // One malformed entry rejects the whole batch
+        assert!(IpcMessage::from_json(r#"[{"type": "get_state"}, {"type": "nope"}]"#).is_err());
+
+        let batch = IpcMessage::Batch(vec![IpcMessage::GetState, IpcMessage::Shutdown]);
+        assert!(batch.requests_shutdown());
+        assert!(!batch.is_batchable());
+        let packed = rmp_serde::to_vec_named(&[IpcMessage::GetState, IpcMessage::Shutdown]).unwrap();
+        assert!(IpcMessage::from_msgpack(&packed).unwrap().requests_shutdown());
+        assert!(IpcMessage::from_json(&batch.to_json().unwrap()).unwrap().requests_shutdown());
+    }
+
+    #[test]
+    fn test_window_control_messages() {
+        let json = r#"{"type": "set_window_size", "payload": {"width": 400, "height": 720.5}}"#;
+        match IpcMessage::from_json(json).unwrap() {
+            IpcMessage::SetWindowSize { width, height } => assert_eq!((width, height), (400.0, 720.5)),
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        let json = r#"{"type": "set_presentation_mode", "payload": {"enabled": true}}"#;
+        let parsed = IpcMessage::from_json(json).unwrap();
+        assert!(matches!(parsed, IpcMessage::SetPresentationMode { enabled: true, fullscreen: false }));
+    }
+
+    #[test]
+    fn test_envelope_request_id() {
+        let json = r#"{"type": "get_frame", "payload": {"state": 5}, "id": 7}"#;
+        let envelope = Envelope::from_json(json).unwrap();
+        assert_eq!(envelope.id, Some(RequestId::Number(7)));
+        assert!(matches!(envelope.message, IpcMessage::GetFrame { state: 5, frame: 0 }));
+
+        let envelope = Envelope::from_json(r#"{"id": "q1", "type": "get_state"}"#).unwrap();
+        let reply = Envelope::reply(envelope.id, IpcMessage::error(error_codes::INVALID_REQUEST, "nope"));
+        let json = reply.to_json().unwrap();
+        assert!(json.contains(r#""id":"q1""#));
+        assert!(json.contains(r#""type":"error""#));
+        assert!(matches!(IpcMessage::from_json(&json).unwrap(), IpcMessage::Error { code: 5, .. }));
+
+        // No id field unless the request had one
+        let json = Envelope::new(IpcMessage::Ready).to_json().unwrap();
+        assert_eq!(json, r#"{"type":"ready"}"#);
+
+        let packed = Envelope::reply(Some(RequestId::Number(3)), IpcMessage::GetState).to_msgpack().unwrap();
+        let unpacked = Envelope::from_msgpack(&packed).unwrap();
+        assert_eq!(unpacked.id, Some(RequestId::Number(3)));
+        assert!(matches!(unpacked.message, IpcMessage::GetState));
+    }
+
+    #[test]
+    fn test_config_slot_messages() {
+        let json = r#"{"type": "load_config_slot", "payload": {"slot": "b", "config": {"loop": {"file": "loop.mp4"}}, "base_dir": "."}}"#;
+        match IpcMessage::from_json(json).unwrap() {
+            IpcMessage::LoadConfigSlot { slot, config, .. } => {
+                assert_eq!(slot, ConfigSlot::B);
+                assert_eq!(config.loop_config.file, "loop.mp4");
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        let parsed = IpcMessage::from_json(r#"{"type": "switch_slot", "payload": {"slot": "a"}}"#).unwrap();
+        assert!(matches!(parsed, IpcMessage::SwitchSlot { slot: ConfigSlot::A }));
+        assert!(IpcMessage::from_json(r#"{"type": "switch_slot", "payload": {"slot": "c"}}"#).is_err());
+    }
+
+    #[test]
+    fn test_msgpack_roundtrip() {
+        let msg = IpcMessage::Frame {
+            width: 2,
+            height: 1,
+            format: FrameFormat::Raw,
+            data: Bytes(vec![0, 1, 2, 3, 4, 5, 6, 7]),
+            state: 5,
+            frame: 42,
+        };
+        // Bytes travel raw in MessagePack and as base64 in JSON
+        let packed = msg.to_msgpack().unwrap();
+        assert!(packed.windows(8).any(|w| w == [0, 1, 2, 3, 4, 5, 6, 7]));
+        assert!(msg.to_json().unwrap().contains(r#""data":"AAECAwQFBgc=""#));
+
+        match IpcMessage::from_msgpack(&packed).unwrap() {
+            IpcMessage::Frame { data, frame, .. } => {
+                assert_eq!(data.0, vec![0, 1, 2, 3, 4, 5, 6, 7]);
+                assert_eq!(frame, 42);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        let json = IpcMessage::from_json(&msg.to_json().unwrap()).unwrap();
+        assert!(matches!(json, IpcMessage::Frame { data, .. } if data.0.len() == 8));
+
+        let packed = IpcMessage::Shutdown.to_msgpack().unwrap();
+        assert!(matches!(IpcMessage::from_msgpack(&packed).unwrap(), IpcMessage::Shutdown));
+    }
+
+    #[test]
+    fn test_event_message() {
+        let msg = IpcMessage::Event(Event::AnimationMilestone { milestone: Milestone::EntryComplete, frame: 120 });
+        let json = msg.to_json().unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"event","payload":{"event":"animation_milestone","milestone":"entry_complete","frame":120}}"#
+        );
+
+        let packed = msg.to_msgpack().unwrap();
+        match IpcMessage::from_msgpack(&packed).unwrap() {
+            IpcMessage::Event(event) => assert_eq!(event.kind(), EventKind::AnimationMilestone),
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        let json = r#"{"type": "subscribe", "payload": {"events": ["state_changed", "intro_ended"]}}"#;
+        match IpcMessage::from_json(json).unwrap() {
+            IpcMessage::Subscribe { events } => assert_eq!(events, [EventKind::StateChanged, EventKind::IntroEnded]),
+            other => panic!("unexpected message: {:?}", other),
+        }
+        let parsed = IpcMessage::from_json(r#"{"type": "unsubscribe", "payload": {}}"#).unwrap();
+        assert!(matches!(parsed, IpcMessage::Unsubscribe { events } if events.is_empty()));
+    }
+
+    #[test]
+    fn test_loop_transform_messages() {
+        let json = r#"{"type": "set_cropbox", "payload": {"cropbox": [10, 20, 360, 640]}}"#;
+        let parsed = IpcMessage::from_json(json).unwrap();
+        assert!(matches!(parsed, IpcMessage::SetCropbox { cropbox: Some((10, 20, 360, 640)) }));
+
+        let parsed = IpcMessage::from_json(r#"{"type": "set_cropbox", "payload": {"cropbox": null}}"#).unwrap();
+        assert!(matches!(parsed, IpcMessage::SetCropbox { cropbox: None }));
+
+        let parsed = IpcMessage::from_json(r#"{"type": "set_rotation", "payload": {"rotation": 90}}"#).unwrap();
+        assert!(matches!(parsed, IpcMessage::SetRotation { rotation: 90 }));
+    }
+
     #[test]
     fn test_control_command() {
         let msg = IpcMessage::Control(ControlCommand::Play);
         let json = msg.to_json().unwrap();
         assert!(json.contains("play"));
+
+        let json = r#"{"type": "control", "payload": {"step_frame": -3}}"#;
+        let parsed = IpcMessage::from_json(json).unwrap();
+        assert!(matches!(parsed, IpcMessage::Control(ControlCommand::StepFrame(-3))));
+
+        let json = r#"{"type": "control", "payload": {"set_speed": 0.25}}"#;
+        let parsed = IpcMessage::from_json(json).unwrap();
+        assert!(matches!(parsed, IpcMessage::Control(ControlCommand::SetSpeed(speed)) if speed == 0.25));
+    }
+
+    #[test]
+    fn test_state_update_rate() {
+        let json = r#"{"type": "set_state_updates", "payload": {"rate": {"every_frames": 1}}}"#;
+        let parsed = IpcMessage::from_json(json).unwrap();
+        assert!(matches!(parsed, IpcMessage::SetStateUpdates { rate: StateUpdateRate::EveryFrames(1) }));
+
+        let json = r#"{"type": "set_state_updates", "payload": {"rate": "on_change"}}"#;
+        let parsed = IpcMessage::from_json(json).unwrap();
+        assert!(matches!(parsed, IpcMessage::SetStateUpdates { rate: StateUpdateRate::OnChange }));
+    }
+
+    #[test]
+    fn test_set_serial_message() {
+        let parsed = IpcMessage::from_json(r#"{"type": "set_serial", "payload": {"serial": "EP-1"}}"#).unwrap();
+        assert!(matches!(parsed, IpcMessage::SetSerial { serial: Some(ref s) } if s == "EP-1"));
+        let parsed = IpcMessage::from_json(r#"{"type": "set_serial", "payload": {}}"#).unwrap();
+        assert!(matches!(parsed, IpcMessage::SetSerial { serial: None }));
+    }
+
+    #[test]
+    fn test_set_status_bar_message() {
+        let json = r#"{"type": "set_status_bar", "payload": {"enabled": true, "battery": 15, "time": "23:59"}}"#;
+        match IpcMessage::from_json(json).unwrap() {
+            IpcMessage::SetStatusBar(status_bar) => {
+                assert!(status_bar.enabled);
+                assert_eq!((status_bar.battery, status_bar.time.as_str()), (15, "23:59"));
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
     }
 }
@@ -1,273 +1,743 @@
 //! IPC Server module
 //!
-//! Implements Named Pipe server for Windows and stdin/stdout fallback.
+//! Implements a local socket server (Named Pipe on Windows, Unix domain
+//! socket elsewhere), TCP and WebSocket servers and stdin/stdout fallback.
+//! All of them speak the same JSON protocol; the HTTP endpoint translates
+//! its requests to it.
+//!
+//! Socket transports accept any number of clients (say the editor and a
+//! debugging tool). Their messages reach the app one at a time through a
+//! single channel; replies go back to the requesting client, tagged with the
+//! request's id if it had one, and everything else is broadcast, except
+//! events, which only reach clients subscribed to their category. Listeners
+//! keep accepting after clients leave, so a restarted editor can reconnect
+//! to the running preview.
 
-use std::io::{BufRead, BufReader, Write};
-use std::sync::mpsc::{Receiver, Sender};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::io::BufReader;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::cell::Cell;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use anyhow::Result;
+use interprocess::local_socket::{GenericFilePath, GenericNamespaced, Name, ToFsName, ToNsName};
+use interprocess::TryClone;
 use tracing::{info, warn, error, debug};
 
-#[cfg(windows)]
-use interprocess::TryClone;
+use super::connection::{Connection, Incoming, LineConnection, Payload, WebSocketConnection};
+use super::http::HttpConnection;
+use super::logging::forward_logs;
+use super::protocol::{Envelope, EventKind, Framing, IpcMessage, RequestId};
+
+/// How long a connection waits for client input before flushing outgoing messages
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How often listeners check for new clients and the end of the session
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often the server pings the client
+const PING_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Default time without client messages before the editor counts as gone
+const DEFAULT_HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default time to wait for a client to reconnect after the last one left
+const DEFAULT_RECONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long the app may take to acknowledge a shutdown request
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Identifies a connected client
+pub type ClientId = u64;
 
-use super::protocol::IpcMessage;
+/// Where replies to a request go: the client that sent it and the request's id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplyTo {
+    pub client: ClientId,
+    pub id: Option<RequestId>,
+}
+
+/// Message from the app to the clients
+#[derive(Debug)]
+enum Outgoing {
+    Broadcast(IpcMessage),
+    To(ReplyTo, IpcMessage),
+}
+
+/// Most events and log records kept for the first client
+const BACKLOG_LIMIT: usize = 64;
+
+/// Most messages queued for a client that is not reading them
+const CLIENT_QUEUE_LIMIT: usize = 256;
+
+/// Broadcasts sent before the first client connected
+///
+/// Only what is still useful is kept: the latest state update and the most
+/// recent events and log records. Metrics describe a moment nobody saw and
+/// are dropped.
+#[derive(Default)]
+struct Backlog {
+    state: Option<IpcMessage>,
+    messages: VecDeque<IpcMessage>,
+}
+
+impl Backlog {
+    fn push(&mut self, msg: IpcMessage) {
+        match msg {
+            IpcMessage::StateUpdate { .. } => self.state = Some(msg),
+            IpcMessage::Metrics(_) => {}
+            msg => {
+                if self.messages.len() == BACKLOG_LIMIT {
+                    self.messages.pop_front();
+                }
+                self.messages.push_back(msg);
+            }
+        }
+    }
+
+    /// Kept messages in the order to send them, the state update last
+    fn drain(&mut self) -> impl Iterator<Item = IpcMessage> + '_ {
+        self.messages.drain(..).chain(self.state.take())
+    }
+}
+
+/// Connected clients and their outgoing queues
+#[derive(Default)]
+struct Clients {
+    queues: HashMap<ClientId, SyncSender<Envelope>>,
+    next_id: ClientId,
+    backlog: Backlog,
+    connected_once: bool,
+    /// When the last client left, while nobody is connected
+    empty_since: Option<Instant>,
+}
+
+impl Clients {
+    /// Queue a message for one client, dropping or disconnecting it if it is behind
+    fn queue(&mut self, id: ClientId, envelope: Envelope) {
+        let Some(queue) = self.queues.get(&id) else { return };
+        let droppable = matches!(
+            envelope.message,
+            IpcMessage::Frame { .. } | IpcMessage::Metrics(_) | IpcMessage::StateUpdate { .. }
+        );
+        match queue.try_send(envelope) {
+            Ok(()) | Err(TrySendError::Disconnected(_)) => {}
+            Err(TrySendError::Full(_)) if droppable => debug!("Client {} is behind, dropped a message", id),
+            Err(TrySendError::Full(_)) => {
+                warn!("Client {} is not reading its messages, disconnecting it", id);
+                // Its session sees the queue close and ends
+                self.queues.remove(&id);
+            }
+        }
+    }
+}
+
+/// Client registry shared by the listener, client threads and the dispatcher
+#[derive(Default)]
+struct Hub {
+    clients: Mutex<Clients>,
+    shutdown: AtomicBool,
+    /// The app acknowledged a shutdown request and closes by itself
+    shutdown_acknowledged: AtomicBool,
+}
+
+impl Hub {
+    /// Register a client, handing it the backlog if it is the first
+    fn register(&self) -> (ClientId, Receiver<Envelope>) {
+        let (tx, rx) = mpsc::sync_channel(CLIENT_QUEUE_LIMIT);
+        let mut clients = self.clients.lock().unwrap();
+        let id = clients.next_id;
+        clients.next_id += 1;
+        for msg in clients.backlog.drain() {
+            let _ = tx.try_send(Envelope::new(msg));
+        }
+        clients.queues.insert(id, tx);
+        clients.connected_once = true;
+        clients.empty_since = None;
+        (id, rx)
+    }
+
+    fn unregister(&self, id: ClientId) {
+        let mut clients = self.clients.lock().unwrap();
+        clients.queues.remove(&id);
+        if clients.queues.is_empty() {
+            clients.empty_since = Some(Instant::now());
+        }
+    }
+
+    /// Queue a message from the app for its recipients
+    ///
+    /// A client whose queue is full misses frames, metrics and state
+    /// updates, which the next one replaces; anything else would leave it
+    /// out of step, so it is disconnected instead.
+    fn route(&self, outgoing: Outgoing) {
+        let mut clients = self.clients.lock().unwrap();
+        match outgoing {
+            Outgoing::To(to, msg) => clients.queue(to.client, Envelope::reply(to.id, msg)),
+            Outgoing::Broadcast(msg) if !clients.connected_once => clients.backlog.push(msg),
+            Outgoing::Broadcast(msg) => {
+                let ids: Vec<ClientId> = clients.queues.keys().copied().collect();
+                for id in ids {
+                    clients.queue(id, Envelope::new(msg.clone()));
+                }
+            }
+        }
+    }
+
+    /// Whether a client asked to shut down, or nobody reconnected within
+    /// `reconnect_timeout` of the last client leaving
+    fn finished(&self, reconnect_timeout: Duration) -> bool {
+        let clients = self.clients.lock().unwrap();
+        self.shutdown.load(Ordering::Relaxed)
+            || clients.empty_since.is_some_and(|since| since.elapsed() >= reconnect_timeout)
+    }
+}
 
 /// IPC Server for communication with Python editor
 pub struct IpcServer {
     /// Channel to send messages to the main thread
-    to_app: Sender<IpcMessage>,
-    /// Channel to receive messages from the main thread
-    from_app: Receiver<IpcMessage>,
+    to_app: Sender<(ReplyTo, IpcMessage)>,
+    /// Connected clients, fed by a dispatcher thread reading from the main thread
+    hub: Arc<Hub>,
+    /// Drop a client that answered pings but has been silent this long
+    heartbeat_timeout: Option<Duration>,
+    /// Stop listening when no client reconnects this long after the last one left
+    reconnect_timeout: Duration,
+    /// Token WebSocket clients must present
+    token: Option<String>,
 }
 
 impl IpcServer {
     /// Create a new IPC server
-    pub fn new(to_app: Sender<IpcMessage>, from_app: Receiver<IpcMessage>) -> Self {
-        Self { to_app, from_app }
+    fn new(to_app: Sender<(ReplyTo, IpcMessage)>, from_app: Receiver<Outgoing>) -> Self {
+        let hub = Arc::new(Hub::default());
+        let dispatch_hub = hub.clone();
+        std::thread::spawn(move || {
+            for outgoing in from_app {
+                dispatch_hub.route(outgoing);
+            }
+        });
+        Self {
+            to_app,
+            hub,
+            heartbeat_timeout: Some(DEFAULT_HEARTBEAT_TIMEOUT),
+            reconnect_timeout: DEFAULT_RECONNECT_TIMEOUT,
+            token: None,
+        }
     }
 
-    /// Run the server using stdin/stdout
-    pub fn run_stdio(&mut self) -> Result<()> {
-        info!("Starting stdio IPC server");
+    /// Require WebSocket clients to connect with `?token=<token>`
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
 
-        let stdin = std::io::stdin();
-        let mut stdout = std::io::stdout();
-        let reader = BufReader::new(stdin.lock());
+    /// Set the heartbeat timeout (None never drops a silent client)
+    pub fn with_heartbeat_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.heartbeat_timeout = timeout;
+        self
+    }
 
-        // Send ready message
-        let ready_msg = IpcMessage::ready();
-        if let Ok(json) = ready_msg.to_json() {
-            let _ = writeln!(stdout, "{}", json);
-            let _ = stdout.flush();
-        }
+    /// Set how long to wait for a client to reconnect before stopping
+    pub fn with_reconnect_timeout(mut self, timeout: Duration) -> Self {
+        self.reconnect_timeout = timeout;
+        self
+    }
 
-        // Read messages from stdin
-        for line in reader.lines() {
-            match line {
-                Ok(line) => {
-                    if line.trim().is_empty() {
-                        continue;
-                    }
+    /// Whether the server stopped because the app acknowledged a shutdown request
+    pub fn shutdown_acknowledged(&self) -> bool {
+        self.hub.shutdown_acknowledged.load(Ordering::Relaxed)
+    }
 
-                    debug!("Received: {}", line);
+    /// Bind a transport and serve it on the calling thread
+    #[cfg(test)]
+    fn run(&mut self, transport: IpcTransport) -> std::io::Result<()> {
+        self.serve(IpcListener::bind(&transport)?);
+        Ok(())
+    }
 
-                    match IpcMessage::from_json(&line) {
-                        Ok(msg) => {
-                            if matches!(msg, IpcMessage::Shutdown) {
-                                info!("Received shutdown command");
-                                break;
-                            }
+    /// Serve clients of a bound listener until the session ends
+    fn serve(&mut self, listener: IpcListener) {
+        use interprocess::local_socket::traits::{Listener, Stream};
 
-                            if self.to_app.send(msg).is_err() {
-                                error!("Failed to send message to app");
-                                break;
-                            }
-                        }
-                        Err(e) => {
-                            warn!("Failed to parse message: {}", e);
-                            let error_msg = IpcMessage::error(
-                                super::protocol::error_codes::INTERNAL_ERROR,
-                                format!("Parse error: {}", e),
-                            );
-                            if let Ok(json) = error_msg.to_json() {
-                                let _ = writeln!(stdout, "{}", json);
-                                let _ = stdout.flush();
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    error!("Failed to read from stdin: {}", e);
-                    break;
-                }
+        match listener {
+            IpcListener::Stdio => {
+                let (id, outgoing) = self.hub.register();
+                let mut session = self.session(id, outgoing);
+                session.serve(&mut LineConnection::new(BufReader::new(std::io::stdin()), std::io::stdout()));
+            }
+            IpcListener::LocalSocket(listener) => self.accept_clients(|| {
+                let stream = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                let reader = BufReader::new(stream.try_clone()?);
+                Ok(LineConnection::new(reader, stream))
+            }),
+            IpcListener::Tcp(listener) => self.accept_clients(|| {
+                let (stream, peer) = listener.accept()?;
+                info!("Client connected: {}", peer);
+                stream.set_nonblocking(false)?;
+                // Messages are small; don't let Nagle hold state updates back
+                let _ = stream.set_nodelay(true);
+                let reader = BufReader::new(stream.try_clone()?);
+                Ok(LineConnection::new(reader, stream))
+            }),
+            IpcListener::WebSocket(listener) => {
+                let token = self.token.clone();
+                self.accept_clients(|| {
+                    let (stream, peer) = listener.accept()?;
+                    info!("Client connected: {}", peer);
+                    stream.set_nonblocking(false)?;
+                    let _ = stream.set_nodelay(true);
+                    WebSocketConnection::accept(stream, token.as_deref())
+                })
+            }
+            IpcListener::Http(listener) => {
+                // HTTP clients leave after every request, so the server
+                // runs until the app shuts down
+                self.reconnect_timeout = Duration::MAX;
+                self.accept_clients(|| {
+                    let (stream, peer) = listener.accept()?;
+                    debug!("HTTP request from {}", peer);
+                    stream.set_nonblocking(false)?;
+                    Ok(HttpConnection::new(stream))
+                })
             }
+        }
+
+        info!("IPC server stopped");
+    }
 
-            // Check for outgoing messages
-            while let Ok(msg) = self.from_app.try_recv() {
-                if let Ok(json) = msg.to_json() {
-                    let _ = writeln!(stdout, "{}", json);
-                    let _ = stdout.flush();
+    /// Serve each accepted client on its own thread until the session ends
+    ///
+    /// `accept` must not block, returning `WouldBlock` if nobody is waiting.
+    fn accept_clients<C: Connection + Send + 'static>(&mut self, mut accept: impl FnMut() -> std::io::Result<C>) {
+        while !self.hub.finished(self.reconnect_timeout) {
+            match accept() {
+                Ok(mut connection) => {
+                    let (id, outgoing) = self.hub.register();
+                    info!("Client {} connected", id);
+                    let mut session = self.session(id, outgoing);
+                    let hub = self.hub.clone();
+                    std::thread::spawn(move || {
+                        session.serve(&mut connection);
+                        hub.unregister(id);
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => std::thread::sleep(ACCEPT_INTERVAL),
+                Err(e) => {
+                    error!("Failed to accept connection: {}", e);
+                    std::thread::sleep(ACCEPT_INTERVAL);
                 }
             }
         }
-
-        info!("Stdio IPC server stopped");
-        Ok(())
     }
 
-    /// Run the server using Windows Named Pipe
-    #[cfg(windows)]
-    pub fn run_named_pipe(&mut self, pipe_name: &str) -> Result<()> {
-        use interprocess::local_socket::{
-            GenericNamespaced, ListenerOptions, ToNsName,
-            traits::Listener,
-        };
+    fn session(&self, id: ClientId, outgoing: Receiver<Envelope>) -> ClientSession {
+        ClientSession {
+            id,
+            to_app: self.to_app.clone(),
+            outgoing,
+            hub: self.hub.clone(),
+            heartbeat_timeout: self.heartbeat_timeout,
+        }
+    }
+}
 
-        info!("Starting Named Pipe IPC server: {}", pipe_name);
-
-        // Create the named pipe listener
-        let name = pipe_name.to_ns_name::<GenericNamespaced>()?;
-        let listener = ListenerOptions::new()
-            .name(name)
-            .create_sync()?;
-
-        info!("Named pipe server listening");
-
-        // Accept a single connection
-        match listener.accept() {
-            Ok(mut stream) => {
-                info!("Client connected");
-
-                // Send ready message
-                let ready_msg = IpcMessage::ready();
-                if let Ok(json) = ready_msg.to_json() {
-                    let mut msg = json;
-                    msg.push('\n');
-                    if let Err(e) = stream.write_all(msg.as_bytes()) {
-                        error!("Failed to send ready message: {}", e);
-                        return Ok(());
-                    }
-                }
+/// One client's side of the server
+struct ClientSession {
+    id: ClientId,
+    to_app: Sender<(ReplyTo, IpcMessage)>,
+    /// Messages from the app for this client
+    outgoing: Receiver<Envelope>,
+    hub: Arc<Hub>,
+    heartbeat_timeout: Option<Duration>,
+}
 
-                // Use buffered reader for the stream
-                let reader_stream = stream.try_clone()?;
-                let mut reader = BufReader::new(reader_stream);
-                let mut line = String::new();
+impl ClientSession {
+    /// Exchange messages with the client until it disconnects, its
+    /// `shutdown` is acknowledged or it stops answering pings
+    ///
+    /// The heartbeat timeout only applies once the client has answered a
+    /// ping, so clients that predate the heartbeat are never dropped.
+    fn serve(&mut self, connection: &mut impl Connection) {
+        let mut framing = Framing::Json;
+        let mut subscriptions = BTreeSet::new();
 
-                loop {
-                    line.clear();
+        // Send ready message
+        if let Err(e) = send_message(connection, framing, &Envelope::new(IpcMessage::ready())) {
+            error!("Failed to send ready message: {}", e);
+            return;
+        }
 
-                    // Try to read a line (non-blocking would be better but this works)
-                    match reader.read_line(&mut line) {
-                        Ok(0) => {
-                            // EOF - client disconnected
-                            info!("Client disconnected");
-                            break;
-                        }
-                        Ok(_) => {
-                            let trimmed = line.trim();
-                            if trimmed.is_empty() {
-                                continue;
-                            }
+        let ping_interval = match self.heartbeat_timeout {
+            Some(timeout) => PING_INTERVAL.min(timeout / 4),
+            None => PING_INTERVAL,
+        };
+        let mut last_ping = Instant::now();
+        let mut last_seen = Instant::now();
+        let mut answers_pings = false;
+        let mut shutdown_requested: Option<Instant> = None;
 
-                            debug!("Received: {}", trimmed);
+        while !self.hub.shutdown.load(Ordering::Relaxed) {
+            if last_ping.elapsed() >= ping_interval {
+                last_ping = Instant::now();
+                if let Err(e) = send_message(connection, framing, &Envelope::new(IpcMessage::Ping)) {
+                    error!("Failed to write to client {}: {}", self.id, e);
+                    break;
+                }
+            }
+            if let Some(timeout) = self.heartbeat_timeout.filter(|_| answers_pings) {
+                if last_seen.elapsed() > timeout {
+                    warn!("Client {} did not respond for {:?}", self.id, timeout);
+                    break;
+                }
+            }
+            if shutdown_requested.is_some_and(|requested| requested.elapsed() > SHUTDOWN_TIMEOUT) {
+                warn!("Shutdown was not acknowledged within {:?}", SHUTDOWN_TIMEOUT);
+                self.hub.shutdown.store(true, Ordering::Relaxed);
+                break;
+            }
 
-                            match IpcMessage::from_json(trimmed) {
-                                Ok(msg) => {
-                                    if matches!(msg, IpcMessage::Shutdown) {
-                                        info!("Received shutdown command");
-                                        break;
-                                    }
+            match connection.recv_timeout(POLL_INTERVAL) {
+                Ok(Incoming::Closed) => {
+                    // EOF - client disconnected
+                    info!("Client {} disconnected", self.id);
+                    break;
+                }
+                Ok(Incoming::Idle) => {}
+                Ok(Incoming::Malformed(e)) => {
+                    warn!("Skipped malformed message from client {}: {}", self.id, e);
+                    last_seen = Instant::now();
+                    let error_msg = IpcMessage::parse_error(e.message, e.offset);
+                    let _ = send_message(connection, framing, &Envelope::new(error_msg));
+                }
+                Ok(Incoming::Message(payload)) => {
+                    debug!("Received from client {}: {:?}", self.id, payload);
+                    last_seen = Instant::now();
 
-                                    if self.to_app.send(msg).is_err() {
-                                        error!("Failed to send message to app");
-                                        break;
-                                    }
+                    match payload.decode() {
+                        Ok(Envelope { id, message: msg }) => {
+                            // A batch reaches the app as one message, so it is applied within one frame
+                            if let IpcMessage::Batch(messages) = &msg {
+                                if !messages.iter().all(IpcMessage::is_batchable) {
+                                    let error_msg = IpcMessage::error(
+                                        super::protocol::error_codes::INVALID_REQUEST,
+                                        "Batches cannot hold ping, pong, set_framing, subscribe, unsubscribe or batch",
+                                    );
+                                    let _ = send_message(connection, framing, &Envelope::reply(id, error_msg));
+                                    continue;
                                 }
-                                Err(e) => {
-                                    warn!("Failed to parse message: {}", e);
+                            }
+                            // The app winds down and acknowledges; the session
+                            // ends once the acknowledgement is out
+                            if msg.requests_shutdown() {
+                                info!("Received shutdown command from client {}", self.id);
+                                shutdown_requested.get_or_insert_with(Instant::now);
+                            }
+                            // Heartbeats are answered here, the app never sees them
+                            if matches!(msg, IpcMessage::Pong) {
+                                answers_pings = true;
+                                continue;
+                            }
+                            if matches!(msg, IpcMessage::Ping) {
+                                let _ = send_message(connection, framing, &Envelope::reply(id, IpcMessage::Pong));
+                                continue;
+                            }
+                            // The reader already follows the client's switch;
+                            // replies switch after the acknowledgement
+                            if let IpcMessage::SetFraming { framing: requested } = msg {
+                                info!("Switching client {} to {:?} framing", self.id, requested);
+                                let reply = IpcMessage::FramingChanged { framing: requested };
+                                let _ = send_message(connection, framing, &Envelope::reply(id, reply));
+                                framing = requested;
+                                continue;
+                            }
+                            // Subscriptions only filter the events sent to this client
+                            if let IpcMessage::Subscribe { events } | IpcMessage::Unsubscribe { events } = &msg {
+                                let events = if events.is_empty() { EventKind::ALL.to_vec() } else { events.clone() };
+                                if matches!(msg, IpcMessage::Subscribe { .. }) {
+                                    subscriptions.extend(events);
+                                } else {
+                                    subscriptions.retain(|kind| !events.contains(kind));
                                 }
+                                let reply = IpcMessage::Subscriptions { events: subscriptions.iter().copied().collect() };
+                                let _ = send_message(connection, framing, &Envelope::reply(id, reply));
+                                continue;
                             }
-                        }
-                        Err(e) => {
-                            error!("Failed to read from pipe: {}", e);
-                            break;
-                        }
-                    }
 
-                    // Send any outgoing messages
-                    while let Ok(msg) = self.from_app.try_recv() {
-                        if let Ok(json) = msg.to_json() {
-                            let mut out = json;
-                            out.push('\n');
-                            if let Err(e) = stream.write_all(out.as_bytes()) {
-                                error!("Failed to write to pipe: {}", e);
+                            if self.to_app.send((ReplyTo { client: self.id, id }, msg)).is_err() {
+                                error!("Failed to send message to app");
                                 break;
                             }
                         }
+                        Err(e) => {
+                            warn!("Failed to parse message: {}", e);
+                            let error_msg = IpcMessage::parse_error(format!("Parse error: {}", e.message), e.offset);
+                            let _ = send_message(connection, framing, &Envelope::reply(payload.request_id(), error_msg));
+                        }
                     }
                 }
+                Err(e) => {
+                    error!("Failed to read from client {}: {}", self.id, e);
+                    break;
+                }
             }
-            Err(e) => {
-                error!("Failed to accept connection: {}", e);
+
+            // Send any outgoing messages
+            loop {
+                let envelope = match self.outgoing.try_recv() {
+                    Ok(envelope) => envelope,
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return,
+                };
+                if let IpcMessage::Event(ref event) = envelope.message {
+                    if !subscriptions.contains(&event.kind()) {
+                        continue;
+                    }
+                }
+                if let Err(e) = send_message(connection, framing, &envelope) {
+                    error!("Failed to write to client {}: {}", self.id, e);
+                    break;
+                }
+                if matches!(envelope.message, IpcMessage::ShutdownAck) {
+                    self.hub.shutdown_acknowledged.store(true, Ordering::Relaxed);
+                    self.hub.shutdown.store(true, Ordering::Relaxed);
+                    break;
+                }
             }
         }
-
-        info!("Named Pipe IPC server stopped");
-        Ok(())
     }
+}
+
+/// Serialize and send one message
+fn send_message(connection: &mut impl Connection, framing: Framing, envelope: &Envelope) -> std::io::Result<()> {
+    connection.send(&Payload::encode(envelope, framing)?)
+}
 
-    #[cfg(not(windows))]
-    pub fn run_named_pipe(&mut self, _pipe_name: &str) -> Result<()> {
-        anyhow::bail!("Named pipes are only supported on Windows")
+/// Map a `--pipe` name to a local socket name
+///
+/// Windows uses `\\.\pipe\<name>`. On Unix a name containing `/` is the
+/// socket file path; other names go to the abstract namespace on Linux and
+/// to `/tmp/<name>` on other systems.
+fn local_socket_name(pipe_name: &str) -> std::io::Result<Name<'static>> {
+    if cfg!(unix) && pipe_name.contains('/') {
+        pipe_name.to_string().to_fs_name::<GenericFilePath>()
+    } else {
+        pipe_name.to_string().to_ns_name::<GenericNamespaced>()
     }
 }
 
 /// IPC message receiver for the main application
 pub struct IpcReceiver {
-    rx: Receiver<IpcMessage>,
+    rx: Receiver<(ReplyTo, IpcMessage)>,
+    closed: Cell<bool>,
 }
 
 impl IpcReceiver {
-    pub fn new(rx: Receiver<IpcMessage>) -> Self {
-        Self { rx }
+    fn new(rx: Receiver<(ReplyTo, IpcMessage)>) -> Self {
+        Self { rx, closed: Cell::new(false) }
+    }
+
+    /// Try to receive a message and its sender without blocking
+    pub fn try_recv(&self) -> Option<(ReplyTo, IpcMessage)> {
+        match self.rx.try_recv() {
+            Ok(msg) => Some(msg),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.closed.set(true);
+                None
+            }
+        }
     }
 
-    /// Try to receive a message without blocking
-    pub fn try_recv(&self) -> Option<IpcMessage> {
-        self.rx.try_recv().ok()
+    /// Whether the server has stopped, as seen by the last `try_recv`
+    pub fn is_closed(&self) -> bool {
+        self.closed.get()
     }
 }
 
 /// IPC message sender for the main application
+#[derive(Clone)]
 pub struct IpcSender {
-    tx: Sender<IpcMessage>,
+    tx: Sender<Outgoing>,
 }
 
 impl IpcSender {
-    pub fn new(tx: Sender<IpcMessage>) -> Self {
+    fn new(tx: Sender<Outgoing>) -> Self {
         Self { tx }
     }
 
-    /// Send a message to the IPC server
+    /// Send a message to every client
     pub fn send(&self, msg: IpcMessage) -> bool {
-        self.tx.send(msg).is_ok()
+        self.tx.send(Outgoing::Broadcast(msg)).is_ok()
+    }
+
+    /// Send a message to one client in reply to its request
+    pub fn reply(&self, to: &ReplyTo, msg: IpcMessage) -> bool {
+        self.tx.send(Outgoing::To(to.clone(), msg)).is_ok()
     }
 }
 
-/// Start IPC server in a background thread
-pub fn start_ipc_server(
-    pipe_name: Option<String>,
-    use_stdio: bool,
-) -> Option<(IpcReceiver, IpcSender)> {
-    if !use_stdio && pipe_name.is_none() {
-        return None;
+/// How the IPC server talks to the editor
+#[derive(Debug, Clone)]
+pub enum IpcTransport {
+    /// stdin/stdout
+    Stdio,
+    /// Named Pipe (Windows) or Unix domain socket
+    LocalSocket(String),
+    /// TCP socket
+    Tcp(SocketAddr),
+    /// WebSocket server, for browser-based tools
+    WebSocket(SocketAddr),
+    /// HTTP REST endpoint, for scripts and CI jobs
+    Http(SocketAddr),
+}
+
+impl IpcTransport {
+    /// Parse a `--tcp` value: a port (listens on localhost) or `host:port`
+    pub fn tcp(value: &str) -> Result<Self> {
+        Ok(IpcTransport::Tcp(listen_addr(value)?))
     }
 
+    /// Parse a `--websocket` value, like `tcp`
+    pub fn websocket(value: &str) -> Result<Self> {
+        Ok(IpcTransport::WebSocket(listen_addr(value)?))
+    }
+
+    /// Parse an `--http` value, like `tcp`
+    pub fn http(value: &str) -> Result<Self> {
+        Ok(IpcTransport::Http(listen_addr(value)?))
+    }
+}
+
+/// A port (localhost) or `host:port` to listen on
+fn listen_addr(value: &str) -> Result<SocketAddr> {
+    match value.parse::<u16>() {
+        Ok(port) => Ok(SocketAddr::from(([127, 0, 0, 1], port))),
+        Err(_) => value
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No address for {}", value)),
+    }
+}
+
+/// A transport's listener, bound before the server thread starts
+enum IpcListener {
+    Stdio,
+    LocalSocket(interprocess::local_socket::Listener),
+    Tcp(TcpListener),
+    WebSocket(TcpListener),
+    Http(TcpListener),
+}
+
+impl IpcListener {
+    /// Start listening on a transport
+    fn bind(transport: &IpcTransport) -> std::io::Result<Self> {
+        use interprocess::local_socket::{ListenerNonblockingMode, ListenerOptions};
+
+        /// A non-blocking TCP listener
+        fn tcp(addr: SocketAddr) -> std::io::Result<TcpListener> {
+            let listener = TcpListener::bind(addr)?;
+            listener.set_nonblocking(true)?;
+            Ok(listener)
+        }
+
+        let listener = match transport {
+            IpcTransport::Stdio => IpcListener::Stdio,
+            // Replace a socket file left behind by a crashed instance
+            IpcTransport::LocalSocket(name) => IpcListener::LocalSocket(
+                ListenerOptions::new()
+                    .name(local_socket_name(name)?)
+                    .try_overwrite(true)
+                    .nonblocking(ListenerNonblockingMode::Accept)
+                    .create_sync()?,
+            ),
+            IpcTransport::Tcp(addr) => IpcListener::Tcp(tcp(*addr)?),
+            IpcTransport::WebSocket(addr) => IpcListener::WebSocket(tcp(*addr)?),
+            IpcTransport::Http(addr) => IpcListener::Http(tcp(*addr)?),
+        };
+        match &listener {
+            IpcListener::Stdio => info!("IPC server on stdin/stdout"),
+            IpcListener::LocalSocket(_) => info!("Local socket server listening"),
+            IpcListener::Tcp(l) => info!("TCP server listening on {}", l.local_addr()?),
+            IpcListener::WebSocket(l) => info!("WebSocket server listening on ws://{}", l.local_addr()?),
+            IpcListener::Http(l) => info!("HTTP server listening on http://{}", l.local_addr()?),
+        }
+        Ok(listener)
+    }
+}
+
+/// IPC server settings
+#[derive(Debug, Clone)]
+pub struct IpcOptions {
+    pub transport: IpcTransport,
+    /// Exit when a client that answers pings goes silent this long (None = never)
+    pub heartbeat_timeout: Option<Duration>,
+    /// Exit when no client reconnects this long after the last one left
+    pub reconnect_timeout: Duration,
+    /// Token WebSocket clients must pass as `?token=...`
+    pub token: Option<String>,
+}
+
+/// Start IPC server in a background thread
+///
+/// The listener is bound before this returns, so a taken port or socket
+/// name is an error here rather than a log line.
+///
+/// The simulator belongs to the editor: once no client is left and none
+/// reconnects in time, or a shutdown goes unacknowledged, the process exits.
+/// After an acknowledged shutdown the app closes its window itself.
+pub fn start_ipc_server(options: IpcOptions) -> std::io::Result<(IpcReceiver, IpcSender)> {
+    let listener = IpcListener::bind(&options.transport)?;
     let (to_app_tx, to_app_rx) = std::sync::mpsc::channel();
     let (from_app_tx, from_app_rx) = std::sync::mpsc::channel();
 
-    let pipe_name_clone = pipe_name.clone();
     std::thread::spawn(move || {
-        let mut server = IpcServer::new(to_app_tx, from_app_rx);
+        let mut server = IpcServer::new(to_app_tx, from_app_rx)
+            .with_heartbeat_timeout(options.heartbeat_timeout)
+            .with_reconnect_timeout(options.reconnect_timeout)
+            .with_token(options.token);
 
-        if use_stdio {
-            if let Err(e) = server.run_stdio() {
-                error!("Stdio server error: {}", e);
-            }
-        } else if let Some(ref name) = pipe_name_clone {
-            if let Err(e) = server.run_named_pipe(name) {
-                error!("Named pipe server error: {}", e);
-            }
+        server.serve(listener);
+        if server.shutdown_acknowledged() {
+            info!("IPC server stopped after shutdown");
+        } else {
+            info!("Editor connection ended, exiting");
+            std::process::exit(0);
         }
     });
 
-    Some((IpcReceiver::new(to_app_rx), IpcSender::new(from_app_tx)))
+    let sender = IpcSender::new(from_app_tx);
+    let log_sender = Mutex::new(sender.clone());
+    forward_logs(move |msg| {
+        if let Ok(sender) = log_sender.lock() {
+            sender.send(msg);
+        }
+    });
+
+    Ok((IpcReceiver::new(to_app_rx), sender))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::{BufRead, Write};
+
+    /// Play the app's part in a shutdown, returning the requests received before it
+    fn acknowledge_shutdown(to_app: &Receiver<(ReplyTo, IpcMessage)>, app: &IpcSender) -> Vec<(ReplyTo, IpcMessage)> {
+        let mut received = Vec::new();
+        loop {
+            match to_app.recv_timeout(Duration::from_secs(5)).expect("shutdown request") {
+                (client, IpcMessage::Shutdown) => {
+                    app.reply(&client, IpcMessage::ShutdownAck);
+                    return received;
+                }
+                request => received.push(request),
+            }
+        }
+    }
 
     #[test]
     fn test_ipc_server_creation() {
@@ -275,4 +745,476 @@ mod tests {
         let (_from_app_tx, from_app_rx) = std::sync::mpsc::channel();
         let _server = IpcServer::new(to_app_tx, from_app_rx);
     }
+
+    #[test]
+    fn test_local_socket_roundtrip() {
+        use interprocess::local_socket::{traits::Stream as _, Stream};
+
+        let name = format!("epass-sim-test-{}", std::process::id());
+        let (to_app_tx, to_app_rx) = std::sync::mpsc::channel();
+        let (from_app_tx, from_app_rx) = std::sync::mpsc::channel();
+        let server_name = name.clone();
+        let server = std::thread::spawn(move || {
+            IpcServer::new(to_app_tx, from_app_rx).run(IpcTransport::LocalSocket(server_name)).unwrap();
+        });
+
+        // The listener may not be up yet
+        let mut stream = None;
+        for _ in 0..50 {
+            match Stream::connect(local_socket_name(&name).unwrap()) {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(_) => std::thread::sleep(std::time::Duration::from_millis(20)),
+            }
+        }
+        let mut stream = stream.expect("connect to local socket");
+
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert!(matches!(IpcMessage::from_json(line.trim()).unwrap(), IpcMessage::Ready));
+
+        writeln!(stream, r#"{{"type": "get_schema"}}"#).unwrap();
+        writeln!(stream, r#"{{"type": "shutdown"}}"#).unwrap();
+        let received = acknowledge_shutdown(&to_app_rx, &IpcSender::new(from_app_tx));
+        server.join().unwrap();
+        assert!(matches!(received[..], [(_, IpcMessage::GetSchema)]));
+    }
+
+    #[test]
+    fn test_tcp_roundtrip() {
+        // Grab a free port, then hand it to the server
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let (to_app_tx, to_app_rx) = std::sync::mpsc::channel();
+        let (from_app_tx, from_app_rx) = std::sync::mpsc::channel();
+        let server = std::thread::spawn(move || {
+            let mut server = IpcServer::new(to_app_tx, from_app_rx);
+            server.run(IpcTransport::Tcp(addr)).unwrap();
+            server.shutdown_acknowledged()
+        });
+
+        let mut stream = None;
+        for _ in 0..50 {
+            match std::net::TcpStream::connect(addr) {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(_) => std::thread::sleep(std::time::Duration::from_millis(20)),
+            }
+        }
+        let mut stream = stream.expect("connect to TCP server");
+
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert!(matches!(IpcMessage::from_json(line.trim()).unwrap(), IpcMessage::Ready));
+
+        writeln!(stream, "not json").unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert!(matches!(IpcMessage::from_json(line.trim()).unwrap(), IpcMessage::Error { .. }));
+
+        // Errors for undecodable requests still carry their id
+        writeln!(stream, r#"{{"type": "no_such_request", "id": 9}}"#).unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        let reply = Envelope::from_json(line.trim()).unwrap();
+        assert_eq!(reply.id, Some(RequestId::Number(9)));
+        assert!(matches!(reply.message, IpcMessage::Error { .. }));
+
+        writeln!(stream, r#"{{"type": "shutdown"}}"#).unwrap();
+        let received = acknowledge_shutdown(&to_app_rx, &IpcSender::new(from_app_tx));
+        assert!(server.join().unwrap());
+        assert!(received.is_empty());
+        let ack = reader.lines().map(|line| IpcMessage::from_json(&line.unwrap()).unwrap()).find(|msg| !matches!(msg, IpcMessage::Ping));
+        assert!(matches!(ack, Some(IpcMessage::ShutdownAck)));
+    }
+
+    #[test]
+    fn test_websocket_roundtrip() {
+        use tungstenite::Message;
+
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let (to_app_tx, to_app_rx) = std::sync::mpsc::channel();
+        let (from_app_tx, from_app_rx) = std::sync::mpsc::channel();
+        let server = std::thread::spawn(move || {
+            IpcServer::new(to_app_tx, from_app_rx).run(IpcTransport::WebSocket(addr)).unwrap();
+        });
+
+        let mut socket = None;
+        for _ in 0..50 {
+            match tungstenite::connect(format!("ws://{}", addr)) {
+                Ok((s, _)) => {
+                    socket = Some(s);
+                    break;
+                }
+                Err(_) => std::thread::sleep(std::time::Duration::from_millis(20)),
+            }
+        }
+        let mut socket = socket.expect("connect to WebSocket server");
+
+        let Message::Text(ready) = socket.read().unwrap() else {
+            panic!("expected a text message");
+        };
+        assert!(matches!(IpcMessage::from_json(&ready).unwrap(), IpcMessage::Ready));
+
+        socket.send(Message::Text(r#"{"type": "get_schema"}"#.to_string())).unwrap();
+        socket.send(Message::Text(r#"{"type": "shutdown"}"#.to_string())).unwrap();
+        let received = acknowledge_shutdown(&to_app_rx, &IpcSender::new(from_app_tx));
+        server.join().unwrap();
+        assert!(matches!(received[..], [(_, IpcMessage::GetSchema)]));
+    }
+
+    #[test]
+    fn test_websocket_rejects_foreign_origin() {
+        use tungstenite::client::IntoClientRequest;
+
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let (to_app_tx, _to_app_rx) = std::sync::mpsc::channel();
+        let (_from_app_tx, from_app_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            IpcServer::new(to_app_tx, from_app_rx)
+                .with_token(Some("secret".to_string()))
+                .run(IpcTransport::WebSocket(addr))
+                .unwrap();
+        });
+
+        // Status of a refused handshake, None if it succeeded
+        let connect = |url: String, origin: Option<&str>| {
+            let mut request = url.into_client_request().unwrap();
+            if let Some(origin) = origin {
+                request.headers_mut().insert("Origin", origin.parse().unwrap());
+            }
+            for _ in 0..50 {
+                match tungstenite::connect(request.clone()) {
+                    Ok(_) => return None,
+                    Err(tungstenite::Error::Http(response)) => return Some(response.status().as_u16()),
+                    Err(_) => std::thread::sleep(std::time::Duration::from_millis(20)),
+                }
+            }
+            panic!("WebSocket server did not come up");
+        };
+        assert_eq!(connect(format!("ws://{}/?token=secret", addr), Some("https://evil.example")), Some(403));
+        assert_eq!(connect(format!("ws://{}/", addr), Some("http://localhost")), Some(403));
+        assert_eq!(connect(format!("ws://{}/?token=secret", addr), Some("http://localhost")), None);
+    }
+
+    #[test]
+    fn test_http_roundtrip() {
+        use std::io::Read;
+        use super::super::protocol::ControlCommand;
+
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let (to_app_tx, to_app_rx) = std::sync::mpsc::channel();
+        let (from_app_tx, from_app_rx) = std::sync::mpsc::channel();
+        // Runs until the test exits; HTTP ignores the reconnect timeout
+        std::thread::spawn(move || {
+            IpcServer::new(to_app_tx, from_app_rx).with_reconnect_timeout(Duration::ZERO).run(IpcTransport::Http(addr)).unwrap();
+        });
+        let app = IpcSender::new(from_app_tx);
+
+        let request = |raw: &str| {
+            let mut stream = None;
+            for _ in 0..50 {
+                match std::net::TcpStream::connect(addr) {
+                    Ok(s) => {
+                        stream = Some(s);
+                        break;
+                    }
+                    Err(_) => std::thread::sleep(std::time::Duration::from_millis(20)),
+                }
+            }
+            let mut stream = stream.expect("connect to HTTP server");
+            stream.write_all(raw.as_bytes()).unwrap();
+            stream
+        };
+        let state = IpcMessage::State { state: 0, state_name: "idle".to_string(), frame: 0, is_playing: true, speed: 1.0 };
+
+        for _ in 0..2 {
+            let mut stream = request("POST /play HTTP/1.1\r\nHost: localhost\r\n\r\n");
+            let (client, msg) = to_app_rx.recv_timeout(Duration::from_secs(5)).expect("play request");
+            let IpcMessage::Batch(batch) = msg else {
+                panic!("expected a batch");
+            };
+            assert!(matches!(batch[..], [IpcMessage::Control(ControlCommand::Play), IpcMessage::GetState]));
+            app.send(IpcMessage::state_update(crate::app::state::PlayState::Idle, 0, true));
+            app.reply(&client, state.clone());
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+            assert!(response.contains(r#""is_playing":true"#), "{}", response);
+        }
+
+        // Unknown endpoints are answered without bothering the app
+        let mut response = String::new();
+        request("GET /nothing HTTP/1.1\r\n\r\n").read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 404"));
+        assert!(to_app_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_heartbeat_timeout() {
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let (to_app_tx, to_app_rx) = std::sync::mpsc::channel();
+        let (_from_app_tx, from_app_rx) = std::sync::mpsc::channel();
+        let server = std::thread::spawn(move || {
+            IpcServer::new(to_app_tx, from_app_rx)
+                .with_heartbeat_timeout(Some(Duration::from_millis(200)))
+                .with_reconnect_timeout(Duration::ZERO)
+                .run(IpcTransport::Tcp(addr))
+                .unwrap();
+        });
+
+        let mut stream = None;
+        for _ in 0..50 {
+            match std::net::TcpStream::connect(addr) {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(_) => std::thread::sleep(std::time::Duration::from_millis(20)),
+            }
+        }
+        let mut stream = stream.expect("connect to TCP server");
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert!(matches!(IpcMessage::from_json(line.trim()).unwrap(), IpcMessage::Ready));
+
+        writeln!(stream, r#"{{"type": "ping"}}"#).unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert!(matches!(IpcMessage::from_json(line.trim()).unwrap(), IpcMessage::Pong));
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert!(matches!(IpcMessage::from_json(line.trim()).unwrap(), IpcMessage::Ping));
+
+        // Answer once, then go silent: the server gives up on the client
+        writeln!(stream, r#"{{"type": "pong"}}"#).unwrap();
+        server.join().unwrap();
+        assert!(to_app_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_reconnect() {
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let (to_app_tx, to_app_rx) = std::sync::mpsc::channel();
+        let (from_app_tx, from_app_rx) = std::sync::mpsc::channel();
+        let server = std::thread::spawn(move || {
+            IpcServer::new(to_app_tx, from_app_rx).run(IpcTransport::Tcp(addr)).unwrap();
+        });
+
+        let connect = || {
+            for _ in 0..50 {
+                if let Ok(stream) = std::net::TcpStream::connect(addr) {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    assert!(matches!(IpcMessage::from_json(line.trim()).unwrap(), IpcMessage::Ready));
+                    return stream;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            panic!("connect to TCP server");
+        };
+
+        // The editor goes away; its restarted instance gets a fresh ready
+        let first = connect();
+        drop(first);
+        std::thread::sleep(ACCEPT_INTERVAL * 2);
+        let mut second = connect();
+
+        writeln!(second, r#"{{"type": "get_state"}}"#).unwrap();
+        writeln!(second, r#"{{"type": "shutdown"}}"#).unwrap();
+        let received = acknowledge_shutdown(&to_app_rx, &IpcSender::new(from_app_tx));
+        server.join().unwrap();
+        assert!(matches!(received[..], [(ReplyTo { client: 1, id: None }, IpcMessage::GetState)]));
+    }
+
+    #[test]
+    fn test_multiple_clients() {
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let (to_app_tx, to_app_rx) = std::sync::mpsc::channel();
+        let (from_app_tx, from_app_rx) = std::sync::mpsc::channel();
+        let server = std::thread::spawn(move || {
+            IpcServer::new(to_app_tx, from_app_rx).run(IpcTransport::Tcp(addr)).unwrap();
+        });
+        let app = IpcSender::new(from_app_tx);
+
+        let connect = || {
+            for _ in 0..50 {
+                if let Ok(stream) = std::net::TcpStream::connect(addr) {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    assert!(matches!(IpcMessage::from_json(line.trim()).unwrap(), IpcMessage::Ready));
+                    return (stream, reader);
+                }
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            panic!("connect to TCP server");
+        };
+        let read = |reader: &mut BufReader<std::net::TcpStream>| loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            match Envelope::from_json(line.trim()).unwrap() {
+                Envelope { message: IpcMessage::Ping, .. } => continue,
+                envelope => break envelope,
+            }
+        };
+        let (mut editor, mut editor_reader) = connect();
+        let (mut tool, mut tool_reader) = connect();
+
+        // Requests reach the app tagged with their sender and id
+        writeln!(tool, r#"{{"type": "get_state", "id": "t1"}}"#).unwrap();
+        let (tool_request, msg) = to_app_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(msg, IpcMessage::GetState));
+        assert_eq!(tool_request.id, Some(RequestId::Text("t1".to_string())));
+
+        // Replies go to the requester only, with its id; broadcasts to everyone
+        app.reply(&tool_request, IpcMessage::GetSchema);
+        app.send(IpcMessage::Pong);
+        let reply = read(&mut tool_reader);
+        assert!(matches!(reply.message, IpcMessage::GetSchema));
+        assert_eq!(reply.id, tool_request.id);
+        assert!(matches!(read(&mut tool_reader), Envelope { id: None, message: IpcMessage::Pong }));
+        assert!(matches!(read(&mut editor_reader), Envelope { id: None, message: IpcMessage::Pong }));
+
+        writeln!(editor, r#"{{"type": "shutdown"}}"#).unwrap();
+        acknowledge_shutdown(&to_app_rx, &app);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_event_subscriptions() {
+        use crate::ipc::Event;
+
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let (to_app_tx, to_app_rx) = std::sync::mpsc::channel();
+        let (from_app_tx, from_app_rx) = std::sync::mpsc::channel();
+        let server = std::thread::spawn(move || {
+            IpcServer::new(to_app_tx, from_app_rx).run(IpcTransport::Tcp(addr)).unwrap();
+        });
+        let app = IpcSender::new(from_app_tx);
+
+        let mut stream = None;
+        for _ in 0..50 {
+            if let Ok(s) = std::net::TcpStream::connect(addr) {
+                stream = Some(s);
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        let mut stream = stream.expect("connect to TCP server");
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut read = || loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            match IpcMessage::from_json(line.trim()).unwrap() {
+                IpcMessage::Ping => continue,
+                msg => break msg,
+            }
+        };
+        assert!(matches!(read(), IpcMessage::Ready));
+
+        writeln!(stream, r#"{{"type": "subscribe", "payload": {{}}}}"#).unwrap();
+        assert!(matches!(read(), IpcMessage::Subscriptions { events } if events == EventKind::ALL));
+        writeln!(stream, r#"{{"type": "unsubscribe", "payload": {{"events": ["intro_ended", "error"]}}}}"#).unwrap();
+        match read() {
+            IpcMessage::Subscriptions { events } => assert_eq!(
+                events,
+                [EventKind::StateChanged, EventKind::TransitionStarted, EventKind::AnimationMilestone, EventKind::Gesture]
+            ),
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        // Unsubscribed categories are dropped, other broadcasts pass
+        app.send(IpcMessage::Event(Event::IntroEnded { frame: 10 }));
+        app.send(IpcMessage::Event(Event::StateChanged { from: 2, to: 3, frame: 10 }));
+        app.send(IpcMessage::Pong);
+        assert!(matches!(read(), IpcMessage::Event(Event::StateChanged { to: 3, .. })));
+        assert!(matches!(read(), IpcMessage::Pong));
+
+        writeln!(stream, r#"{{"type": "shutdown"}}"#).unwrap();
+        acknowledge_shutdown(&to_app_rx, &app);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_backlog_is_bounded() {
+        use crate::ipc::Event;
+
+        let hub = Hub::default();
+        for frame in 0..1000 {
+            hub.route(Outgoing::Broadcast(IpcMessage::StateUpdate { state: 2, frame, is_playing: true }));
+            hub.route(Outgoing::Broadcast(IpcMessage::Event(Event::IntroEnded { frame })));
+        }
+        hub.route(Outgoing::Broadcast(IpcMessage::Metrics(crate::app::MetricsReport {
+            render_fps: 60.0,
+            decode_ms: 1.0,
+            decode_max_ms: 2.0,
+            dropped_frames: 0,
+            stalls: 0,
+            memory_bytes: None,
+        })));
+
+        let (_, rx) = hub.register();
+        let sent: Vec<IpcMessage> = rx.try_iter().map(|envelope| envelope.message).collect();
+        assert_eq!(sent.len(), BACKLOG_LIMIT + 1);
+        assert!(matches!(sent[0], IpcMessage::Event(Event::IntroEnded { frame: 936 })));
+        assert!(matches!(sent[BACKLOG_LIMIT], IpcMessage::StateUpdate { frame: 999, .. }));
+    }
+
+    #[test]
+    fn test_slow_client_queue_is_bounded() {
+        use crate::ipc::Event;
+
+        let hub = Hub::default();
+        let (stalled, stalled_rx) = hub.register();
+        let (_, reading_rx) = hub.register();
+
+        // State updates past the limit are dropped, the client stays
+        for frame in 0..CLIENT_QUEUE_LIMIT as u64 * 2 {
+            hub.route(Outgoing::Broadcast(IpcMessage::StateUpdate { state: 2, frame, is_playing: true }));
+            reading_rx.try_iter().for_each(drop);
+        }
+        assert_eq!(stalled_rx.try_iter().take(CLIENT_QUEUE_LIMIT + 1).count(), CLIENT_QUEUE_LIMIT);
+        assert!(hub.clients.lock().unwrap().queues.contains_key(&stalled));
+
+        // An event it cannot take disconnects it; the reading client gets them all
+        let mut received = 0;
+        for frame in 0..=CLIENT_QUEUE_LIMIT as u64 {
+            hub.route(Outgoing::Broadcast(IpcMessage::Event(Event::IntroEnded { frame })));
+            received += reading_rx.try_iter().count();
+        }
+        assert!(!hub.clients.lock().unwrap().queues.contains_key(&stalled));
+        assert_eq!(stalled_rx.try_iter().count(), CLIENT_QUEUE_LIMIT);
+        assert!(matches!(stalled_rx.try_recv(), Err(TryRecvError::Disconnected)));
+        assert_eq!(received, CLIENT_QUEUE_LIMIT + 1);
+    }
+
+    #[test]
+    fn test_tcp_transport_parse() {
+        assert!(matches!(IpcTransport::tcp("9000").unwrap(), IpcTransport::Tcp(a) if a.to_string() == "127.0.0.1:9000"));
+        assert!(matches!(IpcTransport::http("8080").unwrap(), IpcTransport::Http(a) if a.port() == 8080));
+        assert!(matches!(IpcTransport::tcp("0.0.0.0:9000").unwrap(), IpcTransport::Tcp(a) if a.port() == 9000));
+        assert!(IpcTransport::tcp("nonsense").is_err());
+    }
+
+    #[test]
+    fn test_start_fails_on_taken_port() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let options = IpcOptions {
+            transport: IpcTransport::Tcp(taken.local_addr().unwrap()),
+            heartbeat_timeout: None,
+            reconnect_timeout: DEFAULT_RECONNECT_TIMEOUT,
+            token: None,
+        };
+        let err = start_ipc_server(options).err().expect("port is taken");
+        assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+    }
 }
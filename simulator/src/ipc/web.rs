@@ -0,0 +1,68 @@
+//! IPC stand-in for browser builds
+//!
+//! A page cannot open sockets or read stdin, so there is no transport to
+//! choose and the app never gets an IPC connection. The types mirror the
+//! native server so the app compiles unchanged.
+
+use std::time::Duration;
+
+use super::protocol::{IpcMessage, RequestId};
+
+/// Identifies a connected client
+pub type ClientId = u64;
+
+/// Where replies to a request go: the client that sent it and the request's id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplyTo {
+    pub client: ClientId,
+    pub id: Option<RequestId>,
+}
+
+/// IPC message receiver; never receives anything
+pub struct IpcReceiver;
+
+impl IpcReceiver {
+    /// Always None, no client can connect
+    pub fn try_recv(&self) -> Option<(ReplyTo, IpcMessage)> {
+        None
+    }
+
+    /// Always true, there is no server
+    pub fn is_closed(&self) -> bool {
+        true
+    }
+}
+
+/// IPC message sender; drops everything
+#[derive(Clone)]
+pub struct IpcSender;
+
+impl IpcSender {
+    /// Always false, there is nobody to send to
+    pub fn send(&self, _msg: IpcMessage) -> bool {
+        false
+    }
+
+    /// Always false, there is nobody to reply to
+    pub fn reply(&self, _to: &ReplyTo, _msg: IpcMessage) -> bool {
+        false
+    }
+}
+
+/// How the IPC server talks to the editor; none work in a browser
+#[derive(Debug, Clone)]
+pub enum IpcTransport {}
+
+/// IPC server settings
+#[derive(Debug, Clone)]
+pub struct IpcOptions {
+    pub transport: IpcTransport,
+    pub heartbeat_timeout: Option<Duration>,
+    pub reconnect_timeout: Duration,
+    pub token: Option<String>,
+}
+
+/// Start IPC server; unreachable, as no transport can be named
+pub fn start_ipc_server(options: IpcOptions) -> std::io::Result<(IpcReceiver, IpcSender)> {
+    match options.transport {}
+}
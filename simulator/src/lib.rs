@@ -0,0 +1,9 @@
+//! Arknights Electronic Pass Simulator
+//!
+//! The egui frontend over `simulator_core`. It is also a library, so
+//! bindings can drive the same app the window shows without opening one.
+
+pub mod app;
+pub mod ipc;
+
+pub use simulator_core::{animation, clock, config, render, utils, video};
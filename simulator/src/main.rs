@@ -2,29 +2,85 @@
 //!
 //! A real device preview emulator for the Arknights Pass Material Editor.
 //! Supports standalone execution or IPC communication with the Python editor.
+//! The simulation itself lives in `simulator_core`; this is its egui frontend.
 
-mod app;
-mod config;
-mod render;
-mod animation;
-mod ipc;
-mod utils;
-mod video;
-
-use anyhow::Result;
-use clap::Parser;
-use std::path::PathBuf;
+mod exit_code;
+
+use arknights_pass_simulator::{app, config, ipc, render, utils, video};
+
+use anyhow::{Context, Result};
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
 use tracing::{info, Level};
-use tracing_subscriber::FmtSubscriber;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Layer;
 
-use app::SimulatorApp;
-use config::EPConfig;
+use app::{
+    parse_position, window_size_for_screen, AutoReplay, BenchReport, Clock, HeadlessRenderer, SimulatorApp, ToastLayer,
+    ValidationReport, WindowPlacement,
+};
+use config::{is_package, EPConfig, FirmwareConfig};
+use exit_code::Failure;
+use ipc::{start_ipc_server, IpcLogLayer, IpcOptions, IpcTransport};
+use utils::{parse_duration_us, resolve_asset_path};
+use video::{probe_video, VideoEncoder, VideoInfo};
 
 /// Arknights Electronic Pass Simulator
+///
+/// Without a subcommand the simulator window opens, as with `play`.
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
-struct Args {
-    /// Path to epconfig.json configuration file
+#[command(after_help = exit_code::HELP)]
+struct Cli {
+    #[command(flatten)]
+    global: GlobalArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    play: PlayArgs,
+}
+
+/// Flags every subcommand takes
+#[derive(Args, Debug)]
+struct GlobalArgs {
+    /// Application directory (for program resources like modular assets)
+    #[arg(long, global = true)]
+    app_dir: Option<PathBuf>,
+
+    /// Report unknown overlay option keys as errors instead of warnings
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Enable debug logging
+    #[arg(short, long, global = true)]
+    debug: bool,
+
+    /// How log lines are written
+    #[arg(long, value_enum, default_value_t = LogFormat::Text, global = true)]
+    log_format: LogFormat,
+}
+
+/// Format of the log output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per line with timestamp, level, target and fields
+    Json,
+}
+
+/// Flags of the simulator window
+#[derive(Args, Debug)]
+struct PlayArgs {
+    /// Path to epconfig.json configuration file or .eppkg package
     #[arg(short, long)]
     config: Option<PathBuf>,
 
@@ -32,18 +88,38 @@ struct Args {
     #[arg(short, long)]
     base_dir: Option<PathBuf>,
 
-    /// Application directory (for program resources like modular assets)
-    #[arg(long)]
-    app_dir: Option<PathBuf>,
-
-    /// Named pipe name for IPC communication (Windows)
+    /// Local socket for IPC communication (Named Pipe name on Windows, Unix domain socket name or path elsewhere)
     #[arg(long)]
     pipe: Option<String>,
 
     /// Use stdin/stdout for IPC communication
-    #[arg(long)]
+    #[arg(long, conflicts_with = "pipe")]
     stdio: bool,
 
+    /// Listen for IPC on TCP: a port (localhost only) or host:port
+    #[arg(long, value_name = "PORT", conflicts_with_all = ["pipe", "stdio"])]
+    tcp: Option<String>,
+
+    /// Listen for IPC on a WebSocket: a port (localhost only) or host:port
+    #[arg(long, value_name = "PORT", conflicts_with_all = ["pipe", "stdio", "tcp"])]
+    websocket: Option<String>,
+
+    /// Serve REST control endpoints over HTTP: a port (localhost only) or host:port
+    #[arg(long, value_name = "PORT", conflicts_with_all = ["pipe", "stdio", "tcp", "websocket"])]
+    http: Option<String>,
+
+    /// Secret WebSocket clients must connect with, as `ws://host:port/?token=SECRET`
+    #[arg(long, value_name = "SECRET", requires = "websocket")]
+    ipc_token: Option<String>,
+
+    /// Exit when the editor stops answering pings for this many seconds (0 = never)
+    #[arg(long, value_name = "SECS", default_value = "10")]
+    heartbeat_timeout: u64,
+
+    /// After the editor disconnects, wait this many seconds for it to reconnect before exiting
+    #[arg(long, value_name = "SECS", default_value = "30")]
+    reconnect_timeout: u64,
+
     /// Cropbox in format "x,y,w,h" (rotated video coordinates)
     #[arg(long)]
     cropbox: Option<String>,
@@ -52,31 +128,415 @@ struct Args {
     #[arg(long, default_value = "0")]
     rotation: i32,
 
-    /// Enable debug logging
-    #[arg(short, long)]
-    debug: bool,
+    /// Export the config and its assets as a .eppkg package, then exit
+    #[arg(long, value_name = "PATH", requires = "config")]
+    export_package: Option<PathBuf>,
+
+    /// Write the frame at this playback time (e.g. "12.5s") to --out, then exit
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_us, requires_all = ["config", "out"])]
+    screenshot_at: Option<i64>,
+
+    /// Image file for --screenshot-at (PNG or JPEG, by extension)
+    #[arg(long, value_name = "PATH", requires = "screenshot_at")]
+    out: Option<PathBuf>,
+
+    /// Restart playback this many seconds after reaching the loop, indefinitely
+    #[arg(long, value_name = "SECS")]
+    auto_replay: Option<f64>,
+
+    /// Restart playback this many seconds after it started, indefinitely
+    #[arg(long, value_name = "SECS", conflicts_with = "auto_replay")]
+    auto_replay_total: Option<f64>,
+
+    /// Keep the window above other windows
+    #[arg(long)]
+    always_on_top: bool,
+
+    /// Show this as `{serial}` in overlay texts instead of the config's serial
+    #[arg(long)]
+    serial: Option<String>,
+
+    /// Zoom the window and its contents by this factor
+    #[arg(long, default_value = "1.0")]
+    scale: f32,
+
+    /// Window position in format "x,y" (relative to the monitor given by --monitor)
+    #[arg(long, value_parser = parse_position, allow_hyphen_values = true)]
+    position: Option<egui::Pos2>,
+
+    /// Open the window maximized
+    #[arg(long)]
+    maximized: bool,
+
+    /// Open the window on this monitor (0 = first), centered unless --position is given
+    #[arg(long, value_name = "N")]
+    monitor: Option<usize>,
+
+    /// Advance playback by this much per drawn frame instead of following the
+    /// wall clock (e.g. "16ms"), so runs play the same on every machine
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration_us)]
+    fixed_step: Option<i64>,
+
+    /// Print the epconfig.json JSON Schema and exit
+    #[arg(long)]
+    print_schema: bool,
 
     /// Theme mode to match main application ("dark" or "light")
     #[arg(long, default_value = "dark")]
     theme: String,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+/// What to run; everything but `play` works without a window
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Open the simulator window (the default without a subcommand)
+    Play(Box<PlayArgs>),
+
+    /// Convert a legacy material to the current format, copying its assets
+    ConvertLegacy {
+        /// Legacy material folder, or its config file
+        old_dir: PathBuf,
+
+        /// Folder to write epconfig.json and the assets to
+        out_dir: PathBuf,
+    },
+
+    /// Write a barcode image the way the pass draws it
+    Barcode {
+        /// Text to encode (Code128, printable ASCII)
+        #[arg(long)]
+        text: String,
+
+        /// Output image (e.g. code.png)
+        #[arg(short, long)]
+        out: PathBuf,
+
+        /// Bars run top to bottom, as on the pass
+        #[arg(long)]
+        vertical: bool,
+
+        /// Color the bars with the pass's purple-to-yellow gradient
+        #[arg(long, requires = "vertical")]
+        gradient: bool,
+
+        /// Length of the bars in pixels (defaults to the pass layout's barcode width)
+        #[arg(long)]
+        bar_length: Option<u32>,
+
+        /// Pixels per module along the code
+        #[arg(long, default_value = "1")]
+        scale: u32,
+    },
+
+    /// Play a config headlessly as fast as possible and time each stage
+    Bench {
+        /// epconfig.json file or .eppkg package
+        #[arg(short, long)]
+        config: PathBuf,
+
+        /// Seconds of playback to run through
+        #[arg(long, default_value = "30")]
+        seconds: f64,
+
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Print a video's stream parameters, for pasting into bug reports
+    Probe {
+        /// Video file
+        file: PathBuf,
+
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write a starter epconfig.json with placeholder assets
+    Init {
+        /// Directory to create the material in
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+    },
+
+    /// Check configs and their assets, print a JSON report and fail if any has errors
+    Validate {
+        /// epconfig.json files or .eppkg packages
+        #[arg(required = true)]
+        configs: Vec<PathBuf>,
+    },
+
+    /// Render a preview video of a config without opening a window
+    Render {
+        /// epconfig.json file or .eppkg package
+        #[arg(short, long)]
+        config: PathBuf,
+
+        /// Output video file (e.g. preview.mp4)
+        #[arg(short, long)]
+        out: PathBuf,
+
+        /// Length of playback to render, e.g. "20s" or "1500ms"
+        #[arg(long, value_parser = parse_duration_us, default_value = "10s")]
+        duration: i64,
+
+        /// Playback time to start at
+        #[arg(long, value_parser = parse_duration_us, default_value = "0s")]
+        start: i64,
+
+        /// Frame rate of the video (defaults to the firmware frame rate)
+        #[arg(long)]
+        fps: Option<u32>,
+
+        /// Show this as `{serial}` in overlay texts instead of the config's serial
+        #[arg(long)]
+        serial: Option<String>,
+    },
+}
+
+/// Headless renderer for a loaded config, failing as missing or undecodable
+/// if its loop video is
+fn open_renderer(
+    config: EPConfig,
+    base_dir: PathBuf,
+    app_dir: PathBuf,
+    cropbox: Option<(u32, u32, u32, u32)>,
+    rotation: i32,
+) -> Result<HeadlessRenderer> {
+    let loop_file = resolve_asset_path(&config.loop_config.file, &base_dir);
+    if !loop_file.is_file() {
+        return Err(anyhow::anyhow!("Loop video not found: {}", loop_file.display()).context(Failure::AssetMissing));
+    }
+    HeadlessRenderer::new(config, base_dir, app_dir, cropbox, rotation).context(Failure::Decode)
+}
+
+/// Render `duration_us` of playback from `start_us` into a video at `out`
+fn render_video(
+    config: &Path,
+    out: &Path,
+    start_us: i64,
+    duration_us: i64,
+    fps: Option<u32>,
+    serial: Option<String>,
+    app_dir: PathBuf,
+) -> Result<()> {
+    let (config, base_dir) = EPConfig::load_with_base_dir(config).context(Failure::Config)?;
+    let mut renderer = open_renderer(config, base_dir, app_dir, None, 0)?;
+    renderer.set_serial(serial);
+    let fps = fps.unwrap_or_else(|| renderer.fps());
+    if fps == 0 || duration_us <= 0 {
+        anyhow::bail!("Nothing to render: fps {}, duration {} us", fps, duration_us);
+    }
+    let frames = (duration_us as f64 * fps as f64 / 1_000_000.0).ceil() as u32;
+    let [width, height] = renderer.size();
+    let mut encoder = VideoEncoder::create(out, width, height, fps).context(Failure::Render)?;
+    info!("Rendering {} frames ({}x{} @ {}fps) to {:?}", frames, width, height, fps, out);
+
+    let frame_time_us = |index: u32| start_us + (index as f64 * 1_000_000.0 / fps as f64).round() as i64;
+    renderer.seek(start_us);
+    for index in 0..frames {
+        if index > 0 {
+            renderer.advance(frame_time_us(index) - frame_time_us(index - 1));
+        }
+        encoder.write_frame(&renderer.render()).context(Failure::Render)?;
+    }
+    encoder.finish().context(Failure::Render)?;
+    info!("Rendered {:?}", out);
+    Ok(())
+}
+
+/// Bytes as MiB with two decimals
+fn mebibytes(bytes: u64) -> String {
+    format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// Human-readable probe report for `file`
+fn probe_report(file: &Path, info: &VideoInfo) -> String {
+    let duration = info
+        .duration_secs
+        .map_or_else(|| "unknown".to_string(), |secs| format!("{:.3}s", secs));
+    let decoded = info.decoded_bytes().map_or_else(|| "unknown".to_string(), mebibytes);
+    [
+        format!("file:       {}", file.display()),
+        format!("codec:      {}", info.codec),
+        format!("resolution: {}x{}", info.width, info.height),
+        format!("fps:        {:.3}", info.fps),
+        format!("duration:   {}", duration),
+        format!("rotation:   {}°", info.rotation),
+        format!("memory:     {} per frame, {} fully decoded", mebibytes(info.frame_bytes()), decoded),
+    ]
+    .join("\n")
+}
+
+/// Write the frame at `time_us` of playback, overlays included, to `out`
+fn write_screenshot(renderer: &mut HeadlessRenderer, time_us: i64, out: &Path) -> Result<()> {
+    renderer.seek(time_us);
+    renderer
+        .render()
+        .save(out)
+        .with_context(|| format!("Failed to write {}", out.display()))
+        .context(Failure::Render)?;
+    info!("Screenshot at {} us written to {:?}", time_us, out);
+    Ok(())
+}
+
+/// Run a subcommand
+fn run_command(command: Command, global: &GlobalArgs, app_dir: PathBuf) -> Result<()> {
+    match command {
+        Command::Play(args) => play(*args, global, app_dir)?,
+        Command::ConvertLegacy { old_dir, out_dir } => {
+            let output = config::convert_legacy_material(&old_dir, &out_dir).context(Failure::Config)?;
+            info!("Converted {:?} -> {:?}", old_dir, output);
+        }
+        Command::Barcode { text, out, vertical, gradient, bar_length, scale } => {
+            let bar_length = bar_length.unwrap_or_else(|| FirmwareConfig::get_default().layout.barcode.width);
+            let barcode = render::render_barcode(&text, vertical, gradient, bar_length, scale)
+                .with_context(|| format!("Cannot encode {:?} as a barcode", text))
+                .context(Failure::Render)?;
+            barcode
+                .save(&out)
+                .with_context(|| format!("Failed to write {}", out.display()))
+                .context(Failure::Render)?;
+            info!("Barcode written to {:?}", out);
+        }
+        Command::Bench { config, seconds, json } => {
+            if !seconds.is_finite() || seconds <= 0.0 {
+                anyhow::bail!("--seconds must be positive, got {}", seconds);
+            }
+            let (config, base_dir) = EPConfig::load_with_base_dir(&config).context(Failure::Config)?;
+            let mut renderer = open_renderer(config, base_dir, app_dir, None, 0)?;
+            let report = BenchReport::run(&mut renderer, (seconds * 1_000_000.0).round() as i64);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!("{}", report.to_text());
+            }
+        }
+        Command::Probe { file, json } => {
+            if !file.is_file() {
+                return Err(anyhow::anyhow!("File not found: {}", file.display()).context(Failure::AssetMissing));
+            }
+            let info = probe_video(&file)
+                .with_context(|| format!("Failed to probe {}", file.display()))
+                .context(Failure::Decode)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&info)?);
+            } else {
+                println!("{}", probe_report(&file, &info));
+            }
+        }
+        Command::Init { dir } => {
+            let path = config::write_template(&dir).context(Failure::Render)?;
+            info!("Created {:?}", path);
+        }
+        Command::Validate { configs } => {
+            let report = ValidationReport::run(&configs, global.strict);
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            if !report.ok {
+                return Err(anyhow::Error::new(Failure::Config).context("Validation found errors"));
+            }
+        }
+        Command::Render { config, out, duration, start, fps, serial } => {
+            render_video(&config, &out, start, duration, fps, serial, app_dir)?;
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(exit_code::for_error(&e))
+        }
+    }
+}
+
+/// Parse the command line, rejecting window flags given along with a subcommand
+fn parse_cli<I, T>(args: I) -> Result<Cli, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<std::ffi::OsString> + Clone,
+{
+    let mut command = Cli::command();
+    let matches = command.try_get_matches_from_mut(args)?;
+    if matches.subcommand().is_some() {
+        let window_args = PlayArgs::augment_args(clap::Command::new("play"));
+        let given = window_args
+            .get_arguments()
+            .find(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine));
+        if let Some(arg) = given {
+            let message = format!("--{} only applies to the simulator window", arg.get_long().unwrap_or_default());
+            return Err(command.error(ErrorKind::ArgumentConflict, message));
+        }
+    }
+    Cli::from_arg_matches(&matches)
+}
+
+fn run() -> Result<()> {
+    let cli = parse_cli(std::env::args_os()).unwrap_or_else(|e| e.exit());
+    let command = cli.command.unwrap_or_else(|| Command::Play(Box::new(cli.play)));
 
     // Initialize logging
-    let level = if args.debug { Level::DEBUG } else { Level::INFO };
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(level)
-        .finish();
+    let level = if cli.global.debug { Level::DEBUG } else { Level::INFO };
+    // Tools print their results on stdout, so their logs go to stderr
+    let writer = if matches!(command, Command::Play(_)) {
+        BoxMakeWriter::new(std::io::stdout)
+    } else {
+        BoxMakeWriter::new(std::io::stderr)
+    };
+    let output = match cli.global.log_format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().with_writer(writer).boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer().json().with_writer(writer).boxed(),
+    };
+    // Warnings and errors also go to the editor once IPC is up, and to toasts in the window
+    let subscriber = tracing_subscriber::registry()
+        .with(LevelFilter::from_level(level))
+        .with(output)
+        .with(IpcLogLayer)
+        .with(ToastLayer);
     tracing::subscriber::set_global_default(subscriber)?;
 
+    // Determine app_dir for program resources (modular assets, etc.)
+    let app_dir = cli.global.app_dir.clone().unwrap_or_else(|| {
+        // Default to the directory containing the executable
+        std::env::current_exe()
+            .ok()
+            .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+            .unwrap_or_else(|| PathBuf::from("."))
+    });
+    utils::set_app_dir(app_dir.clone());
+    app::crash::install_panic_hook(&app_dir);
+
+    run_command(command, &cli.global, app_dir)
+}
+
+/// Open the simulator window, or run one of its one-shot flags
+fn play(args: PlayArgs, global: &GlobalArgs, app_dir: PathBuf) -> Result<()> {
+    if args.print_schema {
+        println!("{}", serde_json::to_string_pretty(&EPConfig::schema_json())?);
+        return Ok(());
+    }
+
     info!("Arknights Pass Simulator starting...");
 
     // Load configuration if provided
+    // Packages are extracted to a temp dir, which then becomes the base dir
+    let mut package_dir = None;
     let (initial_config, config_error) = if let Some(config_path) = &args.config {
         info!("Loading config from: {:?}", config_path);
-        match EPConfig::load_from_file(config_path) {
+        app::crash::set_config(config_path.display().to_string());
+        let loaded = if is_package(config_path) {
+            EPConfig::load_package(config_path).map(|(config, dir)| {
+                package_dir = Some(dir);
+                config
+            })
+        } else {
+            EPConfig::load_from_file(config_path)
+        };
+        match loaded {
             Ok(config) => {
                 info!("Config loaded successfully:");
                 info!("  - name: {:?}", config.name);
@@ -93,7 +553,7 @@ fn main() -> Result<()> {
         (None, None)
     };
 
-    let base_dir = args.base_dir.unwrap_or_else(|| {
+    let base_dir = package_dir.or(args.base_dir).unwrap_or_else(|| {
         args.config
             .as_ref()
             .and_then(|p| p.parent())
@@ -102,23 +562,47 @@ fn main() -> Result<()> {
     });
     info!("Base directory: {:?}", base_dir);
 
-    // Determine app_dir for program resources (modular assets, etc.)
-    let app_dir = args.app_dir.unwrap_or_else(|| {
-        // Default to the directory containing the executable
-        std::env::current_exe()
-            .ok()
-            .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-            .unwrap_or_else(|| PathBuf::from("."))
-    });
     info!("App directory: {:?}", app_dir);
 
+    if let Some(ref package_path) = args.export_package {
+        let config = initial_config
+            .ok_or_else(|| anyhow::anyhow!("{}", config_error.unwrap_or_default()))
+            .context(Failure::Config)?;
+        config.export_package(&base_dir, package_path).context(Failure::Render)?;
+        info!("Package exported: {:?}", package_path);
+        return Ok(());
+    }
+
+    // Size the window for the config's screen so the preview keeps its aspect ratio
+    let (screen_width, screen_height) = initial_config
+        .as_ref()
+        .map(|c| c.screen.dimensions())
+        .unwrap_or((360, 640));
+
+    if !(0.25..=4.0).contains(&args.scale) {
+        anyhow::bail!("--scale must be between 0.25 and 4, got {}", args.scale);
+    }
+    let placement = WindowPlacement {
+        scale: args.scale,
+        position: args.position,
+        maximized: args.maximized,
+        monitor: args.monitor,
+    };
+    let clock = match args.fixed_step {
+        Some(step) if step <= 0 => anyhow::bail!("--fixed-step must be positive"),
+        Some(step) => Clock::stepped(Duration::from_micros(step as u64)),
+        None => Clock::default(),
+    };
+
     // Create native options for eframe
     let native_options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([420.0, 860.0])
-            .with_min_inner_size([380.0, 720.0])
-            .with_resizable(true)
-            .with_title("Arknights Pass Simulator"),
+        viewport: placement.apply(
+            egui::ViewportBuilder::default()
+                .with_resizable(true)
+                .with_title("Arknights Pass Simulator"),
+            window_size_for_screen(screen_width, screen_height),
+            [380.0, 720.0],
+        ),
         ..Default::default()
     };
 
@@ -132,28 +616,111 @@ fn main() -> Result<()> {
         }
     });
     let rotation = args.rotation;
+
+    if let (Some(time_us), Some(out)) = (args.screenshot_at, &args.out) {
+        let config = initial_config
+            .ok_or_else(|| anyhow::anyhow!("{}", config_error.unwrap_or_default()))
+            .context(Failure::Config)?;
+        let mut renderer = open_renderer(config, base_dir, app_dir, cropbox, rotation)?;
+        renderer.set_serial(args.serial);
+        return write_screenshot(&mut renderer, time_us, out);
+    }
+
     let is_dark_theme = args.theme != "light";
+    let strict = global.strict;
+    let secs_to_us = |secs: f64| (secs.max(0.0) * 1_000_000.0) as i64;
+    let auto_replay = match (args.auto_replay, args.auto_replay_total) {
+        (Some(secs), _) => AutoReplay::AfterLoop(secs_to_us(secs)),
+        (None, Some(secs)) => AutoReplay::AfterTotal(secs_to_us(secs)),
+        (None, None) => AutoReplay::Off,
+    };
+
+    let ipc_transport = if args.stdio {
+        Some(IpcTransport::Stdio)
+    } else if let Some(pipe) = args.pipe {
+        Some(IpcTransport::LocalSocket(pipe))
+    } else if let Some(ref tcp) = args.tcp {
+        Some(IpcTransport::tcp(tcp).context(Failure::Ipc)?)
+    } else if let Some(ref websocket) = args.websocket {
+        Some(IpcTransport::websocket(websocket).context(Failure::Ipc)?)
+    } else if let Some(ref http) = args.http {
+        Some(IpcTransport::http(http).context(Failure::Ipc)?)
+    } else {
+        None
+    };
+    let heartbeat_timeout = (args.heartbeat_timeout > 0).then(|| Duration::from_secs(args.heartbeat_timeout));
+    // Bind before opening the window, so a taken port fails the launch
+    let ipc = ipc_transport
+        .map(|transport| {
+            let description = format!("{:?}", transport);
+            start_ipc_server(IpcOptions {
+                transport,
+                heartbeat_timeout,
+                reconnect_timeout: Duration::from_secs(args.reconnect_timeout),
+                token: args.ipc_token,
+            })
+            .with_context(|| format!("Failed to start IPC server ({})", description))
+            .context(Failure::Ipc)
+        })
+        .transpose()?;
 
     // Run the application
     eframe::run_native(
         "Arknights Pass Simulator",
         native_options,
         Box::new(move |cc| {
-            Ok(Box::new(SimulatorApp::new(
+            cc.egui_ctx.set_zoom_factor(placement.scale);
+            let mut app = SimulatorApp::new(
                 cc,
                 initial_config,
                 base_dir,
                 app_dir,
-                args.pipe,
-                args.stdio,
+                ipc,
                 cropbox,
                 rotation,
                 is_dark_theme,
                 config_error,
-            )))
+            );
+            app.set_strict_validation(strict);
+            app.set_auto_replay(auto_replay);
+            app.set_clock(clock);
+            if args.always_on_top {
+                app.set_always_on_top(true);
+            }
+            app.set_serial(args.serial);
+            Ok(Box::new(app))
         }),
     )
-    .map_err(|e| anyhow::anyhow!("eframe error: {}", e))?;
+    .map_err(|e| anyhow::anyhow!("eframe error: {}", e))
+    .context(Failure::Render)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+
+        // The editor starts the window without a subcommand
+        let cli = parse_cli(["sim", "--config", "epconfig.json", "--pipe", "editor"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.play.pipe.as_deref(), Some("editor"));
+
+        let cli = parse_cli(["sim", "validate", "epconfig.json", "--strict", "--log-format", "json"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Validate { .. })));
+        assert!(cli.global.strict);
+        assert_eq!(cli.global.log_format, LogFormat::Json);
+
+        let cli = parse_cli(["sim", "play", "-c", "epconfig.json"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Play(ref args)) if args.config.is_some()));
+
+        assert!(parse_cli(["sim", "--config", "epconfig.json", "probe", "loop.mp4"]).is_err());
+        // Global flags go before or after the subcommand
+        let cli = parse_cli(["sim", "--debug", "probe", "loop.mp4"]).unwrap();
+        assert!(cli.global.debug && matches!(cli.command, Some(Command::Probe { .. })));
+    }
+}
@@ -1,7 +0,0 @@
-//! Utility module
-//!
-//! Contains helper functions and types.
-
-mod color;
-
-pub use color::*;
@@ -0,0 +1,72 @@
+//! Web preview of the simulator
+//!
+//! Runs the simulator in a browser canvas, so a material package can be
+//! previewed from a link without installing anything. Build with
+//! `wasm-pack build --target web`; see `index.html` for how a page starts it.
+//!
+//! Browsers have no FFmpeg, and decoding video here is out of scope: still
+//! images and GIFs play, videos don't. A package whose loop is a video still
+//! opens, with the simulator saying why there is no video in place of it.
+
+use std::path::PathBuf;
+
+use wasm_bindgen::prelude::*;
+use web_sys::HtmlCanvasElement;
+
+use arknights_pass_simulator::app::SimulatorApp;
+use arknights_pass_simulator::config::EPConfig;
+use arknights_pass_simulator::utils::resolve_asset_path;
+use arknights_pass_simulator::video::probe_video;
+
+/// A simulator running in one canvas
+#[wasm_bindgen]
+pub struct WebHandle {
+    runner: eframe::WebRunner,
+}
+
+#[wasm_bindgen]
+impl WebHandle {
+    #[allow(clippy::new_without_default)]
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        // Already set if the page creates a second handle
+        tracing_wasm::try_set_as_global_default().ok();
+        Self { runner: eframe::WebRunner::new() }
+    }
+
+    /// Play a package (.eppkg bytes) in `canvas`
+    #[wasm_bindgen]
+    pub async fn start(&self, canvas: HtmlCanvasElement, package: &[u8]) -> Result<(), JsValue> {
+        let (config, base_dir) =
+            EPConfig::load_package_bytes(package).map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+        let loop_path = resolve_asset_path(&config.loop_config.file, &base_dir);
+        let video_error = probe_video(&loop_path)
+            .err()
+            .map(|e| format!("浏览器预览不播放视频，请改用 GIF 或静态图片作为循环素材\n{:#}", e));
+        self.runner
+            .start(
+                canvas,
+                eframe::WebOptions::default(),
+                Box::new(move |cc| {
+                    Ok(Box::new(SimulatorApp::new(
+                        cc,
+                        Some(config),
+                        base_dir,
+                        PathBuf::from("."),
+                        None,
+                        None,
+                        0,
+                        true,
+                        video_error,
+                    )))
+                }),
+            )
+            .await
+    }
+
+    /// Stop the simulator and release the canvas
+    #[wasm_bindgen]
+    pub fn destroy(&self) {
+        self.runner.destroy();
+    }
+}